@@ -0,0 +1,89 @@
+//! the entity system's hot paths, `cargo bench --bench ecs`
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use snek::{
+    entity::{component, Components, Entities, EntityId, EntityManager, Kinematics, Position},
+    math::Vec2,
+    world::Room,
+};
+
+const DT: Duration = Duration::from_millis(16);
+
+/// a headless room of walls laid out in a square, with a tenth as many colliders flying through it
+/// returns the walls
+fn stress_room(walls: usize) -> (EntityManager, Vec<EntityId>) {
+    let (mut man, _input) = EntityManager::headless();
+    let side = (walls as f32).sqrt().ceil().max(1.0) as usize;
+    let tile = move |i: usize| Position::new((i % side) as f32, (i / side) as f32, 0.0);
+
+    let ids = man.spawn_batch(
+        Entities::Wall,
+        &[Components::Position, Components::Collider],
+        (0..walls).map(tile),
+        |wall, position| {
+            wall.set::<component::Position>(position);
+        },
+    );
+    man.spawn_batch(
+        Entities::Basic,
+        &[Components::Position, Components::Collider, Components::Kinematics],
+        (0..walls / 10).map(|i| tile(10 * i)),
+        |fireball, position| {
+            fireball
+                .set::<component::Position>(position)
+                .set::<component::Kinematics>(Kinematics::cruising(Vec2::new(1.0, 0.0)));
+        },
+    );
+    (man, ids)
+}
+
+/// a collider moving next to the walls scans every position for one on its tile
+fn set_position(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_position");
+    for walls in [1_000, 5_000] {
+        let (mut man, _) = stress_room(walls);
+        let probe = man.spawn(Entities::Basic, &[Components::Position, Components::Collider]);
+        let mut probe = man.view(probe).unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(walls), &walls, |b, _| {
+            let mut x = 0.0;
+            b.iter(|| {
+                // off to the side of the walls, so nothing gets hit
+                x += 1.0;
+                probe.set_position(Position::new(-x, -1.0, 0.0));
+            })
+        });
+    }
+    group.finish();
+}
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    for walls in [1_000, 10_000] {
+        let (mut man, _) = stress_room(walls);
+        group.bench_with_input(BenchmarkId::from_parameter(walls), &walls, |b, _| {
+            b.iter(|| man.tick(DT))
+        });
+    }
+    group.finish();
+}
+
+/// what a transition hitches on, a procedural room off the first one
+fn room_generation(c: &mut Criterion) {
+    c.bench_function("procedural room", |b| {
+        b.iter_batched(
+            || {
+                let (mut man, _) = EntityManager::headless();
+                let (first, _) = Room::tut_controls(&mut man);
+                (man, first)
+            },
+            |(mut man, first)| Room::procedural(&mut man, &first),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, set_position, tick, room_generation);
+criterion_main!(benches);
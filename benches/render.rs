@@ -0,0 +1,56 @@
+//! ways of getting a frame's instances to the gpu, `cargo bench --bench render`
+//! needs a display to open a hidden window on, skips itself without one
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use glfw::{OpenGlProfileHint, WindowHint, WindowMode};
+use snek::{
+    common::AsBytes,
+    gl::{self, buffer_flags, ArrayBuffer, DrawContext},
+    render::instanced::Tile,
+};
+
+fn upload(c: &mut Criterion) {
+    let Ok(mut glfw) = glfw::init(|_, description| eprintln!("glfw: {description}")) else {
+        eprintln!("no glfw, skipping the upload benches");
+        return;
+    };
+    glfw.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw.window_hint(WindowHint::ContextVersion(4, 5));
+    glfw.window_hint(WindowHint::Visible(false));
+    let Some((mut window, _events)) = glfw.create_window(64, 64, "bench", WindowMode::Windowed) else {
+        eprintln!("no window, skipping the upload benches");
+        return;
+    };
+    let ctx = DrawContext::create(&mut window);
+    // waits for the upload to actually happen, instead of timing how long it takes to queue up
+    let finish = || unsafe { gl::raw::Finish() };
+
+    let mut group = c.benchmark_group("instance upload");
+    for instances in [1_000, 10_000] {
+        let tiles: Vec<_> = (0..instances).map(|_| Tile::default()).collect();
+        let bytes = unsafe { tiles.as_slice().as_bytes() };
+
+        // what the instanced renderers do, one buffer reserved up front and rewritten every frame
+        let vbo = ArrayBuffer::new(&ctx);
+        vbo.reserve(bytes.len(), buffer_flags::DYNAMIC_STORAGE);
+        group.bench_with_input(BenchmarkId::new("sub data", instances), &instances, |b, _| {
+            b.iter(|| {
+                vbo.update(0, bytes);
+                finish();
+            })
+        });
+
+        // a new buffer every frame, created with the data in it
+        group.bench_with_input(BenchmarkId::new("new buffer", instances), &instances, |b, _| {
+            b.iter(|| {
+                let vbo = ArrayBuffer::new(&ctx);
+                vbo.set(bytes, buffer_flags::DEFAULT);
+                finish();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, upload);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+#version 450 core
+
+layout (binding = 0) uniform sampler2D screen;
+layout (location = 0) uniform float glitch;
+
+in vec2 uv;
+out vec4 fragColor;
+
+void main() {
+    // the split grows towards the edges of the screen
+    vec2 dir = uv - 0.5;
+    vec2 offset = 0.02 * glitch * dir * length(dir);
+
+    fragColor.r = texture(screen, uv + offset).r;
+    fragColor.g = texture(screen, uv).g;
+    fragColor.b = texture(screen, uv - offset).b;
+    fragColor.a = 1.0;
+}
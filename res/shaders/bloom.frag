@@ -0,0 +1,14 @@
+#version 450 core
+
+layout (binding = 0) uniform sampler2D screen;
+layout (binding = 1) uniform sampler2D glow;
+layout (location = 0) uniform float intensity;
+
+in vec2 uv;
+out vec4 fragColor;
+
+void main() {
+    fragColor = texture(screen, uv);
+    fragColor.rgb += intensity * texture(glow, uv).rgb;
+    fragColor.a = 1.0;
+}
@@ -0,0 +1,15 @@
+#version 450 core
+
+// anything brighter than this glows
+#define THRESHOLD 0.5
+
+layout (binding = 0) uniform sampler2D screen;
+
+in vec2 uv;
+out vec4 fragColor;
+
+void main() {
+    vec3 col = texture(screen, uv).rgb;
+    float luma = dot(col, vec3(0.2126, 0.7152, 0.0722));
+    fragColor = vec4(col * smoothstep(THRESHOLD, 1.0, luma), 1.0);
+}
@@ -0,0 +1,25 @@
+#version 450 core
+
+// in texels between taps, wider glow for the same cost
+#define SPREAD 2.0
+
+layout (binding = 0) uniform sampler2D screen;
+// one axis at a time, (1,0) or (0,1)
+layout (location = 0) uniform vec2 direction;
+
+in vec2 uv;
+out vec4 fragColor;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec2 offset = SPREAD * direction / vec2(textureSize(screen, 0));
+
+    vec3 col = weights[0] * texture(screen, uv).rgb;
+    for (int i = 1; i < 5; i++) {
+        col += weights[i] * texture(screen, uv + float(i) * offset).rgb;
+        col += weights[i] * texture(screen, uv - float(i) * offset).rgb;
+    }
+
+    fragColor = vec4(col, 1.0);
+}
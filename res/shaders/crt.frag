@@ -5,6 +5,11 @@
 layout (binding = 0) uniform sampler2D screen;
 layout (location = 0) uniform uint iTime;
 layout (location = 1) uniform float brightness;
+layout (location = 2) uniform float glitch;
+// all 1 for the stock look
+layout (location = 3) uniform float curvature;
+layout (location = 4) uniform float scanlines;
+layout (location = 5) uniform float flicker;
 
 in vec2 uv;
 out vec4 fragColor;
@@ -23,7 +28,7 @@ void add_scan_lines(inout vec3 frag) {
     int y = int(res.y);
 
 
-    float pop = 1.15;
+    float pop = 1.0 + 0.15 * scanlines;
     switch (y % 3) {
         case 0:
             frag.r *= pop;
@@ -38,7 +43,7 @@ void add_scan_lines(inout vec3 frag) {
     //frag.rgb = vec3(sin(fTime));
 
     if ((iTime / 32 + y) % 2 == 0) {
-        frag *= 1.05;
+        frag *= 1.0 + 0.05 * flicker;
     } 
 }
 
@@ -47,8 +52,8 @@ vec2 warp(vec2 uv) {
     uv = 2.0 * uv - 1.0;
 
     float l = length(uv);
-    float e = 1.0 * smoothstep(-0.1, 8.0, l); // lower value = less 'curvature'
-    float s = 0.99; // controls the 'spread' of the curvature (lower = more spread towards edges)
+    float e = curvature * smoothstep(-0.1, 8.0, l); // lower value = less 'curvature'
+    float s = mix(1.0, 0.99, curvature); // controls the 'spread' of the curvature (lower = more spread towards edges)
     float p = pow(l, e);
     uv *= s * p;
 
@@ -57,9 +62,45 @@ vec2 warp(vec2 uv) {
     return 0.5 * uv + 0.5;
 }
 
+float hash(float n) {
+    return fract(sin(n) * 43758.5453);
+}
+
+// shoves random rectangular blocks of the screen around
+vec2 displace_blocks(vec2 uv) {
+    vec2 block = floor(uv * vec2(16.0, 9.0));
+    float seed = hash(dot(block, vec2(12.9898, 78.233)) + float(iTime / 80));
+    // fewer blocks move when the glitch is weak
+    if (seed < 1.0 - 0.3 * glitch) {
+        return uv;
+    }
+    vec2 shove = vec2(hash(seed * 7.0), hash(seed * 13.0)) - 0.5;
+    return uv + 0.08 * glitch * shove;
+}
+
+// tears the screen into horizontal bands and splits the color channels
+vec4 sample_glitched(vec2 uv) {
+    uv = displace_blocks(uv);
+    float band = floor(uv.y * 24.0);
+    float seed = hash(band + float(iTime / 50));
+    // only some of the bands tear
+    float tear = step(1.0 - glitch, seed) * (seed - 0.5) * 0.1 * glitch;
+    uv.x += tear;
+
+    float split = 0.01 * glitch;
+    vec4 col = texture(screen, uv);
+    col.r = texture(screen, uv + vec2(split, 0.0)).r;
+    col.b = texture(screen, uv - vec2(split, 0.0)).b;
+    return col;
+}
+
 void main() {
     vec2 wuv = warp(uv);
-    fragColor = texture(screen, wuv);
+    if (glitch > 0.0) {
+        fragColor = sample_glitched(wuv);
+    } else {
+        fragColor = texture(screen, wuv);
+    }
     fragColor.rgb = pow(fragColor.rgb, vec3(1.23));
 
 
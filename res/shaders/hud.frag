@@ -0,0 +1,13 @@
+#version 450 core
+
+layout (binding = 0) uniform sampler2D glyphs;
+
+in vec2 uv;
+in vec3 col;
+
+out vec4 fragColor;
+
+void main() {
+    float a = texture(glyphs, uv).a;
+    fragColor = vec4(col, a);
+}
@@ -0,0 +1,18 @@
+#version 450 core
+
+// in front of everything else
+#define Z -0.99
+
+// no uScreen here, the hud is already in screen space
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec3 aCol;
+
+out vec2 uv;
+out vec3 col;
+
+void main() {
+    uv = aUV;
+    col = aCol;
+    gl_Position = vec4(aPos, Z, 1.0);
+}
@@ -10,9 +10,12 @@ layout (location = 0) in vec4 aPos;
 layout (location = 1) in mat4 aTransform;
 layout (location = 5) in vec3 aCol;
 
+// multiplies every instance's color, for baked scenery
+layout (location = 0) uniform vec3 uTint;
+
 out flat vec3 color;
 
 void main() {
     gl_Position = uScreen * aTransform * aPos;
-    color = aCol;
+    color = aCol * uTint;
 }
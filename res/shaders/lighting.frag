@@ -0,0 +1,34 @@
+#version 450 core
+
+#define MAX_LIGHTS 16
+// how much of the room still shows outside of every light
+#define AMBIENT 0.03
+
+layout (binding = 0) uniform sampler2D screen;
+layout (location = 0) uniform float darkness;
+layout (location = 1) uniform uint count;
+// xy is the center and zw the radius along either axis, in uv
+layout (location = 2) uniform vec4 lights[MAX_LIGHTS];
+// rgb is the color and a how strongly it glows
+layout (location = 2 + MAX_LIGHTS) uniform vec4 colors[MAX_LIGHTS];
+
+in vec2 uv;
+out vec4 fragColor;
+
+void main() {
+    float light = 0.0;
+    vec3 glow = vec3(0.0);
+    for (uint i = 0; i < count; ++i) {
+        float d = length((uv - lights[i].xy) / lights[i].zw);
+        light = max(light, 1.0 - smoothstep(0.4, 1.0, d));
+
+        float falloff = 1.0 - smoothstep(0.0, 1.0, d);
+        glow += colors[i].rgb * colors[i].a * falloff * falloff;
+    }
+
+    fragColor = texture(screen, uv);
+    fragColor.rgb *= mix(1.0, max(light, AMBIENT), darkness);
+    // glow adds on top, so it shows up in the dark too
+    fragColor.rgb += glow;
+    fragColor.a = 1.0;
+}
@@ -0,0 +1,12 @@
+#version 450 core
+
+in vec2 uv;
+in vec4 col;
+
+out vec4 fragCol;
+
+void main() {
+    // soft round points
+    float d = 1.0 - smoothstep(0.5, 1.0, length(uv));
+    fragCol = vec4(col.rgb, col.a * d);
+}
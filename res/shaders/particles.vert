@@ -0,0 +1,28 @@
+#version 450 core
+
+layout (location = 0) in vec2 aOrigin;
+layout (location = 1) in vec2 aVelocity;
+layout (location = 2) in vec3 aCol;
+layout (location = 3) in float aSize;
+layout (location = 4) in float aBorn;
+layout (location = 5) in float aLifetime;
+
+layout (location = 0) uniform float uTime;
+
+out vec3 vcol;
+out float vsize;
+out float vlife;
+
+void main() {
+    float age = uTime - aBorn;
+    // [0,1] over the particle's life, past 1 means dead
+    float t = age / aLifetime;
+    // ease out so bursts slow down as they spread
+    float travel = aLifetime * (1.0 - (1.0 - t) * (1.0 - t)) * 0.5;
+    vec2 pos = aOrigin + vec2(0.5) + aVelocity * travel;
+    gl_Position = vec4(pos, -0.5, 1.0);
+
+    vcol = aCol;
+    vsize = aSize;
+    vlife = t;
+}
@@ -0,0 +1,12 @@
+#version 450 core
+
+layout (binding = 0) uniform sampler2D screen;
+layout (location = 0) uniform float brightness;
+
+in vec2 uv;
+out vec4 fragColor;
+
+void main() {
+    fragColor = texture(screen, uv);
+    fragColor.a = brightness;
+}
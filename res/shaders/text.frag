@@ -5,30 +5,38 @@
 #define LETTER_SIZE 14.0
 
 layout (binding = 0) uniform sampler2D text;
-layout (location = 0) uniform float uCurrentFrame;
-layout (location = 1) uniform float uTotalFrames;
 
 in vec2 uv;
+flat in vec2 frame;
+flat in vec4 rect;
 
 out vec4 fragColor;
 
 void main() {
+    float currentFrame = frame.x;
+    float totalFrames = frame.y;
+
     vec2 suv = uv;
-    if (uTotalFrames > 1.0) {
+    if (totalFrames > 1.0) {
         // adjust for the whitespace above
         suv.y *= (LINE_SEPARATOR_HEIGHT / LETTER_SIZE);
     }
 
-    vec2 duv = vec2(0.0, uCurrentFrame);
-    suv = (suv + duv) / vec2(1.0, uTotalFrames);
+    vec2 duv = vec2(0.0, currentFrame);
+    suv = (suv + duv) / vec2(1.0, totalFrames);
+
 
+    // effectively clamp to a transparent background
+    if (any(lessThan(suv, vec2(0.0))) || any(greaterThan(suv, vec2(1.0)))) {
+        discard;
+    }
 
-    vec4 foreground = texture(text, suv);
+    vec4 foreground = texture(text, mix(rect.xy, rect.zw, suv));
 
     fragColor.rgb = vec3(1.0 - pow(foreground.a, 0.95));
     fragColor.a = foreground.a;
 
-    if (uCurrentFrame != 0.0) {
+    if (currentFrame != 0.0) {
         fragColor.rgb = 1.0 - fragColor.rgb;
     }
 }
@@ -8,10 +8,18 @@ layout (std140, binding = 0) uniform Common {
 
 layout (location = 0) in vec2 aPos;
 layout (location = 1) in vec2 aUV;
+// (current frame, total frames)
+layout (location = 2) in vec2 aFrame;
+// where the text is in the atlas
+layout (location = 3) in vec4 aRect;
 
 out vec2 uv;
+flat out vec2 frame;
+flat out vec4 rect;
 
 void main() {
     uv = aUV;
+    frame = aFrame;
+    rect = aRect;
     gl_Position = uScreen * vec4(aPos + vec2(0.5), Z, 1.0);
 }
@@ -0,0 +1,18 @@
+#version 450 core
+
+// under the fireballs and swoops
+#define Z -0.3
+
+layout(std140, binding = 0) uniform Common {
+    mat4 uScreen;
+};
+
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec4 aCol;
+
+out vec4 col;
+
+void main() {
+    col = aCol;
+    gl_Position = uScreen * vec4(aPos + vec2(0.5), Z, 1.0);
+}
@@ -0,0 +1,22 @@
+#version 450 core
+
+#define DANGER_COLOR vec3(0.6, 0.0, 0.05)
+
+layout (binding = 0) uniform sampler2D screen;
+layout (location = 0) uniform uint iTime;
+layout (location = 1) uniform float danger;
+
+in vec2 uv;
+out vec4 fragColor;
+
+void main() {
+    fragColor = texture(screen, uv);
+
+    // throbs like a heartbeat
+    float beat = 0.85 + 0.15 * sin(float(iTime) / 120.0);
+    float edge = smoothstep(0.25, 0.75, length(uv - 0.5));
+    float amount = danger * beat * edge;
+
+    fragColor.rgb = mix(fragColor.rgb, DANGER_COLOR * fragColor.rgb + 0.1 * DANGER_COLOR, amount);
+    fragColor.a = 1.0;
+}
@@ -0,0 +1,161 @@
+use std::{
+    ops::{Add, Mul},
+    time::Duration,
+};
+
+use crate::math::{lerp, Vec2, Vec3};
+
+pub type Easing = fn(f32) -> f32;
+
+/// the value a track reaches at a point in time
+/// eased in from the keyframe before it
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<T> {
+    pub at: Duration,
+    pub value: T,
+    pub ease: Easing,
+}
+
+/// keyframes in chronological order
+/// holds the first value before it starts and the last one after it's over
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self { keys: Vec::new() }
+    }
+}
+
+impl<T> Track<T>
+where
+    T: Copy + Add<Output = T>,
+    f32: Mul<T, Output = T>,
+{
+    /// keyframes have to be added in chronological order
+    pub fn key(mut self, at: Duration, value: T, ease: Easing) -> Self {
+        debug_assert!(self.keys.last().is_none_or(|k| k.at <= at));
+        self.keys.push(Keyframe { at, value, ease });
+        self
+    }
+
+    fn length(&self) -> Duration {
+        self.keys.last().map_or(Duration::ZERO, |k| k.at)
+    }
+
+    fn sample(&self, time: Duration) -> Option<T> {
+        let first = self.keys.first()?;
+        if time <= first.at {
+            return Some(first.value);
+        }
+
+        for pair in self.keys.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if time < to.at {
+                let p = (time - from.at).as_secs_f32() / (to.at - from.at).as_secs_f32();
+                return Some(lerp(from.value, to.value, (to.ease)(p)));
+            }
+        }
+
+        self.keys.last().map(|k| k.value)
+    }
+}
+
+/// how an animated entity should be drawn right now
+/// relative to however it would be drawn without the animation
+#[derive(Debug, Clone, Copy)]
+pub struct Pose {
+    pub offset: Vec3,
+    pub scale: Vec2,
+    // how far the color is pushed towards white
+    pub flash: f32,
+    pub alpha: f32,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::default(),
+            scale: Vec2::diagonal(1.0),
+            flash: 0.0,
+            alpha: 1.0,
+        }
+    }
+}
+
+impl Pose {
+    pub fn tint(&self, col: Vec3) -> Vec3 {
+        lerp(col, Vec3::diagonal(1.0), self.flash)
+    }
+}
+
+/// tracks playing side by side
+/// tracks left empty keep the pose's default
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    offset: Track<Vec3>,
+    scale: Track<Vec2>,
+    flash: Track<f32>,
+    alpha: Track<f32>,
+    looping: bool,
+    time: Duration,
+}
+
+impl Animation {
+    pub fn offset(mut self, track: Track<Vec3>) -> Self {
+        self.offset = track;
+        self
+    }
+
+    pub fn scale(mut self, track: Track<Vec2>) -> Self {
+        self.scale = track;
+        self
+    }
+
+    pub fn flash(mut self, track: Track<f32>) -> Self {
+        self.flash = track;
+        self
+    }
+
+    pub fn alpha(mut self, track: Track<f32>) -> Self {
+        self.alpha = track;
+        self
+    }
+
+    /// start over once the longest track is done
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    fn length(&self) -> Duration {
+        self.offset
+            .length()
+            .max(self.scale.length())
+            .max(self.flash.length())
+            .max(self.alpha.length())
+    }
+
+    pub fn tick(&mut self, dt: Duration) {
+        self.time += dt;
+
+        let length = self.length();
+        if self.looping && !length.is_zero() {
+            while self.time >= length {
+                self.time -= length;
+            }
+        }
+    }
+
+    pub fn pose(&self) -> Pose {
+        let default = Pose::default();
+        Pose {
+            offset: self.offset.sample(self.time).unwrap_or(default.offset),
+            scale: self.scale.sample(self.time).unwrap_or(default.scale),
+            flash: self.flash.sample(self.time).unwrap_or(default.flash),
+            alpha: self.alpha.sample(self.time).unwrap_or(default.alpha),
+        }
+    }
+}
@@ -1,89 +1,259 @@
 pub mod wall {
     use crate::{
-        entity::{Components, Entities, EntityId, EntityManager, EntityView, Position},
-        math::Mat4,
-        palette::Palette,
-        render::{instanced::Tile, RenderManager},
+        entity::{component, Components, Entities, EntityId, EntityManager, EntityView, Health, Position, PropKey},
+        math::{lerp, Mat4, Vec3},
+        palette::{Palette, PaletteKey},
+        render::{instanced::Tile, particles::Burst, RenderManager},
     };
 
-    pub fn new(man: &mut EntityManager, position: Position) -> EntityId {
+    // what a cracked wall started with, for how far it's faded
+    const MAX_HP: PropKey<Health> = PropKey::new("max_hp");
+
+    /// a wall at every position, put up in one go
+    pub fn batch(man: &mut EntityManager, positions: impl IntoIterator<Item = Position>) -> Vec<EntityId> {
+        man.spawn_batch(Entities::Wall, &[Components::Position, Components::Collider], positions, |wall, position| {
+            wall.set::<component::Position>(position);
+        })
+    }
+
+    /// a wall strong fireballs can break through, one hit per hp
+    pub fn cracked(man: &mut EntityManager, position: Position, hp: Health) -> EntityId {
         let id = man.spawn(
             Entities::Wall,
-            &[Components::Position, Components::Collider],
+            &[
+                Components::Position,
+                Components::Collider,
+                Components::Health,
+                Components::Particles,
+                Components::Properties,
+            ],
         );
 
         let mut wall = man.view(id).unwrap();
         wall.set_position(position);
+        wall.set::<component::Health>(hp);
+        wall.new_property(self::MAX_HP, hp);
 
         id
     }
 
-    pub fn draw(entity: EntityView, renderer: &mut RenderManager, palette: Palette) {
-        let pos = entity.get_position();
+    pub fn is_cracked(entity: &EntityView) -> bool {
+        entity.has(Components::Health)
+    }
 
+    pub fn hit(this: &mut EntityView) {
+        let hp = this.get::<component::Health>() - 1;
+        this.set::<component::Health>(hp);
+        if hp <= 0 {
+            this.get_particles()
+                .emit(Burst::explosion(this.get_position().into()));
+            this.kill();
+        }
+    }
+
+    /// cracked walls change as they take hits, so they can't be part of the scenery
+    pub fn draw(this: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        if !self::is_cracked(&this) {
+            return;
+        }
+
+        let hp = this.get::<component::Health>() as f32;
+        let max_hp = this.get_property(self::MAX_HP) as f32;
         renderer.push(Tile {
-            transform: Mat4::translate(pos),
-            col: palette.wall,
+            transform: Mat4::translate(this.get_position()),
+            col: lerp(palette.get(PaletteKey::Background), palette.get(PaletteKey::Wall), hp / max_hp),
         });
     }
+
+    /// walls are drawn as part of their room's scenery
+    pub fn tile(entity: &EntityView) -> Tile {
+        Tile {
+            transform: Mat4::translate(entity.get_position()),
+            col: Vec3::diagonal(1.0),
+        }
+    }
 }
 
 pub mod background {
     use crate::{
-        entity::{Components, Entities, EntityId, EntityManager, EntityView, Position},
-        math::{Mat4, Vec2},
-        palette::Palette,
-        render::{instanced::Tile, RenderManager},
+        entity::{component, Components, Entities, EntityId, EntityManager, EntityView, Position},
+        math::{Mat4, Vec2, Vec3},
+        render::instanced::Tile,
     };
 
     pub fn new(man: &mut EntityManager, position: Position, dimensions: Vec2) -> EntityId {
-        let id = man.spawn(
+        man.build(
             Entities::Background,
             &[
                 Components::Position,
                 Components::Collider,
                 Components::Scale,
             ],
-        );
-
-        let mut bg = man.view(id).unwrap();
-        bg.set_position(position);
-        bg.set_scale(dimensions);
-
-        id
+        )
+        .set::<component::Position>(position)
+        .set::<component::Scale>(dimensions)
+        .id()
     }
 
-    pub fn draw(entity: EntityView, renderer: &mut RenderManager, palette: Palette) {
+    /// the background is drawn as part of its room's scenery
+    pub fn tile(entity: &EntityView) -> Tile {
         let pos = entity.get_position();
         let scale = entity.get_scale();
 
-        renderer.push(Tile {
+        Tile {
             transform: Mat4::translate(pos) * Mat4::scale(scale),
-            col: palette.background,
+            col: Vec3::diagonal(1.0),
+        }
+    }
+}
+
+pub mod scenery {
+    use std::rc::Rc;
+
+    use crate::{
+        entity::{component, Color, Components, Entities, EntityId, EntityManager, EntityView},
+        palette::Palette,
+        render::{
+            instanced::{Scenery, Tile},
+            RenderManager,
+        },
+    };
+
+    /// bakes tiles that never move into a single batch
+    /// every tile is tinted by color
+    pub fn new(man: &mut EntityManager, tiles: Vec<Tile>, color: Color) -> EntityId {
+        man.build(
+            Entities::Scenery,
+            &[Components::Color, Components::Properties],
+        )
+        .set::<component::Color>(color)
+        .new_property("tiles", Rc::new(tiles))
+        .id()
+    }
+
+    pub fn draw(entity: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let tiles = entity.with_property("tiles", |t: &Rc<Vec<Tile>>| t.clone());
+        renderer.push(Scenery {
+            id: entity.id(),
+            tiles,
+            col: palette.get(entity.get_color()),
         });
     }
 }
 
 pub mod snake {
-    use std::{process::exit, sync::mpsc::{self, Receiver, Sender}, thread::sleep, time::Duration};
+    use std::{cell::Cell, collections::VecDeque, sync::mpsc::{self, Receiver, Sender}, time::Duration};
 
     use crate::{
-        archetype::{fireball, swoop},
+        archetype::{fireball, swoop, terrain},
+        config, endless,
         entity::{
-            Animation, Components, Direction, Entities, EntityId, EntityManager, EntityView,
-            Position, SelfDestruct,
+            component, BodyLength, Components, Direction, Entities, EntityId, EntityManager, EntityView,
+            Light, Position, PropKey,
         },
-        math::{f32_eq, Mat4, Vec2, Vec3},
+        event::EventKind,
+        math::{f32_eq, lerp, Mat4, TileCoord, Vec2, Vec3, Vec4},
         palette::{self, Palette, PaletteKey},
-        render::{instanced::Tile, shield::Shield, RenderManager},
-        sound::Sounds, time::{Cooldown, Threshold},
+        render::{hud::HudText, instanced::Tile, particles::Burst, post, shield::Shield, trail::Trail, RenderManager},
+        sound::Sounds, status::Status, time::{Cooldown, Threshold}, tunables,
     };
 
-    const STEP: Duration = Duration::from_millis(150);
     const POWER_LEVELUP: i32 = 3;
-    const ATTACK_COOLDOWN: Duration = Duration::from_millis(1000);
     const ATTACK_SPEED_CAP: Duration = Duration::from_millis(500);
     const ATTACK_CDR_PER_POWER: Duration = Duration::from_millis(50);
+    // fruits and kills closer together than this build up the combo
+    const COMBO_WINDOW: Duration = Duration::from_millis(2000);
+    // how long the death plays out before the end screen
+    pub const DEATH_LENGTH: Duration = Duration::from_millis(1500);
+    const DEATH_TRAUMA: f32 = 1.0;
+    // how many times a second the head blinks while dying
+    const DEATH_BLINK: f32 = 8.0;
+    // the body is gone, tail first, this far into the death
+    const DISSOLVE_SHARE: f32 = 0.7;
+    // the screen flares up on every power level
+    const POWERUP_GLOW: Duration = Duration::from_millis(1200);
+    const POWERUP_BLOOM: f32 = 2.0;
+    // how long a boost from a fruit lasts
+    const BOOST_LENGTH: Duration = Duration::from_millis(4000);
+    // how far each tier pulls the snake's color towards the fruit's
+    const TIER_SHIFT: f32 = 0.15;
+    const TIER_PUNCH: f32 = 0.06;
+    // how far the head flashes towards white while the power up glow is fresh
+    const TIER_FLASH: f32 = 0.6;
+    const SHIELD_ALPHA: f32 = 0.6;
+    // room clear rewards
+    const GROWTH_LENGTH: usize = 3;
+    // segments lost to a hit the shield soaks up
+    const SHIELD_SHRINK: usize = 2;
+    // nothing hurts for a moment after a hit, so swarms can't chain it
+    const IFRAMES: Duration = Duration::from_millis(1000);
+    // how many times a second the snake blinks out while recovering
+    const IFRAME_BLINK: f32 = 10.0;
+    // how many times longer a step takes on tar
+    const TAR_SLOWDOWN: u32 = 2;
+    // how far the snake can see in dark rooms, in tiles
+    const LIGHT_RADIUS: f32 = 5.0;
+    const LIGHT_INTENSITY: f32 = 0.12;
+    // the head leaves a short streak behind it
+    const TRAIL_WIDTH: f32 = 0.5;
+    const TRAIL_ALPHA: f32 = 0.35;
+    const TRAIL_LENGTH: Duration = Duration::from_millis(250);
+    const FOCUS_CDR: Duration = Duration::from_millis(100);
+    // attack cooldown bar under the head, in tiles
+    const COOLDOWN_BAR: Vec2 = Vec2 { x: 0.8, y: 0.1 };
+    const COOLDOWN_BAR_GAP: f32 = 0.2;
+    // the empty part of the bar sits this far behind the filled part
+    const COOLDOWN_TRACK_DEPTH: f32 = 0.01;
+
+    // hud layout in screen space
+    const HUD_LEFT: f32 = -0.9;
+    const HUD_TOP: f32 = 0.9;
+    const HUD_GLYPH_SIZE: f32 = 0.05;
+    const HUD_LINE_HEIGHT: f32 = 0.075;
+
+    // what the snake's found by
+    pub const TAG: &str = "player";
+    // power comes from score
+    pub const SCORE: PropKey<i32> = PropKey::new("score");
+    // the body, head first
+    const SEGMENTS: PropKey<VecDeque<EntityId>> = PropKey::new("segments");
+    // how long the snake's been dying, only there once it is
+    const DEATH: PropKey<Duration> = PropKey::new("death");
+    // on attacks, back to the snake that fired them
+    const OWNER_KILL_TX: PropKey<Sender<()>> = PropKey::new("owner_kill_tx");
+
+    /// what the snake attacks with, unlocked by power level
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum Tier {
+        Swoop,
+        StrongSwoop,
+        Fireball,
+        StrongFireball,
+        Piercing,
+    }
+
+    impl Tier {
+        const ALL: [Tier; 5] = [Tier::Swoop, Tier::StrongSwoop, Tier::Fireball, Tier::StrongFireball, Tier::Piercing];
+
+        pub fn of(power: i32) -> Self {
+            let unlocked = tunables::get().power_tiers.iter().take_while(|&&p| power >= p).count();
+            Self::ALL[unlocked]
+        }
+
+        fn attack(self, man: &mut EntityManager, pos: Vec3, dir: Direction, mouse: Vec3) -> EntityId {
+            match self {
+                Self::Swoop => super::swoop::weak_attack(man, pos, dir),
+                Self::StrongSwoop => super::swoop::strong_attack(man, pos, dir),
+                Self::Fireball => super::fireball::weak_attack(man, pos, mouse),
+                Self::StrongFireball => super::fireball::strong_attack(man, pos, mouse),
+                Self::Piercing => super::fireball::piercing_attack(man, pos, mouse),
+            }
+        }
+
+        pub fn color(self, palette: Palette) -> Vec3 {
+            lerp(palette.get(PaletteKey::Snake), palette.get(PaletteKey::Fruit), self::TIER_SHIFT * self as usize as f32)
+        }
+    }
 
     pub fn new(man: &mut EntityManager, position: Vec2) -> EntityId {
         let id = man.spawn(
@@ -96,37 +266,40 @@ pub mod snake {
                 Components::BodyLength,
                 Components::Timer,
                 Components::Spawner,
-                Components::Animation,
                 Components::Properties,
                 Components::Sound,
+                Components::Camera,
+                Components::StatusEffects,
+                Components::Light,
             ],
         );
 
+        man.tag(id, self::TAG);
         let mut snake = man.view(id).unwrap();
         snake.set_position((position, -1.0).into());
-        snake.access_timer(|t| t.set_threshold(STEP));
+        snake.set::<component::Light>(Light::new(PaletteKey::Snake, self::LIGHT_RADIUS, self::LIGHT_INTENSITY));
+        snake.access_timer(|t| t.set_threshold(self::step()));
 
-        snake.new_property("score", 0);
+        snake.new_property(self::SCORE, 0);
         snake.new_property("smoothing", true);
         snake.new_property("shield", false);
         snake.new_property("can_attack", false);
-        snake.new_property("attack_timer", Cooldown::new(self::ATTACK_COOLDOWN));
+        snake.new_property("attack_timer", Cooldown::new(config::get().attack_cooldown));
+        snake.new_property("focus", Duration::ZERO);
+        snake.new_property("combo", 0u32);
+        snake.new_property("combo_timer", Cooldown::new(self::COMBO_WINDOW));
+        snake.new_property("powerup_glow", Cooldown::new(self::POWERUP_GLOW));
 
-        id
-    }
+        let (kill_tx, kill_rx) = mpsc::channel::<()>();
+        snake.new_property("kill_tx", kill_tx);
+        snake.new_property("kill_rx", kill_rx);
 
-    pub fn make_move_trigger(man: &mut EntityManager, id: EntityId) -> Receiver<()> {
-        let this = man.view(id).unwrap();
-        let (tx, rx) = mpsc::channel();
-        this.new_property("move_tx", tx);
-        rx
-    }
+        // body segments, nearest to the head first
+        snake.new_property(self::SEGMENTS, VecDeque::new());
+        // whatever the head stepped on last, until its next step
+        snake.new_property("terrain", None::<terrain::Kind>);
 
-    pub fn make_attack_trigger(man: &mut EntityManager, id: EntityId) -> Receiver<()> {
-        let this = man.view(id).unwrap();
-        let (tx, rx) = mpsc::channel();
-        this.new_property("attack_tx", tx);
-        rx
+        id
     }
 
     pub fn add_attack_enable_trigger(man: &mut EntityManager, id: EntityId, trigger: Receiver<()>) {
@@ -134,67 +307,295 @@ pub mod snake {
         this.new_property("enable_attack_trigger", trigger);
     }
 
-    fn body(
-        man: &mut EntityManager,
-        position: Position,
-        neighbors: Vec<Direction>,
-        lifetime: SelfDestruct,
-    ) -> EntityId {
+    /// catches the snake up on tutorial rooms it skipped, as if it had eaten their fruits
+    pub fn head_start(man: &mut EntityManager, id: EntityId, fruits: i32, can_attack: bool) {
+        let mut this = man.view(id).unwrap();
+        this.with_mut_property(self::SCORE, |s| *s += fruits);
+        for _ in 0..fruits {
+            self::lengthen(&mut this);
+        }
+        this.set_property("can_attack", can_attack);
+
+        let cooldown = self::attack_cooldown(&this);
+        this.with_mut_property("attack_timer", |t: &mut Cooldown| t.set_cooldown(cooldown));
+    }
+
+    fn segment(man: &mut EntityManager, head: EntityId, position: Position, neighbors: Vec<Direction>) -> EntityId {
         let id = man.spawn(
             Entities::SnakeBody,
             &[
                 Components::Position,
                 Components::Collider,
-                Components::SelfDestruct,
-                Components::Timer,
+                Components::Spawner,
                 Components::Properties,
+                Components::Particles,
             ],
         );
 
-        let mut body = man.view(id).unwrap();
-        body.set_position(position);
-        body.set_self_destruct(lifetime);
-        body.new_property("neighbors", neighbors);
-        body.access_timer(|t| t.set_threshold(STEP));
+        let mut segment = man.view(id).unwrap();
+        segment.set_position(position);
+        segment.new_property("neighbors", neighbors);
+        segment.new_property("tail", false);
+        segment.new_property("head", head);
 
         id
     }
 
-    pub fn body_tick(dt: Duration, entity: &mut EntityView) {
-        if !entity.access_timer(|t| t.tick(dt)) {
+    // the body is one step behind the head:
+    // the tail moves up to where the head just was,
+    // unless the snake is still growing, then a new segment goes there instead
+    fn follow(man: &mut EntityManager, head: EntityId, position: Position, neighbors: Vec<Direction>) {
+        let (len, mut segments) = {
+            let head = man.view(head).unwrap();
+            (head.get_body_length() as usize, head.get_property(self::SEGMENTS))
+        };
+        if len == 0 {
             return;
         }
 
-        let life = entity.get_self_destruct();
-        if life <= 1 {
-            entity.kill();
+        let front = if segments.len() < len {
+            self::segment(man, head, position, neighbors)
         } else {
-            entity.set_self_destruct(life - 1)
+            let tail = segments.pop_back().unwrap();
+            let mut segment = man.view(tail).unwrap();
+            segment.set_position(position);
+            segment.set_property("neighbors", neighbors);
+            segment.set_property("tail", false);
+            tail
+        };
+        segments.push_front(front);
+
+        if let Some(&tail) = segments.back() {
+            man.view(tail).unwrap().set_property("tail", true);
+        }
+        man.view(head).unwrap().set_property(self::SEGMENTS, segments);
+    }
+
+    // the segments past the first few are gone, tail first
+    fn dissolve(man: &mut EntityManager, head: EntityId, left: usize) {
+        let mut segments = man.view(head).unwrap().get_property(self::SEGMENTS);
+        while segments.len() > left {
+            let tail = segments.pop_back().unwrap();
+            let segment = man.view(tail).unwrap();
+            segment.get_particles().emit(Burst::sparkle(segment.get_position().into()));
+            man.kill(tail);
+        }
+        man.view(head).unwrap().set_property(self::SEGMENTS, segments);
+    }
+
+    // the body is left with its first few segments, and won't grow them back
+    fn cut(man: &mut EntityManager, head: EntityId, left: usize) {
+        let segments = man.view(head).unwrap().with_property(self::SEGMENTS, |s| s.len());
+        if segments <= left {
+            return;
+        }
+
+        self::dissolve(man, head, left);
+        let mut head = man.view(head).unwrap();
+        let len = head.get_body_length() - (segments - left) as BodyLength;
+        head.set_body_length(len);
+        head.get_sound().play(Sounds::Shrink);
+        head.publish(EventKind::SnakeShrunk);
+    }
+
+    /// lose up to n segments off the tail
+    pub fn shrink(head: &mut EntityView, n: usize) {
+        if self::is_dying(head) {
+            return;
         }
+
+        let len = head.with_property(self::SEGMENTS, |s| s.len());
+        let (id, left) = (head.id(), len.saturating_sub(n));
+        head.request_spawn(Box::new(move |man| self::cut(man, id, left)));
+    }
+
+    /// something cut through the body, the segment and everything behind it falls off
+    pub fn trim(segment: &mut EntityView) {
+        let (id, head) = (segment.id(), segment.get_property::<EntityId>("head"));
+        segment.request_spawn(Box::new(move |man| {
+            let view = man.view(head).unwrap();
+            if self::is_dying(&view) {
+                return;
+            }
+            let segments = view.get_property(self::SEGMENTS);
+            // already fell off to an earlier cut
+            if let Some(left) = segments.iter().position(|&s| s == id) {
+                self::cut(man, head, left);
+            }
+        }));
+    }
+
+    /// with a fragile body, a hit anywhere along it is the end
+    /// the head's the one that plays the death out
+    pub fn shatter(segment: &mut EntityView) {
+        let head = segment.get_property::<EntityId>("head");
+        segment.request_spawn(Box::new(move |man| {
+            if let Some(mut head) = man.view(head) {
+                self::die_sequence(&mut head);
+            }
+        }));
+    }
+
+    thread_local! {
+        // so the body can take on the head's colors
+        static TIER: Cell<Tier> = const { Cell::new(Tier::Swoop) };
+        // how far the head is into its step, so the tail can slide along
+        static STEP: Cell<f32> = const { Cell::new(0.0) };
+        // whether the snake is blinked out while recovering from a hit
+        static FLICKER: Cell<bool> = const { Cell::new(false) };
+        // the player's setting, steps take longer the lower it is
+        static SPEED: Cell<f32> = const { Cell::new(1.0) };
+    }
+
+    /// a fraction of the usual speed, for players who need more time to react
+    pub fn set_speed(speed: f32) {
+        SPEED.set(speed);
+    }
+
+    // how long a step normally takes
+    fn step() -> Duration {
+        config::get().step.div_f32(SPEED.get())
+    }
+
+    /// whether the head's death sequence has started
+    /// the game keeps running while the snake dies, so later collisions during the sequence are ignored
+    pub fn is_dying(head: &EntityView) -> bool {
+        head.has_property(self::DEATH.name())
     }
 
     pub fn die_sequence(head: &mut EntityView) {
+        if self::is_dying(head) {
+            return;
+        }
+
         head.get_sound().play(Sounds::Die);
-        sleep(Duration::from_millis(750));
-        exit(0);
+        head.get_camera().shake(self::DEATH_TRAUMA);
+        head.new_property(self::DEATH, Duration::ZERO);
+        let len = head.with_property(self::SEGMENTS, |s| s.len());
+        head.new_property("death_length", len);
+    }
+
+    // the head stays put and the body dissolves one segment at a time
+    fn death_tick(dt: Duration, head: &mut EntityView) {
+        let death = head.with_mut_property(self::DEATH, |d| {
+            *d += dt;
+            *d
+        });
+        let pct = (death.as_secs_f32() / self::DEATH_LENGTH.mul_f32(self::DISSOLVE_SHARE).as_secs_f32()).min(1.0);
+        let len = head.get_property::<usize>("death_length");
+        let left = len - (pct * len as f32).ceil() as usize;
+
+        if head.with_property(self::SEGMENTS, |s| s.len()) > left {
+            let id = head.id();
+            head.request_spawn(Box::new(move |man| self::dissolve(man, id, left)));
+        }
+    }
+
+    /// whatever would kill the snake, unless it's shielded
+    /// the shield still costs it some of its body
+    pub fn hurt(head: &mut EntityView) {
+        if self::is_recovering(head) {
+            return;
+        }
+
+        if self::is_shielded(head) {
+            head.publish(EventKind::SnakeHurt);
+            head.with_mut::<component::StatusEffects, _>(|s| s.apply(Status::Recovering, self::IFRAMES));
+            self::shrink(head, self::SHIELD_SHRINK);
+        } else {
+            self::die_sequence(head);
+        }
+    }
+
+    pub fn boost(this: &mut EntityView, status: Status) {
+        this.with_mut::<component::StatusEffects, _>(|s| s.apply(status, self::BOOST_LENGTH));
+    }
+
+    /// whether hitting enemies and its own body is harmless right now
+    pub fn is_shielded(this: &EntityView) -> bool {
+        this.get::<component::StatusEffects>().has(Status::Shield)
+    }
+
+    pub fn stand_on(head: &mut EntityView, kind: terrain::Kind) {
+        head.set_property("terrain", Some(kind));
+    }
+
+    fn is_recovering(this: &EntityView) -> bool {
+        this.get::<component::StatusEffects>().has(Status::Recovering)
+    }
+
+    /// mark an attack as belonging to the snake so its kills count towards the combo
+    fn own_attack(man: &mut EntityManager, attack: EntityId, kill_tx: Sender<()>) {
+        let attack = man.view(attack).unwrap();
+        attack.new_property(self::OWNER_KILL_TX, kill_tx);
+    }
+
+    /// called on an attack that just killed an enemy
+    pub fn notify_kill(attack: &mut EntityView) {
+        // only the snake's own attacks carry one
+        let _ = attack.try_with_property(self::OWNER_KILL_TX, |t| t.send(()));
+    }
+
+    fn chain(this: &mut EntityView) {
+        let chaining = this.with_property("combo_timer", |t: &Cooldown| t.is_cooling_down());
+        this.with_mut_property("combo", |c: &mut u32| {
+            *c = if chaining { *c + 1 } else { 1 };
+        });
+        this.with_mut_property("combo_timer", |t: &mut Cooldown| t.cool_down());
     }
 
     pub fn grow(this: &mut EntityView) {
-        let new_score = this.with_mut_property("score", |s: &mut i32| {
+        self::chain(this);
+
+        let new_score = this.with_mut_property(self::SCORE, |s| {
             let new_score = *s + 1;
             *s = new_score;
             new_score
         });
 
-        let cdr = self::ATTACK_CDR_PER_POWER * new_score as _;
-        let new_cd = self::ATTACK_COOLDOWN.saturating_sub(cdr);
-        let capped_cd = self::ATTACK_SPEED_CAP.max(new_cd);
+        let power = new_score / self::POWER_LEVELUP;
+        if new_score % self::POWER_LEVELUP == 0 {
+            this.with_mut_property("powerup_glow", |t: &mut Cooldown| t.cool_down());
+
+            if Tier::of(power) != Tier::of(power - 1) {
+                this.get_sound().play(Sounds::RoomUnlocked);
+                this.get_camera().punch(self::TIER_PUNCH);
+            }
+        }
+
+        let cooldown = self::attack_cooldown(this);
         this.with_mut_property("attack_timer", |t: &mut Cooldown| {
-            t.set_cooldown(capped_cd);
+            t.set_cooldown(cooldown);
             t.reset();
         });
 
         this.with_mut_property("smoothing", |s| *s = true);
+        self::lengthen(this);
+    }
+
+    // shorter with every power level and focus reward, down to the cap
+    fn attack_cooldown(this: &EntityView) -> Duration {
+        let power = this.get_property(self::SCORE) / self::POWER_LEVELUP;
+        let cdr = self::ATTACK_CDR_PER_POWER * power as _ + this.get_property::<Duration>("focus");
+        self::ATTACK_SPEED_CAP.max(config::get().attack_cooldown.saturating_sub(cdr))
+    }
+
+    /// a few more body segments, to reward clearing a room
+    pub fn growth(this: &mut EntityView) {
+        for _ in 0..self::GROWTH_LENGTH {
+            self::lengthen(this);
+        }
+    }
+
+    /// attacks cool down faster for the rest of the run
+    pub fn focus(this: &mut EntityView) {
+        this.with_mut_property("focus", |f: &mut Duration| *f += self::FOCUS_CDR);
+        let cooldown = self::attack_cooldown(this);
+        this.with_mut_property("attack_timer", |t: &mut Cooldown| t.set_cooldown(cooldown));
+    }
+
+    /// one more body segment, without counting towards the score
+    pub fn lengthen(this: &mut EntityView) {
         let mut len = this.get_body_length();
         if len == 0 {
             len += 1;
@@ -202,7 +603,21 @@ pub mod snake {
         this.set_body_length(len + 1);
     }
 
+    /// the next attack doesn't have to wait out the cooldown
+    pub fn ready_attack(this: &mut EntityView) {
+        this.with_mut_property("attack_timer", |t: &mut Cooldown| t.reset());
+    }
+
     pub fn head_tick(dt: Duration, snake: &mut EntityView) {
+        let recovering = snake.get::<component::StatusEffects>().remaining(Status::Recovering).as_secs_f32();
+        FLICKER.set(recovering > 0.0 && (recovering * self::IFRAME_BLINK).fract() < 0.5);
+
+        if self::is_dying(snake) {
+            self::death_tick(dt, snake);
+            return;
+        }
+        endless::snake_tick(dt, snake);
+
         if snake.has_property("enable_attack_trigger") {
             if snake.with_property("enable_attack_trigger", |t: &Receiver<()>| t.try_recv().is_ok()) {
                 snake.set_property("can_attack", true);
@@ -211,25 +626,51 @@ pub mod snake {
         }
 
         snake.with_mut_property("attack_timer", |t: &mut Cooldown| t.tick(dt));
+        snake.with_mut_property("powerup_glow", |t: &mut Cooldown| t.tick(dt));
 
-        if !snake.access_timer(|t| t.tick(dt)) {
-            return;
+        let kills = snake.with_property("kill_rx", |r: &Receiver<()>| r.try_iter().count());
+        for _ in 0..kills {
+            self::chain(snake);
+        }
+
+        let combo_over = snake.with_mut_property("combo_timer", |t: &mut Cooldown| {
+            t.tick(dt);
+            !t.is_cooling_down()
+        });
+        if combo_over {
+            snake.set_property("combo", 0u32);
         }
 
-        snake.set_animation(Animation::Idle);
+        let pace = snake.get::<component::StatusEffects>().pace();
+        TIER.set(Tier::of(snake.get_property(self::SCORE) / self::POWER_LEVELUP));
+        let terrain = snake.get_property::<Option<terrain::Kind>>("terrain");
+        let step = match terrain {
+            Some(terrain::Kind::Tar) => self::step() * self::TAR_SLOWDOWN,
+            _ => self::step(),
+        };
+        snake.access_timer(|t| t.set_threshold(step));
+
+        let stepped = snake.access_timer(|t| t.tick(dt.mul_f32(pace)));
+        STEP.set(snake.access_timer(|t| t.progress()));
+        if !stepped {
+            return;
+        }
+        snake.set_property("terrain", None::<terrain::Kind>);
 
         let pos = snake.get_position();
         let last_dir = snake.get_direction();
-        let len = snake.get_body_length();
         let mouse = (snake.get_mouse(), 0.0).into();
 
         let dir = loop {
             if let Some(k) = snake.get_key() {
                 use glfw::Key as K;
+                let mirror = tunables::get().mirror_controls;
                 let new_dir = match k {
                     K::W | K::Up => Direction::Up,
+                    K::A | K::Left if mirror => Direction::Right,
                     K::A | K::Left => Direction::Left,
                     K::S | K::Down => Direction::Down,
+                    K::D | K::Right if mirror => Direction::Left,
                     K::D | K::Right => Direction::Right,
                     // K::Q => {
                     //     snake.request_spawn(Box::new(move |man| {
@@ -248,32 +689,43 @@ pub mod snake {
                         if snake.with_property("attack_timer", |t: &Cooldown| t.is_cooling_down()) { continue; }
                         snake.with_mut_property("attack_timer", |t: &mut Cooldown| t.cool_down());
 
-                        if snake.has_property("attack_tx") {
-                            let _ = snake.with_property("attack_tx", |t: &Sender<()>| t.send(()));
-                        }
+                        snake.publish(EventKind::SnakeAttacked);
+
+                        let pos = pos + last_dir.into();
+                        let tier = Tier::of(snake.get_property(self::SCORE) / self::POWER_LEVELUP);
+                        let kill_tx = snake.with_property("kill_tx", |t: &Sender<()>| t.clone());
+                        snake.request_spawn(Box::new(move |man| {
+                            let attack = tier.attack(man, pos, last_dir, mouse);
+                            self::own_attack(man, attack, kill_tx);
+                        }));
+                        continue;
+                    }
+                    K::E => {
+                        // the ice attack unlocks with fireballs
+                        let power = snake.get_property(self::SCORE) / self::POWER_LEVELUP;
+                        if Tier::of(power) < Tier::Fireball { continue; }
+                        if snake.with_property("attack_timer", |t: &Cooldown| t.is_cooling_down()) { continue; }
+                        snake.with_mut_property("attack_timer", |t: &mut Cooldown| t.cool_down());
+
+                        snake.publish(EventKind::SnakeAttacked);
 
                         let pos = pos + last_dir.into();
-                        let power = snake.get_property::<i32>("score") / self::POWER_LEVELUP;
+                        let kill_tx = snake.with_property("kill_tx", |t: &Sender<()>| t.clone());
                         snake.request_spawn(Box::new(move |man| {
-                            match power {
-                                0 | 1 => super::swoop::weak_attack(man, pos, last_dir),
-                                2 => super::swoop::strong_attack(man, pos, last_dir),
-                                3 => super::fireball::weak_attack(man, pos, mouse),
-                                e if e >= 4 => super::fireball::strong_attack(man, pos, mouse),
-                                _ => panic!(),
-                            };
+                            let attack = super::fireball::ice_attack(man, pos, mouse);
+                            self::own_attack(man, attack, kill_tx);
                         }));
                         continue;
                     }
                     _ => continue,
                 };
+                // flipped every way around for as long as endless mode glitches the controls
+                let new_dir = if endless::inverted() { new_dir.reverse() } else { new_dir };
 
                 if new_dir != last_dir && new_dir != last_dir.reverse() {
                     snake.set_direction(new_dir);
                     snake.get_sound().play(Sounds::Move);
-                    if snake.has_property("move_tx") {
-                        let _ = snake.with_property("move_tx", |t: &Sender<()>| t.send(()));
-                    }
+                    snake.publish(EventKind::SnakeMoved);
                     break new_dir;
                 }
             }
@@ -281,14 +733,27 @@ pub mod snake {
             break last_dir;
         };
 
-        if len > 0 {
+        let id = snake.id();
+        snake.request_spawn(Box::new(move |man| {
+            self::follow(man, id, pos, vec![dir, last_dir.reverse()]);
+        }));
+
+        let new_pos = self::on_tile(pos + Vec3::from((dir.into(), 0.0)));
+        snake.set_position(new_pos);
+
+        // a belt keeps carrying the head after it steps off,
+        // with the body following along as if it had moved twice
+        if let Some(terrain::Kind::Belt(push)) = terrain {
             snake.request_spawn(Box::new(move |man| {
-                body(man, pos, vec![dir, last_dir.reverse()], len);
+                self::follow(man, id, new_pos, vec![push, dir.reverse()]);
             }));
+            snake.set_position(self::on_tile(new_pos + Vec3::from((push.into(), 0.0))));
         }
+    }
 
-        let new_pos = pos + Vec3::from((dir.into(), 0.0));
-        snake.set_position(new_pos);
+    // right on the corner of the tile it's on, so float error from stepping never adds up
+    fn on_tile(pos: Position) -> Position {
+        Position::from((Vec2::from(TileCoord::of(pos.into())), pos.z))
     }
 
     // fn draw_shield(
@@ -340,13 +805,76 @@ pub mod snake {
     //     }));
     // }
 
+    /// the score as shown, with the mutators' multiplier
+    pub fn score(this: &EntityView) -> i32 {
+        let score = this.get_property(self::SCORE);
+        // mutators only change the displayed score so power levels stay the same
+        (score as f32 * tunables::get().score_multiplier).round() as i32
+    }
+
+    fn draw_hud(entity: &EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let power = entity.get_property(self::SCORE) / self::POWER_LEVELUP;
+        let score = self::score(entity);
+        let combo = entity.get_property::<u32>("combo");
+
+        let line = |n: f32| Vec2::new(HUD_LEFT, HUD_TOP - n * HUD_LINE_HEIGHT);
+        renderer.push(HudText::new(&score.to_string(), line(0.0), HUD_GLYPH_SIZE, palette.get(PaletteKey::Ui)));
+        renderer.push(HudText::new(&format!("P{power}"), line(1.0), HUD_GLYPH_SIZE, palette.get(PaletteKey::Snake)));
+        if combo > 1 {
+            renderer.push(HudText::new(&format!("X{combo}"), line(2.0), HUD_GLYPH_SIZE, palette.get(PaletteKey::Fruit)));
+        }
+
+        let effects = entity.get::<component::StatusEffects>();
+        let active = Status::ALL.into_iter().filter(|&s| effects.has(s));
+        for (i, status) in active.enumerate() {
+            let secs = effects.remaining(status).as_secs_f32().ceil();
+            let text = format!("{} {secs}", status.icon());
+            renderer.push(HudText::new(&text, line(3.0 + i as f32), HUD_GLYPH_SIZE, status.color()));
+        }
+    }
+
+    // fills up under the head until the next attack is ready
+    fn draw_cooldown(entity: &EntityView, head: Vec3, renderer: &mut RenderManager, palette: Palette) {
+        let (cooling, pct) = entity.with_property("attack_timer", |t: &Cooldown| (t.is_cooling_down(), t.progress()));
+        if !cooling {
+            return;
+        }
+
+        let left = 0.5 * (1.0 - self::COOLDOWN_BAR.x);
+        let pos = head + Vec3::new(left, -self::COOLDOWN_BAR_GAP, 0.0);
+        let size = Vec2::new(pct * self::COOLDOWN_BAR.x, self::COOLDOWN_BAR.y);
+        renderer.push(Tile {
+            transform: Mat4::translate(pos) * Mat4::scale(size),
+            col: palette.get(PaletteKey::Ui),
+        });
+        let track = Vec3::new(0.0, 0.0, self::COOLDOWN_TRACK_DEPTH);
+        renderer.push(Tile {
+            transform: Mat4::translate(pos + track) * Mat4::scale(self::COOLDOWN_BAR),
+            col: palette.get(PaletteKey::UiBackground),
+        });
+    }
+
+    // the body takes on the head's colors, and blinks out along with it
+    fn body_color(palette: Palette) -> Vec3 {
+        if FLICKER.get() {
+            palette.get(PaletteKey::Background)
+        } else {
+            TIER.get().color(palette)
+        }
+    }
+
     pub fn draw(mut entity: EntityView, renderer: &mut RenderManager, palette: Palette) {
-        let mut pos = entity.get_position();
+        let pos = entity.get_position();
 
         if entity.which() == Entities::SnakeHead {
+            self::draw_hud(&entity, renderer, palette);
+
+            let glow = entity.with_property("powerup_glow", |t: &Cooldown| 1.0 - t.progress());
+            renderer.set_bloom(post::BLOOM_INTENSITY + self::POWERUP_BLOOM * glow * glow);
+
             let pct = entity.access_timer(|t| t.progress());
 
-            let smoothing = entity.with_property("smoothing", |&s| s);
+            let smoothing = tunables::get().smoothing && entity.with_property("smoothing", |&s| s);
             let delta = if smoothing {
                 (pct - 1.0) * Vec3::from(entity.get_direction())
             } else {
@@ -354,10 +882,32 @@ pub mod snake {
             };
 
             let pd = pos + delta;
+            let effects = entity.get::<component::StatusEffects>();
+            let tier = Tier::of(entity.get_property(self::SCORE) / self::POWER_LEVELUP);
+            let mut col = lerp(tier.color(palette), palette.get(PaletteKey::White), self::TIER_FLASH * glow * glow);
+            if let Ok(death) = entity.try_with_property(self::DEATH, |d| d.as_secs_f32()) {
+                if (death * self::DEATH_BLINK).fract() < 0.5 {
+                    col = palette.get(PaletteKey::White);
+                }
+            }
             renderer.push(Tile {
                 transform: Mat4::translate(pd),
-                col: palette.snake,
+                col: if FLICKER.get() { palette.get(PaletteKey::Background) } else { effects.tint(col) },
             });
+            renderer.push(Trail {
+                id: entity.id(),
+                pos: pd.into(),
+                width: self::TRAIL_WIDTH,
+                col: Vec4::from((effects.tint(col), self::TRAIL_ALPHA)),
+                length: self::TRAIL_LENGTH,
+            });
+
+            if effects.has(Status::Shield) {
+                let col = Vec4::from((Status::Shield.color(), self::SHIELD_ALPHA));
+                renderer.push(Shield::new(pd.into(), col, false, 0.4).push_quad());
+            }
+
+            self::draw_cooldown(&entity, pd, renderer, palette);
 
             // let shield = Shield::new(pd.into(), palette.snake, 0.4)
             //     .push_side(facing.into())
@@ -377,15 +927,15 @@ pub mod snake {
             };
 
             // draw_shield(pd, &neighbors, renderer, palette);
-        } else if entity.get_self_destruct() == 1 {
+        } else if entity.get_property::<bool>("tail") {
             // tail
-            let pct = entity.access_timer(|t| t.progress());
+            let pct = STEP.get();
             let direction = entity.with_property("neighbors", |n: &Vec<Direction>| n[0]);
             let delta = Vec3::from((pct * Vec2::from(direction), 0.0));
             let pd = pos + delta;
             renderer.push(Tile {
                 transform: Mat4::translate(pd),
-                col: palette.snake,
+                col: self::body_color(palette),
             });
 
             // draw_shield(pd, &[direction], renderer, palette);
@@ -399,7 +949,7 @@ pub mod snake {
             // body
             renderer.push(Tile {
                 transform: Mat4::translate(pos),
-                col: palette.snake,
+                col: self::body_color(palette),
             });
             // renderer.push(
             //     Shield::new(pos.into(), palette.snake, 0.4)
@@ -409,26 +959,33 @@ pub mod snake {
             entity.with_property("neighbors", |neighbors: &Vec<Direction>| {
                 // draw_shield(pos, neighbors, renderer, palette);
             });
-            pos.z = -0.1 * entity.get_self_destruct() as f32;
         }
     }
 }
 
 pub mod fruit {
-    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::time::Duration;
 
-    use rand::{thread_rng, Rng};
+    use rand::Rng;
 
     use crate::{
+        animation::{Animation, Track},
         entity::{Components, Entities, EntityId, EntityManager, EntityView},
-        math::{Mat4, Vec2, Vec3, Vec4},
-        palette::Palette,
-        render::{instanced::Tile, RenderManager},
+        event::EventKind,
+        math::{ease, Mat4, Vec2, Vec3, Vec4},
+        palette::{Palette, PaletteKey},
+        render::{instanced::Tile, particles::Burst, RenderManager},
+        rng::{self, Stream},
         sound::Sounds,
+        status::Status,
     };
 
+    // one in this many fruits hands out a boost
+    const BOOST_ODDS: u32 = 6;
+    const BOOSTS: [Status; 2] = [Status::Haste, Status::Shield];
+
     pub fn new(man: &mut EntityManager) -> EntityId {
-        let mut rng = thread_rng();
+        let mut rng = rng::get(Stream::World);
         let x = rng.gen_range(-10..10) as f32;
         let y = rng.gen_range(-10..10) as f32;
 
@@ -443,30 +1000,20 @@ pub mod fruit {
                 Components::Collider,
                 Components::Spawner,
                 Components::Sound,
+                Components::Particles,
+                Components::Animation,
                 Components::Properties,
             ],
         );
 
         let mut fruit = man.view(id).unwrap();
         fruit.set_position(Vec3::new(pos.x, pos.y, 0.0));
+        fruit.set_animation(self::pulse());
+        fruit.new_property("boost", None::<Status>);
 
         id
     }
 
-    pub fn make_eaten_trigger(man: &mut EntityManager, id: EntityId) -> Receiver<()> {
-        let this = man.view(id).unwrap();
-        let (tx, rx) = mpsc::channel();
-        this.new_property("eat_tx", tx);
-        rx
-    }
-
-    pub fn make_kill_trigger(man: &mut EntityManager, id: EntityId) -> Receiver<()> {
-        let this = man.view(id).unwrap();
-        let (tx, rx) = mpsc::channel();
-        this.new_property("kill_tx", tx);
-        rx
-    }
-
     /// put a fruit at x,y
     /// -1 means unlimited respawns
     /// pos is the center of the bounds
@@ -484,23 +1031,61 @@ pub mod fruit {
         id
     }
 
-    pub fn draw(entity: EntityView, renderer: &mut RenderManager, palette: Palette) {
-        let pos = entity.get_position();
+    const PULSE_LENGTH: Duration = Duration::from_millis(900);
+    const PULSE_SCALE: f32 = 1.15;
+    const PULSE_FLASH: f32 = 0.25;
+
+    // swells and brightens, then settles back
+    fn pulse() -> Animation {
+        let half = self::PULSE_LENGTH / 2;
+        Animation::default()
+            .scale(
+                Track::default()
+                    .key(Duration::ZERO, Vec2::diagonal(1.0), ease::linear)
+                    .key(half, Vec2::diagonal(self::PULSE_SCALE), ease::out_quad)
+                    .key(self::PULSE_LENGTH, Vec2::diagonal(1.0), ease::in_out_quad),
+            )
+            .flash(
+                Track::default()
+                    .key(Duration::ZERO, 0.0, ease::linear)
+                    .key(half, self::PULSE_FLASH, ease::out_quad)
+                    .key(self::PULSE_LENGTH, 0.0, ease::in_out_quad),
+            )
+            .looping()
+    }
 
+    pub fn draw(entity: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let pose = entity.get_pose();
+        // grow around the center of the tile
+        let shrink = 0.5 * (Vec2::diagonal(1.0) - pose.scale);
+        let pos = entity.get_position() + pose.offset + Vec3::from((shrink, 0.0));
+
+        let col = match self::boost(&entity) {
+            Some(status) => status.tint(palette.get(PaletteKey::Fruit)),
+            None => palette.get(PaletteKey::Fruit),
+        };
         renderer.push(Tile {
-            transform: Mat4::translate(pos),
-            col: palette.fruit,
+            transform: Mat4::translate(pos) * Mat4::scale(pose.scale),
+            col: pose.tint(col),
         });
+        super::marker::fruit(renderer, pos, pose.scale, palette);
+    }
+
+    /// what eating the fruit does on top of growing
+    pub fn boost(fruit: &EntityView) -> Option<Status> {
+        fruit.get_property("boost")
     }
 
     pub fn respawn(fruit: &mut EntityView) {
+        fruit
+            .get_particles()
+            .emit(Burst::sparkle(fruit.get_position().into()));
+
         let pos = if fruit.has_property("respawns") {
             let respawns = fruit.with_property("respawns", |&r: &i32| r);
             if respawns == 0 {
                 fruit.kill();
-                if fruit.has_property("kill_tx") {
-                    let _ = fruit.with_property("kill_tx", |tx: &Sender<()>| tx.send(()));
-                }
+                fruit.publish(EventKind::FruitDepleted);
                 return;
             } else {
                 fruit.with_mut_property("respawns", |r: &mut i32| *r -= 1);
@@ -518,48 +1103,526 @@ pub mod fruit {
             Vec2::default()
         };
 
-        fruit.get_sound().play(Sounds::Eat);
-        if fruit.has_property("eat_tx") {
-            let _ = fruit.with_property("eat_tx", |tx: &Sender<()>| tx.send(()));
-        }
+        fruit.get_sound().play_at(Sounds::Eat, fruit.get_position().into());
+        fruit.publish(EventKind::FruitEaten);
+
+        let mut rng = rng::get(Stream::World);
+        let boost = rng
+            .gen_ratio(1, self::BOOST_ODDS)
+            .then(|| self::BOOSTS[rng.gen_range(0..self::BOOSTS.len())]);
+        fruit.set_property("boost", boost);
 
         fruit.set_position((pos, 0.0).into());
     }
 }
 
-pub mod fireball {
-    use std::time::Duration;
+pub mod pickup {
+    use rand::Rng;
 
     use crate::{
-        entity::{
-            Color, Components, Direction, Entities, EntityId, EntityManager, EntityView, Position, Speed,
-        },
-        math::{ease, Vec3, Vec4},
+        entity::{Budget, Components, Entities, EntityId, EntityManager, EntityView},
+        event::EventKind,
+        math::{Mat4, Vec2, Vec3, Vec4},
         palette::{Palette, PaletteKey},
-        render::{fireball::Fireball, RenderManager},
+        render::{instanced::Tile, particles::Burst, shield::Shield, RenderManager},
         sound::Sounds,
+        status::Status,
     };
 
-    fn new(
-        man: &mut EntityManager,
-        color: Color,
-        radius: f32,
-        position: Position,
-        target: Position,
-        speed: Speed,
-    ) -> EntityId {
-        let id = man.spawn(
+    // enemies this close to a bomb take a hit
+    const BOMB_RADIUS: f32 = 6.0;
+    const BOMB_TRAUMA: f32 = 0.6;
+    const CORE_SCALE: f32 = 0.5;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Pickup {
+        /// one more body segment
+        Segment,
+        /// the next attack is ready right away
+        Cooldown,
+        /// a while of not getting hurt
+        Shield,
+        /// clears every projectile and hits the enemies around it
+        Bomb,
+        /// a few more body segments, only handed out for clearing a room
+        Growth,
+        /// faster attacks for the rest of the run, only handed out for clearing a room
+        Focus,
+    }
+
+    impl Pickup {
+        // relative odds, the stronger the rarer
+        const WEIGHTS: [(Pickup, u32); 4] = [
+            (Pickup::Segment, 6),
+            (Pickup::Cooldown, 4),
+            (Pickup::Shield, 2),
+            (Pickup::Bomb, 1),
+        ];
+
+        pub fn roll(rng: &mut impl Rng) -> Self {
+            let total = Self::WEIGHTS.iter().map(|&(_, w)| w).sum();
+            let mut roll = rng.gen_range(0..total);
+            for (pickup, weight) in Self::WEIGHTS {
+                if roll < weight {
+                    return pickup;
+                }
+                roll -= weight;
+            }
+            unreachable!()
+        }
+
+        fn color(self) -> PaletteKey {
+            match self {
+                Self::Segment => PaletteKey::Snake,
+                Self::Cooldown => PaletteKey::Fruit,
+                Self::Shield => PaletteKey::Wall,
+                Self::Bomb => PaletteKey::Enemy,
+                Self::Growth => PaletteKey::Snake,
+                Self::Focus => PaletteKey::Fruit,
+            }
+        }
+    }
+
+    pub fn new(man: &mut EntityManager, kind: Pickup, position: Vec2) -> EntityId {
+        let id = man.spawn(Entities::Pickup, &[
+            Components::Position,
+            Components::Collider,
+            Components::Color,
+            Components::Spawner,
+            Components::Sound,
+            Components::Particles,
+            Components::Camera,
+            Components::Properties,
+        ]);
+
+        let mut pickup = man.view(id).unwrap();
+        pickup.set_position((position, 0.0).into());
+        pickup.set_color(kind.color());
+        pickup.new_property("kind", kind);
+
+        id
+    }
+
+    /// the snake's head just ran into the pickup
+    pub fn collect(this: &mut EntityView, head: &mut EntityView) {
+        match this.get_property::<Pickup>("kind") {
+            Pickup::Segment => super::snake::lengthen(head),
+            Pickup::Cooldown => super::snake::ready_attack(head),
+            Pickup::Shield => super::snake::boost(head, Status::Shield),
+            Pickup::Bomb => {
+                let center = Vec2::from(this.get_position());
+                this.get_camera().shake(self::BOMB_TRAUMA);
+                this.request_spawn(Box::new(move |man| self::detonate(man, center)));
+            }
+            Pickup::Growth => super::snake::growth(head),
+            Pickup::Focus => super::snake::focus(head),
+        }
+
+        this.publish(EventKind::PickupCollected);
+        this.get_particles()
+            .emit(Burst::sparkle(this.get_position().into()));
+        this.get_sound().play(Sounds::ShieldUp);
+        this.kill();
+    }
+
+    fn detonate(man: &mut EntityManager, center: Vec2) {
+        let mut projectiles = Vec::new();
+        let mut enemies = Vec::new();
+        for e in man.query(&[Components::Position]) {
+            if e.which().budget() == Some(Budget::Projectile) {
+                projectiles.push(e.id());
+            } else if e.which() == Entities::Enemy
+                && Vec2::distance(e.get_position().into(), center) < self::BOMB_RADIUS
+            {
+                enemies.push(e.id());
+            }
+        }
+
+        for id in projectiles {
+            man.kill(id);
+        }
+        for id in enemies {
+            if let Some(mut enemy) = man.view(id) {
+                super::enemy::hit(&mut enemy);
+            }
+        }
+    }
+
+    pub fn draw(this: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let pos = this.get_position();
+        let col = palette.get(this.get_color());
+
+        // a small core inside a ring, so pickups don't pass for fruit
+        let inset = Vec3::from((Vec2::diagonal(0.5 * (1.0 - self::CORE_SCALE)), 0.0));
+        renderer.push(Tile {
+            transform: Mat4::translate(pos + inset) * Mat4::scale(Vec2::diagonal(self::CORE_SCALE)),
+            col,
+        });
+        renderer.push(Shield::new(pos.into(), Vec4::from((col, 1.0)), false, 0.4).push_quad());
+    }
+}
+
+pub mod hazard {
+    use crate::{
+        entity::{component, Components, Entities, EntityId, EntityManager, EntityView, Kinematics},
+        math::{lerp, Vec2, Vec3, Vec4},
+        palette::{Palette, PaletteKey},
+        render::{fireball::Fireball, RenderManager},
+    };
+
+    const RADIUS: f32 = 0.4;
+    // how far trimmers are pulled towards white, to tell them apart
+    const TRIMMER_TINT: f32 = 0.5;
+
+    /// sweeps back and forth between two points, deadly to the touch
+    pub fn new(man: &mut EntityManager, from: Vec2, to: Vec2, speed: f32) -> EntityId {
+        let id = man.spawn(
+            Entities::Hazard,
+            &[
+                Components::Position,
+                Components::Collider,
+                Components::Kinematics,
+                Components::Properties,
+            ],
+        );
+
+        let mut this = man.view(id).unwrap();
+        this.set_position(Vec3::from((from, 0.0)));
+        this.set::<component::Kinematics>(Kinematics::cruising(speed * (to - from).normalize()));
+        this.new_property("from", from);
+        this.new_property("to", to);
+        this.new_property("trims", false);
+
+        id
+    }
+
+    /// a hazard that also cuts the snake's body off wherever it sweeps through it
+    pub fn trimmer(man: &mut EntityManager, from: Vec2, to: Vec2, speed: f32) -> EntityId {
+        let id = self::new(man, from, to, speed);
+        man.view(id).unwrap().set_property("trims", true);
+        id
+    }
+
+    pub fn trims(this: &EntityView) -> bool {
+        this.get_property("trims")
+    }
+
+    // turns around once it's gone past whichever end it was heading for
+    pub fn tick(this: &mut EntityView) {
+        let pos = Vec2::from(this.get_position());
+        let from = this.get_property::<Vec2>("from");
+        let to = this.get_property::<Vec2>("to");
+
+        let kinematics = this.get::<component::Kinematics>();
+        let forward = Vec2::dot(kinematics.velocity, to - from) > 0.0;
+        let end = if forward { to } else { from };
+        if Vec2::dot(kinematics.velocity, end - pos) < 0.0 {
+            this.with_mut::<component::Kinematics, _>(|k| k.velocity = -k.velocity);
+        }
+    }
+
+    pub fn draw(this: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let col = if self::trims(&this) {
+            lerp(palette.get(PaletteKey::Hazard), palette.get(PaletteKey::White), self::TRIMMER_TINT)
+        } else {
+            palette.get(PaletteKey::Hazard)
+        };
+        renderer.push(Fireball {
+            pos: this.get_position().into(),
+            col: Vec4::from((col, 1.0)),
+            radius: self::RADIUS,
+        })
+    }
+}
+
+pub mod terrain {
+    use crate::{
+        entity::{Components, Direction, Entities, EntityId, EntityManager, EntityView, Position},
+        math::{lerp, Mat4, Vec2, Vec3},
+        palette::{Palette, PaletteKey},
+        render::{instanced::Tile, RenderManager},
+    };
+
+    // spikes only take up the middle of their tile
+    const SPIKES_SIZE: f32 = 0.6;
+    // how much of a belt the stripe on its far edge takes up
+    const BELT_STRIPE: f32 = 0.2;
+    const BELT_STRIPE_TINT: f32 = 0.4;
+
+    /// floor tiles that do something to the snake when its head steps on them
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Kind {
+        /// hurts like anything else would
+        Spikes,
+        /// kills, shield or not
+        Lava,
+        /// carries the snake one more tile along every step
+        Belt(Direction),
+        /// takes twice as long to get off of
+        Tar,
+    }
+
+    pub fn new(man: &mut EntityManager, kind: Kind, position: Position) -> EntityId {
+        let id = man.spawn(
+            Entities::Terrain,
+            &[
+                Components::Position,
+                Components::Collider,
+                Components::Properties,
+            ],
+        );
+
+        let mut this = man.view(id).unwrap();
+        this.set_position(position);
+        this.new_property("kind", kind);
+
+        id
+    }
+
+    pub fn kind(this: &EntityView) -> Kind {
+        this.get_property("kind")
+    }
+
+    pub fn step_on(this: &EntityView, head: &mut EntityView) {
+        match self::kind(this) {
+            Kind::Spikes => super::snake::hurt(head),
+            Kind::Lava => super::snake::die_sequence(head),
+            kind => super::snake::stand_on(head, kind),
+        }
+    }
+
+    pub fn draw(this: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let pos = this.get_position();
+        let full = |col| Tile {
+            transform: Mat4::translate(pos),
+            col,
+        };
+
+        match self::kind(&this) {
+            Kind::Spikes => {
+                let inset = 0.5 * (1.0 - self::SPIKES_SIZE);
+                renderer.push(Tile {
+                    transform: Mat4::translate(pos + Vec3::new(inset, inset, 0.0))
+                        * Mat4::scale(Vec2::diagonal(self::SPIKES_SIZE)),
+                    col: palette.get(PaletteKey::Spikes),
+                });
+            }
+            Kind::Lava => renderer.push(full(palette.get(PaletteKey::Lava))),
+            Kind::Tar => renderer.push(full(palette.get(PaletteKey::Tar))),
+            Kind::Belt(dir) => {
+                renderer.push(full(palette.get(PaletteKey::Belt)));
+
+                // thin across the way it carries, on the edge it carries towards
+                let dir = Vec2::from(dir);
+                let along = Vec2::new(dir.x.abs(), dir.y.abs());
+                let size = Vec2::diagonal(1.0) - (1.0 - self::BELT_STRIPE) * along;
+                let offset = (1.0 - self::BELT_STRIPE) * dir.max(Vec2::default());
+                renderer.push(Tile {
+                    transform: Mat4::translate(pos + Vec3::from((offset, 0.0))) * Mat4::scale(size),
+                    col: lerp(palette.get(PaletteKey::Belt), palette.get(PaletteKey::White), self::BELT_STRIPE_TINT),
+                });
+            }
+        }
+    }
+}
+
+pub mod obstacle {
+    use std::{iter, time::Duration};
+
+    use crate::{
+        entity::{Components, Entities, EntityId, EntityManager, EntityView, Position},
+        math::{lerp, Mat4, Vec2, Vec3},
+        palette::{Palette, PaletteKey},
+        render::{instanced::Tile, RenderManager},
+    };
+
+    /// a wall tile that walks along a path, one tile every step
+    /// a tile listed more than once in a row is a wait,
+    /// and the path starts over once it runs out
+    pub fn new(man: &mut EntityManager, path: Vec<Vec2>, depth: f32, step: Duration) -> EntityId {
+        let id = man.spawn(
+            Entities::Obstacle,
+            &[
+                Components::Position,
+                Components::Collider,
+                Components::Timer,
+                Components::Properties,
+            ],
+        );
+
+        let mut this = man.view(id).unwrap();
+        this.set_position(Position::from((path[0], depth)));
+        this.access_timer(|t| t.set_threshold(step));
+        this.new_property("from", path[0]);
+        this.new_property("at", 0usize);
+        this.new_property("path", path);
+
+        id
+    }
+
+    /// there and back again in a straight line, waiting at either end
+    /// from and to have to be lined up on a row or column
+    pub fn shuttle(from: Vec2, to: Vec2, wait: usize) -> Vec<Vec2> {
+        let d = to - from;
+        let steps = d.x.abs().max(d.y.abs()) as usize;
+        // whole tiles, so it never ends up between two of them
+        let dir = d / steps as f32;
+        let line: Vec<_> = (0..=steps).map(|i| from + i as f32 * dir).collect();
+
+        iter::repeat_n(from, wait)
+            .chain(line[1..].iter().copied())
+            .chain(iter::repeat_n(to, wait))
+            .chain(line[1..steps].iter().rev().copied())
+            .collect()
+    }
+
+    pub fn tick(dt: Duration, this: &mut EntityView) {
+        if !this.access_timer(|t| t.tick(dt)) {
+            return;
+        }
+
+        let pos = this.get_position();
+        let (at, next) = this.with_property("path", |path: &Vec<Vec2>| {
+            let at = (this.get_property::<usize>("at") + 1) % path.len();
+            (at, path[at])
+        });
+        this.set_property("at", at);
+        this.set_property("from", Vec2::from(pos));
+
+        // waiting in place doesn't run into anything new
+        if next != pos.into() {
+            this.set_position(Position::from((next, pos.z)));
+        }
+    }
+
+    /// slides over from its last tile, the same way the snake does
+    pub fn draw(mut this: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let pct = this.access_timer(|t| t.progress());
+        let pos = this.get_position();
+        let from = this.get_property::<Vec2>("from");
+        let drawn = lerp(from, pos.into(), pct);
+
+        renderer.push(Tile {
+            transform: Mat4::translate(Vec3::from((drawn, pos.z))),
+            col: palette.get(PaletteKey::Wall),
+        });
+    }
+}
+
+pub mod portal {
+    use crate::{
+        entity::{Components, Direction, Entities, EntityId, EntityManager, EntityView, Position},
+        math::{Mat4, Vec2, Vec3},
+        palette::{Palette, PaletteKey},
+        render::{instanced::Tile, RenderManager},
+        sound::Sounds,
+    };
+
+    const PORTAL_GLITCH: f32 = 0.4;
+    // the hole in the middle of the ring
+    const HOLE_SIZE: f32 = 0.5;
+
+    /// only takes the snake anywhere once it's linked
+    /// facing is the way the head has to be going to go through, any way if it's None
+    pub fn new(man: &mut EntityManager, position: Position, facing: Option<Direction>) -> EntityId {
+        let id = man.spawn(
+            Entities::Portal,
+            &[
+                Components::Position,
+                Components::Collider,
+                Components::Properties,
+                Components::Sound,
+                Components::Glitch,
+            ],
+        );
+
+        let mut this = man.view(id).unwrap();
+        this.set_position(position);
+        this.new_property("facing", facing);
+        this.new_property("exit", None::<Vec2>);
+
+        id
+    }
+
+    /// going through either one comes out of the other
+    pub fn link(man: &mut EntityManager, a: EntityId, b: EntityId) {
+        let a = man.view(a).unwrap();
+        let b = man.view(b).unwrap();
+        a.set_property("exit", Some(Vec2::from(b.get_position())));
+        b.set_property("exit", Some(Vec2::from(a.get_position())));
+    }
+
+    /// the head comes out the tile past the other end, still going the same way
+    /// the body follows it through on its own, since it walks wherever the head's been
+    pub fn enter(this: &mut EntityView, head: &mut EntityView) {
+        let dir = head.get_direction();
+        if this.get_property::<Option<Direction>>("facing").is_some_and(|f| f != dir) {
+            return;
+        }
+        let Some(exit) = this.get_property::<Option<Vec2>>("exit") else {
+            return;
+        };
+
+        let pos = head.get_position();
+        head.set_position(Position::from((exit + dir.into(), pos.z)));
+
+        this.get_sound().play_at(Sounds::glitch(), pos.into());
+        this.get_glitch().glitch(self::PORTAL_GLITCH);
+    }
+
+    pub fn draw(this: EntityView, renderer: &mut RenderManager, palette: Palette) {
+        let pos = this.get_position();
+        renderer.push(Tile {
+            transform: Mat4::translate(pos),
+            col: palette.get(PaletteKey::Fruit),
+        });
+
+        let inset = 0.5 * (1.0 - self::HOLE_SIZE);
+        renderer.push(Tile {
+            transform: Mat4::translate(pos + Vec3::new(inset, inset, -0.01)) * Mat4::scale(Vec2::diagonal(self::HOLE_SIZE)),
+            col: palette.get(PaletteKey::Background),
+        });
+    }
+}
+
+pub mod fireball {
+    use std::time::Duration;
+
+    use crate::{
+        entity::{
+            component, Color, Components, Direction, Entities, EntityId, EntityManager, EntityView,
+            FadeIn, Kinematics, Light, Position,
+        },
+        math::{Vec2, Vec4},
+        palette::{Palette, PaletteKey},
+        render::{fireball::Fireball, particles::Burst, trail::Trail, RenderManager},
+        sound::Sounds,
+        status::Status,
+    };
+
+    fn new(
+        man: &mut EntityManager,
+        color: Color,
+        radius: f32,
+        position: Position,
+        target: Position,
+        speed: f32,
+    ) -> EntityId {
+        let id = man.spawn(
             Entities::Fireball,
             &[
                 Components::Position,
                 Components::Collider,
                 Components::Direction,
-                Components::Speed,
+                Components::Kinematics,
                 Components::Scale,
                 Components::Color,
                 Components::Sound,
-                Components::Timer,
+                Components::FadeIn,
+                Components::Particles,
+                Components::Camera,
                 Components::Properties,
+                Components::Light,
             ],
         );
 
@@ -567,56 +1630,129 @@ pub mod fireball {
         let mut fireball = man.view(id).unwrap();
         fireball.set_position(position);
         fireball.set_direction(Direction::Raw(direction.into()));
-        fireball.set_speed(speed);
+        fireball.set::<component::Kinematics>(Kinematics::cruising(speed * Vec2::from(direction)));
         fireball.set_scale(radius.into());
         fireball.set_color(color);
-        fireball.get_sound().play(Sounds::Fireball);
-        fireball.access_timer(|t| t.set_threshold(self::RAMP_TIME));
-        fireball.new_property("alpha", 0.0f32);
-        fireball.new_property("is_ramping", true);
+        fireball.set::<component::Light>(Light::new(color, self::LIGHT_RADIUS, self::LIGHT_INTENSITY));
+        fireball.get_sound().play_at(Sounds::Fireball, position.into());
+        fireball.set::<component::FadeIn>(FadeIn::new(self::RAMP_TIME));
+        fireball.new_property("inflicts", None::<Status>);
+        fireball.new_property("strong", false);
+        fireball.new_property("piercing", false);
+        fireball.new_property("pierced", Vec::<EntityId>::new());
 
         id
     }
 
     const RAMP_TIME: Duration = Duration::from_millis(200);
-    const PLAYER_SPEED: Speed = 10.0;
+    // how far around itself a fireball lights up dark rooms, in tiles
+    const LIGHT_RADIUS: f32 = 2.5;
+    const LIGHT_INTENSITY: f32 = 0.35;
+    // comet tail, as wide as this many times the radius where it leaves the fireball
+    const TRAIL_WIDTH: f32 = 1.5;
+    const TRAIL_ALPHA: f32 = 0.5;
+    const TRAIL_LENGTH: Duration = Duration::from_millis(300);
+    const PLAYER_SPEED: f32 = 10.0;
     const PLAYER_RADIUS: f32 = 0.45;
     const STRONG: f32 = 1.75;
+    const STRONG_PUNCH: f32 = 0.04;
+    const ICE_LENGTH: Duration = Duration::from_millis(3000);
+    const IMPACT_TRAUMA: f32 = 0.2;
 
     pub fn weak_attack(man: &mut EntityManager, position: Position, mouse_position: Position) -> EntityId {
-        self::new(man, PaletteKey::Snake, self::PLAYER_RADIUS, position, mouse_position, self::PLAYER_SPEED)
+        self::new(man, PaletteKey::Projectile, self::PLAYER_RADIUS, position, mouse_position, self::PLAYER_SPEED)
     }
 
     pub fn strong_attack(man: &mut EntityManager, position: Position, mouse_position: Position) -> EntityId {
-        self::new(man, PaletteKey::Snake, self::STRONG * self::PLAYER_RADIUS, position, mouse_position, self::STRONG * self::PLAYER_SPEED)
+        let id = self::new(man, PaletteKey::Projectile, self::STRONG * self::PLAYER_RADIUS, position, mouse_position, self::STRONG * self::PLAYER_SPEED);
+        let fireball = man.view(id).unwrap();
+        fireball.get_camera().punch(self::STRONG_PUNCH);
+        fireball.set_property("strong", true);
+        id
     }
 
-    pub fn tick(dt: Duration, this: &mut EntityView) {
-        if this.access_timer(|t| t.tick(dt)) {
-            this.set_property("is_ramping", false);
-        }
+    /// goes through every enemy in its way
+    pub fn piercing_attack(man: &mut EntityManager, position: Position, mouse_position: Position) -> EntityId {
+        let id = self::strong_attack(man, position, mouse_position);
+        man.view(id).unwrap().set_property("piercing", true);
+        id
+    }
 
-        let pct = if this.get_property("is_ramping") {
-            this.access_timer(|t| t.progress())
+    /// slows down whatever it hits
+    pub fn ice_attack(man: &mut EntityManager, position: Position, mouse_position: Position) -> EntityId {
+        let id = self::weak_attack(man, position, mouse_position);
+        man.view(id).unwrap().set_property("inflicts", Some(Status::Slow));
+        id
+    }
+
+    /// whether the enemy takes the hit
+    /// piercing fireballs keep going, but only hit each enemy once
+    pub fn hit_enemy(this: &mut EntityView, enemy: &mut EntityView) -> bool {
+        if !this.get_property::<bool>("piercing") {
+            this.kill();
         } else {
-            1.0
-        };
+            let id = enemy.id();
+            let first = this.with_mut_property("pierced", |p: &mut Vec<EntityId>| {
+                let first = !p.contains(&id);
+                if first {
+                    p.push(id);
+                }
+                first
+            });
+            if !first {
+                return false;
+            }
+        }
+
+        self::inflict(this, enemy);
+        true
+    }
 
-        let alpha = ease::in_expo(pct);
-        this.set_property("alpha", alpha);
+    /// called on the enemy the fireball just hit
+    fn inflict(this: &EntityView, enemy: &mut EntityView) {
+        if let Some(status) = this.get_property::<Option<Status>>("inflicts") {
+            enemy.with_mut::<component::StatusEffects, _>(|s| s.apply(status, self::ICE_LENGTH));
+        }
+    }
 
-        let pos = this.get_position();
-        let dpos = dt.as_secs_f32() * this.get_speed() * Vec3::from(this.get_direction());
-        this.set_position(pos + dpos);
+    /// kicks up dust back towards where the fireball came from
+    /// strong fireballs chip away at cracked walls
+    pub fn hit_wall(this: &mut EntityView, wall: &mut EntityView) {
+        let normal = -Vec2::from(this.get_direction());
+        this.get_particles()
+            .emit(Burst::dust(this.get_position().into(), normal));
+        this.get_sound().play_at(Sounds::CrtClick, this.get_position().into());
+
+        if this.get_property("strong") {
+            this.get_camera().shake(self::IMPACT_TRAUMA);
+            if super::wall::is_cracked(wall) {
+                super::wall::hit(wall);
+            }
+        }
+
+        this.kill();
     }
 
     pub fn draw(this: EntityView, renderer: &mut RenderManager, palette: Palette) {
-        let alpha = this.get_property("alpha");
-        let col = Vec4::from((palette.get(this.get_color()), alpha));
+        let alpha = this.get::<component::FadeIn>().alpha();
+        let col = palette.get(this.get_color());
+        let col = match this.get_property::<Option<Status>>("inflicts") {
+            Some(status) => status.tint(col),
+            None => col,
+        };
+        let col = Vec4::from((col, alpha));
+        let radius = this.get_scale().x;
+        renderer.push(Trail {
+            id: this.id(),
+            pos: this.get_position().into(),
+            width: self::TRAIL_WIDTH * radius,
+            col: Vec4 { w: self::TRAIL_ALPHA * alpha, ..col },
+            length: self::TRAIL_LENGTH,
+        });
         renderer.push(Fireball {
             pos: this.get_position().into(),
             col,
-            radius: this.get_scale().x,
+            radius,
         })
     }
 }
@@ -670,17 +1806,18 @@ pub mod swoop {
     use std::time::Duration;
 
     use crate::{
-        entity::{Components, Direction, Entities, EntityId, EntityManager, EntityView},
-        math::{ease, Vec3},
+        animation::{Animation, Track},
+        config,
+        entity::{component, Components, Direction, Entities, EntityId, EntityManager, EntityView, Kinematics, Lifetime},
+        math::{ease, Rect, Vec2, Vec3},
         render::{self, RenderManager},
         sound::Sounds,
     };
 
-    // speed in tiles per second
-    const SWOOP_SPEED: f32 = 12.0;
     const SWOOP_LIFETIME: Duration = Duration::from_millis(500);
     const STARTING_SCALE: f32 = 1.0;
     const STRONG: f32 = 1.5;
+    const STRONG_PUNCH: f32 = 0.03;
 
     fn new(
         man: &mut EntityManager,
@@ -695,21 +1832,34 @@ pub mod swoop {
                 Components::Position,
                 Components::Direction,
                 Components::Collider,
-                Components::Speed,
+                Components::Kinematics,
                 Components::Scale,
-                Components::Timer,
-                Components::Properties,
+                Components::Lifetime,
+                Components::Animation,
+                Components::Camera,
+                Components::Spawner,
             ],
         );
 
         let mut swoop = man.view(id).unwrap();
         swoop.set_position(spawn_pos);
         swoop.set_direction(direction);
-        swoop.set_speed(speed);
+        swoop.set::<component::Kinematics>(Kinematics::cruising(speed * Vec2::from(direction)));
         swoop.set_scale((scale).into());
-        swoop.access_timer(|t| t.set_threshold(self::SWOOP_LIFETIME));
-        swoop.new_property("alpha", 1.0f32);
-        swoop.new_property("starting_scale", scale);
+        swoop.set::<component::Lifetime>(Lifetime::new(self::SWOOP_LIFETIME));
+        swoop.set_animation(
+            Animation::default()
+                .scale(
+                    Track::default()
+                        .key(Duration::ZERO, Vec2::diagonal(1.0), ease::linear)
+                        .key(self::SWOOP_LIFETIME, Vec2::default(), ease::in_back),
+                )
+                .alpha(
+                    Track::default()
+                        .key(Duration::ZERO, 1.0, ease::linear)
+                        .key(self::SWOOP_LIFETIME, 0.0, ease::out_quad),
+                ),
+        );
 
         super::oneshot::play_sound(man, Sounds::Swoop);
 
@@ -717,35 +1867,52 @@ pub mod swoop {
     }
 
     pub fn weak_attack(man: &mut EntityManager, spawn_pos: Vec3, direction: Direction) -> EntityId {
-        self::new(man, spawn_pos, direction, self::SWOOP_SPEED, self::STARTING_SCALE)
+        self::new(man, spawn_pos, direction, config::get().swoop_speed, self::STARTING_SCALE)
     }
 
     pub fn strong_attack(man: &mut EntityManager, spawn_pos: Vec3, direction: Direction) -> EntityId {
-        self::new(man, spawn_pos, direction, self::SWOOP_SPEED * self::STRONG, self::STARTING_SCALE * self::STRONG)
+        let id = self::new(man, spawn_pos, direction, config::get().swoop_speed * self::STRONG, self::STARTING_SCALE * self::STRONG);
+        man.view(id).unwrap().get_camera().punch(self::STRONG_PUNCH);
+        id
     }
     
 
-    pub fn tick(dt: Duration, this: &mut EntityView) {
-        if this.access_timer(|t| t.tick(dt)) {
-            this.kill();
-            return;
-        }
+    /// the swoop hurts whatever its shape covers, not just the tile it's on
+    pub fn tick(this: &mut EntityView) {
+        let id = this.id();
+        let center = Vec2::from(this.get_position()) + Vec2::diagonal(0.5);
+        let reach = Vec2::diagonal(0.5 * this.get_scale().x * this.get_pose().scale.x);
+        let hitbox = Rect::new(center - reach, center + reach);
+        this.request_spawn(Box::new(move |man| self::sweep(man, id, hitbox)));
+    }
 
-        let pct = this.access_timer(|t| t.progress());
-        this.set_property("alpha", 1.0 - ease::out_quad(pct));
-        let starting_scale = this.get_property::<f32>("starting_scale");
-        this.set_scale((starting_scale * (1.0 - ease::in_back(pct))).into());
+    // the first enemy in the hitbox takes the hit
+    fn sweep(man: &mut EntityManager, id: EntityId, hitbox: Rect) {
+        let target = man
+            .query(&[Components::Position, Components::Collider])
+            .filter(|e| e.which() == Entities::Enemy)
+            .find(|e| {
+                let pos = Vec2::from(e.get_position());
+                hitbox.overlaps(Rect::new(pos, pos + Vec2::diagonal(1.0)))
+            })
+            .map(|e| e.id());
+
+        let (Some(mut swoop), Some(mut enemy)) = (man.view(id), target.and_then(|t| man.view(t))) else {
+            return;
+        };
 
-        let pos = this.get_position();
-        let d = dt.as_secs_f32() * this.get_speed() * Vec3::from(this.get_direction());
-        this.set_position(pos + d);
+        swoop.kill();
+        if super::enemy::hit(&mut enemy) {
+            super::snake::notify_kill(&mut swoop);
+        }
     }
 
     pub fn draw(this: EntityView, renderer: &mut RenderManager) {
-        let pos = this.get_position().into();
-        let scale = this.get_scale().x;
+        let pose = this.get_pose();
+        let pos = (this.get_position() + pose.offset).into();
+        let scale = this.get_scale().x * pose.scale.x;
         let direction = this.get_direction();
-        let alpha = this.get_property("alpha");
+        let alpha = pose.alpha;
         renderer.push(render::swoop::Swoop::new(pos, scale, direction, alpha));
     }
 }
@@ -753,35 +1920,46 @@ pub mod swoop {
 pub mod text {
     use std::{sync::mpsc::Receiver, time::Duration};
 
-    use rand::{thread_rng, Rng};
-
-    use crate::{entity::{Components, Entities, EntityId, EntityManager, EntityView}, math::Vec2, render::{text::{Text, TextNames}, RenderManager}, sound::Sounds};
+    use crate::{entity::{component, Components, Entities, EntityId, EntityManager, EntityView, Light, TextGlitch}, math::Vec2, palette::PaletteKey, render::{text::{Text, TextNames}, RenderManager}, sound::Sounds};
 
     pub const ANIMATION_TICK: u64 = 150;
+    // screen glitch when a trigger (like eating a glitch fruit) sets the text off
+    const TRIGGER_GLITCH: f32 = 0.8;
+    // text glows faintly, and flares up for as long as a glitch frame shows
+    const LIGHT_RADIUS: f32 = 3.0;
+    const LIGHT_INTENSITY: f32 = 0.08;
+    const GLITCH_INTENSITY: f32 = 0.5;
 
     pub fn new(man: &mut EntityManager, name: TextNames, position: Vec2, scale: f32) -> EntityId {
         let id = man.spawn(Entities::Text, &[
             Components::Position,
-            Components::Timer,
+            Components::TextGlitch,
             Components::Spawner,
+            Components::Glitch,
+            Components::Light,
 
             Components::Properties,
         ]);
         
         let mut text = man.view(id).unwrap();
         text.set_position((position, 0.0).into());
-        text.access_timer(|t| t.set_threshold(Duration::from_millis(self::ANIMATION_TICK)));
+        text.set::<component::Light>(Light::new(PaletteKey::Fruit, self::LIGHT_RADIUS, self::LIGHT_INTENSITY));
+        text.set::<component::TextGlitch>(TextGlitch::new(
+            name.frames(),
+            Duration::from_millis(self::ANIMATION_TICK),
+            Duration::from_millis(self::AVERAGE_GLITCH_INTERVAL),
+            self::LIGHT_INTENSITY,
+            self::GLITCH_INTENSITY,
+        ));
         text.new_property("name", name);
-        text.new_property("frame", 0usize);
         text.new_property("scale", scale);
-        text.new_property("glitching_enabled", false);
 
         id
     }
     
     pub fn enable_glitching(man: &mut EntityManager, id: EntityId) {
-        let view = man.view(id).unwrap();
-        view.with_mut_property("glitching_enabled", |b: &mut bool| *b = true);
+        let mut view = man.view(id).unwrap();
+        view.with_mut::<component::TextGlitch, _>(|g| g.enabled = true);
     }
 
     pub fn add_glitch_trigger(man: &mut EntityManager, id: EntityId, glitch_rx: Receiver<()>) {
@@ -790,57 +1968,31 @@ pub mod text {
     }
     
     // target is 1 glitch every 1.5 seconds (=1500ms)
-    pub const AVERAGE_GLITCH_INTERVAL: u32 = 2000;
-
-    fn glitch(this: &mut EntityView) {
-        let mut rng = thread_rng();
-        let name = this.with_property("name", |&n: &TextNames| n);
-        let next_frame = rng.gen_range(1..name.frames());
-        this.with_mut_property("frame", |f: &mut usize| *f = next_frame);
-        this.request_spawn(Box::new(|man| super::oneshot::play_sound(man, Sounds::glitch())));
-    }
-
-    pub fn tick(dt: Duration, this: &mut EntityView) {
-        let tick = this.access_timer(|t| t.tick(dt));
+    pub const AVERAGE_GLITCH_INTERVAL: u64 = 2000;
 
+    /// the glitching itself goes on in the entity manager's step, alongside everything else
+    /// only the triggers that set it off are left to the tick
+    pub fn tick(_dt: Duration, this: &mut EntityView) {
         if this.has_property("glitch_rx") {
             let rx = this.with_property("glitch_rx", |rx: &Receiver<()>| rx.try_recv());
             if let Ok(_) = rx {
-                this.with_mut_property("glitching_enabled", |g: &mut bool| *g = true);
-                self::glitch(this);
+                this.with_mut::<component::TextGlitch, _>(|g| {
+                    g.enabled = true;
+                    g.glitch();
+                });
+                this.request_spawn(Box::new(|man| super::oneshot::play_sound(man, Sounds::glitch())));
+                this.get_glitch().glitch(self::TRIGGER_GLITCH);
 
                 this.remove_property("glitch_rx");
-                return;
-            }
-        }
-
-        let name = this.with_property("name", |&n: &TextNames| n);
-        let frame = this.with_property("frame", |&f: &usize| f);
-
-        if !tick { return; }
-        let glitching_enabled = this.with_property("glitching_enabled", |&b: &bool| b);
-            if !glitching_enabled { return; }
-
-        if frame > 0 {
-            // if animation is ongoing reset it
-            this.with_mut_property("frame", |f: &mut usize| *f = 0);
-        }
-
-        if name.frames() > 1 {
-            let mut rng = thread_rng();
-            // if not animating, check if should animate
-            if rng.gen_ratio(self::ANIMATION_TICK as _, self::AVERAGE_GLITCH_INTERVAL) {
-                self::glitch(this);
             }
         }
-
     }
 
     pub fn draw(this: EntityView, renderer: &mut RenderManager) {
         let position = this.get_position().into();
         let name = this.with_property("name", |n: &TextNames| *n);
 
-        let frame = this.with_property("frame", |&f: &usize| f);
+        let frame = this.get::<component::TextGlitch>().frame;
         let scale = this.with_property("scale", |&s: &f32| s);
         let text = Text::place_at(name, position, name.dimensions(), scale, frame);
 
@@ -848,6 +2000,59 @@ pub mod text {
     }
 }
 
+pub mod label {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        animation::{Animation, Track},
+        common::Result,
+        entity::{Components, Entities, EntityId, EntityManager, EntityView},
+        math::{ease, Vec2, Vec3},
+        render::{text::Text, RenderManager},
+    };
+
+    const HOP_LENGTH: Duration = Duration::from_millis(300);
+    const HOP_HEIGHT: f32 = 0.3;
+
+    /// text made from an arbitrary string, centered on position
+    /// fails if the font can't draw the string
+    pub fn new(man: &mut EntityManager, text: &str, position: Vec2, scale: f32) -> Result<EntityId> {
+        Text::from_str(text)?;
+
+        let id = man.spawn(Entities::Label, &[
+            Components::Position,
+            Components::Animation,
+            Components::Properties,
+        ]);
+
+        let mut label = man.view(id).unwrap();
+        label.set_position((position, 0.0).into());
+        label.new_property("text", text.to_string());
+        label.new_property("scale", scale);
+
+        Ok(id)
+    }
+
+    /// a little jump, to draw the eye
+    pub fn hop(this: &mut EntityView) {
+        let up = self::HOP_LENGTH / 3;
+        this.set_animation(Animation::default().offset(
+            Track::default()
+                .key(Duration::ZERO, Vec3::default(), ease::linear)
+                .key(up, Vec3::new(0.0, self::HOP_HEIGHT, 0.0), ease::out_quad)
+                .key(self::HOP_LENGTH, Vec3::default(), ease::in_back),
+        ));
+    }
+
+    pub fn draw(this: EntityView, renderer: &mut RenderManager) {
+        let position = (this.get_position() + this.get_pose().offset).into();
+        let scale = this.with_property("scale", |&s: &f32| s);
+        let text = this.with_property("text", |t: &String| Text::from_str(t)).unwrap();
+
+        renderer.push(text.place(position, scale));
+    }
+}
+
 pub mod logic {
     use std::time::Duration;
 
@@ -870,26 +2075,44 @@ pub mod logic {
 }
 
 pub mod enemy {
-    use std::{sync::mpsc::{self, Receiver, Sender}, time::Duration};
+    use std::time::Duration;
 
-    use crate::{entity::{Components, Entities, EntityId, EntityManager, EntityView}, math::{self, ease, Mat4, Vec2, Vec4}, palette::Palette, render::{instanced::Tile, shield::Shield, RenderManager}, time::Cooldown};
+    use crate::{entity::{component, Components, Entities, EntityId, EntityManager, EntityView, Health, PropKey, Shielding}, event::EventKind, math::{self, Mat4, Vec2, Vec3, Vec4}, palette::{Palette, PaletteKey}, render::{instanced::Tile, particles::Burst, shield::Shield, RenderManager}};
 
+    // what it spawned with, for how cracked its shield looks
+    const MAX_HP: PropKey<Health> = PropKey::new("max_hp");
+    // hits left
+    const HP: PropKey<Health> = PropKey::new("hp");
     const POWERDOWN_TIME: Duration = Duration::from_millis(500);
+    // hp readout hovering over shielded enemies
+    const HP_LABEL_OFFSET: Vec3 = Vec3 { x: 0.0, y: 0.9, z: 0.0 };
+    // in world units per font pixel, labels are 14 pixels tall
+    const HP_LABEL_SCALE: f32 = 1.0 / 32.0;
 
     pub fn new(man: &mut EntityManager, position: Vec2, hp: i32) -> EntityId {
         let id = man.spawn(Entities::Enemy, &[
             Components::Position,
             Components::Collider,
+            Components::Particles,
+            Components::Spawner,
+            Components::StatusEffects,
+            Components::Shielding,
             Components::Properties,
         ]);
 
+        // only worth showing when it takes more than one hit
+        let label = (hp > 1).then(|| {
+            let label = super::label::new(man, &hp.to_string(), position, self::HP_LABEL_SCALE).unwrap();
+            man.attach(label, id, self::HP_LABEL_OFFSET);
+            label
+        });
+
         let mut this = man.view(id).unwrap();
         this.set_position((position, 0.0).into());
-        this.new_property("max_hp", hp);
-        this.new_property("hp", hp);
-        this.new_property("shield_power", 0.0f32);
-        this.new_property("shield_power_alpha", 0.0f32);
-        this.new_property("shield_powerdown_timer", Cooldown::new(self::POWERDOWN_TIME));
+        this.new_property(self::MAX_HP, hp);
+        this.new_property(self::HP, hp);
+        this.set::<component::Shielding>(Shielding::new(self::POWERDOWN_TIME));
+        this.new_property("hp_label", label);
         self::calculate_shield(&mut this);
         
         id
@@ -903,57 +2126,51 @@ pub mod enemy {
         self::new(man, position, 2)
     }
 
-    pub fn make_kill_trigger(man: &mut EntityManager, id: EntityId) -> Receiver<()> {
-        let this = man.view(id).unwrap();
-        let (tx, rx) = mpsc::channel();
-        this.new_property("kill_tx", tx);
-        rx
-    }
-
-    pub fn hit(this: &mut EntityView) {
-        let hp: i32 = this.get_property("hp");
+    /// returns true if the hit killed the enemy
+    pub fn hit(this: &mut EntityView) -> bool {
+        let hp = this.get_property(self::HP);
         if hp == 1 {
+            this.get_particles()
+                .emit(Burst::explosion(this.get_position().into()));
             this.kill();
-            if this.has_property("kill_tx") {
-                let _ = this.with_property("kill_tx", |t: &Sender<()>| t.send(()));
-            }
+            this.publish(EventKind::EnemyDied);
+            true
         } else {
-            this.set_property("hp", hp - 1);
+            this.set_property(self::HP, hp - 1);
+            if let Some(label) = this.get_property::<Option<EntityId>>("hp_label") {
+                this.request_spawn(Box::new(move |man| {
+                    if let Some(mut label) = man.view(label) {
+                        label.set_property("text", (hp - 1).to_string());
+                        super::label::hop(&mut label);
+                    }
+                }));
+            }
             self::calculate_shield(this);
-            this.with_mut_property("shield_powerdown_timer", |t: &mut Cooldown| t.cool_down());
+            this.with_mut::<component::Shielding, _>(|s| s.powerdown.cool_down());
+            false
         }
     }
 
     fn calculate_shield(this: &mut EntityView) {
-        let hp = this.get_property::<i32>("hp") as f32;
-        let max_hp = this.get_property::<i32>("max_hp") as f32;
+        let hp = this.get_property(self::HP) as f32;
+        let max_hp = this.get_property(self::MAX_HP) as f32;
         let shield_power = (hp - 1.0) / (max_hp - 1.0);
-        this.set_property("shield_power", shield_power * 0.8);
-    }
-
-    pub fn tick(dt: Duration, this: &mut EntityView) {
-        let pct = this.with_mut_property("shield_powerdown_timer", |t: &mut Cooldown| {
-            t.tick(dt);
-            t.progress()
-        });
-
-        let shield: f32 = this.get_property("shield_power");
-        let delta = 1.0 - shield;
-        let alpha =  1.0 - delta * ease::in_expo(pct);
-        this.set_property("shield_power_alpha", alpha);
+        this.with_mut::<component::Shielding, _>(|s| s.power = shield_power * 0.8);
     }
 
     pub fn draw(this: EntityView, renderer: &mut RenderManager, palette: Palette) {
         let pos = this.get_position();
+        let col = this.get::<component::StatusEffects>().tint(palette.get(PaletteKey::Enemy));
         let body = Tile {
             transform: Mat4::translate(pos),
-            col: palette.enemy,
+            col,
         };
         renderer.push(body);
+        super::marker::enemy(renderer, pos, palette);
 
-        let alpha = this.get_property("shield_power_alpha");
+        let alpha = this.get::<component::Shielding>().alpha();
         if alpha > math::EPSILON {
-            let col = Vec4::from((palette.enemy, alpha));
+            let col = Vec4::from((col, alpha));
             let shield = Shield::new(pos.into(), col, false, 0.4)
                 .push_quad();
 
@@ -962,6 +2179,218 @@ pub mod enemy {
     }
 }
 
+pub mod wave_spawner {
+    use std::{
+        collections::HashSet,
+        rc::Rc,
+        sync::mpsc::{self, Receiver, Sender},
+        time::Duration,
+    };
+
+    use crate::{
+        archetype::enemy,
+        entity::{Components, Entities, EntityId, EntityManager, EntityView, Position},
+        event::EventKind,
+        math::{TileCoord, Vec2},
+    };
+
+    // in world units per font pixel, labels are 14 pixels tall
+    const COUNTER_SCALE: f32 = 1.0 / 20.0;
+    // in tiles, how close to the head an enemy can show up
+    const HEAD_MARGIN: i32 = 3;
+    // random spots tried per enemy before giving up on it
+    const PLACE_TRIES: usize = 32;
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct Wave {
+        pub enemies: usize,
+        pub hp: i32,
+    }
+
+    /// spawns enemies in waves
+    /// the next wave comes once the timer runs out or the current wave is cleared
+    /// position is where the wave counter's label goes
+    /// done is notified once the final wave is cleared
+    pub fn new(
+        man: &mut EntityManager,
+        position: Vec2,
+        waves: Vec<Wave>,
+        interval: Duration,
+        rand_gen: impl Fn(Vec2) -> Vec2 + 'static,
+        done: Sender<()>,
+    ) -> EntityId {
+        let id = man.spawn(
+            Entities::WaveSpawner,
+            &[
+                Components::Position,
+                Components::Timer,
+                Components::Spawner,
+                Components::Properties,
+            ],
+        );
+
+        let counter = self::counter(0, waves.len());
+        let label = super::label::new(man, &counter, position, self::COUNTER_SCALE).unwrap();
+        man.attach(label, id, Position::default());
+
+        let (kill_tx, kill_rx) = mpsc::channel::<()>();
+        let mut this = man.view(id).unwrap();
+        this.set_position((position, 0.0).into());
+        this.access_timer(|t| t.set_threshold(interval));
+        this.new_property("label", label);
+        this.new_property("waves", waves);
+        this.new_property("wave", 0usize);
+        this.new_property("alive", 0usize);
+        this.new_property("enemy_kill_tx", kill_tx);
+        this.new_property("enemy_kill_rx", kill_rx);
+        let shared: Rc<dyn Fn(Vec2) -> Vec2> = Rc::new(rand_gen);
+        this.new_property("rand_gen", shared);
+        this.new_property("done_tx", done);
+
+        id
+    }
+
+    // the wave that's on, or coming up before the first one
+    fn counter(wave: usize, total: usize) -> String {
+        format!("WAVE {}/{total}", wave.clamp(1, total.max(1)))
+    }
+
+    fn spawn_wave(this: &mut EntityView, wave: Wave) {
+        let rand_gen = this.with_property("rand_gen", |r: &Rc<dyn Fn(Vec2) -> Vec2>| r.clone());
+        let kill_tx = this.with_property("enemy_kill_tx", |t: &Sender<()>| t.clone());
+        let spawner = this.id();
+        this.request_spawn(Box::new(move |man| {
+            // later waves come in mid-fight, so they keep clear of the snake and whatever's already out
+            let mut taken: HashSet<TileCoord> = man
+                .query(&[Components::Position, Components::Collider])
+                .map(|e| TileCoord::of(e.get_position().into()))
+                .collect();
+            let heads: Vec<TileCoord> = man
+                .query(&[Components::Position])
+                .filter(|e| e.which() == Entities::SnakeHead)
+                .map(|e| TileCoord::of(e.get_position().into()))
+                .collect();
+            let near_head = |t: TileCoord| {
+                heads
+                    .iter()
+                    .any(|h| (t.x - h.x).abs() <= HEAD_MARGIN && (t.y - h.y).abs() <= HEAD_MARGIN)
+            };
+
+            let mut placed = 0;
+            for _ in 0..wave.enemies {
+                // a crowded room gets fewer enemies rather than hanging
+                let free = (0..PLACE_TRIES)
+                    .map(|_| TileCoord::of(rand_gen(Vec2::diagonal(0.5))))
+                    .find(|&t| !taken.contains(&t) && !near_head(t));
+                let Some(tile) = free else {
+                    continue;
+                };
+                taken.insert(tile);
+
+                let e = enemy::new(man, tile.into(), wave.hp);
+                let kill_tx = kill_tx.clone();
+                man.events()
+                    .subscribe_with(EventKind::EnemyDied, Some(e), move |_| kill_tx.send(()).is_ok());
+                placed += 1;
+            }
+
+            if placed < wave.enemies {
+                log::warn!("only found room for {placed} of the wave's {} enemies", wave.enemies);
+            }
+            if let Some(spawner) = man.view(spawner) {
+                spawner.with_mut_property("alive", |a: &mut usize| *a += placed);
+            }
+        }));
+
+        let wave = this.with_mut_property("wave", |w: &mut usize| {
+            *w += 1;
+            *w
+        });
+
+        let counter = self::counter(wave, this.with_property("waves", |w: &Vec<Wave>| w.len()));
+        let label = this.get_property::<EntityId>("label");
+        this.request_spawn(Box::new(move |man| {
+            if let Some(mut label) = man.view(label) {
+                label.set_property("text", counter);
+                super::label::hop(&mut label);
+            }
+        }));
+    }
+
+    pub fn tick(dt: Duration, this: &mut EntityView) {
+        let kills = this.with_property("enemy_kill_rx", |rx: &Receiver<()>| rx.try_iter().count());
+        let alive = this.with_mut_property("alive", |a: &mut usize| {
+            *a = a.saturating_sub(kills);
+            *a
+        });
+
+        let wave = this.get_property::<usize>("wave");
+        let next = this.with_property("waves", |w: &Vec<Wave>| w.get(wave).copied());
+        let Some(next) = next else {
+            // all waves are out, wait for the last of them to die
+            if alive == 0 {
+                let _ = this.with_property("done_tx", |t: &Sender<()>| t.send(()));
+                this.kill();
+            }
+            return;
+        };
+
+        let timer_up = this.access_timer(|t| t.tick(dt));
+        if timer_up || alive == 0 {
+            this.access_timer(|t| t.reset());
+            self::spawn_wave(this, next);
+        }
+    }
+}
+
+pub mod annotation {
+    use core::fmt;
+
+    use crate::{
+        entity::{Components, Entities, EntityId, EntityManager, EntityView},
+        math::Vec2,
+    };
+
+    /// designer-facing metadata, for the debug tools to show
+    /// annotations are never ticked, only the collision overlay draws them, and the game never reads them back
+    #[derive(Debug, Clone)]
+    pub enum Annotation {
+        /// free-form comment
+        Note(String),
+        /// a named location to spawn things at
+        SpawnPoint(&'static str),
+        /// where the camera should look at, and how much of the room it should fit
+        CameraHint { zoom: f32 },
+    }
+
+    impl fmt::Display for Annotation {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Note(note) => write!(f, "// {note}"),
+                Self::SpawnPoint(name) => write!(f, "spawn: {name}"),
+                Self::CameraHint { zoom } => write!(f, "camera: x{zoom:.2}"),
+            }
+        }
+    }
+
+    pub fn new(man: &mut EntityManager, position: Vec2, annotation: Annotation) -> EntityId {
+        let id = man.spawn(
+            Entities::Annotation,
+            &[Components::Position, Components::Properties],
+        );
+
+        let mut this = man.view(id).unwrap();
+        this.set_position((position, 0.0).into());
+        this.new_property("annotation", annotation);
+
+        id
+    }
+
+    pub fn get(this: &EntityView) -> Annotation {
+        this.get_property("annotation")
+    }
+}
+
 pub mod oneshot {
     use crate::{
         entity::{Components, Entities, EntityManager},
@@ -975,3 +2404,56 @@ pub mod oneshot {
         man.kill(id);
     }
 }
+
+/// shapes drawn on top of fruits and enemies, so they can be told apart without telling their colors apart
+pub mod marker {
+    use std::cell::Cell;
+
+    use crate::{
+        math::{Mat4, Vec2, Vec3},
+        palette::{Palette, PaletteKey},
+        render::{instanced::Tile, RenderManager},
+    };
+
+    // in front of whatever they're drawn on, so they win the depth test
+    const DEPTH: f32 = 0.01;
+    // how much of its tile a fruit's dot covers
+    const DOT: f32 = 0.4;
+    // an enemy's cross, as long as and as thick as this much of its tile
+    const CROSS_LENGTH: f32 = 0.7;
+    const CROSS_THICKNESS: f32 = 0.2;
+
+    thread_local! {
+        static SHOWN: Cell<bool> = const { Cell::new(false) };
+    }
+
+    pub fn show(shown: bool) {
+        SHOWN.set(shown);
+    }
+
+    // centered on the tile with the given corner and scale
+    fn push(renderer: &mut RenderManager, corner: Vec3, scale: Vec2, size: Vec2, palette: Palette) {
+        let size = size * scale;
+        let offset = 0.5 * (scale - size);
+        renderer.push(Tile {
+            transform: Mat4::translate(corner + Vec3::new(offset.x, offset.y, -self::DEPTH)) * Mat4::scale(size),
+            col: palette.get(PaletteKey::Background),
+        });
+    }
+
+    /// a dot in the middle
+    pub fn fruit(renderer: &mut RenderManager, corner: Vec3, scale: Vec2, palette: Palette) {
+        if SHOWN.get() {
+            self::push(renderer, corner, scale, Vec2::diagonal(self::DOT), palette);
+        }
+    }
+
+    /// a plus through the middle
+    pub fn enemy(renderer: &mut RenderManager, corner: Vec3, palette: Palette) {
+        if SHOWN.get() {
+            let scale = Vec2::diagonal(1.0);
+            self::push(renderer, corner, scale, Vec2::new(self::CROSS_LENGTH, self::CROSS_THICKNESS), palette);
+            self::push(renderer, corner, scale, Vec2::new(self::CROSS_THICKNESS, self::CROSS_LENGTH), palette);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use crate::sound::{SoundManager, Sounds};
+
+// when each sound of the power-on plays
+// the screen turns on with the last one
+const STEPS: [(Duration, Sounds); 3] = [
+    (Duration::ZERO, Sounds::CrtClick),
+    (Duration::from_millis(1250), Sounds::CrtBuzz),
+    (Duration::from_millis(2750), Sounds::CrtOn),
+];
+
+/// the crt powering on before the game starts
+/// driven by ticks so the window keeps responding while it plays out
+pub struct Boot {
+    elapsed: Duration,
+    next_step: usize,
+}
+
+impl Default for Boot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Boot {
+    pub fn new() -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            next_step: 0,
+        }
+    }
+
+    /// plays whatever is due, returns true once the crt is on
+    pub fn tick(&mut self, dt: Duration, sound: &SoundManager) -> bool {
+        self.elapsed += dt;
+        while let Some(&(at, step)) = STEPS.get(self.next_step) {
+            if self.elapsed < at {
+                break;
+            }
+            sound.play(step);
+            self.next_step += 1;
+        }
+
+        self.next_step == STEPS.len()
+    }
+
+    /// turn the crt on with the next tick
+    pub fn skip(&mut self) {
+        let last = STEPS.len() - 1;
+        self.next_step = self.next_step.max(last);
+        self.elapsed = self.elapsed.max(STEPS[last].0);
+    }
+}
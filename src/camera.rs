@@ -0,0 +1,356 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver, Sender},
+    time::Duration,
+};
+
+use rand::{thread_rng, Rng};
+
+use crate::math::{ease, Mat4, Tween, Vec2, Vec3};
+
+// trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+// screen offset at full trauma, in ndc
+const MAX_SHAKE: f32 = 0.04;
+// zoom punch remaining after one second
+const PUNCH_DECAY: f32 = 0.002;
+// in ndc
+const DRIFT_AMPLITUDE: f32 = 0.003;
+// radians per second
+const DRIFT_SPEED: Vec2 = Vec2 { x: 0.37, y: 0.23 };
+
+// rooms wider or taller than this (in tiles) don't fit on screen
+pub const MAX_FIXED_SIZE: f32 = 32.0;
+// how many tiles a following camera shows
+pub const FOLLOW_WINDOW: f32 = 24.0;
+// how far the target can stray from the center before the camera moves
+pub const FOLLOW_DEADZONE: Vec2 = Vec2 { x: 3.0, y: 3.0 };
+
+/// what happened to the pans during a tick
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PanEvents {
+    pub started: bool,
+    pub finished: bool,
+}
+
+/// queue of camera pans
+/// every pan starts from wherever the camera is when it begins,
+/// so interrupting or chaining pans never snaps the view
+pub struct Pans {
+    view: Mat4,
+    active: Option<Tween<Mat4>>,
+    queued: VecDeque<(Mat4, Duration)>,
+    just_started: bool,
+}
+
+impl Pans {
+    pub fn new(view: Mat4) -> Self {
+        Self {
+            view,
+            active: None,
+            queued: VecDeque::new(),
+            just_started: false,
+        }
+    }
+
+    /// the current interpolated view
+    pub fn view(&self) -> Mat4 {
+        self.view
+    }
+
+    pub fn is_panning(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// drop every pending pan and head to the new view right away
+    pub fn interrupt(&mut self, to: Mat4, length: Duration) {
+        self.queued.clear();
+        self.start(to, length);
+    }
+
+    /// pan to the new view once every pending pan is over
+    pub fn chain(&mut self, to: Mat4, length: Duration) {
+        if self.is_panning() {
+            self.queued.push_back((to, length));
+        } else {
+            self.start(to, length);
+        }
+    }
+
+    /// move the destination of the very last pan
+    /// for targets that keep moving while the camera heads to them
+    pub fn retarget(&mut self, to: Mat4) {
+        if let Some((last, _)) = self.queued.back_mut() {
+            *last = to;
+        } else if let Some(tween) = &mut self.active {
+            tween.retarget(to);
+        }
+    }
+
+    fn start(&mut self, to: Mat4, length: Duration) {
+        self.active = Some(Tween::new(self.view, to, length, ease::out_expo));
+        self.just_started = true;
+    }
+
+    pub fn tick(&mut self, dt: Duration) -> PanEvents {
+        let mut events = PanEvents {
+            started: self.just_started,
+            finished: false,
+        };
+        self.just_started = false;
+
+        let Some(tween) = &mut self.active else {
+            return events;
+        };
+
+        tween.tick(dt);
+        self.view = tween.value();
+        if !tween.is_done() {
+            return events;
+        }
+
+        self.active = None;
+        events.finished = true;
+
+        if let Some((to, length)) = self.queued.pop_front() {
+            self.start(to, length);
+            events.started = true;
+            self.just_started = false;
+        }
+
+        events
+    }
+}
+
+enum Effect {
+    Shake(f32),
+    Punch(f32),
+}
+
+/// lets entities kick the camera without owning it
+#[derive(Clone)]
+pub struct Kicker {
+    tx: Sender<Effect>,
+}
+
+impl Kicker {
+    /// trauma in [0,1], stacks with whatever shake is going on
+    pub fn shake(&self, trauma: f32) {
+        let _ = self.tx.send(Effect::Shake(trauma));
+    }
+
+    /// momentarily zoom in by the given fraction
+    pub fn punch(&self, zoom: f32) {
+        let _ = self.tx.send(Effect::Punch(zoom));
+    }
+}
+
+/// short lived camera effects
+/// composed on top of whatever view the pans settle on
+pub struct Effects {
+    tx: Sender<Effect>,
+    rx: Receiver<Effect>,
+    trauma: f32,
+    punch: f32,
+    time: f32,
+    // shakes are dropped while off
+    shaking: bool,
+    // punches are dropped and the drift stops while on
+    still: bool,
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            tx,
+            rx,
+            trauma: 0.0,
+            punch: 0.0,
+            time: 0.0,
+            shaking: true,
+            still: false,
+        }
+    }
+
+    pub fn kicker(&self) -> Kicker {
+        Kicker {
+            tx: self.tx.clone(),
+        }
+    }
+
+    pub fn tick(&mut self, dt: Duration) {
+        for effect in self.rx.try_iter() {
+            match effect {
+                Effect::Shake(_) if !self.shaking => (),
+                Effect::Shake(trauma) => self.trauma = (self.trauma + trauma).min(1.0),
+                Effect::Punch(_) if self.still => (),
+                Effect::Punch(zoom) => self.punch = self.punch.max(zoom),
+            }
+        }
+
+        let dt = dt.as_secs_f32();
+        self.time += dt;
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        self.punch *= PUNCH_DECAY.powf(dt);
+    }
+
+    pub fn apply(&self, view: Mat4) -> Mat4 {
+        // squaring the trauma makes small shakes subtle and big ones violent
+        let shake = MAX_SHAKE * self.trauma * self.trauma;
+        let mut rng = thread_rng();
+        let jitter = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0));
+
+        let drift = if self.still {
+            Vec2::default()
+        } else {
+            Vec2::new((DRIFT_SPEED.x * self.time).sin(), (DRIFT_SPEED.y * self.time).cos())
+        };
+
+        let offset = shake * jitter + DRIFT_AMPLITUDE * drift;
+        let zoom = 1.0 + self.punch;
+        Mat4::translate(Vec3::new(offset.x, offset.y, 0.0)) * Mat4::scale(Vec2::diagonal(zoom)) * view
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Mode {
+    /// frame a whole room, moving only when panned
+    Fixed,
+    /// keep the target inside a deadzone around the middle of a square window
+    Follow { window: f32, deadzone: Vec2 },
+}
+
+/// owns everything that decides what ends up on screen
+/// pans drive fixed rooms and cinematic paths,
+/// following takes over whenever no pan is playing
+pub struct Camera {
+    pans: Pans,
+    effects: Effects,
+    mode: Mode,
+    center: Vec2,
+    // still heading into a followed room
+    settling: bool,
+    view: Mat4,
+}
+
+impl Camera {
+    pub fn new(view: Mat4) -> Self {
+        Self {
+            pans: Pans::new(view),
+            effects: Effects::new(),
+            mode: Mode::Fixed,
+            center: Vec2::default(),
+            settling: false,
+            view,
+        }
+    }
+
+    pub fn kicker(&self) -> Kicker {
+        self.effects.kicker()
+    }
+
+    /// turning shakes off stops the one going on too
+    pub fn set_shaking(&mut self, shaking: bool) {
+        self.effects.shaking = shaking;
+        if !shaking {
+            self.effects.trauma = 0.0;
+        }
+    }
+
+    /// for reduced motion, no punches and no drifting around
+    /// shakes are up to [`Camera::set_shaking`]
+    pub fn set_still(&mut self, still: bool) {
+        self.effects.still = still;
+        if still {
+            self.effects.punch = 0.0;
+        }
+    }
+
+    /// the view to draw with, effects included
+    pub fn view(&self) -> Mat4 {
+        self.view
+    }
+
+    /// the view without effects
+    /// for picking, so aiming doesn't jitter with the shake
+    pub fn steady_view(&self) -> Mat4 {
+        self.pans.view()
+    }
+
+    /// when switching to follow, the camera starts out centered on target
+    pub fn set_mode(&mut self, mode: Mode, target: Vec2) {
+        self.mode = mode;
+        self.center = target;
+    }
+
+    /// see [`Pans::interrupt`]
+    /// the view is fixed when the pan is over
+    pub fn interrupt(&mut self, to: Mat4, length: Duration) {
+        self.set_mode(Mode::Fixed, self.center);
+        self.settling = false;
+        self.pans.interrupt(to, length);
+    }
+
+    /// pan into a room once every pending pan is over,
+    /// framing it the way its mode wants
+    /// room_view is where fixed rooms end up
+    pub fn enter(&mut self, mode: Mode, room_view: Mat4, target: Vec2, length: Duration) {
+        self.set_mode(mode, target);
+        match mode {
+            Mode::Fixed => self.pans.chain(room_view, length),
+            Mode::Follow { window, .. } => {
+                self.pans.chain(Mat4::screen(target, window, window), length);
+                self.settling = true;
+            }
+        }
+    }
+
+    /// fly through every view in order once the current pans are over
+    /// the current mode picks back up after the last one
+    pub fn cinematic(&mut self, path: &[(Mat4, Duration)]) {
+        for &(to, length) in path {
+            self.pans.chain(to, length);
+        }
+    }
+
+    /// pull back from wherever the camera is, around the middle of the screen
+    /// the view is fixed when it's over
+    pub fn zoom_out(&mut self, factor: f32, length: Duration) {
+        let to = Mat4::scale(Vec2::diagonal(1.0 / factor)) * self.steady_view();
+        self.interrupt(to, length);
+    }
+
+    /// target is what a following camera keeps in view, in world space
+    pub fn tick(&mut self, dt: Duration, target: Vec2) -> PanEvents {
+        let events = self.pans.tick(dt);
+
+        if let Mode::Follow { window, deadzone } = self.mode {
+            self.center = Self::track(self.center, target, deadzone);
+            let view = Mat4::screen(self.center, window, window);
+            if !self.pans.is_panning() {
+                self.settling = false;
+                self.pans = Pans::new(view);
+            } else if self.settling {
+                // the snake keeps moving during the pan in
+                self.pans.retarget(view);
+            }
+        }
+
+        self.effects.tick(dt);
+        self.view = self.effects.apply(self.pans.view());
+
+        events
+    }
+
+    // drag the center just enough to bring the target back in the deadzone
+    fn track(center: Vec2, target: Vec2, deadzone: Vec2) -> Vec2 {
+        center.clamp(target - deadzone, target + deadzone)
+    }
+}
@@ -0,0 +1,118 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    imageops::{self, FilterType},
+    Delay, Frame, RgbaImage,
+};
+
+use crate::{common, gl::FrameBuffer};
+
+// where captures go, in the data dir
+const DIR: &str = "captures";
+// how often a frame makes it into the clip, gifs can't keep up with the screen anyway
+const CLIP_FRAME: Duration = Duration::from_millis(100);
+// how far back a clip goes
+const CLIP_LENGTH: Duration = Duration::from_secs(5);
+// clips are shrunk down this much, so holding onto them stays cheap
+const CLIP_DOWNSCALE: u32 = 3;
+
+/// `--clips` keeps the last few seconds around for saving
+pub fn clips_from_args(mut args: impl Iterator<Item = String>) -> bool {
+    args.any(|a| a == "--clips")
+}
+
+// a new file in the captures folder, named after when it was taken
+fn stamped(extension: &str) -> Option<PathBuf> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let path = common::data_path(DIR)?.join(format!("snek-{millis}.{extension}"));
+    fs::create_dir_all(path.parent()?).ok()?;
+    Some(path)
+}
+
+fn read_screen() -> RgbaImage {
+    let (width, height, pixels) = FrameBuffer::read_screen();
+    let image = RgbaImage::from_raw(width, height, pixels).expect("should have read the whole screen");
+    // gl's rows go bottom up
+    imageops::flip_vertical(&image)
+}
+
+/// saves what's on the screen as a png
+/// call once the frame's been drawn, it has all the post passes on it then
+/// returns where it went
+pub fn screenshot() -> Option<PathBuf> {
+    let path = stamped("png")?;
+    if let Err(e) = read_screen().save(&path) {
+        log::warn!("couldn't save a screenshot to {}: {e}", path.display());
+        return None;
+    }
+    log::info!("saved a screenshot to {}", path.display());
+    Some(path)
+}
+
+/// the last few seconds of the screen, kept in case they're worth sharing
+#[derive(Default)]
+pub struct Clip {
+    frames: VecDeque<(RgbaImage, Instant)>,
+}
+
+impl Clip {
+    /// call once the frame's been drawn
+    pub fn record(&mut self) {
+        let now = Instant::now();
+        if self.frames.back().is_some_and(|&(_, at)| now - at < CLIP_FRAME) {
+            return;
+        }
+
+        let frame = read_screen();
+        let (width, height) = (frame.width() / CLIP_DOWNSCALE, frame.height() / CLIP_DOWNSCALE);
+        let frame = imageops::resize(&frame, width, height, FilterType::Triangle);
+        self.frames.push_back((frame, now));
+        while self.frames.front().is_some_and(|&(_, at)| now - at > CLIP_LENGTH) {
+            self.frames.pop_front();
+        }
+    }
+
+    /// encoding takes a while, so the gif gets written off the main thread
+    /// returns where it's going
+    pub fn save(&self) -> Option<PathBuf> {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let path = stamped("gif")?;
+        let file = BufWriter::new(File::create(&path).ok()?);
+        // each frame stays up until the next one was taken
+        let delays: Vec<_> = self
+            .frames
+            .iter()
+            .zip(self.frames.iter().skip(1))
+            .map(|((_, from), (_, to))| *to - *from)
+            .chain(std::iter::once(CLIP_FRAME))
+            .collect();
+        let frames: Vec<_> = self.frames.iter().map(|(frame, _)| frame.clone()).collect();
+
+        thread::spawn(move || {
+            let mut encoder = GifEncoder::new(file);
+            let _ = encoder.set_repeat(Repeat::Infinite);
+            for (frame, delay) in frames.into_iter().zip(delays) {
+                let delay = Delay::from_saturating_duration(delay);
+                if let Err(e) = encoder.encode_frame(Frame::from_parts(frame, 0, 0, delay)) {
+                    log::warn!("couldn't finish a clip: {e}");
+                    return;
+                }
+            }
+            log::info!("saved a clip");
+        });
+        Some(path)
+    }
+}
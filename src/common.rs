@@ -1,3 +1,5 @@
+use std::{fmt, path::PathBuf};
+
 #[derive(Debug)]
 pub enum Error {
     FileNotFound,
@@ -8,11 +10,46 @@ pub enum Error {
     BadDirection,
     InvalidSoundId,
     InvalidTextNameId,
+    MissingGlyph(char),
+    InvalidMutator,
+    BadReplay,
+    BadTemplate(String),
+    BadConfig(String),
+    BadCommand(String),
+    BadPalette(String),
+    MissingProperty(&'static str),
+    PropertyType(&'static str),
+    Gl(String),
+    Texture(String),
+    Audio(String),
+}
+
+/// what the player gets told when the game can't go on
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gl(e) => write!(f, "the graphics driver reported an error\n{e}"),
+            Self::ShaderCompilationError(log) => write!(f, "a shader didn't compile\n{log}"),
+            Self::Texture(e) => write!(f, "a texture couldn't be loaded\n{e}"),
+            Self::Audio(e) => write!(f, "the sound couldn't be set up\n{e}"),
+            e => write!(f, "{e:?}"),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// where a file that outlives the run goes, in the user's data dir
+pub fn data_path(file: &str) -> Option<PathBuf> {
+    let dir = std::env::var_os("APPDATA").or_else(|| std::env::var_os("HOME"))?;
+    Some(PathBuf::from(dir).join("snek").join(file))
+}
+
 pub trait AsBytes {
+    /// the raw bytes, for handing off to the gpu
+    ///
+    /// # Safety
+    /// the type has to be plain old data, with no padding or pointers in it
     unsafe fn as_bytes(&self) -> &[u8];
 }
 
@@ -0,0 +1,124 @@
+use std::{fs, ops::RangeInclusive, path::PathBuf, sync::OnceLock, time::Duration};
+
+use toml::Value;
+
+use crate::{
+    common::{self, Error, Result},
+    resources,
+};
+
+/// balance constants, tuned without recompiling
+/// see `res/config.toml` for what each one does
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub step: Duration,
+    pub attack_cooldown: Duration,
+    pub swoop_speed: f32,
+    pub room_size: RangeInclusive<usize>,
+    pub hall_width: RangeInclusive<usize>,
+    pub hall_length: RangeInclusive<usize>,
+    pub pan_length: Duration,
+}
+
+impl Config {
+    /// the embedded defaults with the overrides in contents laid over them
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut config = self::parse_table(resources::CONFIG)?;
+        self::merge(&mut config, self::parse_table(contents.as_bytes())?);
+
+        let get = |section: &str, key: &str| {
+            config
+                .get(section)
+                .and_then(|s| s.get(key))
+                .ok_or_else(|| Error::BadConfig(format!("missing {section}.{key}")))
+        };
+        let millis = |section, key| {
+            let ms = get(section, key)?
+                .as_integer()
+                .and_then(|ms| u64::try_from(ms).ok())
+                .ok_or_else(|| Error::BadConfig(format!("{section}.{key} isn't a duration in ms")))?;
+            Ok(Duration::from_millis(ms))
+        };
+        let float = |section, key| {
+            let value = get(section, key)?;
+            value
+                .as_float()
+                .or_else(|| value.as_integer().map(|i| i as f64))
+                .map(|f| f as f32)
+                .ok_or_else(|| Error::BadConfig(format!("{section}.{key} isn't a number")))
+        };
+        let range = |section, key| {
+            let bounds = get(section, key)?
+                .as_array()
+                .map(|a| a.iter().filter_map(|v| v.as_integer()).collect::<Vec<_>>());
+            match bounds.as_deref() {
+                Some(&[min, max]) if 0 < min && min <= max => Ok(min as usize..=max as usize),
+                _ => Err(Error::BadConfig(format!("{section}.{key} isn't a [min, max] pair"))),
+            }
+        };
+
+        Ok(Self {
+            step: millis("snake", "step_ms")?,
+            attack_cooldown: millis("snake", "attack_cooldown_ms")?,
+            swoop_speed: float("swoop", "speed")?,
+            room_size: range("rooms", "size")?,
+            hall_width: range("rooms", "hall_width")?,
+            hall_length: range("rooms", "hall_length")?,
+            pan_length: millis("camera", "pan_ms")?,
+        })
+    }
+
+    /// the overrides come from `--config <path>`, or the data dir if that's not given
+    /// no override file means the defaults
+    pub fn load(args: impl Iterator<Item = String>) -> Result<Self> {
+        let path = self::path_from_args(args);
+        let contents = match &path {
+            Some(path) => fs::read_to_string(path).map_err(|_| Error::FileNotFound)?,
+            None => common::data_path("config.toml")
+                .and_then(|p| fs::read_to_string(p).ok())
+                .unwrap_or_default(),
+        };
+        Self::parse(&contents)
+    }
+}
+
+/// e.g. `snek --config balance.toml`
+fn path_from_args(args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.skip_while(|a| a != "--config").skip(1);
+    args.next().map(PathBuf::from)
+}
+
+fn parse_table(contents: &[u8]) -> Result<Value> {
+    let contents = std::str::from_utf8(contents).map_err(|_| Error::BadConfig("not utf-8".to_string()))?;
+    contents
+        .parse::<Value>()
+        .map_err(|e| Error::BadConfig(e.to_string()))
+}
+
+// tables merge key by key, anything else in overrides replaces what's in base
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Table(base), Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => self::merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// only the first call has any effect
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| Config::parse("").expect("bad embedded config"))
+}
@@ -0,0 +1,169 @@
+use std::{collections::VecDeque, str::FromStr};
+
+use glfw::Key;
+
+use crate::{
+    common::{Error, Result},
+    math::Vec2,
+    palette::Palette,
+    render::{hud::HudText, text::Font, RenderManager},
+};
+
+// how many lines of output stay on screen
+const LOG_LINES: usize = 8;
+// layout in screen space, from the top left
+const LEFT: f32 = -0.95;
+const TOP: f32 = 0.95;
+const GLYPH_SIZE: f32 = 0.035;
+const LINE_HEIGHT: f32 = 0.055;
+
+/// things the console can spawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spawnable {
+    Enemy,
+    ShieldedEnemy,
+    Pickup,
+}
+
+/// what the console can give the snake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gift {
+    Score,
+    Length,
+}
+
+/// a line typed into the console, e.g. `spawn enemy 5`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Spawn(Spawnable, usize),
+    Give(Gift, i32),
+    /// the room after this one is the run's nth, whatever it was going to be
+    Goto(usize),
+    SetPalette(String),
+    /// as if the room's goal was met
+    Clear,
+    Help,
+}
+
+impl Command {
+    pub const HELP: &'static str = "spawn enemy/shielded/pickup N, give score/length N, goto room N, set palette NAME, clear";
+}
+
+fn count<T: FromStr>(word: Option<&str>, default: T) -> Result<T> {
+    match word {
+        Some(n) => n.parse().map_err(|_| Error::BadCommand(format!("{n} isn't a number"))),
+        None => Ok(default),
+    }
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_ascii_lowercase();
+        let mut words = s.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (Some("spawn"), Some(what)) => {
+                let what = match what {
+                    "enemy" => Spawnable::Enemy,
+                    "shielded" => Spawnable::ShieldedEnemy,
+                    "pickup" => Spawnable::Pickup,
+                    _ => return Err(Error::BadCommand(format!("can't spawn {what}"))),
+                };
+                Self::Spawn(what, count(words.next(), 1)?)
+            }
+            (Some("give"), Some(what)) => {
+                let what = match what {
+                    "score" => Gift::Score,
+                    "length" => Gift::Length,
+                    _ => return Err(Error::BadCommand(format!("can't give {what}"))),
+                };
+                Self::Give(what, count(words.next(), 1)?)
+            }
+            (Some("goto"), Some("room")) => match words.next() {
+                Some(n) => Self::Goto(count(Some(n), 0)?),
+                None => return Err(Error::BadCommand("goto room which?".to_string())),
+            },
+            (Some("set"), Some("palette")) => match words.next() {
+                Some(name) => Self::SetPalette(name.to_string()),
+                None => return Err(Error::BadCommand("set palette to what?".to_string())),
+            },
+            (Some("clear"), None) => Self::Clear,
+            (Some("help"), None) => Self::Help,
+            _ => return Err(Error::BadCommand(format!("unknown command {s}"))),
+        };
+
+        match words.next() {
+            Some(extra) => Err(Error::BadCommand(format!("didn't expect {extra}"))),
+            None => Ok(command),
+        }
+    }
+}
+
+/// developer console, for testing rooms and balance without playing up to them
+/// commands are parsed here and carried out by whoever owns the run
+#[derive(Debug, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    log: VecDeque<String>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// the font can't draw everything, so the rest never makes it in
+    pub fn type_char(&mut self, c: char) {
+        if self.open && Font::get().has_glyph(c) {
+            self.input.push(c);
+        }
+    }
+
+    /// returns the command once one gets entered
+    /// closes on escape or the key it was opened with
+    pub fn key(&mut self, key: Key) -> Option<Result<Command>> {
+        match key {
+            Key::Backspace => {
+                self.input.pop();
+            }
+            Key::Escape | Key::GraveAccent => self.open = false,
+            Key::Enter if !self.input.trim().is_empty() => {
+                let line = std::mem::take(&mut self.input);
+                self.print(format!("> {line}"));
+                return Some(line.parse());
+            }
+            _ => (),
+        }
+
+        None
+    }
+
+    pub fn print(&mut self, line: String) {
+        self.log.push_back(line);
+        if self.log.len() > LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut RenderManager, palette: Palette) {
+        if !self.open {
+            return;
+        }
+
+        let prompt = format!("> {}", self.input);
+        let lines = self.log.iter().chain(std::iter::once(&prompt));
+        for (i, line) in lines.enumerate() {
+            // errors can quote anything
+            let line = Font::get().sanitize(line);
+            let position = Vec2::new(LEFT, TOP - i as f32 * LINE_HEIGHT);
+            let col = if i < self.log.len() { palette.ui } else { palette.fruit };
+            renderer.push(HudText::new(&line, position, GLYPH_SIZE, col));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    time::Duration,
+};
+
+use rand::Rng;
+
+use crate::{
+    palette::Palette,
+    rng::{self, Stream},
+    time::{Cooldown, Threshold},
+    world,
+};
+
+// how many procedural rooms it takes to reach full corruption
+const ROOMS_TO_FULL: usize = 12;
+// how often a burst is rolled for
+const ROLL_INTERVAL: u32 = 250;
+// target is 1 burst every 4 seconds at full corruption
+const AVERAGE_BURST_INTERVAL: u32 = 4000;
+const BURST_LENGTH: Duration = Duration::from_millis(200);
+// glitches triggered by gameplay fade out over this long at full strength
+const EVENT_LENGTH: Duration = Duration::from_millis(400);
+
+/// lets entities glitch the screen without owning the corruption
+#[derive(Clone)]
+pub struct Glitcher {
+    tx: Sender<f32>,
+}
+
+impl Glitcher {
+    /// strength in [0,1], the strongest glitch going on wins
+    pub fn glitch(&self, strength: f32) {
+        let _ = self.tx.send(strength);
+    }
+}
+
+/// Global corruption level, driven by how deep into the run the player is
+/// Occasionally bursts, glitching the whole screen
+pub struct Corruption {
+    level: f32,
+    roll: Threshold,
+    burst: Cooldown,
+    swap_palette: bool,
+    tx: Sender<f32>,
+    rx: Receiver<f32>,
+    // what's left of the last triggered glitch
+    event: f32,
+}
+
+impl Default for Corruption {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Corruption {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            level: 0.0,
+            roll: Threshold::new(Duration::from_millis(ROLL_INTERVAL as _)),
+            burst: Cooldown::new(BURST_LENGTH),
+            swap_palette: false,
+            tx,
+            rx,
+            event: 0.0,
+        }
+    }
+
+    pub fn glitcher(&self) -> Glitcher {
+        Glitcher {
+            tx: self.tx.clone(),
+        }
+    }
+
+    /// recalculate the corruption level from the room counter
+    /// the tutorial rooms are always clean
+    pub fn set_depth(&mut self, room_ctr: usize) {
+        let depth = room_ctr.saturating_sub(world::TUTORIAL_ROOMS);
+        self.level = (depth as f32 / ROOMS_TO_FULL as f32).min(1.0);
+    }
+
+    /// [0,1] where 0 is clean and 1 is fully corrupted
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// glitch the screen right away, see [`Glitcher::glitch`]
+    pub fn glitch(&mut self, strength: f32) {
+        self.event = self.event.max(strength);
+    }
+
+    /// returns true if a burst has just started
+    pub fn tick(&mut self, dt: Duration) -> bool {
+        let strongest = self.rx.try_iter().fold(0.0, f32::max);
+        let fade = dt.as_secs_f32() / EVENT_LENGTH.as_secs_f32();
+        self.event = (self.event - fade).max(strongest).max(0.0);
+
+        self.burst.tick(dt);
+        if !self.roll.tick(dt) || self.level <= 0.0 || self.burst.is_cooling_down() {
+            return false;
+        }
+
+        // scale the odds of a burst by the corruption level
+        let chance = (self.level * ROLL_INTERVAL as f32) as u32;
+        let mut rng = rng::get(Stream::Corruption);
+        if chance > 0 && rng.gen_ratio(chance, AVERAGE_BURST_INTERVAL) {
+            self.burst.cool_down();
+            self.swap_palette = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// strength of the full-screen glitch for this frame
+    pub fn intensity(&self) -> f32 {
+        let burst = if self.burst.is_cooling_down() {
+            self.level * (1.0 - self.burst.progress())
+        } else {
+            0.0
+        };
+        burst.max(self.event)
+    }
+
+    /// the palette to draw this frame with
+    /// swaps color channels on the first frame of a burst
+    pub fn palette(&mut self, palette: Palette) -> Palette {
+        if self.swap_palette {
+            self.swap_palette = false;
+            palette.swap_channels()
+        } else {
+            palette
+        }
+    }
+}
@@ -0,0 +1,45 @@
+// what the dialog's titled
+const TITLE: &str = "snek?";
+
+/// tells the player why the game can't go on
+/// there's usually no terminal to read on windows, so it pops up a message box
+pub fn show_error(message: &str) {
+    log::error!("{message}");
+    self::show(message);
+}
+
+#[cfg(windows)]
+fn show(message: &str) {
+    use std::{ffi::c_void, ptr::null_mut};
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn MessageBoxW(window: *mut c_void, text: *const u16, caption: *const u16, kind: u32) -> i32;
+    }
+    const MB_ICONERROR: u32 = 0x10;
+
+    let wide = |s: &str| s.encode_utf16().chain(std::iter::once(0)).collect::<Vec<_>>();
+    let (text, caption) = (wide(message), wide(TITLE));
+    unsafe { MessageBoxW(null_mut(), text.as_ptr(), caption.as_ptr(), MB_ICONERROR) };
+}
+
+// whichever dialog tool is around, stderr already has it otherwise
+#[cfg(not(windows))]
+fn show(message: &str) {
+    use std::process::Command;
+
+    let tools: [&[&str]; 2] = [
+        &["zenity", "--error", "--no-markup", "--title", TITLE, "--text"],
+        &["kdialog", "--title", TITLE, "--error"],
+    ];
+    for tool in tools {
+        let shown = Command::new(tool[0])
+            .args(&tool[1..])
+            .arg(message)
+            .status()
+            .is_ok_and(|s| s.success());
+        if shown {
+            return;
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use crate::math::lerp;
+
+// how many procedural rooms it takes for the curve to flatten out
+const ROOMS_TO_PEAK: usize = 15;
+// enemies added to every wave, per procedural room
+const ENEMIES_PER_ROOM: f32 = 0.5;
+// hit points added to tough enemies, per procedural room
+const HP_PER_ROOM: f32 = 1.0 / 3.0;
+const HP_CAP: (i32, i32) = (6, 9);
+const WAVE_INTERVAL: (Duration, Duration) = (Duration::from_millis(8000), Duration::from_millis(5000));
+const FRUIT_SCALE: (f32, f32) = (1.0, 0.5);
+const HAZARD_SPEED: (f32, f32) = (4.0, 7.0);
+const SPIKES: (f32, f32) = (4.0, 12.0);
+
+/// how hard a procedural room is
+/// every knob follows the same curve, driven by how many procedural rooms came before
+#[derive(Debug, Clone, Copy)]
+pub struct Difficulty {
+    pub extra_enemies: usize,
+    pub extra_hp: i32,
+    pub hp_cap: i32,
+    /// how long until the next wave comes in uncleared
+    pub wave_interval: Duration,
+    pub hazard_speed: f32,
+    /// spike tiles scattered around rooms that have them
+    pub spikes: usize,
+    // what fraction of the usual fruits show up
+    fruit_scale: f32,
+}
+
+impl Difficulty {
+    /// depth is the number of procedural rooms cleared so far
+    pub fn at(depth: usize) -> Self {
+        let level = (depth as f32 / ROOMS_TO_PEAK as f32).min(1.0);
+        let between = |(from, to): (f32, f32)| lerp(from, to, level);
+
+        Self {
+            extra_enemies: (ENEMIES_PER_ROOM * depth as f32) as usize,
+            extra_hp: (HP_PER_ROOM * depth as f32) as i32,
+            hp_cap: between((HP_CAP.0 as f32, HP_CAP.1 as f32)).round() as i32,
+            wave_interval: WAVE_INTERVAL.0.mul_f32(1.0 - level) + WAVE_INTERVAL.1.mul_f32(level),
+            fruit_scale: between(FRUIT_SCALE),
+            hazard_speed: between(HAZARD_SPEED),
+            spikes: between(SPIKES).round() as usize,
+        }
+    }
+
+    /// fruits get rarer the deeper the room, but there's always one
+    pub fn fruits(&self, usual: i32) -> i32 {
+        ((usual as f32 * self.fruit_scale).round() as i32).max(1)
+    }
+
+    pub fn hp(&self, base: i32) -> i32 {
+        (base + self.extra_hp).min(self.hp_cap)
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    cell::{Cell, RefCell},
+    time::Duration,
+};
+
+use rand::Rng;
+
+use crate::{
+    entity::EntityView,
+    rng::{self, Stream},
+};
+
+// procedural rooms in between each modifier stacking on
+const ROOMS_PER_MODIFIER: usize = 3;
+// inverted controls glitch in for this long out of every period
+const INVERT_PERIOD: Duration = Duration::from_millis(8000);
+const INVERT_LENGTH: Duration = Duration::from_millis(2500);
+
+/// what endless mode stacks on a run the deeper it gets
+/// unlike mutators they kick in mid-run, so they're read as they go instead of through the tunables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Darkness,
+    FastEnemies,
+    NoSmoothing,
+    InvertedControls,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 4] = [
+        Modifier::Darkness,
+        Modifier::FastEnemies,
+        Modifier::NoSmoothing,
+        Modifier::InvertedControls,
+    ];
+
+    /// shown in the room it kicks in
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Darkness => "LIGHTS OUT",
+            Self::FastEnemies => "DOUBLE TIME",
+            Self::NoSmoothing => "NO SMOOTHING",
+            Self::InvertedControls => "CROSSED WIRES",
+        }
+    }
+}
+
+thread_local! {
+    static ENDLESS: Cell<bool> = const { Cell::new(false) };
+    // in the order they kicked in
+    static ACTIVE: RefCell<Vec<Modifier>> = const { RefCell::new(Vec::new()) };
+    // how long the snake's been going, for glitching the controls in and out
+    static CLOCK: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// turns endless mode on for the run, with nothing stacked yet
+pub fn start() {
+    ENDLESS.set(true);
+    ACTIVE.with_borrow_mut(Vec::clear);
+    CLOCK.set(Duration::ZERO);
+}
+
+pub fn is_endless() -> bool {
+    ENDLESS.get()
+}
+
+pub fn active() -> Vec<Modifier> {
+    ACTIVE.with_borrow(Clone::clone)
+}
+
+pub fn is_active(modifier: Modifier) -> bool {
+    ACTIVE.with_borrow(|a| a.contains(&modifier))
+}
+
+/// called as every procedural room is made, with how deep past the tutorial it is
+/// every few rooms one that isn't on yet gets stacked on, and returned so the room can announce it
+pub fn descend(depth: usize) -> Option<Modifier> {
+    if !self::is_endless() || depth == 0 || !depth.is_multiple_of(ROOMS_PER_MODIFIER) {
+        return None;
+    }
+
+    ACTIVE.with_borrow_mut(|active| {
+        let left: Vec<_> = Modifier::ALL.into_iter().filter(|m| !active.contains(m)).collect();
+        if left.is_empty() {
+            return None;
+        }
+        let modifier = left[rng::get(Stream::World).gen_range(0..left.len())];
+        log::info!("endless mode stacked on {modifier:?}");
+        active.push(modifier);
+        Some(modifier)
+    })
+}
+
+/// the snake's side of the modifiers, every tick
+pub fn snake_tick(dt: Duration, snake: &mut EntityView) {
+    CLOCK.set(CLOCK.get() + dt);
+    if self::is_active(Modifier::NoSmoothing) {
+        snake.set_property("smoothing", false);
+    }
+}
+
+/// whether the controls are flipped right now
+/// they glitch in and out once inverted controls are on
+pub fn inverted() -> bool {
+    if !self::is_active(Modifier::InvertedControls) {
+        return false;
+    }
+    let into_period = CLOCK.get().as_millis() % INVERT_PERIOD.as_millis();
+    into_period >= (INVERT_PERIOD - INVERT_LENGTH).as_millis()
+}
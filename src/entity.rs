@@ -2,20 +2,28 @@ use core::fmt;
 use std::{
     any::Any,
     cell::{Ref, RefCell, RefMut},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    panic,
     rc::Rc,
     sync::mpsc::{self, Receiver, Sender},
+    thread,
     time::Duration,
 };
 
 use glfw::Key;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use crate::{
-    archetype::oneshot, math::{self, Vec2, Vec3}, palette::{Palette, PaletteKey}, render::RenderManager, sound::Player, time
+    animation::Pose, archetype::oneshot, common::{Error, Result}, camera::{Effects, Kicker}, corruption::{Corruption, Glitcher}, event::{Event, EventBus, EventKind}, math::{self, Rect, TileCoord, Vec2, Vec3}, palette::{Palette, PaletteKey}, render::{particles::{Burst, Emitter}, post::PointLight, RenderManager}, rng::{self, Stream}, sound::{Player, SoundManager, Sounds}, status::Status, time
 };
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+// glows, shields and particles spill past an entity's tile
+const CULL_MARGIN: f32 = 2.0;
+// below this many components, starting threads costs more than ticking them all on one
+const PARALLEL_MIN: usize = 2048;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Entities {
     #[default]
     Basic,
@@ -31,6 +39,15 @@ pub enum Entities {
     Text,
     Logic,
     Enemy,
+    WaveSpawner,
+    Annotation,
+    Label,
+    Scenery,
+    Pickup,
+    Hazard,
+    Terrain,
+    Obstacle,
+    Portal,
 }
 
 impl fmt::Display for Entities {
@@ -40,17 +57,85 @@ impl fmt::Display for Entities {
 }
 
 impl Entities {
+    /// which per-room budget the entity counts towards
+    pub fn budget(self) -> Option<Budget> {
+        match self {
+            Self::Fireball | Self::Swoop => Some(Budget::Projectile),
+            _ => None,
+        }
+    }
+
+    /// spawned and killed so often that their ids get handed out again
+    fn pooled(self) -> bool {
+        matches!(self, Self::Fireball | Self::Swoop | Self::SnakeBody)
+    }
+
+    /// world space area the entity draws in, for culling
+    /// entities that draw to the hud or don't have a position are always drawn
+    fn bounds(self, entity: &EntityView) -> Option<Rect> {
+        match self {
+            Self::Fruit
+            | Self::SnakeBody
+            | Self::Enemy
+            | Self::Fireball
+            | Self::Swoop
+            | Self::Pickup
+            | Self::Hazard
+            | Self::Terrain
+            | Self::Obstacle
+            | Self::Portal => {
+                let pos = Vec2::from(entity.try_get_position()?);
+                Some(Rect::new(pos, pos + Vec2::diagonal(1.0)).grow(CULL_MARGIN))
+            }
+            _ => None,
+        }
+    }
+
+    /// what the entity's tick and draw can't do without
+    fn requires(self) -> &'static [Components] {
+        use Components as C;
+        match self {
+            Self::SnakeHead => &[
+                C::Position,
+                C::Direction,
+                C::Input,
+                C::BodyLength,
+                C::Timer,
+                C::Spawner,
+                C::StatusEffects,
+                C::Properties,
+                C::Sound,
+            ],
+            Self::Fireball => &[C::Position, C::Direction, C::Kinematics, C::Scale, C::Color, C::FadeIn, C::Properties],
+            Self::Swoop => &[C::Position, C::Direction, C::Scale, C::Spawner, C::Animation],
+            Self::Label => &[C::Position, C::Animation, C::Properties],
+            Self::Text => &[C::Position, C::Spawner, C::Glitch, C::TextGlitch, C::Properties],
+            Self::WaveSpawner => &[C::Timer, C::Spawner, C::Properties],
+            Self::Obstacle => &[C::Position, C::Timer, C::Properties],
+            Self::Enemy => &[C::Position, C::StatusEffects, C::Shielding],
+            Self::Fruit => &[C::Position, C::Animation, C::Properties],
+            Self::Pickup => &[C::Position, C::Color],
+            Self::Hazard => &[C::Position, C::Kinematics, C::Properties],
+            Self::SnakeBody | Self::Terrain => &[C::Position, C::Properties],
+            // cracked walls also have health and properties, plain ones aren't drawn on their own
+            Self::Wall | Self::Portal => &[C::Position],
+            Self::Scenery => &[C::Color, C::Properties],
+            Self::Logic => &[C::Properties],
+            Self::Basic | Self::Background | Self::_Enemy | Self::Trigger | Self::Annotation => &[],
+        }
+    }
+
     pub fn tick(self, dt: Duration, entity: &mut EntityView<'_>) {
         use crate::archetype::*;
 
         match self {
             Self::SnakeHead => snake::head_tick(dt, entity),
-            Self::SnakeBody => snake::body_tick(dt, entity),
-            Self::Fireball => fireball::tick(dt, entity),
-            Self::Swoop => swoop::tick(dt, entity),
+            Self::Swoop => swoop::tick(entity),
             Self::Text => text::tick(dt, entity),
             Self::Logic => logic::tick(dt, entity),
-            Self::Enemy => enemy::tick(dt, entity),
+            Self::WaveSpawner => wave_spawner::tick(dt, entity),
+            Self::Hazard => hazard::tick(entity),
+            Self::Obstacle => obstacle::tick(dt, entity),
             _ => (),
         }
     }
@@ -59,14 +144,20 @@ impl Entities {
         use crate::archetype::*;
 
         match self {
+            Self::Scenery => scenery::draw(entity, renderer, palette),
             Self::Wall => wall::draw(entity, renderer, palette),
-            Self::Background => background::draw(entity, renderer, palette),
             Self::Fruit => fruit::draw(entity, renderer, palette),
             Self::SnakeHead | Self::SnakeBody => snake::draw(entity, renderer, palette),
             Self::Fireball => fireball::draw(entity, renderer, palette),
             Self::Swoop => swoop::draw(entity, renderer),
             Self::Text => text::draw(entity, renderer),
             Self::Enemy => enemy::draw(entity, renderer, palette),
+            Self::Label => label::draw(entity, renderer),
+            Self::Pickup => pickup::draw(entity, renderer, palette),
+            Self::Hazard => hazard::draw(entity, renderer, palette),
+            Self::Terrain => terrain::draw(entity, renderer, palette),
+            Self::Obstacle => obstacle::draw(entity, renderer, palette),
+            Self::Portal => portal::draw(entity, renderer, palette),
             _ => (),
         }
     }
@@ -79,15 +170,25 @@ pub enum Components {
     Collider,
     Input,
     BodyLength,
-    SelfDestruct,
     Scale,
     Timer,
     Spawner,
     Animation,
     Color,
-    Speed,
+    Kinematics,
+    Parent,
+    Lifetime,
+    StatusEffects,
+    Health,
     Properties,
     Sound,
+    Particles,
+    Camera,
+    Glitch,
+    Light,
+    FadeIn,
+    Shielding,
+    TextGlitch,
 }
 
 impl fmt::Display for Components {
@@ -97,11 +198,39 @@ impl fmt::Display for Components {
 }
 
 impl Components {
+    pub const ALL: [Components; 24] = [
+        Components::Position,
+        Components::Direction,
+        Components::Collider,
+        Components::Input,
+        Components::BodyLength,
+        Components::Scale,
+        Components::Timer,
+        Components::Spawner,
+        Components::Animation,
+        Components::Color,
+        Components::Kinematics,
+        Components::Parent,
+        Components::Lifetime,
+        Components::StatusEffects,
+        Components::Health,
+        Components::Properties,
+        Components::Sound,
+        Components::Particles,
+        Components::Camera,
+        Components::Glitch,
+        Components::Light,
+        Components::FadeIn,
+        Components::Shielding,
+        Components::TextGlitch,
+    ];
+
     fn _requires(self) -> Vec<Components> {
         use Components as C;
         match self {
             C::Direction => vec![C::Position],
             C::Collider => vec![C::Position],
+            C::Light => vec![C::Position],
             _ => vec![],
         }
     }
@@ -109,6 +238,25 @@ impl Components {
 
 pub type Position = crate::math::Vec3;
 
+/// best guess at what a property holds
+/// they're stringly typed, so this can only try the usual suspects
+fn describe(value: &dyn Any) -> String {
+    macro_rules! try_types {
+        ($($t:ty),*) => {
+            $(if let Some(v) = value.downcast_ref::<$t>() {
+                return format!("{v:?}");
+            })*
+        };
+    }
+
+    try_types!(
+        bool, i32, u32, i16, usize, f32, Duration, String, &'static str, Vec2, Vec3, Direction,
+        Option<EntityId>, Vec<EntityId>, Vec<Direction>, std::collections::VecDeque<EntityId>,
+        Option<Status>, Option<crate::archetype::terrain::Kind>, crate::archetype::annotation::Annotation
+    );
+    "?".to_string()
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum Direction {
     #[default]
@@ -152,7 +300,7 @@ impl Direction {
             Direction::Left,
         ];
 
-        let mut rng = thread_rng();
+        let mut rng = rng::get(Stream::World);
         let idx = rng.gen_range(0..CHOICES.len());
 
         CHOICES[idx]
@@ -215,24 +363,49 @@ impl Collider {
         use crate::archetype::*;
         use Entities as E;
         if let Some((head, fruit)) = Self::is_between(E::SnakeHead, E::Fruit, e1, e2) {
+            if let Some(status) = fruit::boost(fruit) {
+                snake::boost(head, status);
+            }
             fruit::respawn(fruit);
             snake::grow(head);
         } else if let Some((head, _body)) = Self::is_between(E::SnakeHead, E::SnakeBody, e1, e2) {
-            snake::die_sequence(head);
+            snake::hurt(head);
         } else if let Some((head, _wall)) = Self::is_between(E::SnakeHead, E::Wall, e1, e2) {
             snake::die_sequence(head);
-        } else if let Some((fireball, _wall)) = Self::is_between(E::Fireball, E::Wall, e1, e2) {
-            fireball.kill();
+        } else if let Some((head, _obstacle)) = Self::is_between(E::SnakeHead, E::Obstacle, e1, e2) {
+            snake::die_sequence(head);
+        } else if let Some((_obstacle, body)) = Self::is_between(E::Obstacle, E::SnakeBody, e1, e2) {
+            snake::trim(body);
+        } else if let Some((_fireball, body)) = Self::is_between(E::Fireball, E::SnakeBody, e1, e2) {
+            if crate::tunables::get().fragile_body {
+                snake::shatter(body);
+            }
+        } else if let Some((fireball, wall)) = Self::is_between(E::Fireball, E::Wall, e1, e2) {
+            fireball::hit_wall(fireball, wall);
         } else if let Some((fireball, enemy)) = Self::is_between(E::Fireball, E::Enemy, e1, e2) {
-            fireball.kill();
-            enemy::hit(enemy);
+            if fireball::hit_enemy(fireball, enemy) && enemy::hit(enemy) {
+                snake::notify_kill(fireball);
+            }
+        } else if let Some((fireball, obstacle)) = Self::is_between(E::Fireball, E::Obstacle, e1, e2) {
+            fireball::hit_wall(fireball, obstacle);
         } else if let Some((swoop, _wall)) = Self::is_between(E::Swoop, E::Wall, e1, e2) {
             swoop.kill();
-        } else if let Some((swoop, enemy)) = Self::is_between(E::Swoop, E::Enemy, e1, e2) {
+        } else if let Some((swoop, _obstacle)) = Self::is_between(E::Swoop, E::Obstacle, e1, e2) {
             swoop.kill();
-            enemy::hit(enemy);
-        } else if let Some((snake, enemy)) = Self::is_between(E::SnakeHead, E::Enemy, e1, e2) {
-            snake::die_sequence(snake);
+        } else if let Some((snake, _enemy)) = Self::is_between(E::SnakeHead, E::Enemy, e1, e2) {
+            snake::hurt(snake);
+        } else if let Some((snake, _hazard)) = Self::is_between(E::SnakeHead, E::Hazard, e1, e2) {
+            snake::hurt(snake);
+        } else if let Some((head, portal)) = Self::is_between(E::SnakeHead, E::Portal, e1, e2) {
+            portal::enter(portal, head);
+        } else if let Some((head, tile)) = Self::is_between(E::SnakeHead, E::Terrain, e1, e2) {
+            terrain::step_on(tile, head);
+        } else if let Some((hazard, body)) = Self::is_between(E::Hazard, E::SnakeBody, e1, e2) {
+            if hazard::trims(hazard) {
+                snake::trim(body);
+            }
+        } else if let Some((head, pickup)) = Self::is_between(E::SnakeHead, E::Pickup, e1, e2) {
+            pickup::collect(pickup, head);
         }
         else if let Some((trigger, other)) = Self::at_least(Entities::Trigger, e1, e2) {
             trigger::activated(trigger, other);
@@ -271,30 +444,398 @@ impl Input {
         self.key_rx.try_recv().ok()
     }
 
+    /// forget every key that hasn't been handled yet
+    pub fn flush(&mut self) {
+        while self.key_rx.try_recv().is_ok() {}
+    }
+
     pub fn get_mouse(&self) -> Vec2 {
         self.mouse_pos
     }
 }
 
+/// velocity driven movement, integrated once per tick by the entity manager
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Kinematics {
+    // in tiles per second
+    pub velocity: Vec2,
+    // in tiles per second squared, for gravity or thrust
+    pub acceleration: Vec2,
+    // fraction of the velocity lost every second
+    pub drag: f32,
+    // zero leaves the speed unbounded
+    pub max_speed: f32,
+}
+
+impl Kinematics {
+    /// keeps going at the same velocity forever
+    pub fn cruising(velocity: Vec2) -> Self {
+        Self {
+            velocity,
+            ..Default::default()
+        }
+    }
+
+    // how far to move this tick
+    fn step(&mut self, dt: Duration) -> Vec2 {
+        let dt = dt.as_secs_f32();
+        self.velocity += dt * self.acceleration;
+        self.velocity = (1.0 - self.drag).max(0.0).powf(dt) * self.velocity;
+
+        let speed = self.velocity.len();
+        if self.max_speed > 0.0 && speed > self.max_speed {
+            self.velocity = (self.max_speed / speed) * self.velocity;
+        }
+
+        dt * self.velocity
+    }
+}
+
+/// a glow around the entity, which is also all that can be seen of dark rooms
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Light {
+    pub color: PaletteKey,
+    // in tiles
+    pub radius: f32,
+    // zero only lights up the dark
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn new(color: PaletteKey, radius: f32, intensity: f32) -> Self {
+        Self { color, radius, intensity }
+    }
+}
+
+/// goes from invisible to fully there over its length, for fireballs ramping up
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FadeIn {
+    elapsed: Duration,
+    length: Duration,
+}
+
+impl FadeIn {
+    pub fn new(length: Duration) -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            length,
+        }
+    }
+
+    pub fn alpha(&self) -> f32 {
+        if self.length.is_zero() {
+            1.0
+        } else {
+            math::ease::in_expo(self.elapsed.as_secs_f32() / self.length.as_secs_f32())
+        }
+    }
+
+    fn tick(&mut self, dt: Duration) {
+        self.elapsed = (self.elapsed + dt).min(self.length);
+    }
+}
+
+/// an enemy's shield, flaring up when it's hit and powering back down after
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Shielding {
+    // how much shows once it's powered down, from the hits left
+    pub power: f32,
+    pub powerdown: time::Cooldown,
+    alpha: f32,
+}
+
+impl Shielding {
+    pub fn new(powerdown: Duration) -> Self {
+        Self {
+            powerdown: time::Cooldown::new(powerdown),
+            ..Default::default()
+        }
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    // slowed down along with the rest of the enemy
+    fn tick(&mut self, dt: Duration, pace: f32) {
+        self.powerdown.tick(dt.mul_f32(pace));
+        let delta = 1.0 - self.power;
+        self.alpha = 1.0 - delta * math::ease::in_expo(self.powerdown.progress());
+    }
+}
+
+/// text that every so often shows one of its glitched frames for a moment
+/// its light flares up for as long as the glitch frame shows
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TextGlitch {
+    pub frame: usize,
+    frames: usize,
+    pub enabled: bool,
+    // how long a frame lasts, each one has a chance of glitching
+    timer: Timer,
+    // how long between glitches on average
+    interval: Duration,
+    glow: f32,
+    flare: f32,
+}
+
+impl TextGlitch {
+    pub fn new(frames: usize, tick: Duration, interval: Duration, glow: f32, flare: f32) -> Self {
+        Self {
+            frame: 0,
+            frames,
+            enabled: false,
+            timer: Timer::new(tick),
+            interval,
+            glow,
+            flare,
+        }
+    }
+
+    /// straight to one of the glitched frames
+    pub fn glitch(&mut self) {
+        if self.frames > 1 {
+            self.frame = rand::thread_rng().gen_range(1..self.frames);
+        }
+    }
+
+    pub fn intensity(&self) -> f32 {
+        if self.frame > 0 {
+            self.flare
+        } else {
+            self.glow
+        }
+    }
+
+    // true when a glitch frame starts showing
+    fn tick(&mut self, dt: Duration) -> bool {
+        if !self.timer.tick(dt) || !self.enabled {
+            return false;
+        }
+
+        // glitch frames only last the one frame
+        self.frame = 0;
+        let tick = self.timer.threshold().as_millis() as u32;
+        let interval = self.interval.as_millis() as u32;
+        if self.frames > 1 && rand::thread_rng().gen_ratio(tick.min(interval), interval.max(1)) {
+            self.glitch();
+            return true;
+        }
+        false
+    }
+}
+
+/// who an entity is attached to, see [`EntityManager::attach`]
+/// entities start out attached to themselves, which is the same as having no parent
+#[derive(Debug, Clone, Copy)]
+pub struct Parent {
+    pub id: EntityId,
+    // from the parent's position
+    pub offset: Position,
+}
+
+/// how long an entity has left before the entity manager kills it off
+/// the default one expires on the first tick
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Lifetime {
+    elapsed: Duration,
+    length: Duration,
+}
+
+impl Lifetime {
+    pub fn new(length: Duration) -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            length,
+        }
+    }
+
+    /// from 0 when spawned to 1 when expired, for fading out
+    pub fn _progress(&self) -> f32 {
+        if self.length.is_zero() {
+            1.0
+        } else {
+            (self.elapsed.as_secs_f32() / self.length.as_secs_f32()).min(1.0)
+        }
+    }
+
+    // true once expired
+    fn tick(&mut self, dt: Duration) -> bool {
+        self.elapsed += dt;
+        self.elapsed >= self.length
+    }
+}
+
 pub type BodyLength = i16;
-pub type SelfDestruct = i16;
 pub type Scale = crate::math::Vec2;
+pub type Health = i32;
 pub type Timer = time::Threshold;
 pub type Property = Rc<RefCell<dyn Any>>;
 pub type Properties = HashMap<&'static str, Property>;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq)]
-pub enum Animation {
-    #[default]
-    Idle,
-    _Growing,
+/// a property's name along with what it holds, declared once as a const
+/// so a typo or a changed type fails to compile instead of panicking mid-run
+pub struct PropKey<T> {
+    name: &'static str,
+    holds: PhantomData<fn() -> T>,
+}
+
+impl<T> PropKey<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            holds: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> Clone for PropKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PropKey<T> {}
+
+/// what property methods take, a typed key or a bare name for the older stringly typed ones
+pub trait PropName<T> {
+    fn name(&self) -> &'static str;
+}
+
+impl<T> PropName<T> for &'static str {
+    fn name(&self) -> &'static str {
+        self
+    }
+}
+
+impl<T> PropName<T> for PropKey<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
 }
 
+pub type Animation = crate::animation::Animation;
+pub type StatusEffects = crate::status::StatusEffects;
+
 pub type Color = PaletteKey;
-pub type Speed = f32;
 pub type Sound = Player;
+pub type Particles = Emitter;
+pub type Camera = Kicker;
+pub type Glitch = Glitcher;
+
+/// a component that holds a plain value,
+/// read and written through [`EntityView::get`] and [`EntityView::set`]
+pub trait Component {
+    type Value: Clone;
+    const KIND: Components;
 
-pub type EntityId = usize;
+    fn storage(storages: &Storages) -> &Storage<Self::Value>;
+    fn storage_mut(storages: &mut Storages) -> &mut Storage<Self::Value>;
+
+    fn set(storages: &mut Storages, entity: EntityId, value: Self::Value) {
+        Self::storage_mut(storages).insert(entity, value);
+    }
+}
+
+// every marker is named after its component and stores the type of the same name
+// components that need to do more than store the value name a setter on Storages
+macro_rules! components {
+    ($($name:ident => $field:ident $(, set = $set:ident)?;)*) => {
+        /// markers naming each value component, e.g. `entity.get::<component::Position>()`
+        pub mod component {
+            $(pub struct $name;)*
+        }
+
+        $(impl Component for component::$name {
+            type Value = $name;
+            const KIND: Components = Components::$name;
+
+            fn storage(storages: &Storages) -> &Storage<$name> {
+                &storages.$field
+            }
+
+            fn storage_mut(storages: &mut Storages) -> &mut Storage<$name> {
+                &mut storages.$field
+            }
+
+            $(fn set(storages: &mut Storages, entity: EntityId, value: $name) {
+                storages.$set(entity, value)
+            })?
+        })*
+    };
+}
+
+components! {
+    Position => positions, set = set_position;
+    Direction => directions;
+    BodyLength => body_lengths;
+    Scale => scales;
+    Animation => animations;
+    Color => colors;
+    Kinematics => kinematics;
+    Parent => parents;
+    Lifetime => lifetimes;
+    StatusEffects => status_effects;
+    Health => healths;
+    Sound => sounds;
+    Particles => particles;
+    Camera => cameras;
+    Glitch => glitches;
+    Light => lights;
+    FadeIn => fades;
+    Shielding => shieldings;
+    TextGlitch => text_glitches;
+}
+
+/// a slot in the storages, along with how many entities had the slot before
+/// slots get reused, the generation tells a stale id from the entity that took over its slot
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityId {
+    index: usize,
+    generation: u32,
+}
+
+impl EntityId {
+    /// the next entity to get the slot
+    fn next(self) -> Self {
+        Self {
+            index: self.index,
+            generation: self.generation.wrapping_add(1),
+        }
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.generation {
+            0 => write!(f, "{}", self.index),
+            g => write!(f, "{}v{g}", self.index),
+        }
+    }
+}
+
+/// kinds of entities that can be spammed
+/// each room caps how many of them can be alive at once
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Budget {
+    Projectile,
+}
+
+impl Budget {
+    pub const ALL: [Budget; 1] = [Budget::Projectile];
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BudgetUsage {
+    pub limit: usize,
+    pub alive: usize,
+    // how many were killed off early to stay within the limit
+    pub evicted: usize,
+}
 
 pub struct EntityView<'m> {
     id: EntityId,
@@ -322,10 +863,19 @@ impl<'m> EntityView<'m> {
         let _ = self.kill_signal.send(self.id);
     }
 
-    pub fn _id(&self) -> EntityId {
+    pub fn id(&self) -> EntityId {
         self.id
     }
 
+    /// goes out to subscribers at the end of the tick
+    pub fn publish(&self, kind: EventKind) {
+        let event = Event {
+            kind,
+            source: self.id,
+        };
+        let _ = self.storage().events.send(event);
+    }
+
     pub fn which(&self) -> Entities {
         self.type_
     }
@@ -342,43 +892,86 @@ impl<'m> EntityView<'m> {
         t.expect(&format!("{} should have {}", self.type_, component))
     }
 
+    pub fn get<C: Component>(&self) -> C::Value {
+        self.unwrap(self.try_get::<C>(), C::KIND)
+    }
+
+    /// for components the entity might not have
+    pub fn try_get<C: Component>(&self) -> Option<C::Value> {
+        self.storage().get::<C>(self.id)
+    }
+
+    pub fn set<C: Component>(&mut self, value: C::Value) {
+        self.storage_mut().set::<C>(self.id, value)
+    }
+
+    /// change a component in place
+    /// skips whatever else setting it would do, like checking for collisions
+    pub fn with_mut<C: Component, R>(&mut self, f: impl FnOnce(&mut C::Value) -> R) -> R {
+        let mut storage = self.storage_mut();
+        let value = C::storage_mut(&mut storage).get_mut(&self.id);
+        f(self.unwrap(value, C::KIND))
+    }
+
     pub fn get_sound(&self) -> Sound {
-        self.unwrap(self.storage().get_sound(self.id), Components::Sound)
+        self.get::<component::Sound>()
+    }
+
+    pub fn get_particles(&self) -> Particles {
+        self.get::<component::Particles>()
     }
 
-    pub fn new_property(&self, name: &'static str, value: impl Any) {
-        self.storage_mut().new_property(self.id, name, value)
+    pub fn get_camera(&self) -> Camera {
+        self.get::<component::Camera>()
+    }
+
+    pub fn get_glitch(&self) -> Glitch {
+        self.get::<component::Glitch>()
+    }
+
+    pub fn new_property<T: 'static>(&self, name: impl PropName<T>, value: T) {
+        self.storage_mut().new_property(self.id, name.name(), value)
     }
 
     pub fn remove_property(&self, name: &str) {
         self.storage_mut().remove_property(self.id, name);
     }
 
+    pub fn has(&self, component: Components) -> bool {
+        self.storage().has_component(self.id, component)
+    }
+
     pub fn has_property(&self, name: &str) -> bool {
         self.storage().get_property(self.id, name).is_some()
     }
 
-    pub fn get_property<T: Clone + 'static>(&self, name: &str) -> T {
-        self.with_property::<T, T>(name, |f| f.clone())
+    pub fn get_property<T: Clone + 'static>(&self, name: impl PropName<T>) -> T {
+        self.with_property(name, |f: &T| f.clone())
     }
 
-    pub fn set_property<T: 'static>(&self, name: &str, value: T) {
+    pub fn set_property<T: 'static>(&self, name: impl PropName<T>, value: T) {
         self.with_mut_property(name, |f| *f = value);
     }
 
-    pub fn with_property<P: 'static, R>(&self, name: &str, f: impl FnOnce(&P) -> R) -> R {
+    /// for properties an entity might not have, instead of checking has_property first
+    pub fn try_with_property<P: 'static, R>(&self, name: impl PropName<P>, f: impl FnOnce(&P) -> R) -> Result<R> {
+        let name = name.name();
         let prop = self
             .storage()
             .get_property(self.id, name)
-            .expect("entity should have specified property");
+            .ok_or(Error::MissingProperty(name))?;
         let any = prop.borrow();
-        let p = any
-            .downcast_ref()
-            .expect("property should have matching type");
-        f(p)
+        let p = any.downcast_ref().ok_or(Error::PropertyType(name))?;
+        Ok(f(p))
+    }
+
+    pub fn with_property<P: 'static, R>(&self, name: impl PropName<P>, f: impl FnOnce(&P) -> R) -> R {
+        self.try_with_property(name, f)
+            .expect("entity should have specified property, of matching type")
     }
 
-    pub fn with_mut_property<P: 'static, R>(&self, name: &str, f: impl FnOnce(&mut P) -> R) -> R {
+    pub fn with_mut_property<P: 'static, R>(&self, name: impl PropName<P>, f: impl FnOnce(&mut P) -> R) -> R {
+        let name = name.name();
         let prop = self
             .storage()
             .get_property(self.id, name)
@@ -391,48 +984,39 @@ impl<'m> EntityView<'m> {
     }
 
     pub fn get_position(&self) -> Position {
-        self.unwrap(self.storage().get_position(self.id), Components::Position)
+        self.get::<component::Position>()
     }
+
+    pub fn try_get_position(&self) -> Option<Position> {
+        self.try_get::<component::Position>()
+    }
+
     pub fn set_position(&mut self, position: Position) {
-        self.storage_mut().set_position(self.id, position)
+        self.set::<component::Position>(position)
     }
 
     pub fn get_direction(&self) -> Direction {
-        self.unwrap(self.storage().get_direction(self.id), Components::Direction)
+        self.get::<component::Direction>()
     }
 
     pub fn set_direction(&mut self, direction: Direction) {
-        self.storage_mut().set_direction(self.id, direction)
+        self.set::<component::Direction>(direction)
     }
 
     pub fn get_body_length(&self) -> BodyLength {
-        self.unwrap(
-            self.storage().get_body_length(self.id),
-            Components::BodyLength,
-        )
+        self.get::<component::BodyLength>()
     }
 
     pub fn set_body_length(&mut self, body_length: BodyLength) {
-        self.storage_mut().set_body_length(self.id, body_length);
-    }
-
-    pub fn get_self_destruct(&self) -> SelfDestruct {
-        self.unwrap(
-            self.storage().get_self_destruct(self.id),
-            Components::SelfDestruct,
-        )
-    }
-
-    pub fn set_self_destruct(&mut self, self_destruct: SelfDestruct) {
-        self.storage_mut().set_self_destruct(self.id, self_destruct);
+        self.set::<component::BodyLength>(body_length)
     }
 
     pub fn get_scale(&self) -> Scale {
-        self.unwrap(self.storage().get_scale(self.id), Components::Scale)
+        self.get::<component::Scale>()
     }
 
     pub fn set_scale(&mut self, scale: Scale) {
-        self.storage_mut().set_scale(self.id, scale)
+        self.set::<component::Scale>(scale)
     }
 
     pub fn get_key(&mut self) -> Option<Key> {
@@ -443,31 +1027,22 @@ impl<'m> EntityView<'m> {
         self.unwrap(self.storage().get_mouse(self.id), Components::Input)
     }
 
-    pub fn _get_animation(&self) -> Animation {
-        self.unwrap(
-            self.storage()._get_animation(self.id),
-            Components::Animation,
-        )
+    /// where the animation is at, without copying its tracks
+    pub fn get_pose(&self) -> Pose {
+        let pose = self.storage().animations.get(&self.id).map(Animation::pose);
+        self.unwrap(pose, Components::Animation)
     }
 
     pub fn set_animation(&mut self, animation: Animation) {
-        self.storage_mut().set_animation(self.id, animation)
+        self.set::<component::Animation>(animation)
     }
 
     pub fn get_color(&self) -> Color {
-        self.unwrap(self.storage().get_color(self.id), Components::Color)
+        self.get::<component::Color>()
     }
 
     pub fn set_color(&mut self, color: Color) {
-        self.storage_mut().set_color(self.id, color);
-    }
-
-    pub fn get_speed(&self) -> Speed {
-        self.unwrap(self.storage().get_speed(self.id), Components::Speed)
-    }
-
-    pub fn set_speed(&mut self, speed: Speed) {
-        self.storage_mut().set_speed(self.id, speed);
+        self.set::<component::Color>(color)
     }
 
     pub fn access_timer<T>(&mut self, f: impl FnOnce(&mut Timer) -> T) -> T {
@@ -494,108 +1069,363 @@ impl<'m> EntityView<'m> {
     }
 }
 
-type Storage<T> = HashMap<EntityId, T>;
+/// a freshly spawned entity, set up straight through the storages, see [`EntityManager::build`]
+pub struct EntityBuilder<'m> {
+    id: EntityId,
+    storage: &'m mut Storages,
+}
+
+impl EntityBuilder<'_> {
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    pub fn set<C: Component>(&mut self, value: C::Value) -> &mut Self {
+        self.storage.set::<C>(self.id, value);
+        self
+    }
+
+    pub fn new_property<T: 'static>(&mut self, name: impl PropName<T>, value: T) -> &mut Self {
+        self.storage.new_property(self.id, name.name(), value);
+        self
+    }
+}
+
+/// one component for every entity that has it, packed together so systems run over them in one go
+/// found through a table indexed by slot, slots stay few since they get reused
+pub struct Storage<T> {
+    // where in the dense arrays every slot's component is
+    sparse: Vec<Option<usize>>,
+    ids: Vec<EntityId>,
+    values: Vec<T>,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self {
+            sparse: Vec::new(),
+            ids: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    // stale ids find nothing, even if their slot is taken
+    fn index(&self, entity: EntityId) -> Option<usize> {
+        let i = self.sparse.get(entity.index).copied().flatten()?;
+        (self.ids[i] == entity).then_some(i)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains_key(&self, entity: &EntityId) -> bool {
+        self.index(*entity).is_some()
+    }
+
+    pub fn get(&self, entity: &EntityId) -> Option<&T> {
+        self.index(*entity).map(|i| &self.values[i])
+    }
+
+    pub fn get_mut(&mut self, entity: &EntityId) -> Option<&mut T> {
+        self.index(*entity).map(|i| &mut self.values[i])
+    }
+
+    /// returns what the entity had before
+    /// whatever a stale id left behind in the slot is dropped
+    pub fn insert(&mut self, entity: EntityId, value: T) -> Option<T> {
+        if let Some(i) = self.index(entity) {
+            return Some(std::mem::replace(&mut self.values[i], value));
+        }
+
+        if let Some(stale) = self.sparse.get(entity.index).copied().flatten().map(|i| self.ids[i]) {
+            self.remove(&stale);
+        }
+        if self.sparse.len() <= entity.index {
+            self.sparse.resize(entity.index + 1, None);
+        }
+        self.sparse[entity.index] = Some(self.values.len());
+        self.ids.push(entity);
+        self.values.push(value);
+        None
+    }
+
+    /// the last component moves into the hole, so order isn't kept
+    pub fn remove(&mut self, entity: &EntityId) -> Option<T> {
+        let i = self.index(*entity)?;
+        self.sparse[entity.index] = None;
+        self.ids.swap_remove(i);
+        let value = self.values.swap_remove(i);
+        if let Some(&moved) = self.ids.get(i) {
+            self.sparse[moved.index] = Some(i);
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> std::iter::Zip<std::slice::Iter<'_, EntityId>, std::slice::Iter<'_, T>> {
+        self.ids.iter().zip(self.values.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> std::iter::Zip<std::slice::Iter<'_, EntityId>, std::slice::IterMut<'_, T>> {
+        self.ids.iter().zip(self.values.iter_mut())
+    }
+
+    pub fn values_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.values.iter_mut()
+    }
+}
+
+impl<'s, T> IntoIterator for &'s Storage<T> {
+    type Item = (&'s EntityId, &'s T);
+    type IntoIter = std::iter::Zip<std::slice::Iter<'s, EntityId>, std::slice::Iter<'s, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'s, T> IntoIterator for &'s mut Storage<T> {
+    type Item = (&'s EntityId, &'s mut T);
+    type IntoIter = std::iter::Zip<std::slice::Iter<'s, EntityId>, std::slice::IterMut<'s, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
 
 type EntityManagerRequest = Box<dyn FnOnce(&mut EntityManager)>;
 
-struct Storages {
+/// lists the tick fills and empties every frame
+/// they stick around between frames, so ticking doesn't allocate once they're big enough
+#[derive(Default)]
+struct Scratch {
+    steps: Vec<(EntityId, Vec2)>,
+    expired: Vec<EntityId>,
+    children: Vec<EntityId>,
+    glitched: Vec<EntityId>,
+}
+
+pub struct Storages {
     spawn_requests: Sender<EntityManagerRequest>,
     collisions: Sender<(EntityId, EntityId)>,
+    events: Sender<Event>,
     sound: Sound,
+    particle_emitter: Particles,
+    kicker: Camera,
+    glitcher: Glitch,
 
     positions: Storage<Position>,
     directions: Storage<Direction>,
     colliders: Storage<Collider>,
     keyboards: Storage<Input>,
     body_lengths: Storage<BodyLength>,
-    self_destructs: Storage<SelfDestruct>,
     scales: Storage<Scale>,
     timers: Storage<Timer>,
     spawners: Storage<()>,
     animations: Storage<Animation>,
     colors: Storage<Color>,
-    speeds: Storage<Speed>,
+    kinematics: Storage<Kinematics>,
+    parents: Storage<Parent>,
+    lifetimes: Storage<Lifetime>,
+    status_effects: Storage<StatusEffects>,
+    healths: Storage<Health>,
     properties: Storage<Properties>,
     sounds: Storage<Sound>,
+    particles: Storage<Particles>,
+    cameras: Storage<Camera>,
+    glitches: Storage<Glitch>,
+    lights: Storage<Light>,
+    fades: Storage<FadeIn>,
+    shieldings: Storage<Shielding>,
+    text_glitches: Storage<TextGlitch>,
+
+    // entities that sit still until they're thawed, like the ones in rooms left behind
+    frozen: HashSet<EntityId>,
+    scratch: Scratch,
+    // names key entities can be found by, in the order they were tagged
+    tags: HashMap<&'static str, Vec<EntityId>>,
 }
 
 impl Storages {
     pub fn new(
         spawn_requests: Sender<EntityManagerRequest>,
         collisions: Sender<(EntityId, EntityId)>,
+        events: Sender<Event>,
         sound: Sound,
+        particle_emitter: Particles,
+        kicker: Camera,
+        glitcher: Glitch,
     ) -> Self {
         Self {
             spawn_requests,
             collisions,
+            events,
             sound,
+            particle_emitter,
+            kicker,
+            glitcher,
 
             positions: Default::default(),
             directions: Default::default(),
             colliders: Default::default(),
             keyboards: Default::default(),
             body_lengths: Default::default(),
-            self_destructs: Default::default(),
             scales: Default::default(),
             timers: Default::default(),
             spawners: Default::default(),
             animations: Default::default(),
             colors: Default::default(),
-            speeds: Default::default(),
+            kinematics: Default::default(),
+            parents: Default::default(),
+            lifetimes: Default::default(),
+            status_effects: Default::default(),
+            healths: Default::default(),
             properties: Default::default(),
             sounds: Default::default(),
+            particles: Default::default(),
+            cameras: Default::default(),
+            glitches: Default::default(),
+            lights: Default::default(),
+            fades: Default::default(),
+            shieldings: Default::default(),
+            text_glitches: Default::default(),
+
+            frozen: Default::default(),
+            scratch: Default::default(),
+            tags: Default::default(),
         }
     }
 
     pub fn kill(&mut self, entity: EntityId) {
-        // binary search is legal because entity id is ever-increasing
-        // and insertion happens only at the end (thus keeping the vector sorted)
         self.positions.remove(&entity);
         self.directions.remove(&entity);
         self.colliders.remove(&entity);
         self.keyboards.remove(&entity);
         self.body_lengths.remove(&entity);
-        self.self_destructs.remove(&entity);
         self.scales.remove(&entity);
         self.timers.remove(&entity);
         self.spawners.remove(&entity);
         self.animations.remove(&entity);
         self.colors.remove(&entity);
-        self.speeds.remove(&entity);
+        self.kinematics.remove(&entity);
+        self.parents.remove(&entity);
+        self.lifetimes.remove(&entity);
+        self.status_effects.remove(&entity);
+        self.healths.remove(&entity);
         self.properties.remove(&entity);
         self.sounds.remove(&entity);
+        self.particles.remove(&entity);
+        self.cameras.remove(&entity);
+        self.glitches.remove(&entity);
+        self.lights.remove(&entity);
+        self.fades.remove(&entity);
+        self.shieldings.remove(&entity);
+        self.text_glitches.remove(&entity);
+        self.frozen.remove(&entity);
+        for tagged in self.tags.values_mut() {
+            tagged.retain(|&e| e != entity);
+        }
     }
 
     pub fn add_component(&mut self, entity: EntityId, component: Components) {
         use Components as C;
         match component {
-            C::Position => self.set_position(entity, Position::default()),
-            C::Direction => self.set_direction(entity, Direction::default()),
+            C::Position => self.set::<component::Position>(entity, Position::default()),
+            C::Direction => self.set::<component::Direction>(entity, Direction::default()),
             C::Collider => self.add_collider(entity),
             C::Input => self.add_keyboard(entity),
-            C::BodyLength => self.set_body_length(entity, 0),
-            C::SelfDestruct => self.set_self_destruct(entity, 0),
-            C::Scale => self.set_scale(entity, Scale::diagonal(1.0)),
+            C::BodyLength => self.set::<component::BodyLength>(entity, 0),
+            C::Scale => self.set::<component::Scale>(entity, Scale::diagonal(1.0)),
             C::Timer => {
                 self.timers.insert(entity, Timer::default());
             }
             C::Spawner => {
                 self.spawners.insert(entity, ());
             }
-            C::Animation => self.set_animation(entity, Animation::default()),
-            C::Color => self.set_color(entity, Color::default()),
-            C::Speed => self.set_speed(entity, Speed::default()),
+            C::Animation => self.set::<component::Animation>(entity, Animation::default()),
+            C::Color => self.set::<component::Color>(entity, Color::default()),
+            C::Kinematics => self.set::<component::Kinematics>(entity, Kinematics::default()),
+            C::Parent => self.set::<component::Parent>(entity, Parent {
+                id: entity,
+                offset: Position::default(),
+            }),
+            C::Lifetime => self.set::<component::Lifetime>(entity, Lifetime::default()),
+            C::StatusEffects => self.set::<component::StatusEffects>(entity, StatusEffects::default()),
+            C::Health => self.set::<component::Health>(entity, 1),
             C::Properties => {
                 self.properties.insert(entity, Default::default());
             }
             C::Sound => {
                 self.sounds.insert(entity, self.sound.clone());
             }
+            C::Particles => {
+                self.particles
+                    .insert(entity, self.particle_emitter.clone());
+            }
+            C::Camera => {
+                self.cameras.insert(entity, self.kicker.clone());
+            }
+            C::Glitch => {
+                self.glitches.insert(entity, self.glitcher.clone());
+            }
+            C::Light => self.set::<component::Light>(entity, Light::default()),
+            C::FadeIn => self.set::<component::FadeIn>(entity, FadeIn::default()),
+            C::Shielding => self.set::<component::Shielding>(entity, Shielding::default()),
+            C::TextGlitch => self.set::<component::TextGlitch>(entity, TextGlitch::default()),
         }
     }
 
-    pub fn get_sound(&self, entity: EntityId) -> Option<Sound> {
-        self.sounds.get(&entity).cloned()
+    /// the first component the entity's type relies on that it doesn't have
+    fn missing(&self, entity: EntityId, which: Entities) -> Option<Components> {
+        which
+            .requires()
+            .iter()
+            .copied()
+            .find(|&c| !self.has_component(entity, c))
+    }
+
+    pub fn has_component(&self, entity: EntityId, component: Components) -> bool {
+        use Components as C;
+        match component {
+            C::Position => self.positions.contains_key(&entity),
+            C::Direction => self.directions.contains_key(&entity),
+            C::Collider => self.colliders.contains_key(&entity),
+            C::Input => self.keyboards.contains_key(&entity),
+            C::BodyLength => self.body_lengths.contains_key(&entity),
+            C::Scale => self.scales.contains_key(&entity),
+            C::Timer => self.timers.contains_key(&entity),
+            C::Spawner => self.spawners.contains_key(&entity),
+            C::Animation => self.animations.contains_key(&entity),
+            C::Color => self.colors.contains_key(&entity),
+            C::Kinematics => self.kinematics.contains_key(&entity),
+            C::Parent => self.parents.contains_key(&entity),
+            C::Lifetime => self.lifetimes.contains_key(&entity),
+            C::StatusEffects => self.status_effects.contains_key(&entity),
+            C::Health => self.healths.contains_key(&entity),
+            C::Properties => self.properties.contains_key(&entity),
+            C::Sound => self.sounds.contains_key(&entity),
+            C::Particles => self.particles.contains_key(&entity),
+            C::Camera => self.cameras.contains_key(&entity),
+            C::Glitch => self.glitches.contains_key(&entity),
+            C::Light => self.lights.contains_key(&entity),
+            C::FadeIn => self.fades.contains_key(&entity),
+            C::Shielding => self.shieldings.contains_key(&entity),
+            C::TextGlitch => self.text_glitches.contains_key(&entity),
+        }
+    }
+
+    pub fn get<C: Component>(&self, entity: EntityId) -> Option<C::Value> {
+        C::storage(self).get(&entity).cloned()
+    }
+
+    pub fn set<C: Component>(&mut self, entity: EntityId, value: C::Value) {
+        C::set(self, entity, value)
     }
 
     pub fn new_property(&mut self, entity: EntityId, name: &'static str, value: impl Any) {
@@ -615,21 +1445,17 @@ impl Storages {
             .cloned()
     }
 
-    pub fn get_position(&self, entity: EntityId) -> Option<Position> {
-        self.positions.get(&entity).copied()
-    }
-
     pub fn set_position(&mut self, entity: EntityId, position: Position) {
         // check collision
         if self.is_collider(entity) {
+            let self_tile = TileCoord::of(position.into());
             for (&other, &other_pos) in &self.positions {
                 if !self.is_collider(other) {
                     continue;
                 }
 
-                let self_pos = Vec2::from(position).floor();
                 // let self_scale = self.get_scale(entity).unwrap_or(Vec2::diagonal(1.0)) - Vec2::diagonal(math::EPSILON);
-                let other_pos = Vec2::from(other_pos).floor();
+                let other_tile = TileCoord::of(other_pos.into());
 
                 // let corners = [
                 //     self_pos,
@@ -642,7 +1468,7 @@ impl Storages {
                     // let corner = corner.floor();
                     // print!("checking corner: {corner:?} - ");
                     // if corner.eq(other_pos) {
-                    if self_pos.eq(other_pos) {
+                    if self_tile == other_tile {
                         // println!("pos: {self_pos:?}");
                         let _ = self.collisions.send((entity, other));
                         // println!("hit");
@@ -657,13 +1483,6 @@ impl Storages {
         self.positions.insert(entity, position);
     }
 
-    pub fn get_direction(&self, entity: EntityId) -> Option<Direction> {
-        self.directions.get(&entity).copied()
-    }
-    pub fn set_direction(&mut self, entity: EntityId, direction: Direction) {
-        self.directions.insert(entity, direction);
-    }
-
     pub fn add_collider(&mut self, entity: EntityId) {
         self.colliders.insert(entity, Collider::default());
     }
@@ -692,52 +1511,153 @@ impl Storages {
         }
     }
 
-    pub fn get_body_length(&self, entity: EntityId) -> Option<BodyLength> {
-        self.body_lengths.get(&entity).copied()
-    }
-
-    pub fn set_body_length(&mut self, entity: EntityId, body_length: BodyLength) {
-        self.body_lengths.insert(entity, body_length);
-    }
-
-    pub fn get_self_destruct(&self, entity: EntityId) -> Option<SelfDestruct> {
-        self.self_destructs.get(&entity).copied()
-    }
-
-    pub fn set_self_destruct(&mut self, entity: EntityId, self_destruct: SelfDestruct) {
-        self.self_destructs.insert(entity, self_destruct);
-    }
-
-    pub fn get_scale(&self, entity: EntityId) -> Option<Scale> {
-        self.scales.get(&entity).copied()
-    }
-
-    pub fn set_scale(&mut self, entity: EntityId, scale: Scale) {
-        self.scales.insert(entity, scale);
-    }
-
-    pub fn _get_animation(&self, entity: EntityId) -> Option<Animation> {
-        self.animations.get(&entity).copied()
+    pub fn flush_keys(&mut self) {
+        for kb in self.keyboards.values_mut() {
+            kb.flush();
+        }
     }
 
-    pub fn set_animation(&mut self, entity: EntityId, animation: Animation) {
-        self.animations.insert(entity, animation);
-    }
+    /// moves everything with kinematics, colliding along the way,
+    /// plays animations, wears status effects off, ages everything that has a lifetime,
+    /// fades fireballs in, powers enemy shields down and glitches text
+    /// each of them only touches its own storages, so with enough entities around they run side by side
+    /// moves are applied after, one at a time, since they can collide
+    /// so are the sounds glitches make, since they go through the spawn queue
+    /// leaves whoever expired in the scratch, by id so runs stay reproducible
+    fn step(&mut self, dt: Duration) {
+        let Self {
+            kinematics,
+            animations,
+            status_effects,
+            lifetimes,
+            fades,
+            shieldings,
+            text_glitches,
+            lights,
+            frozen,
+            scratch,
+            ..
+        } = self;
+        let frozen = &*frozen;
+        let parallel = kinematics.len()
+            + animations.len()
+            + status_effects.len()
+            + lifetimes.len()
+            + fades.len()
+            + shieldings.len()
+            + text_glitches.len()
+            >= PARALLEL_MIN;
+        let Scratch { steps, expired, glitched, .. } = scratch;
+        steps.clear();
+        expired.clear();
+        glitched.clear();
+
+        let mut steer = move || {
+            let moving = kinematics.iter_mut().filter(|(id, _)| !frozen.contains(id));
+            steps.extend(moving.map(|(&id, k)| (id, k.step(dt))));
+        };
+        let animate = move || {
+            for (id, animation) in animations {
+                if !frozen.contains(id) {
+                    animation.tick(dt);
+                }
+            }
+        };
+        // shields go by the pace the status effects leave the enemy at
+        let wear_off = move || {
+            for (id, effects) in &mut *status_effects {
+                if !frozen.contains(id) {
+                    effects.tick(dt);
+                }
+            }
+            for (id, shielding) in shieldings {
+                if !frozen.contains(id) {
+                    let pace = status_effects.get(id).map_or(1.0, StatusEffects::pace);
+                    shielding.tick(dt, pace);
+                }
+            }
+        };
+        let mut age = move || {
+            let aging = lifetimes.iter_mut().filter(|(id, _)| !frozen.contains(id));
+            expired.extend(aging.filter_map(|(&id, lifetime)| lifetime.tick(dt).then_some(id)));
+            expired.sort();
+        };
+        let fade = move || {
+            for (id, fade) in fades {
+                if !frozen.contains(id) {
+                    fade.tick(dt);
+                }
+            }
+        };
+        let mut glitch = move || {
+            for (&id, text) in text_glitches.iter_mut().filter(|(id, _)| !frozen.contains(id)) {
+                if text.tick(dt) {
+                    glitched.push(id);
+                }
+                if let Some(light) = lights.get_mut(&id) {
+                    light.intensity = text.intensity();
+                }
+            }
+            glitched.sort();
+        };
+
+        if parallel {
+            thread::scope(|s| {
+                let workers = [
+                    s.spawn(steer),
+                    s.spawn(animate),
+                    s.spawn(age),
+                    s.spawn(fade),
+                    s.spawn(glitch),
+                ];
+                wear_off();
+
+                // a panic on a worker goes on as if it happened here
+                for worker in workers {
+                    worker.join().unwrap_or_else(|e| panic::resume_unwind(e));
+                }
+            })
+        } else {
+            steer();
+            animate();
+            wear_off();
+            age();
+            fade();
+            glitch();
+        }
 
-    pub fn get_color(&self, entity: EntityId) -> Option<Color> {
-        self.colors.get(&entity).copied()
-    }
+        // keep collisions in id order so runs stay reproducible
+        let mut steps = std::mem::take(&mut self.scratch.steps);
+        steps.sort_by_key(|&(id, _)| id);
+        for &(id, d) in &steps {
+            if let Some(&p) = self.positions.get(&id) {
+                self.set_position(id, p + Vec3::from((d, 0.0)));
+            }
+        }
+        self.scratch.steps = steps;
 
-    pub fn set_color(&mut self, entity: EntityId, color: Color) {
-        self.colors.insert(entity, color);
+        for _ in &self.scratch.glitched {
+            let _ = self.spawn_requests.send(Box::new(|man| oneshot::play_sound(man, Sounds::glitch())));
+        }
     }
 
-    pub fn get_speed(&self, entity: EntityId) -> Option<Speed> {
-        self.speeds.get(&entity).copied()
-    }
+    // where an entity belongs once every parent up the chain is accounted for
+    // none if something up the chain is gone
+    fn world_position(&self, entity: EntityId) -> Option<Position> {
+        let mut offset = Position::default();
+        let mut id = entity;
+        // bounded, in case someone attached a loop
+        for _ in 0..=self.parents.len() {
+            match self.parents.get(&id) {
+                Some(parent) if parent.id != id => {
+                    offset += parent.offset;
+                    id = parent.id;
+                }
+                _ => break,
+            }
+        }
 
-    pub fn set_speed(&mut self, entity: EntityId, speed: Speed) {
-        self.speeds.insert(entity, speed);
+        self.positions.get(&id).map(|&p| p + offset)
     }
 
     fn access_timer(&mut self, entity: EntityId) -> Option<&mut Timer> {
@@ -758,9 +1678,15 @@ impl Storages {
 }
 
 pub struct EntityManager {
-    tracker: EntityId,
-    entities: Vec<EntityId>,
-    types: Vec<Entities>,
+    // the next slot nobody's had yet
+    tracker: usize,
+    // oldest first, with holes where entities died so killing one doesn't shift everything after it
+    spawned: Vec<Option<(EntityId, Entities)>>,
+    holes: usize,
+    // what every live entity is and where it is in spawned, to look it up by id
+    alive: Storage<(Entities, usize)>,
+    // the last ids to have the slots pooled types can take over
+    pool: HashMap<Entities, Vec<EntityId>>,
 
     keystrokes: Receiver<Key>,
     mouse_movements: Receiver<Vec2>,
@@ -768,19 +1694,70 @@ pub struct EntityManager {
     collision_requests: Receiver<(EntityId, EntityId)>,
     dying_rx: Receiver<EntityId>,
     dying_tx: Sender<EntityId>,
+    bursts: Receiver<Burst>,
+    published: Receiver<Event>,
+    events: EventBus,
     storage: RefCell<Storages>,
+
+    budgets: HashMap<Budget, BudgetUsage>,
+    // skipped for missing components, already warned about
+    malformed: HashSet<EntityId>,
+}
+
+/// entities missing something their type relies on get skipped instead of crashing the run
+/// they only get warned about the first time
+fn is_whole(storage: &Storages, malformed: &mut HashSet<EntityId>, entity: EntityId, which: Entities) -> bool {
+    let Some(missing) = storage.missing(entity, which) else {
+        return true;
+    };
+    if malformed.insert(entity) {
+        log::warn!("skipping {which} {entity}, it has no {missing}");
+    }
+    false
+}
+
+/// where a headless entity manager reads its input from
+pub struct MockInput {
+    pub keys: Sender<Key>,
+    pub mouse: Sender<Vec2>,
 }
 
 impl EntityManager {
-    pub fn new(keystroke_rx: Receiver<Key>, mouse_rx: Receiver<Vec2>, sound: Sound) -> Self {
+    /// an entity manager that needs no window
+    /// input comes from the returned queues,
+    /// sounds, camera kicks and glitches go nowhere
+    pub fn headless() -> (Self, MockInput) {
+        let (keys, keystroke_rx) = mpsc::channel();
+        let (mouse, mouse_rx) = mpsc::channel();
+        let man = Self::new(
+            keystroke_rx,
+            mouse_rx,
+            SoundManager::silent().player(),
+            Effects::new().kicker(),
+            Corruption::new().glitcher(),
+        );
+        (man, MockInput { keys, mouse })
+    }
+
+    pub fn new(
+        keystroke_rx: Receiver<Key>,
+        mouse_rx: Receiver<Vec2>,
+        sound: Sound,
+        camera: Camera,
+        glitch: Glitch,
+    ) -> Self {
         let (spawn_tx, spawn_rx) = mpsc::channel();
         let (collisions_tx, collisions_rx) = mpsc::channel();
         let (dying_tx, dying_rx) = mpsc::channel();
+        let (bursts_tx, bursts_rx) = mpsc::channel();
+        let (events_tx, events_rx) = mpsc::channel();
 
         Self {
             tracker: Default::default(),
-            entities: Default::default(),
-            types: Default::default(),
+            spawned: Default::default(),
+            holes: Default::default(),
+            alive: Default::default(),
+            pool: Default::default(),
 
             keystrokes: keystroke_rx,
             mouse_movements: mouse_rx,
@@ -788,54 +1765,272 @@ impl EntityManager {
             collision_requests: collisions_rx,
             dying_rx,
             dying_tx,
-            storage: RefCell::new(Storages::new(spawn_tx, collisions_tx, sound)),
+            bursts: bursts_rx,
+            published: events_rx,
+            events: Default::default(),
+            storage: RefCell::new(Storages::new(
+                spawn_tx,
+                collisions_tx,
+                events_tx,
+                sound,
+                Emitter::new(bursts_tx),
+                camera,
+                glitch,
+            )),
+
+            budgets: Default::default(),
+            malformed: Default::default(),
         }
     }
 
-    pub fn spawn(&mut self, type_: Entities, components: &[Components]) -> EntityId {
-        let id = self.tracker;
-        self.tracker += 1;
+    /// budgets left out are unbounded
+    pub fn set_budgets(&mut self, limits: &[(Budget, usize)]) {
+        self.budgets.clear();
+        for &(budget, limit) in limits {
+            let alive = self.live().filter(|(_, t)| t.budget() == Some(budget)).count();
+            self.budgets.insert(budget, BudgetUsage {
+                limit,
+                alive,
+                evicted: 0,
+            });
+        }
+    }
+
+    pub fn budget_usage(&self, budget: Budget) -> Option<BudgetUsage> {
+        self.budgets.get(&budget).copied()
+    }
+
+    /// evict the oldest entities in the budget until there's space for one more
+    fn make_room_for(&mut self, budget: Budget) {
+        loop {
+            let Some(usage) = self.budgets.get(&budget) else {
+                return;
+            };
+            if usage.alive < usage.limit {
+                return;
+            }
 
-        self.entities.push(id);
-        self.types.push(type_);
+            // entities are sorted by age
+            let oldest = self.live().find(|(_, t)| t.budget() == Some(budget)).map(|(id, _)| id);
 
-        for c in components {
-            if let Ok(mut storage) = self.storage.try_borrow_mut() {
-                storage.add_component(id, *c);
+            let Some(oldest) = oldest else {
+                return;
+            };
+            self.kill(oldest);
+            if let Some(usage) = self.budgets.get_mut(&budget) {
+                usage.evicted += 1;
             }
         }
+    }
+
+    // an id for a new entity with no components yet
+    fn reserve(&mut self, type_: Entities) -> EntityId {
+        if let Some(budget) = type_.budget() {
+            self.make_room_for(budget);
+            if let Some(usage) = self.budgets.get_mut(&budget) {
+                usage.alive += 1;
+            }
+        }
+
+        let recycled = self.pool.get_mut(&type_).and_then(|ids| ids.pop());
+        let id = recycled.map(EntityId::next).unwrap_or_else(|| {
+            self.tracker += 1;
+            EntityId {
+                index: self.tracker - 1,
+                generation: 0,
+            }
+        });
+
+        self.alive.insert(id, (type_, self.spawned.len()));
+        self.spawned.push(Some((id, type_)));
 
         id
     }
 
-    pub fn _iter_mut(&mut self) -> impl Iterator<Item = EntityView> {
-        self.entities.iter().filter_map(|&id| self.view(id))
+    pub fn spawn(&mut self, type_: Entities, components: &[Components]) -> EntityId {
+        self.build(type_, components).id()
+    }
+
+    /// spawns an entity and sets it up without going through a view
+    pub fn build(&mut self, type_: Entities, components: &[Components]) -> EntityBuilder<'_> {
+        let id = self.reserve(type_);
+        let storage = self.storage.get_mut();
+        for &c in components {
+            storage.add_component(id, c);
+        }
+
+        EntityBuilder { id, storage }
+    }
+
+    /// spawns an entity of the same type and components for every item, set up by init
+    /// for putting down hundreds of walls at a time
+    pub fn spawn_batch<T>(
+        &mut self,
+        type_: Entities,
+        components: &[Components],
+        items: impl IntoIterator<Item = T>,
+        mut init: impl FnMut(&mut EntityBuilder, T),
+    ) -> Vec<EntityId> {
+        let items: Vec<_> = items.into_iter().collect();
+        let ids: Vec<_> = items.iter().map(|_| self.reserve(type_)).collect();
+
+        let storage = self.storage.get_mut();
+        for (&id, item) in ids.iter().zip(items) {
+            for &c in components {
+                storage.add_component(id, c);
+            }
+            init(&mut EntityBuilder { id, storage: &mut *storage }, item);
+        }
+
+        ids
+    }
+
+    pub fn _iter_mut(&mut self) -> impl Iterator<Item = EntityView<'_>> {
+        self.live().filter_map(|(id, _)| self.view(id))
+    }
+
+    /// every live entity and what it is, oldest first
+    fn live(&self) -> impl Iterator<Item = (EntityId, Entities)> + '_ {
+        self.spawned.iter().flatten().copied()
     }
 
     pub fn kill(&mut self, entity: EntityId) {
-        // binary search is legal because entity id is ever-increasing
-        // and insertion happens only at the end (thus keeping the vector sorted)
-        if let Ok(index) = self.entities.binary_search(&entity) {
-            self.entities.remove(index);
-            self.types.remove(index);
+        let Some((type_, slot)) = self.alive.remove(&entity) else {
+            return;
+        };
+        // ids get reused, so the list is in spawn order rather than id order
+        self.spawned[slot] = None;
+        self.holes += 1;
+        if self.holes > self.spawned.len() / 2 {
+            self.compact();
+        }
+        self.malformed.remove(&entity);
+
+        if let Some(usage) = type_.budget().and_then(|b| self.budgets.get_mut(&b)) {
+            usage.alive -= 1;
+        }
+
+        // remove components if they exist
+        self.storage.get_mut().kill(entity);
 
-            // remove components if they exist
-            if let Ok(mut storage) = self.storage.try_borrow_mut() {
-                storage.kill(entity);
+        if type_.pooled() {
+            self.pool.entry(type_).or_default().push(entity);
+        }
+    }
+
+    // once there's more holes than entities they get squeezed out, so it stays linear over many kills
+    fn compact(&mut self) {
+        self.spawned.retain(Option::is_some);
+        for (slot, &(id, _)) in self.spawned.iter().flatten().enumerate() {
+            if let Some(alive) = self.alive.get_mut(&id) {
+                alive.1 = slot;
             }
         }
+        self.holes = 0;
+    }
+
+    /// how many ids are waiting to be reused, for the debug overlay
+    pub fn pooled(&self) -> usize {
+        self.pool.values().map(Vec::len).sum()
     }
 
-    pub fn view(&self, entity: EntityId) -> Option<EntityView> {
-        let index = self.entities.binary_search(&entity).ok()?;
+    /// alive right now, frozen ones included
+    pub fn count(&self) -> usize {
+        self.alive.len()
+    }
+
+    /// everything whose tile covers pos, oldest first
+    pub fn entities_at(&self, pos: Vec2) -> Vec<EntityId> {
+        let tile = TileCoord::of(pos);
+        let storage = self.storage.borrow();
+        self.live()
+            .map(|(id, _)| id)
+            .filter(|id| {
+                storage
+                    .get::<component::Position>(*id)
+                    .is_some_and(|p| TileCoord::of(p.into()) == tile)
+            })
+            .collect()
+    }
+
+    /// the entity's type, components and properties, a line each, for debugging
+    pub fn inspect(&self, entity: EntityId) -> Option<Vec<String>> {
+        let view = self.view(entity)?;
+        let storage = self.storage.borrow();
+
+        let mut lines = vec![format!("{:?} {entity}", view.which())];
+        if let Some(pos) = storage.get::<component::Position>(entity) {
+            lines.push(format!("at {} {}", pos.x, pos.y));
+        }
+        let components: Vec<_> = Components::ALL
+            .into_iter()
+            .filter(|&c| storage.has_component(entity, c))
+            .map(|c| c.to_string())
+            .collect();
+        lines.push(components.join(" "));
+
+        if let Some(properties) = storage.properties.get(&entity) {
+            let mut properties: Vec<_> = properties
+                .iter()
+                .map(|(name, value)| format!("{name} {}", describe(&*value.borrow())))
+                .collect();
+            properties.sort();
+            lines.extend(properties);
+        }
+        Some(lines)
+    }
+
+    pub fn view(&self, entity: EntityId) -> Option<EntityView<'_>> {
+        let &(type_, _) = self.alive.get(&entity)?;
         Some(EntityView::new(
             entity,
-            self.types[index],
+            type_,
             &self.storage,
             self.dying_tx.clone(),
         ))
     }
 
+    /// every entity that has all of the components, oldest first
+    /// for systems that care about what entities have rather than what they are
+    pub fn query<'a>(
+        &'a self,
+        components: &'a [Components],
+    ) -> impl Iterator<Item = EntityView<'a>> + 'a {
+        self.live()
+            .filter(move |&(id, _)| {
+                let storage = self.storage.borrow();
+                components.iter().all(|&c| storage.has_component(id, c))
+            })
+            .map(move |(id, type_)| EntityView::new(id, type_, &self.storage, self.dying_tx.clone()))
+    }
+
+    /// names the entity, so whoever needs it can look it up instead of being handed its id
+    pub fn tag(&mut self, entity: EntityId, tag: &'static str) {
+        self.storage.get_mut().tags.entry(tag).or_default().push(entity);
+    }
+
+    /// the first entity that got the tag and is still alive
+    pub fn find_by_tag(&self, tag: &str) -> Option<EntityId> {
+        self.storage.borrow().tags.get(tag)?.first().copied()
+    }
+
+    /// frozen entities don't tick, move, animate or age
+    pub fn freeze(&mut self, entity: EntityId, frozen: bool) {
+        let set = &mut self.storage.get_mut().frozen;
+        if frozen {
+            set.insert(entity);
+        } else {
+            set.remove(&entity);
+        }
+    }
+
+    /// drops keys that were pressed but haven't been handled yet
+    /// so they don't all go off at once after a pause
+    pub fn flush_keys(&mut self) {
+        while self.keystrokes.try_recv().is_ok() {}
+        self.storage.borrow_mut().flush_keys();
+    }
+
     pub fn tick(&mut self, dt: Duration) {
         // handle keystrokes
         while let Ok(key) = self.keystrokes.try_recv() {
@@ -848,11 +2043,26 @@ impl EntityManager {
         }
 
         // tick entities
-        for &id in &self.entities {
+        for &(id, which) in self.spawned.iter().flatten() {
+            if self.storage.borrow().frozen.contains(&id) {
+                continue;
+            }
+            if !self::is_whole(&self.storage.borrow(), &mut self.malformed, id, which) {
+                continue;
+            }
             let mut view = self.view(id).unwrap();
             view.which().tick(dt, &mut view);
         }
 
+        // move, animate, wear off and age everything in one go
+        self.storage.get_mut().step(dt);
+        let mut expired = std::mem::take(&mut self.storage.get_mut().scratch.expired);
+        for &id in &expired {
+            self.kill(id);
+        }
+        expired.clear();
+        self.storage.get_mut().scratch.expired = expired;
+
         // handle killing off entities
         while let Ok(dying) = self.dying_rx.try_recv() {
             self.kill(dying);
@@ -863,6 +2073,8 @@ impl EntityManager {
             spawn_request(self);
         }
 
+        self.resolve_hierarchy();
+
         // check collisions
         while let Ok((id1, id2)) = self.collision_requests.try_recv() {
             if let Some(mut e1) = self.view(id1) {
@@ -871,12 +2083,79 @@ impl EntityManager {
                 }
             }
         }
+
+        // everything that happened this tick
+        while let Ok(event) = self.published.try_recv() {
+            self.events.publish(event);
+        }
+    }
+
+    /// glues child to parent, offset away from its position
+    /// the child follows the parent around and dies with it
+    pub fn attach(&mut self, child: EntityId, parent: EntityId, offset: Position) {
+        self.storage
+            .borrow_mut()
+            .set::<component::Parent>(child, Parent { id: parent, offset });
+    }
+
+    // children follow their parents, orphans die
+    fn resolve_hierarchy(&mut self) {
+        let storage = self.storage.get_mut();
+        let mut children = std::mem::take(&mut storage.scratch.children);
+        children.extend(
+            storage
+                .parents
+                .iter()
+                .filter(|(&child, parent)| parent.id != child)
+                .map(|(&child, _)| child),
+        );
+        children.sort();
+
+        for &child in &children {
+            let position = self.storage.get_mut().world_position(child);
+            match position {
+                Some(position) => self.storage.get_mut().set::<component::Position>(child, position),
+                None => self.kill(child),
+            }
+        }
+        children.clear();
+        self.storage.get_mut().scratch.children = children;
+    }
+
+    pub fn events(&mut self) -> &mut EventBus {
+        &mut self.events
     }
 
     pub fn draw(&mut self, renderer: &mut RenderManager, palette: Palette) {
-        for id in self.entities.iter().cloned() {
+        for &(id, which) in self.spawned.iter().flatten() {
+            if !self::is_whole(&self.storage.borrow(), &mut self.malformed, id, which) {
+                continue;
+            }
             let view = self.view(id).unwrap();
-            view.which().draw(view, renderer, palette);
+            // lights shine in from off screen too
+            let light = self.storage.borrow().get::<component::Light>(id);
+            if let (Some(light), Some(position)) = (light, view.try_get_position()) {
+                let center = Vec2::from(position) + Vec2::diagonal(0.5);
+                let reach = Rect::new(center, center).grow(light.radius);
+                if renderer.is_visible(reach) {
+                    renderer.push_light(PointLight {
+                        center,
+                        radius: light.radius,
+                        color: palette.get(light.color),
+                        intensity: light.intensity,
+                    });
+                }
+            }
+            if which.bounds(&view).is_some_and(|b| !renderer.is_visible(b)) {
+                continue;
+            }
+
+            which.draw(view, renderer, palette);
+        }
+
+        // bursts are resolved against this frame's palette
+        for burst in self.bursts.try_iter() {
+            renderer.push((burst, palette.get(burst.col)));
         }
     }
 }
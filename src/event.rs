@@ -0,0 +1,73 @@
+use std::sync::mpsc::{self, Receiver};
+
+use crate::entity::EntityId;
+
+/// things happening in the world that rooms and archetypes react to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// the snake turned
+    SnakeMoved,
+    SnakeAttacked,
+    /// something that would've killed the snake hit its shield instead
+    SnakeHurt,
+    /// lost some of its body
+    SnakeShrunk,
+    FruitEaten,
+    /// a fruit ran out of respawns
+    FruitDepleted,
+    /// killed in a fight, not just cleaned up with its room
+    EnemyDied,
+    PickupCollected,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+    pub kind: EventKind,
+    /// the entity it happened to
+    pub source: EntityId,
+}
+
+struct Subscription {
+    kind: EventKind,
+    // None listens to every entity
+    source: Option<EntityId>,
+    // returns false once nobody's listening anymore
+    notify: Box<dyn FnMut(Event) -> bool>,
+}
+
+/// routes published events to whoever subscribed to them
+/// subscriptions go away on their own once the listener is dropped
+#[derive(Default)]
+pub struct EventBus {
+    subscriptions: Vec<Subscription>,
+}
+
+impl EventBus {
+    /// the receiver gets a message every time a matching event is published
+    pub fn subscribe(&mut self, kind: EventKind, source: Option<EntityId>) -> Receiver<()> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribe_with(kind, source, move |_| tx.send(()).is_ok());
+        rx
+    }
+
+    /// notify returns false to unsubscribe
+    pub fn subscribe_with(
+        &mut self,
+        kind: EventKind,
+        source: Option<EntityId>,
+        notify: impl FnMut(Event) -> bool + 'static,
+    ) {
+        self.subscriptions.push(Subscription {
+            kind,
+            source,
+            notify: Box::new(notify),
+        });
+    }
+
+    pub fn publish(&mut self, event: Event) {
+        self.subscriptions.retain_mut(|s| {
+            let matches = s.kind == event.kind && s.source.is_none_or(|id| id == event.source);
+            !matches || (s.notify)(event)
+        });
+    }
+}
@@ -1,16 +1,18 @@
 use std::{
+    cell::Cell,
     ffi::CString,
     fs::read_to_string,
     mem::{offset_of, size_of},
     path::Path,
     ptr::{null, null_mut},
+    time::Duration,
 };
 
 use glfw::Context;
 
 use crate::{
     common::{Error, Result},
-    math::{Mat4, Vec3, Vec4},
+    math::{Mat4, Vec2, Vec3, Vec4},
     render::instanced::{Tile, Vertex},
     resources,
 };
@@ -31,21 +33,58 @@ macro_rules! call {
 
 pub(crate) use call;
 
+thread_local! {
+    // draw calls made since the last time they were taken
+    static DRAW_CALLS: Cell<usize> = const { Cell::new(0) };
+    // the first gl error since the last time errors were taken
+    static ERROR: Cell<Option<String>> = const { Cell::new(None) };
+}
+
+/// a [`call!`] that draws something, counted for the debug overlay
+macro_rules! draw {
+    ($gl_call:expr) => {{
+        crate::gl::count_draw_call();
+        crate::gl::call!($gl_call)
+    }};
+}
+
+pub(crate) use draw;
+
+pub fn count_draw_call() {
+    DRAW_CALLS.set(DRAW_CALLS.get() + 1);
+}
+
+/// how many draw calls were made since the last time this got called
+pub fn take_draw_calls() -> usize {
+    DRAW_CALLS.replace(0)
+}
+
+/// errors don't stop anything, only the first one is kept until [`take_error`] picks it up
 pub fn check_error(file_name: &str, line: u32, expr: &str) {
     let e = unsafe { raw::GetError() };
     let error_string = match e {
         raw::NO_ERROR => return,
-        raw::INVALID_ENUM => "GL_INVALID_ENUM",
-        raw::INVALID_VALUE => "GL_INVALID_VALUE",
-        raw::INVALID_OPERATION => "GL_INVALID_OPERATION",
-        raw::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
-        raw::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
-        raw::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
-        raw::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
-        e => panic!("glGetError returned unknown error enum [{}]", e),
+        raw::INVALID_ENUM => "GL_INVALID_ENUM".to_string(),
+        raw::INVALID_VALUE => "GL_INVALID_VALUE".to_string(),
+        raw::INVALID_OPERATION => "GL_INVALID_OPERATION".to_string(),
+        raw::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION".to_string(),
+        raw::STACK_OVERFLOW => "GL_STACK_OVERFLOW".to_string(),
+        raw::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW".to_string(),
+        raw::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY".to_string(),
+        e => format!("unknown error enum [{e}]"),
     };
 
-    panic!("[{}:{}] gl{} -> {}", file_name, line, expr, error_string);
+    let error = format!("[{}:{}] gl{} -> {}", file_name, line, expr, error_string);
+    log::error!("{error}");
+    ERROR.set(ERROR.take().or(Some(error)));
+}
+
+/// the first gl error since the last call, if there was one
+pub fn take_error() -> Result<()> {
+    match ERROR.take() {
+        Some(error) => Err(Error::Gl(error)),
+        None => Ok(()),
+    }
 }
 
 pub struct DrawContext(());
@@ -54,6 +93,28 @@ impl DrawContext {
     pub fn create(window: &mut glfw::Window) -> Self {
         window.make_current();
         raw::load_with(|procname| window.get_proc_address(procname));
+        // what's running the game matters most when it's what breaks on someone else's machine
+        for (name, what) in [("vendor", raw::VENDOR), ("renderer", raw::RENDERER), ("version", raw::VERSION)] {
+            let string = call!(GetString(what));
+            if !string.is_null() {
+                let string = unsafe { std::ffi::CStr::from_ptr(string.cast()) };
+                log::info!("gl {name}: {}", string.to_string_lossy());
+            }
+        }
+
+        // backface culling & apparently I can't specify vertices
+        call!(FrontFace(CW));
+        call!(Enable(CULL_FACE));
+        // enable depth buffer
+        call!(Enable(DEPTH_TEST));
+        // enable blending
+        call!(Enable(BLEND));
+        call!(BlendFunc(SRC_ALPHA, ONE_MINUS_SRC_ALPHA));
+        // enable gamma correction
+        call!(Enable(FRAMEBUFFER_SRGB));
+        // enable AA
+        call!(Enable(MULTISAMPLE));
+
         Self(())
     }
 }
@@ -237,12 +298,24 @@ impl<'a, const T: raw::GLenum> Drop for Buffer<'a, T> {
     }
 }
 
-pub struct Shader<'a>(GlObject<'a>);
+pub struct Shader<'a> {
+    // swapped for a fresh program when the shader gets reloaded
+    id: Cell<GlObjectId>,
+    _ctx: &'a DrawContext,
+    // where it was loaded from, and which change to res/ it's up to date with
+    #[cfg(feature = "hot-reload")]
+    reload: std::cell::OnceCell<(resources::Shader, Cell<usize>)>,
+}
 
 impl<'a> Shader<'a> {
     fn new(ctx: &'a DrawContext) -> Self {
         let id = call!(CreateProgram());
-        Self(GlObject { id, _ctx: ctx })
+        Self {
+            id: Cell::new(id),
+            _ctx: ctx,
+            #[cfg(feature = "hot-reload")]
+            reload: Default::default(),
+        }
     }
 
     pub fn _from_file(ctx: &'a DrawContext, path: &Path) -> Result<Self> {
@@ -262,7 +335,15 @@ impl<'a> Shader<'a> {
         this.compile()
     }
 
+    /// with hot reloading on, it's recompiled whenever its files change
     pub fn from_resource(ctx: &'a DrawContext, resource: resources::Shader) -> Result<Self> {
+        let this = Self::build(ctx, resource)?;
+        #[cfg(feature = "hot-reload")]
+        let _ = this.reload.set((resource, Cell::new(crate::hot_reload::generation())));
+        Ok(this)
+    }
+
+    fn build(ctx: &'a DrawContext, resource: resources::Shader) -> Result<Self> {
         const TYPES: [raw::GLenum; 3] = [
             raw::VERTEX_SHADER,
             raw::FRAGMENT_SHADER,
@@ -270,21 +351,50 @@ impl<'a> Shader<'a> {
         ];
 
         let this = Self::new(ctx);
-        for (idx, source) in resource.into_iter().enumerate() {
-            let type_ = TYPES[idx];
-            this.load(source, type_)?;
+        for (stage, type_) in resource.iter().zip(TYPES) {
+            // say which file it was, the log alone only has line numbers
+            this.load(&stage.source(), type_).map_err(|e| match e {
+                Error::ShaderCompilationError(log) => {
+                    Error::ShaderCompilationError(format!("{}\n{log}", stage.path))
+                }
+                e => e,
+            })?;
         }
 
         this.compile()
     }
 
+    /// a shader that doesn't compile leaves the old one in place
+    #[cfg(feature = "hot-reload")]
+    fn reload(&self) {
+        let Some((resource, seen)) = self.reload.get() else {
+            return;
+        };
+        let generation = crate::hot_reload::generation();
+        if seen.replace(generation) == generation {
+            return;
+        }
+
+        let paths = resource.iter().map(|s| s.path).collect::<Vec<_>>().join(", ");
+        match Self::build(self._ctx, resource) {
+            // the old program goes with what's left of the new shader
+            Ok(fresh) => {
+                self.id.swap(&fresh.id);
+                log::info!("reloaded {paths}");
+            }
+            Err(e) => log::warn!("kept {paths} as it was: {e}"),
+        }
+    }
+
     pub fn apply(&self) {
-        call!(UseProgram(self.0.id));
+        #[cfg(feature = "hot-reload")]
+        self.reload();
+        call!(UseProgram(self.id.get()));
     }
 
     pub fn _locate_uniform(&self, name: &str) -> Option<raw::GLint> {
         let name = CString::new(name).expect("Bad uniform name");
-        let location = call!(GetUniformLocation(self.0.id, name.as_ptr().cast()));
+        let location = call!(GetUniformLocation(self.id.get(), name.as_ptr().cast()));
         if location != -1 {
             Some(location)
         } else {
@@ -329,33 +439,35 @@ impl<'a> Shader<'a> {
             ));
 
             let log = String::from_utf8(log).map_err(|_| Error::ParseError)?;
+            log::error!("shader of type {shader_type:#x} didn't compile:\n{log}");
             return Err(Error::ShaderCompilationError(log));
         }
 
-        call!(AttachShader(self.0.id, shader));
+        call!(AttachShader(self.id.get(), shader));
 
         call!(DeleteShader(shader));
         Ok(())
     }
 
     fn compile(self) -> Result<Self> {
-        call!(LinkProgram(self.0.id));
+        call!(LinkProgram(self.id.get()));
 
         let mut ok = 0;
-        call!(GetProgramiv(self.0.id, LINK_STATUS, &mut ok));
+        call!(GetProgramiv(self.id.get(), LINK_STATUS, &mut ok));
         if ok != raw::TRUE as _ {
             let mut log_len = 0;
-            call!(GetProgramiv(self.0.id, INFO_LOG_LENGTH, &mut log_len));
+            call!(GetProgramiv(self.id.get(), INFO_LOG_LENGTH, &mut log_len));
             log_len -= 1; // no need for null terminator
             let mut log = vec![0u8; log_len as _];
             call!(GetProgramInfoLog(
-                self.0.id,
+                self.id.get(),
                 log_len,
                 null_mut(),
                 log.as_mut_ptr().cast()
             ));
 
             let log = String::from_utf8(log).map_err(|_| Error::ParseError)?;
+            log::error!("shader program didn't link:\n{log}");
             return Err(Error::ShaderCompilationError(log));
         }
         Ok(self)
@@ -364,7 +476,7 @@ impl<'a> Shader<'a> {
 
 impl<'a> Drop for Shader<'a> {
     fn drop(&mut self) {
-        call!(DeleteProgram(self.0.id));
+        call!(DeleteProgram(self.id.get()));
     }
 }
 
@@ -379,12 +491,24 @@ impl Uniform for Mat4 {
     }
 }
 
+impl Uniform for Vec2 {
+    fn uniform(&self, layout_location: raw::GLint) {
+        call!(Uniform2f(layout_location, self.x, self.y))
+    }
+}
+
 impl Uniform for Vec3 {
     fn uniform(&self, layout_location: raw::GLint) {
         call!(Uniform3f(layout_location, self.x, self.y, self.z))
     }
 }
 
+impl Uniform for Vec4 {
+    fn uniform(&self, layout_location: raw::GLint) {
+        call!(Uniform4f(layout_location, self.x, self.y, self.z, self.w))
+    }
+}
+
 impl Uniform for f32 {
     fn uniform(&self, layout_location: raw::GLint) {
         call!(Uniform1f(layout_location, *self))
@@ -433,6 +557,46 @@ impl<'a, const T: raw::GLenum> Drop for Texture<'a, T> {
     }
 }
 
+/// how long the gpu takes on whatever's drawn between begin and end
+/// results come in late, so read them the frame after
+pub struct TimerQuery<'a>(GlObject<'a>);
+
+impl<'a> TimerQuery<'a> {
+    pub fn new(ctx: &'a DrawContext) -> Self {
+        let mut id = 0;
+        call!(CreateQueries(TIME_ELAPSED, 1, &mut id));
+        Self(GlObject { id, _ctx: ctx })
+    }
+
+    /// only one can be running at a time
+    pub fn begin(&self) {
+        call!(BeginQuery(TIME_ELAPSED, self.0.id));
+    }
+
+    pub fn end(&self) {
+        call!(EndQuery(TIME_ELAPSED));
+    }
+
+    /// none until the gpu is done, never blocks
+    pub fn result(&self) -> Option<Duration> {
+        let mut available = 0;
+        call!(GetQueryObjectiv(self.0.id, QUERY_RESULT_AVAILABLE, &mut available));
+        if available == 0 {
+            return None;
+        }
+
+        let mut nanos = 0;
+        call!(GetQueryObjectui64v(self.0.id, QUERY_RESULT, &mut nanos));
+        Some(Duration::from_nanos(nanos))
+    }
+}
+
+impl<'a> Drop for TimerQuery<'a> {
+    fn drop(&mut self) {
+        call!(DeleteQueries(1, &self.0.id))
+    }
+}
+
 pub struct RenderBuffer<'a>(GlObject<'a>);
 
 impl<'a> RenderBuffer<'a> {
@@ -548,6 +712,27 @@ impl<'a> FrameBuffer<'a> {
         self.color_buffer.bind(slot)
     }
 
+    /// what's on the screen, as rgba rows from the bottom up
+    pub fn read_screen() -> (u32, u32, Vec<u8>) {
+        let mut viewport: [raw::GLint; 4] = Default::default();
+        call!(GetIntegerv(VIEWPORT, viewport.as_mut_ptr()));
+        let (width, height) = (viewport[2], viewport[3]);
+
+        let mut pixels = vec![0; 4 * width as usize * height as usize];
+        Self::apply_default();
+        call!(PixelStorei(PACK_ALIGNMENT, 1));
+        call!(ReadPixels(
+            0,
+            0,
+            width,
+            height,
+            raw::RGBA,
+            raw::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as _
+        ));
+        (width as _, height as _, pixels)
+    }
+
     /// clears the currently bound framebuffer
     pub fn clear() {
         call!(Clear(DEPTH_BUFFER_BIT | COLOR_BUFFER_BIT));
@@ -0,0 +1,126 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::common;
+
+const FILE: &str = "highscores.txt";
+const TABLE_SIZE: usize = 10;
+
+/// one finished run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub score: i32,
+    /// replays the run's rooms with `--seed`
+    pub seed: u64,
+    /// yyyy-mm-dd
+    pub date: String,
+}
+
+impl Entry {
+    /// a run that just ended
+    pub fn new(score: i32, seed: u64) -> Self {
+        Self {
+            score,
+            seed,
+            date: self::today(),
+        }
+    }
+
+    // stored as `score seed date`, one per line
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        let score = parts.next()?.parse().ok()?;
+        let seed = parts.next()?.parse().ok()?;
+        let date = parts.next()?.to_string();
+        Some(Self { score, seed, date })
+    }
+}
+
+/// the best runs so far, highest score first
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    entries: Vec<Entry>,
+}
+
+impl Table {
+    /// an empty table if there's nothing saved yet
+    pub fn load() -> Self {
+        let contents = common::data_path(FILE)
+            .and_then(|p| fs::read_to_string(p).ok())
+            .unwrap_or_default();
+        let mut entries = contents.lines().filter_map(Entry::parse).collect::<Vec<_>>();
+        entries.sort_by_key(|e| Reverse(e.score));
+        entries.truncate(TABLE_SIZE);
+        Self { entries }
+    }
+
+    /// returns where the entry ranks, if it made it onto the table at all
+    /// ties go below whoever got there first
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        let rank = self.entries.partition_point(|e| e.score >= entry.score);
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(TABLE_SIZE);
+        Some(rank)
+    }
+
+    /// returns false if the table couldn't be written
+    pub fn save(&self) -> bool {
+        let Some(path) = common::data_path(FILE) else {
+            return false;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+
+        let contents = self
+            .entries
+            .iter()
+            .map(|e| format!("{} {} {}\n", e.score, e.seed, e.date))
+            .collect::<String>();
+        fs::write(path, contents).is_ok()
+    }
+
+    pub fn best(&self) -> Option<&Entry> {
+        self.entries.first()
+    }
+
+    /// a line per entry
+    pub fn lines(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| format!("{:>2}. {:<6} {}  SEED {}", i + 1, e.score, e.date, e.seed))
+            .collect()
+    }
+}
+
+/// the date in utc, there's no timezone info to go on
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (y, m, d) = self::civil_from_days((secs / 86400) as i64);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+// days since 1970-01-01 to a gregorian date
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
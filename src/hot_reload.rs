@@ -0,0 +1,70 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Once,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+// how often res/ gets checked for changes
+const POLL: Duration = Duration::from_millis(250);
+
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// where a resource lives in the source tree
+pub fn path(resource: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("res").join(resource)
+}
+
+/// the resource as it is on disk right now
+pub fn read(resource: &str) -> Option<Vec<u8>> {
+    match fs::read(self::path(resource)) {
+        Ok(contents) => Some(contents),
+        Err(e) => {
+            log::warn!("couldn't read {resource}, using the built-in one: {e}");
+            None
+        }
+    }
+}
+
+/// goes up every time anything in res/ changes
+/// whatever loaded from there reloads once it's behind
+pub fn generation() -> usize {
+    static WATCH: Once = Once::new();
+    WATCH.call_once(|| {
+        thread::spawn(self::watch);
+    });
+    GENERATION.load(Ordering::Relaxed)
+}
+
+fn watch() {
+    let res = self::path("");
+    let mut last = self::latest_change(&res);
+    loop {
+        thread::sleep(POLL);
+        let latest = self::latest_change(&res);
+        if latest != last {
+            last = latest;
+            GENERATION.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// when anything under dir was last modified
+fn latest_change(dir: &Path) -> Option<SystemTime> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            if meta.is_dir() {
+                self::latest_change(&entry.path())
+            } else {
+                meta.modified().ok()
+            }
+        })
+        .max()
+}
@@ -0,0 +1,38 @@
+//! everything the game's made of, so benches and tests can get at it too
+//! the binary only sets up the window and runs the game loop
+
+pub mod animation;
+pub mod archetype;
+pub mod boot;
+pub mod camera;
+pub mod capture;
+pub mod common;
+pub mod config;
+pub mod console;
+pub mod corruption;
+pub mod dialog;
+pub mod difficulty;
+pub mod endless;
+pub mod entity;
+pub mod event;
+pub mod gl;
+pub mod highscore;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod logging;
+pub mod math;
+pub mod menu;
+pub mod mutator;
+pub mod palette;
+pub mod render;
+pub mod replay;
+pub mod resources;
+pub mod rng;
+pub mod settings;
+pub mod sound;
+pub mod stats;
+pub mod status;
+pub mod time;
+pub mod template;
+pub mod tunables;
+pub mod world;
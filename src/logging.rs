@@ -0,0 +1,97 @@
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fs, panic,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::common;
+
+// how many of the latest lines make it into a crash report
+const HISTORY: usize = 64;
+// in the data dir, only the latest crash is kept
+const CRASH_FILE: &str = "crash.txt";
+// quieter than that doesn't reach stderr, though it still ends up in crash reports
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// writes to stderr and remembers the last few lines for crash reports
+struct Logger {
+    start: Instant,
+    // what makes it to stderr
+    level: LevelFilter,
+    history: Mutex<VecDeque<String>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let secs = self.start.elapsed().as_secs_f32();
+        let line = format!("[{secs:>9.3} {:<5} {}] {}", record.level(), record.target(), record.args());
+        if record.level() <= self.level {
+            eprintln!("{line}");
+        }
+
+        // whatever panicked might have been holding onto the lock
+        let Ok(mut history) = self.history.lock() else {
+            return;
+        };
+        history.push_back(line);
+        if history.len() > HISTORY {
+            history.pop_front();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// `SNEK_LOG=debug` and the like change how much reaches stderr
+/// crashes get written to a file along with whatever was logged right before
+pub fn init() {
+    let level = std::env::var("SNEK_LOG")
+        .ok()
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(DEFAULT_LEVEL);
+    let logger = LOGGER.get_or_init(|| Logger {
+        start: Instant::now(),
+        level,
+        history: Mutex::new(VecDeque::with_capacity(HISTORY)),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(LevelFilter::Trace);
+    }
+
+    let report = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        report(info);
+        if let Some(path) = self::write_crash(&info.to_string()) {
+            eprintln!("crash report written to {}", path.display());
+        }
+    }));
+}
+
+fn write_crash(what: &str) -> Option<PathBuf> {
+    let backtrace = Backtrace::force_capture();
+    let history = LOGGER
+        .get()
+        .and_then(|l| l.history.try_lock().ok())
+        .map(|h| h.iter().cloned().collect::<Vec<_>>().join("\n"))
+        .unwrap_or_default();
+    let contents = format!(
+        "{what}\n\nversion {}\n\nbacktrace:\n{backtrace}\n\nlast logged:\n{history}\n",
+        env!("CARGO_PKG_VERSION"),
+    );
+
+    let path = common::data_path(CRASH_FILE)?;
+    fs::create_dir_all(path.parent()?).ok()?;
+    fs::write(&path, contents).ok()?;
+    Some(path)
+}
@@ -1,42 +1,78 @@
 #![windows_subsystem = "windows"]
 
-use std::mem::{self, swap};
-
+use std::collections::VecDeque;
 use std::sync::mpsc::{self, Receiver, Sender};
 
-use std::thread::sleep;
 use std::time::{Duration, Instant};
 
+use boot::Boot;
+use camera::Camera;
+use capture::Clip;
 use common::AsBytes;
-use entity::EntityManager;
+use common::{Error, Result};
+use config::Config;
+use console::{Command, Console, Gift, Spawnable};
+use corruption::Corruption;
+use entity::{component, BodyLength, Budget, Components, Direction, Entities, EntityId, EntityManager};
+use event::EventKind;
 use gl::{DrawContext, UniformBuffer};
 use glfw::{Context, OpenGlProfileHint};
+use highscore::Table;
 use glfw::{Key, WindowHint};
-use math::{ease, lerp, Vec2, Vec3};
+use math::{Mat4, Rect, TileCoord, Vec2, Vec3};
+use menu::Menu;
 use palette::Palette;
+use rand::{thread_rng, Rng};
 use render::fireball::FireballManager;
-use render::instanced::InstancedShapeManager;
-use render::shield::ShieldManager;
+use render::hud::{HudManager, HudText};
+use render::instanced::{InstancedShapeManager, Tile};
+use render::particles::ParticleManager;
+use render::post::{CrtSettings, PostEffect};
+use render::shield::{Shield, ShieldManager};
 use render::swoop::SwoopManager;
-use render::text::TextManager;
+use render::trail::TrailManager;
+use render::text::{Font, Text, TextManager};
 use render::RenderManager;
-use sound::{SoundManager, Sounds};
-
-use crate::math::{Mat4, Vec4};
-
-mod archetype;
-mod common;
-mod entity;
-mod gl;
-mod math;
-mod palette;
-mod render;
-mod resources;
-mod sound;
-mod time;
-mod world;
+use replay::{Event, Replay};
+use settings::{Action, Settings};
+use sound::{Channel, Music, SoundManager, Sounds};
+use stats::{Summary, Tracker};
+use tunables::Tunables;
+
+use crate::math::Vec4;
+
+use snek::{
+    archetype, boot, camera, capture, common, config, console, corruption, dialog, endless, entity, event, gl,
+    highscore, logging, math, menu, mutator, palette, render, replay, resources, rng, settings, sound, stats, tunables, world,
+};
 
 const SCALE_FACTOR: f32 = 0.85;
+// how many rooms past the tutorial complete a mutator run
+const MUTATOR_COMPLETION_DEPTH: usize = 5;
+// about a minute at 60fps
+const HEADLESS_TICKS: usize = 3600;
+// screen glitch on walking into a new room
+const ROOM_GLITCH: f32 = 0.5;
+// the entrance slamming shut behind the snake
+const SEAL_TRAUMA: f32 = 0.3;
+// how far out the camera starts before flying into the first room
+const INTRO_ZOOM: f32 = 0.25;
+// how far the camera pulls back while the snake dies
+const DEATH_ZOOM: f32 = 1.5;
+// so keys mashed while dying don't skip the end screen
+const END_SCREEN_DELAY: Duration = Duration::from_millis(1000);
+const MUSIC_FADE: Duration = Duration::from_millis(2000);
+// how long it takes the lights to go out in a dark room, or come back on once the snake leaves
+const DARKNESS_FADE: Duration = Duration::from_millis(800);
+// debug speeds, on F9, F10 and F11
+const TIME_SCALES: [f32; 3] = [0.25, 1.0, 4.0];
+// what a single step goes forward by, about a frame at 60fps
+const FRAME_STEP: Duration = Duration::from_millis(16);
+// a hit the shield soaks up slows everything down for a moment
+const HIT_TIME_SCALE: f32 = 0.3;
+const HIT_SLOWMO: Duration = Duration::from_millis(250);
+// the crt's hum under everything else
+const HUM_GAIN: f32 = 0.15;
 // mouse to world coords
 // mouse is in screen space coordinates
 // normalize to [0,1] range
@@ -44,144 +80,900 @@ const SCALE_FACTOR: f32 = 0.85;
 // multiply by the inverse of the screen matrix
 // mouse is now in world coordinates
 
+/// everything that needs a gl context
+/// headless runs go without
+struct Gfx<'a> {
+    renderer: RenderManager<'a>,
+    common_uniforms: UniformBuffer<'a>,
+    // captures wait for the frame to be drawn
+    screenshot: bool,
+    save_clip: bool,
+    clip: Option<Clip>,
+}
+
+impl<'a> Gfx<'a> {
+    fn new(ctx: &'a DrawContext, starting_view: Mat4, settings: &Settings) -> Result<Self> {
+        let common_uniforms = UniformBuffer::new(ctx);
+        common_uniforms.bind_buffer_base(0);
+        common_uniforms.set(
+            unsafe { starting_view.as_bytes() },
+            gl::buffer_flags::DYNAMIC_STORAGE,
+        );
+
+        let mut renderer = RenderManager::new(ctx)?;
+        let mut crt = CrtSettings::from_args(std::env::args());
+        crt.enabled &= settings.crt;
+        if settings.reduced_motion {
+            crt.flicker = 0.0;
+        }
+        renderer.set_crt(crt);
+        renderer.add_renderer(InstancedShapeManager::quads(ctx, 16 * 1024)?);
+        renderer.add_renderer(TextManager::new(ctx, 1024)?);
+        // effects draw in the order they're added
+        renderer.add_renderer(TrailManager::new(ctx, 1024)?);
+        renderer.add_renderer(SwoopManager::new(ctx, 16)?);
+        renderer.add_renderer(ParticleManager::new(ctx, 4096)?);
+        renderer.add_renderer(FireballManager::new(ctx, 512)?);
+        renderer.add_renderer(ShieldManager::new(ctx, 512)?);
+        // room for the debug overlay's frame time graph too
+        renderer.add_renderer(HudManager::new(ctx, 1024)?);
+        // anything that went wrong setting up is better found out now than mid-run
+        gl::take_error()?;
+
+        Ok(Self {
+            renderer,
+            common_uniforms,
+            screenshot: false,
+            save_clip: false,
+            clip: capture::clips_from_args(std::env::args()).then(Clip::default),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TitleItem {
+    Start,
+    Endless,
+    Chapter,
+    Settings,
+    Quit,
+}
+
+/// the first thing on screen once the crt's on
+struct TitleScreen {
+    menu: Menu<TitleItem>,
+    best: Option<i32>,
+    // where the run starts, see world::first_room
+    chapter: usize,
+}
+
+impl TitleScreen {
+    /// replays always start from the top, so there's no picking a chapter or endless mode for them
+    fn new(chapters: bool) -> Self {
+        let mut items = vec![(TitleItem::Start, "START".to_string())];
+        if chapters {
+            items.push((TitleItem::Endless, "ENDLESS".to_string()));
+            items.push((TitleItem::Chapter, Self::chapter_label(0)));
+        }
+        items.extend([
+            (TitleItem::Settings, "SETTINGS".to_string()),
+            (TitleItem::Quit, "QUIT".to_string()),
+        ]);
+        let menu = Menu::new(items);
+        let best = Table::load().best().map(|e| e.score);
+        Self { menu, best, chapter: 0 }
+    }
+
+    fn chapter_label(chapter: usize) -> String {
+        format!("CHAPTER < {} >", world::chapter_name(chapter))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsItem {
+    MasterVolume,
+    SfxVolume,
+    AmbienceVolume,
+    UiVolume,
+    MusicVolume,
+    Mute,
+    Crt,
+    ScreenShake,
+    ReducedMotion,
+    SnakeSpeed,
+    Markers,
+    Fullscreen,
+    Palette,
+    Controls,
+    Back,
+}
+
+impl SettingsItem {
+    const ALL: [SettingsItem; 15] = [
+        SettingsItem::MasterVolume,
+        SettingsItem::SfxVolume,
+        SettingsItem::AmbienceVolume,
+        SettingsItem::UiVolume,
+        SettingsItem::MusicVolume,
+        SettingsItem::Mute,
+        SettingsItem::Crt,
+        SettingsItem::ScreenShake,
+        SettingsItem::ReducedMotion,
+        SettingsItem::SnakeSpeed,
+        SettingsItem::Markers,
+        SettingsItem::Fullscreen,
+        SettingsItem::Palette,
+        SettingsItem::Controls,
+        SettingsItem::Back,
+    ];
+
+    fn label(self, settings: &Settings) -> String {
+        let volume = |v: f32| (v * 100.0).round() as i32;
+        let flag = |on| if on { "ON" } else { "OFF" };
+        match self {
+            Self::MasterVolume => format!("MASTER VOLUME < {} >", volume(settings.master_volume)),
+            Self::SfxVolume => format!("SFX VOLUME < {} >", volume(settings.sfx_volume)),
+            Self::AmbienceVolume => format!("AMBIENCE VOLUME < {} >", volume(settings.ambience_volume)),
+            Self::UiVolume => format!("UI VOLUME < {} >", volume(settings.ui_volume)),
+            Self::MusicVolume => format!("MUSIC VOLUME < {} >", volume(settings.music_volume)),
+            Self::Mute => format!("MUTE {}", flag(settings.muted)),
+            Self::Crt => format!("CRT {}", flag(settings.crt)),
+            Self::ScreenShake => format!("SCREEN SHAKE {}", flag(settings.screen_shake)),
+            Self::ReducedMotion => format!("REDUCED MOTION {}", flag(settings.reduced_motion)),
+            Self::SnakeSpeed => format!("SNAKE SPEED < {} >", volume(settings.snake_speed)),
+            Self::Markers => format!("SHAPE MARKERS {}", flag(settings.markers)),
+            Self::Fullscreen => format!("FULLSCREEN {}", flag(settings.fullscreen)),
+            Self::Palette => format!("PALETTE < {} >", settings.palette.to_ascii_uppercase()),
+            Self::Controls => "CONTROLS".to_string(),
+            Self::Back => "BACK".to_string(),
+        }
+    }
+}
+
+struct SettingsScreen {
+    menu: Menu<SettingsItem>,
+}
+
+impl SettingsScreen {
+    fn new(settings: &Settings) -> Self {
+        let items = SettingsItem::ALL.into_iter().map(|i| (i, i.label(settings))).collect();
+        Self { menu: Menu::new(items) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlsItem {
+    Bind(Action),
+    Back,
+}
+
+impl ControlsItem {
+    fn label(self, settings: &Settings) -> String {
+        match self {
+            Self::Bind(action) => {
+                let key = settings::key_name(settings.binding(action)).unwrap_or("?");
+                format!("{} {key}", action.name())
+            }
+            Self::Back => "BACK".to_string(),
+        }
+    }
+}
+
+/// rebinding keys, picking an action waits for the key to bind it to
+struct ControlsScreen {
+    menu: Menu<ControlsItem>,
+    waiting: Option<Action>,
+}
+
+impl ControlsScreen {
+    fn new(settings: &Settings) -> Self {
+        let items = Action::ALL
+            .into_iter()
+            .map(ControlsItem::Bind)
+            .chain(std::iter::once(ControlsItem::Back))
+            .map(|i| (i, i.label(settings)))
+            .collect();
+        Self {
+            menu: Menu::new(items),
+            waiting: None,
+        }
+    }
+}
+
+/// what's left once the run's over
+struct EndScreen {
+    summary: Summary,
+    scores: Table,
+    // where the run placed on the table
+    rank: Option<usize>,
+    shown: Duration,
+}
+
+/// what the game's busy with, in the order it goes through them
+enum State {
+    /// the crt is powering on
+    Boot(Boot),
+    Title(TitleScreen),
+    Settings(SettingsScreen),
+    Controls(ControlsScreen),
+    Playing,
+    /// shown until a key is pressed
+    Over(EndScreen),
+}
+
 struct Game<'a> {
-    pan_to_hall_trigger: Option<Receiver<()>>,
+    // a pair per exit, until one of them gets taken
+    exit_triggers: Vec<(Receiver<()>, Receiver<()>)>,
     pan_to_room_trigger: Option<Receiver<()>>,
     open_hall_trigger: Receiver<()>,
+    // the room's cleared, its hall opens once a reward is picked
+    reward_trigger: Option<Receiver<()>>,
+    // the snake's on its way into the room, wall it in once it's all the way in
+    seal_entrance: bool,
+    // so the room doesn't get cleared twice
+    cleared: bool,
+    // the console cleared the room, as if its goal was met
+    force_clear: bool,
+
+    // how long the snake's been dying for
+    dying: Duration,
+    // how dark it is away from the lights, [0,1], fades with the room the snake's in
+    darkness: f32,
+    stats: Tracker,
+    state: State,
+    settings: Settings,
+    // the window should close
+    quit: bool,
+    // the window lost focus mid-run, the run waits for a key once it's back
+    paused: bool,
+    // debugging, the run only goes forward a tick at a time
+    frame_stepping: bool,
+    // one tick goes through while frame stepping
+    step: bool,
+    // how fast the run goes, for debugging, see [`TIME_SCALES`]
+    time_scale: f32,
+    // how much longer the last big hit keeps things slowed down, in real time
+    slowmo: Duration,
+    hits: Receiver<()>,
 
-    // mouse position in world coordinates
     view_width: f32,
     view_height: f32,
+    // mouse position in world coordinates
+    mouse: Vec2,
 
-    lerping: bool,
-    accum: Duration,
-    next_view: Mat4,
-    current_view: Mat4,
-    last_view: Mat4,
+    camera: Camera,
 
     last_room: Option<world::Room>,
+    // rooms left behind to backtrack into, the oldest first
+    behind: VecDeque<world::Room>,
+    // which one of them the snake is in, if any
+    visiting: Option<usize>,
     room: world::Room,
     room_ctr: usize,
+    graph: world::Graph,
+    corruption: Corruption,
     man: EntityManager,
     keystroke_tx: Sender<Key>,
     mouse_tx: Sender<Vec2>,
-    palette: Palette,
-    renderer: RenderManager<'a>,
+    // towards the room's palette if it has one, the player's otherwise
+    palette: palette::Fade,
+    debug_overlay: bool,
+    collision_overlay: bool,
+    // the run waits while entities get picked apart
+    inspecting: bool,
+    inspected: Option<EntityId>,
+    console: Console,
+    replay: Option<Replay>,
+    gfx: Option<Gfx<'a>>,
     sound: SoundManager,
-    common_uniforms: UniformBuffer<'a>,
 }
 
 impl<'a> Game<'a> {
-    fn new(ctx: &'a DrawContext, view_width: f32, view_height: f32) -> Self {
-        let normal = Mat4::screen(Vec2::default(), 75.0, 75.0);
-
-        let tile_renderer = InstancedShapeManager::quads(ctx, 16 * 1024);
-        let fireball_renderer = FireballManager::new(ctx, 512);
+    /// no context means a headless run, with no sound either
+    fn new(ctx: Option<&'a DrawContext>, view_width: f32, view_height: f32, settings: Settings) -> Result<Self> {
+        let mut camera = Camera::new(Mat4::identity());
+        let corruption = Corruption::new();
+        let (sound, mut man, keystroke_tx, mouse_tx) = if ctx.is_some() {
+            let (keystroke_tx, keystroke_rx) = mpsc::channel();
+            let (mouse_tx, mouse_rx) = mpsc::channel();
+            let sound = SoundManager::new();
+            let man = EntityManager::new(
+                keystroke_rx,
+                mouse_rx,
+                sound.player(),
+                camera.kicker(),
+                corruption.glitcher(),
+            );
+            (sound, man, keystroke_tx, mouse_tx)
+        } else {
+            // nothing gets seen or heard anyway
+            let (man, input) = EntityManager::headless();
+            (SoundManager::silent(), man, input.keys, input.mouse)
+        };
+        // the title screen can still skip ahead, see Self::skip_to
+        let (room, open_hall_trigger, room_ctr) = world::first_room(&mut man, 0);
+        man.set_budgets(room.budgets());
+        let starting_view = self::fly_in(&mut camera, &room, &settings);
 
-        let (keystroke_tx, keystroke_rx) = mpsc::channel();
-        let (mouse_tx, mouse_rx) = mpsc::channel();
-        let sound = SoundManager::new();
-        let mut man = EntityManager::new(keystroke_rx, mouse_rx, sound.player());
-        let (room, open_hall_trigger) = world::Room::tut_controls(&mut man);
-        let starting_view = room.view();
+        let gfx = ctx.map(|ctx| Gfx::new(ctx, starting_view, &settings)).transpose()?;
+        self::set_volumes(&sound, &settings);
+        self::set_motion(&mut camera, &settings);
+        archetype::marker::show(settings.markers);
+        let palette = palette::Fade::new(&settings.palette);
+        let stats = Tracker::new(&mut man);
+        let hits = man.events().subscribe(EventKind::SnakeHurt, None);
 
-        let common_uniforms = UniformBuffer::new(ctx);
-        common_uniforms.bind_buffer_base(0);
-        common_uniforms.set(
-            unsafe { starting_view.as_bytes() },
-            gl::buffer_flags::DYNAMIC_STORAGE,
-        );
-
-        // crt startup sequence
-        sound.play(Sounds::CrtClick);
-        sleep(Duration::from_millis(1250));
-        sound.play(Sounds::CrtBuzz);
-        sleep(Duration::from_millis(1500));
-        sound.play(Sounds::CrtOn);
-
-        let mut renderer = RenderManager::new(ctx);
-        renderer.add_renderer(tile_renderer);
-        renderer.add_renderer(fireball_renderer);
-        renderer.add_renderer(ShieldManager::new(ctx, 512));
-        renderer.add_renderer(SwoopManager::new(ctx, 16));
-        renderer.add_renderer(TextManager::new(ctx));
-
-        Self {
-            pan_to_hall_trigger: None,
+        Ok(Self {
+            exit_triggers: Vec::new(),
             pan_to_room_trigger: None,
             open_hall_trigger,
+            reward_trigger: None,
+            seal_entrance: false,
+            cleared: false,
+            force_clear: false,
+
+            dying: Duration::ZERO,
+            darkness: 0.0,
+            stats,
+            // there's no crt to power on without a screen, or anyone to pick from a menu
+            state: match ctx {
+                Some(_) => State::Boot(Boot::new()),
+                None => State::Playing,
+            },
+            settings,
+            quit: false,
+            paused: false,
+            frame_stepping: false,
+            step: false,
+            time_scale: 1.0,
+            slowmo: Duration::ZERO,
+            hits,
 
             view_width,
             view_height,
+            mouse: Vec2::default(),
 
-            lerping: false,
-            accum: Duration::ZERO,
-            current_view: room.view(),
-            next_view: normal,
-            last_view: normal,
+            camera,
 
             last_room: None,
+            behind: VecDeque::new(),
+            visiting: None,
             room,
-            room_ctr: 0,
+            room_ctr,
+            graph: world::Graph::default(),
+            corruption,
             man,
             keystroke_tx,
             mouse_tx,
-            palette: palette::crt(),
-            renderer,
+            palette,
+            debug_overlay: false,
+            collision_overlay: false,
+            inspecting: false,
+            inspected: None,
+            console: Console::default(),
+            replay: None,
+            gfx,
             sound,
-            common_uniforms,
-        }
+        })
     }
 
     fn draw(&mut self) {
-        self.man.draw(&mut self.renderer, self.palette);
-        self.renderer.draw();
+        let dying = self.snake_dying();
+        let Some(gfx) = &mut self.gfx else {
+            return;
+        };
+
+        if let State::Boot(_) = self.state {
+            // the monitor is still off
+            gl::FrameBuffer::clear();
+            return;
+        }
+
+        let renderer = &mut gfx.renderer;
+        let palette = self.palette.update(self.room.palette().unwrap_or(&self.settings.palette));
+        // the corruption still rolls its bursts with reduced motion on, they just don't show
+        let glitched = self.corruption.palette(palette);
+        let (palette, glitch) = match self.settings.reduced_motion {
+            true => (palette, 0.0),
+            false => (glitched, self.corruption.intensity()),
+        };
+        renderer.set_glitch(glitch);
+        // the snake dies in one hit, so it's only ever in danger once it's dying
+        let danger = if dying { 1.0 } else { 0.0 };
+        renderer.set_danger(danger);
+        // menus on top of the run have to stay readable
+        let darkness = if let State::Playing = self.state { self.darkness } else { 0.0 };
+        renderer.set_darkness(darkness);
+        if self.collision_overlay {
+            // pushed ahead of the entities so it wins the depth test against them
+            Self::draw_collision_overlay(&self.man, &self.room, renderer, palette);
+        }
+        self.man.draw(renderer, palette);
+        if self.debug_overlay {
+            Self::draw_debug_overlay(&self.man, &self.graph, renderer, palette);
+        }
+        if self.inspecting {
+            Self::draw_inspector(&self.man, self.inspected, renderer, palette);
+        }
+        match &self.state {
+            State::Title(title) => Self::draw_title_screen(title, renderer, palette),
+            State::Settings(screen) => {
+                Self::draw_settings_screen(screen, self.sound.is_available(), renderer, palette)
+            }
+            State::Controls(screen) => Self::draw_controls_screen(screen, renderer, palette),
+            State::Over(end) => Self::draw_end_screen(end, renderer, palette),
+            _ => (),
+        }
+        if self.paused {
+            let text = "PAUSED\nPRESS ANY KEY";
+            renderer.push(HudText::new(text, Vec2::new(-0.3, 0.1), 0.07, palette.ui));
+        }
+        if self.time_scale != 1.0 {
+            let text = format!("{}X", self.time_scale);
+            renderer.push(HudText::new(&text, Vec2::new(0.8, -0.9), 0.05, palette.ui));
+        }
+        self.console.draw(renderer, palette);
+        renderer.draw();
+
+        if std::mem::take(&mut gfx.screenshot) {
+            let reply = capture::screenshot().map_or("couldn't save the screenshot".to_string(), |path| {
+                format!("saved {}", path.display())
+            });
+            self.console.print(reply);
+        }
+        if let Some(clip) = &mut gfx.clip {
+            clip.record();
+            if std::mem::take(&mut gfx.save_clip) {
+                let reply = clip.save().map_or("couldn't save the clip".to_string(), |path| {
+                    format!("saving {}", path.display())
+                });
+                self.console.print(reply);
+            }
+        }
     }
 
-    fn move_camera(&mut self, new_view: Mat4) {
-        self.next_view = new_view;
-        self.last_view = self.current_view;
-        self.lerping = true;
-        self.sound.play(Sounds::CameraPan);
+    fn draw_title_screen(title: &TitleScreen, renderer: &mut RenderManager, palette: Palette) {
+        const LEFT: f32 = -0.5;
+        const LOGO_TOP: f32 = 0.6;
+        const LOGO_SIZE: f32 = 0.25;
+        const MENU_TOP: f32 = 0.0;
+        const GLYPH_SIZE: f32 = 0.07;
+        const BEST_TOP: f32 = -0.7;
+        const BEST_SIZE: f32 = 0.04;
+
+        renderer.push(HudText::new("SNEK?", Vec2::new(LEFT, LOGO_TOP), LOGO_SIZE, palette.snake));
+        title.menu.draw(renderer, Vec2::new(LEFT, MENU_TOP), GLYPH_SIZE, palette);
+        if let Some(best) = title.best {
+            let text = format!("BEST {best}");
+            renderer.push(HudText::new(&text, Vec2::new(LEFT, BEST_TOP), BEST_SIZE, palette.ui));
+        }
     }
 
-    fn tick(&mut self, dt: Duration) {
-        let max = Duration::from_millis(1000);
-        if self.lerping {
-            if self.accum < max {
-                let pct = self.accum.as_secs_f32() / max.as_secs_f32();
-                // let p = self.bezier.apply(pct);
-                let p = ease::out_expo(pct);
-                self.current_view = lerp(self.last_view, self.next_view, p);
-                self.common_uniforms
-                    .update(0, unsafe { self.current_view.as_bytes() });
-                self.accum += dt;
-            } else {
-                self.lerping = false;
-                self.accum = Duration::ZERO;
-                swap(&mut self.current_view, &mut self.next_view);
+    /// audio is whether there's a device to play sounds on
+    fn draw_settings_screen(screen: &SettingsScreen, audio: bool, renderer: &mut RenderManager, palette: Palette) {
+        const LEFT: f32 = -0.7;
+        const TOP: f32 = 0.7;
+        const TITLE_SIZE: f32 = 0.1;
+        const GLYPH_SIZE: f32 = 0.05;
+        const HINT_TOP: f32 = -0.75;
+        const HINT_SIZE: f32 = 0.035;
+
+        renderer.push(HudText::new("SETTINGS", Vec2::new(LEFT, TOP), TITLE_SIZE, palette.ui));
+        let menu_top = Vec2::new(LEFT, TOP - 2.0 * TITLE_SIZE);
+        screen.menu.draw(renderer, menu_top, GLYPH_SIZE, palette);
+        let mut hint = "LEFT/RIGHT TO CHANGE\nFULLSCREEN APPLIES NEXT START".to_string();
+        if !audio {
+            hint += "\nNO AUDIO DEVICE, LOOKING FOR ONE";
+        }
+        renderer.push(HudText::new(&hint, Vec2::new(LEFT, HINT_TOP), HINT_SIZE, palette.ui));
+    }
+
+    fn draw_controls_screen(screen: &ControlsScreen, renderer: &mut RenderManager, palette: Palette) {
+        const LEFT: f32 = -0.7;
+        const TOP: f32 = 0.7;
+        const TITLE_SIZE: f32 = 0.1;
+        const GLYPH_SIZE: f32 = 0.05;
+        const HINT_TOP: f32 = -0.7;
+        const HINT_SIZE: f32 = 0.035;
+
+        renderer.push(HudText::new("CONTROLS", Vec2::new(LEFT, TOP), TITLE_SIZE, palette.ui));
+        let menu_top = Vec2::new(LEFT, TOP - 2.0 * TITLE_SIZE);
+        screen.menu.draw(renderer, menu_top, GLYPH_SIZE, palette);
+        let hint = match screen.waiting {
+            Some(action) => format!("PRESS A KEY FOR {}\nESCAPE TO CANCEL", action.name()),
+            None => "PICK AN ACTION TO REBIND IT".to_string(),
+        };
+        renderer.push(HudText::new(&hint, Vec2::new(LEFT, HINT_TOP), HINT_SIZE, palette.ui));
+    }
+
+    fn draw_end_screen(end: &EndScreen, renderer: &mut RenderManager, palette: Palette) {
+        const LEFT: f32 = -0.8;
+        const TOP: f32 = 0.8;
+        const TITLE_SIZE: f32 = 0.1;
+        const GLYPH_SIZE: f32 = 0.04;
+        const LINE_HEIGHT: f32 = 0.065;
+
+        renderer.push(HudText::new("GAME OVER", Vec2::new(LEFT, TOP), TITLE_SIZE, palette.ui));
+        let mut y = TOP - 2.0 * TITLE_SIZE;
+        let mut line = |renderer: &mut RenderManager, text: &str, col| {
+            renderer.push(HudText::new(text, Vec2::new(LEFT, y), GLYPH_SIZE, col));
+            y -= LINE_HEIGHT;
+        };
+
+        for stat in end.summary.lines() {
+            line(renderer, &stat, palette.ui);
+        }
+        line(renderer, "", palette.ui);
+        line(renderer, "HIGH SCORES", palette.ui);
+        for (i, score) in end.scores.lines().into_iter().enumerate() {
+            // the run that just ended stands out
+            let col = if end.rank == Some(i) { palette.fruit } else { palette.ui };
+            line(renderer, &score, col);
+        }
+        line(renderer, "", palette.ui);
+        line(renderer, "PRESS ANY KEY", palette.ui);
+    }
+
+    fn draw_debug_overlay(man: &EntityManager, graph: &world::Graph, renderer: &mut RenderManager, palette: Palette) {
+        const LEFT: f32 = -0.9;
+        const BOTTOM: f32 = -0.9;
+        const GLYPH_SIZE: f32 = 0.03;
+        const LINE_HEIGHT: f32 = 0.05;
+        // the frame time graph goes over the lines, a column per frame
+        const GRAPH_COLUMN: f32 = 0.012;
+        const GRAPH_ROW: f32 = 0.02;
+        const GRAPH_ROWS: usize = 4;
+        // each row is this much frame time, so two make a frame at 60 fps
+        const GRAPH_STEP: Duration = Duration::from_micros(8333);
+
+        let stats = renderer.stats();
+        let gpu = stats
+            .gpu_time
+            .map_or("?".to_string(), |t| format!("{:.1}", 1000.0 * t.as_secs_f32()));
+        let timing = format!(
+            "fps {:.0} frame {:.1}ms gpu {gpu}ms",
+            stats.fps(),
+            1000.0 * stats.average_frame_time().as_secs_f32(),
+        );
+        let draws = format!(
+            "entities {} pooled {} draw calls {}",
+            man.count(),
+            man.pooled(),
+            stats.draw_calls
+        );
+        let pushed: Vec<_> = stats
+            .pushed
+            .iter()
+            .map(|(name, n)| format!("{name} pushed {n}"))
+            .collect();
+        let columns: Vec<_> = stats
+            .frame_times
+            .iter()
+            .map(|&t| (t.as_secs_f32() / GRAPH_STEP.as_secs_f32()).ceil() as usize)
+            .collect();
+
+        let colliders = man.query(&[Components::Position, Components::Collider]).count();
+        let colliders = format!("colliders {colliders}");
+
+        let lines = Budget::ALL.into_iter().filter_map(|b| {
+            let usage = man.budget_usage(b)?;
+            Some(format!(
+                "{b:?} {}/{} evicted {}",
+                usage.alive, usage.limit, usage.evicted
+            ))
+        });
+
+        let dropped = renderer
+            .dropped()
+            .into_iter()
+            .map(|(name, n)| format!("{name} dropped {n}"));
+        // so a weird run can be replayed with --seed
+        let seed = format!("seed {}", rng::current_seed());
+        let extent = graph.extent().map_or(Vec2::default(), |r| r.max - r.min);
+        let rooms = format!(
+            "rooms {} risks {} skipped {} span {}x{}",
+            graph.rooms(),
+            graph.risks(),
+            graph.skipped(),
+            extent.x,
+            extent.y,
+        );
+        let lines = lines
+            .chain(dropped)
+            .chain(pushed)
+            .chain(std::iter::once(draws))
+            .chain(std::iter::once(timing))
+            .chain(std::iter::once(colliders))
+            .chain(std::iter::once(rooms))
+            .chain(std::iter::once(seed));
+
+        let mut top = BOTTOM;
+        for (i, line) in lines.enumerate() {
+            top = BOTTOM + (i + 1) as f32 * LINE_HEIGHT;
+            renderer.push(HudText::new(&line, Vec2::new(LEFT, top), GLYPH_SIZE, palette.ui));
+        }
+
+        // frames slower than the graph goes stay at the top, in red
+        for (i, rows) in columns.into_iter().enumerate() {
+            let col = if rows > GRAPH_ROWS { palette.enemy } else { palette.ui };
+            for row in 0..rows.min(GRAPH_ROWS) {
+                let position = Vec2::new(
+                    LEFT + i as f32 * GRAPH_COLUMN,
+                    top + LINE_HEIGHT + row as f32 * GRAPH_ROW,
+                );
+                renderer.push(HudText::new("!", position, GLYPH_SIZE, col));
+            }
+        }
+    }
+
+    /// outlines what collides, what triggers and where the walls and their holes are
+    /// along with the room's annotations
+    fn draw_collision_overlay(man: &EntityManager, room: &world::Room, renderer: &mut RenderManager, palette: Palette) {
+        // thin enough to show which side of a tile edge something lands on
+        const THICKNESS: f32 = 0.08;
+        // as near as the snake, so nothing hides it
+        const DEPTH: f32 = -1.0;
+        // in world units per font pixel, half a label's size
+        const ANNOTATION_SCALE: f32 = 1.0 / 40.0;
+
+        let mut outline = |rect: Rect, col: Vec3| {
+            let size = rect.max - rect.min;
+            let edges = [
+                (rect.min, Vec2::new(size.x, THICKNESS)),
+                (Vec2::new(rect.min.x, rect.max.y - THICKNESS), Vec2::new(size.x, THICKNESS)),
+                (rect.min, Vec2::new(THICKNESS, size.y)),
+                (Vec2::new(rect.max.x - THICKNESS, rect.min.y), Vec2::new(THICKNESS, size.y)),
+            ];
+            for (pos, size) in edges {
+                renderer.push(Tile {
+                    transform: Mat4::translate((pos, DEPTH).into()) * Mat4::scale(size),
+                    col,
+                });
+            }
+        };
+
+        // collisions only ever compare the tiles positions fall on
+        for e in man.query(&[Components::Position, Components::Collider]) {
+            let tile = Vec2::from(TileCoord::of(e.get_position().into()));
+            let col = if e.which() == Entities::Trigger { palette.fruit } else { palette.white };
+            outline(Rect::new(tile, tile + Vec2::diagonal(1.0)), col);
+        }
+
+        for (kind, rect) in room.outlines() {
+            let col = match kind {
+                world::Outline::Bounds => palette.snake,
+                world::Outline::Opening => palette.enemy,
+            };
+            outline(rect, col);
+        }
+
+        let annotations = room.annotations(man);
+        for (pos, _) in &annotations {
+            let tile = Vec2::from(TileCoord::of(*pos));
+            outline(Rect::new(tile, tile + Vec2::diagonal(1.0)), palette.ui);
+        }
+        let font = Font::get();
+        for (pos, annotation) in annotations {
+            // just over the tile, so the outline doesn't cover it
+            let position = Vec2::from(TileCoord::of(pos)) + Vec2::new(0.5, -0.5);
+            if let Ok(text) = font.sanitize(&annotation.to_string()).parse::<Text>() {
+                renderer.push(text.place(position, ANNOTATION_SCALE));
+            }
+        }
+    }
+
+    fn draw_inspector(man: &EntityManager, inspected: Option<EntityId>, renderer: &mut RenderManager, palette: Palette) {
+        const LEFT: f32 = 0.2;
+        const TOP: f32 = 0.9;
+        const GLYPH_SIZE: f32 = 0.03;
+        const LINE_HEIGHT: f32 = 0.045;
+        const HIGHLIGHT_ALPHA: f32 = 0.8;
+        const HINT: &str = "INSPECTING\nCLICK TO PICK, AGAIN FOR WHAT'S UNDER\nDELETE KILLS, ARROWS NUDGE";
 
-                // now that the last room is out of view get rid of it
-                if let Some(mut room) = self.last_room.take() {
-                    room.destroy(&mut self.man);
+        renderer.push(HudText::new(HINT, Vec2::new(LEFT, TOP), GLYPH_SIZE, palette.ui));
+
+        let Some(id) = inspected else {
+            return;
+        };
+        let lines = man.inspect(id).unwrap_or_else(|| vec![format!("{id} is gone")]);
+        let font = Font::get();
+        for (i, line) in lines.iter().enumerate() {
+            let position = Vec2::new(LEFT, TOP - (i + 4) as f32 * LINE_HEIGHT);
+            renderer.push(HudText::new(&font.sanitize(line), position, GLYPH_SIZE, palette.fruit));
+        }
+
+        if let Some(view) = man.view(id) {
+            let col = Vec4::from((palette.fruit, HIGHLIGHT_ALPHA));
+            renderer.push(Shield::new(view.get_position().into(), col, false, 0.5).push_quad());
+        }
+    }
+
+    /// picks whatever's under the mouse
+    /// clicking the same tile again goes through everything stacked on it
+    fn click(&mut self) {
+        if !self.inspecting || !matches!(self.state, State::Playing) {
+            return;
+        }
+
+        let under = self.man.entities_at(self.mouse);
+        let next = match self.inspected.and_then(|id| under.iter().position(|&e| e == id)) {
+            Some(i) => under.get(i + 1).or(under.first()),
+            None => under.last(),
+        };
+        self.inspected = next.copied();
+    }
+
+    /// keys for the inspected entity
+    /// returns whether the key got used up
+    fn inspector_key(&mut self, key: Key) -> bool {
+        // kills and nudges aren't recorded, so a replay only gets looked at
+        if self.replay.is_some() {
+            return false;
+        }
+        let Some(id) = self.inspected else {
+            return false;
+        };
+
+        let nudge = match key {
+            Key::Delete => {
+                self.man.kill(id);
+                self.inspected = None;
+                return true;
+            }
+            Key::Up => Direction::Up,
+            Key::Down => Direction::Down,
+            Key::Left => Direction::Left,
+            Key::Right => Direction::Right,
+            _ => return false,
+        };
+        if let Some(mut view) = self.man.view(id) {
+            let pos = view.get_position() + Vec3::from((Vec2::from(nudge), 0.0));
+            view.set_position(pos);
+        }
+        true
+    }
+
+    /// slows down or speeds up what actually went by
+    fn scale_time(&mut self, dt: Duration) -> Duration {
+        if self.hits.try_iter().count() > 0 {
+            self.slowmo = HIT_SLOWMO;
+        }
+        let scale = if self.slowmo.is_zero() { 1.0 } else { HIT_TIME_SCALE };
+        self.slowmo = self.slowmo.saturating_sub(dt);
+
+        dt.mul_f32(self.time_scale * scale)
+    }
+
+    /// records the tick or feeds back the recorded one
+    /// returns how long the tick should be
+    fn replay_tick(&mut self, dt: Duration) -> Duration {
+        match &mut self.replay {
+            Some(Replay::Recording(r)) => r.record(Event::Tick(dt)),
+            Some(Replay::Playback(p)) => {
+                let Some((inputs, dt)) = p.next_tick() else {
+                    // the recording's over, the player takes it from here
+                    self.replay = None;
+                    return dt;
+                };
+
+                for input in inputs {
+                    match input {
+                        Event::Key(key) => {
+                            let _ = self.keystroke_tx.send(key);
+                        }
+                        Event::Mouse(pos) => {
+                            let _ = self.mouse_tx.send(pos);
+                        }
+                        Event::Tick(_) => (),
+                    }
                 }
+                return dt;
+            }
+            None => (),
+        }
+
+        dt
+    }
+
+    fn tick(&mut self, dt: Duration) {
+        match &mut self.state {
+            State::Boot(boot) => {
+                if boot.tick(dt, &self.sound) {
+                    self.state = State::Title(TitleScreen::new(self.replay.is_none()));
+                    self.sound.play_loop(Sounds::CrtBuzz, HUM_GAIN);
+                    self.sound.play_music(Music::Calm, MUSIC_FADE);
+                    if let Some(gfx) = &mut self.gfx {
+                        gfx.renderer.power_on();
+                    }
+                }
+                return;
+            }
+            // the run hasn't started yet
+            State::Title(_) | State::Settings(_) | State::Controls(_) => return,
+            State::Playing => (),
+            // nothing moves on the end screen
+            State::Over(end) => {
+                end.shown += dt;
+                return;
+            }
+        }
+
+        // the run waits while a command gets typed in, or something gets inspected
+        let stepping = self.frame_stepping && std::mem::take(&mut self.step);
+        if self.paused || (self.frame_stepping && !stepping) || self.console.is_open() || self.inspecting {
+            return;
+        }
+
+        let dt = if stepping { FRAME_STEP } else { self.scale_time(dt) };
+        // what gets recorded is already scaled, so playback goes the same
+        let dt = self.replay_tick(dt);
+
+        let target = self
+            .man
+            .find_by_tag(archetype::snake::TAG)
+            .and_then(|s| self.man.view(s))
+            .map(|s| s.get_position().into())
+            .unwrap_or(self.room.position());
+        let pans = self.camera.tick(dt, target);
+        // heard from wherever the snake is, since that's where the player's looking
+        self.sound.set_listener(target);
+        if pans.started {
+            self.sound.play(Sounds::CameraPan);
+        }
+
+        // the camera can move every frame, even when it's not panning
+        let view = self.camera.view();
+        if let Some(gfx) = &mut self.gfx {
+            gfx.common_uniforms.update(0, unsafe { view.as_bytes() });
+            gfx.renderer.set_view(view);
+        }
+
+        if pans.finished {
+            // now that the last room is out of view, freeze it or get rid of it
+            if let Some(room) = self.last_room.take() {
+                self.leave_behind(room);
             }
         }
 
         self.man.tick(dt);
+        if self.snake_dying() {
+            if self.dying.is_zero() {
+                self.camera.zoom_out(DEATH_ZOOM, archetype::snake::DEATH_LENGTH);
+            }
+            self.dying += dt;
+            if self.dying >= archetype::snake::DEATH_LENGTH {
+                self.end_run();
+                return;
+            }
+        } else {
+            self.stats.tick(dt);
+        }
+        self.room.update(&self.man);
+        self.room.track(&self.man, dt);
+        self.backtrack(target);
 
-        // hall enter trigger
-        if self
-            .pan_to_hall_trigger
-            .as_ref()
-            .map(|rx| rx.try_recv().is_ok())
-            .unwrap_or_default()
-        {
-            // pan to hall
-            self.move_camera(self.room.view_hall());
+        // the lights go out once the snake's head is in a dark room
+        let dark = self.visiting.is_none() && self.room.is_dark() && self.room.contains(target);
+        let fade = dt.as_secs_f32() / DARKNESS_FADE.as_secs_f32();
+        self.darkness = if dark { self.darkness + fade } else { self.darkness - fade }.clamp(0.0, 1.0);
 
-            // close hall entrance off
-            //self.current_room.close_hall_entrance(&mut self.man);
+        if self.corruption.tick(dt) {
+            self.sound.play(Sounds::glitch());
+        }
+
+        // hall enter trigger, for whichever exit the snake goes for
+        let taken = self.exit_triggers.iter().position(|(near, _)| near.try_recv().is_ok());
+        if let Some(exit) = taken {
+            let (_, far) = self.exit_triggers.swap_remove(exit);
+            self.exit_triggers.clear();
+            self.pan_to_room_trigger = Some(far);
+            self.room.take_exit(&mut self.man, exit);
+            self.graph.visit(&self.room);
+
+            // pan to hall
+            // cut off whatever pan is going on, it blends from where the camera is now
+            self.camera
+                .interrupt(self.room.view_hall(), self::pan_length(&self.settings));
 
             // prepare next room
             // it's okay to reset open_hall_trigger here
@@ -189,29 +981,189 @@ impl<'a> Game<'a> {
             let (mut next_room, next_trigger) =
                 world::next_room(&mut self.room_ctr)(&mut self.man, &self.room);
             self.open_hall_trigger = next_trigger;
+            self.cleared = false;
+            log::info!("heading into room {}", self.room_ctr);
+
+            // the deeper the run, the more corrupted everything gets
+            self.corruption.set_depth(self.room_ctr);
+
+            if self.room_ctr == world::TUTORIAL_ROOMS + MUTATOR_COMPLETION_DEPTH
+                && mutator::mark_completed(&tunables::get().mutators)
+            {
+                self.sound.play(Sounds::RoomUnlocked);
+            }
+            self.sound.set_bitcrush(0.5 * self.corruption.level());
 
             self.room.swap(&mut next_room);
             self.last_room = Some(next_room);
+            self.man.set_budgets(self.room.budgets());
         }
 
         // hall leave trigger
+        // only the first time, walking back through the hall is up to backtracking
         if self
             .pan_to_room_trigger
             .as_ref()
             .map(|rx| rx.try_recv().is_ok())
             .unwrap_or_default()
         {
+            self.pan_to_room_trigger = None;
+            // there's no going back unless backtracking is on
+            self.seal_entrance = tunables::get().backtrack_rooms == 0;
             // pan to new room
-            self.move_camera(self.room.view());
+            // wait for the pan to the hall to settle first
+            self.corruption.glitch(ROOM_GLITCH);
+            self.sound.play_music(self.room.music(), MUSIC_FADE);
+            self.camera.enter(
+                self.room.camera_mode(),
+                self.room.view(),
+                target,
+                self::pan_length(&self.settings),
+            );
+        }
+
+        if self.seal_entrance && self.room.holds_snake(&self.man) {
+            self.seal_entrance = false;
+            if self.room.close_hall_entrance(&mut self.man) {
+                self.sound.play(Sounds::CrtClick);
+                self.camera.kicker().shake(SEAL_TRAUMA);
+            }
+        }
+
+        // room clear trigger
+        let mut open_hall = false;
+        if self.open_hall_trigger.try_recv().is_ok() || std::mem::take(&mut self.force_clear) {
+            self.cleared = true;
+            self.stats.clear_room();
+            self.reward_trigger = self.room.finish(&mut self.man);
+            open_hall = self.reward_trigger.is_none();
+        }
+
+        // reward trigger
+        if self
+            .reward_trigger
+            .as_ref()
+            .map(|rx| rx.try_recv().is_ok())
+            .unwrap_or_default()
+        {
+            self.reward_trigger = None;
+            self.room.claim_reward(&mut self.man);
+            open_hall = true;
         }
 
         // hall open trigger
-        if self.open_hall_trigger.try_recv().is_ok() {
-            if let Some((hall, room)) = self.room.open_hallway(&mut self.man) {
-                self.pan_to_hall_trigger = Some(hall);
-                self.pan_to_room_trigger = Some(room);
+        if open_hall {
+            if let Some(exits) = self.room.open_hallway(&mut self.man) {
+                self.exit_triggers = exits;
+            }
+        }
+    }
+
+    /// whether the snake's death sequence has started
+    fn snake_dying(&self) -> bool {
+        self.man
+            .find_by_tag(archetype::snake::TAG)
+            .and_then(|s| self.man.view(s))
+            .is_some_and(|s| archetype::snake::is_dying(&s))
+    }
+
+    fn end_run(&mut self) {
+        // everything goes quiet for the end screen
+        self.sound.stop_music(MUSIC_FADE);
+        self.sound.stop_loop(Sounds::CrtBuzz, MUSIC_FADE);
+        let summary = Summary::new(self.stats.run());
+        let mut scores = Table::load();
+        let score = self
+            .man
+            .find_by_tag(archetype::snake::TAG)
+            .and_then(|s| self.man.view(s))
+            .map_or(0, |s| archetype::snake::score(&s));
+        // replays and headless runs don't count
+        let live = self.gfx.is_some() && !matches!(self.replay, Some(Replay::Playback(_)));
+        let mut rank = None;
+        if live {
+            if !summary.save() {
+                log::warn!("couldn't save the run's stats");
+            }
+            rank = scores.insert(highscore::Entry::new(score, rng::current_seed()));
+            if rank.is_some() && !scores.save() {
+                log::warn!("couldn't save the high scores");
             }
         }
+        log::info!("run over in room {} with {score} points", self.room_ctr);
+
+        self.state = State::Over(EndScreen {
+            summary,
+            scores,
+            rank,
+            shown: Duration::ZERO,
+        });
+    }
+
+    /// trades the room the run was set to start in for the chapter's, before anything's happened in it
+    fn skip_to(&mut self, chapter: usize) {
+        if chapter == 0 {
+            return;
+        }
+
+        if let Some(snake) = self.man.find_by_tag(archetype::snake::TAG) {
+            self.man.kill(snake);
+        }
+        self.room.destroy(&mut self.man);
+
+        let (room, open_hall_trigger, room_ctr) = world::first_room(&mut self.man, chapter);
+        self.room = room;
+        self.open_hall_trigger = open_hall_trigger;
+        self.room_ctr = room_ctr;
+        log::info!("skipped ahead to room {room_ctr}");
+
+        self.man.set_budgets(self.room.budgets());
+        self.corruption.set_depth(room_ctr);
+        self::fly_in(&mut self.camera, &self.room, &self.settings);
+        // keeps track of the new snake
+        self.stats = Tracker::new(&mut self.man);
+    }
+
+    /// keeps as many rooms as the tunables allow, frozen until they're walked back into
+    fn leave_behind(&mut self, room: world::Room) {
+        room.freeze(&mut self.man, true);
+        self.behind.push_back(room);
+
+        while self.behind.len() > tunables::get().backtrack_rooms {
+            let mut oldest = self.behind.pop_front().unwrap();
+            oldest.destroy(&mut self.man);
+            self.visiting = match self.visiting {
+                Some(0) | None => None,
+                Some(i) => Some(i - 1),
+            };
+        }
+    }
+
+    /// follows the snake into rooms it left behind, and back out of them
+    /// the room it's in wakes up while the one it left freezes again
+    fn backtrack(&mut self, head: Vec2) {
+        let visiting = match self.behind.iter().position(|r| r.contains(head)) {
+            Some(i) => Some(i),
+            None if self.room.contains(head) => None,
+            // somewhere in a hall
+            None => return,
+        };
+        if visiting == self.visiting {
+            return;
+        }
+
+        if let Some(i) = self.visiting {
+            self.behind[i].freeze(&mut self.man, true);
+        }
+        let room = match visiting {
+            Some(i) => {
+                self.behind[i].freeze(&mut self.man, false);
+                &self.behind[i]
+            }
+            None => &self.room,
+        };
+        self.camera.enter(room.camera_mode(), room.view(), head, self::pan_length(&self.settings));
+        self.visiting = visiting;
     }
 
     fn key_press(&mut self, key: Key, is_down: bool) {
@@ -219,6 +1171,164 @@ impl<'a> Game<'a> {
             return;
         }
 
+        // nothing else gets keys while a command is being typed
+        if self.console.is_open() {
+            let reply = match self.console.key(key) {
+                Some(Ok(command)) => self.run_command(command),
+                Some(Err(Error::BadCommand(e))) => e,
+                Some(Err(e)) => format!("{e:?}"),
+                None => return,
+            };
+            self.console.print(reply);
+            return;
+        }
+
+        // mute from anywhere, not just the settings
+        if key == Key::M {
+            self.settings.muted = !self.settings.muted;
+            if let State::Settings(screen) = &mut self.state {
+                screen.menu.set_label(SettingsItem::Mute, SettingsItem::Mute.label(&self.settings));
+            }
+            self.apply_settings();
+            return;
+        }
+
+        // from anywhere, menus make for screenshots too
+        if key == Key::F12 || key == Key::PrintScreen {
+            if let Some(gfx) = &mut self.gfx {
+                gfx.screenshot |= key == Key::F12;
+                gfx.save_clip |= key == Key::PrintScreen;
+            }
+            return;
+        }
+
+        // steps through the run a tick at a time, stopping it first
+        // scroll lock lets it go again
+        if key == Key::Pause && matches!(self.state, State::Playing) {
+            if self.frame_stepping {
+                self.step = true;
+            } else {
+                self.frame_stepping = true;
+            }
+            return;
+        }
+        if key == Key::ScrollLock && self.frame_stepping {
+            self.frame_stepping = false;
+            self.step = false;
+            return;
+        }
+
+        // picking back up after a pause, without the key reaching the snake
+        if self.paused {
+            self.paused = false;
+            return;
+        }
+
+        match &mut self.state {
+            // any key skips the power-on, without reaching the snake
+            State::Boot(boot) => {
+                boot.skip();
+                return;
+            }
+            State::Title(title) => {
+                let step = match key {
+                    Key::A | Key::Left => world::CHAPTERS - 1,
+                    Key::D | Key::Right => 1,
+                    _ => 0,
+                };
+                let picked = match title.menu.selected() {
+                    TitleItem::Chapter if step != 0 => Some(TitleItem::Chapter),
+                    _ => title.menu.key(key),
+                };
+                match picked {
+                    Some(TitleItem::Start) => {
+                        let chapter = title.chapter;
+                        self.skip_to(chapter);
+                        self.state = State::Playing;
+                    }
+                    // straight past the tutorial, where the modifiers start stacking up
+                    Some(TitleItem::Endless) => {
+                        endless::start();
+                        self.skip_to(world::CHAPTERS - 1);
+                        self.state = State::Playing;
+                    }
+                    Some(TitleItem::Chapter) => {
+                        // enter goes forward too
+                        title.chapter = (title.chapter + step.max(1)) % world::CHAPTERS;
+                        title.menu.set_label(TitleItem::Chapter, TitleScreen::chapter_label(title.chapter));
+                    }
+                    Some(TitleItem::Settings) => {
+                        self.state = State::Settings(SettingsScreen::new(&self.settings))
+                    }
+                    Some(TitleItem::Quit) => self.quit = true,
+                    None => (),
+                }
+                return;
+            }
+            State::Settings(_) => {
+                self.settings_key(key);
+                return;
+            }
+            State::Controls(_) => {
+                self.controls_key(key);
+                return;
+            }
+            State::Playing => (),
+            State::Over(end) => {
+                self.quit = end.shown >= END_SCREEN_DELAY;
+                return;
+            }
+        }
+        // commands aren't recorded, so they'd throw a replay off either way
+        if key == Key::GraveAccent && self.replay.is_none() {
+            self.console.toggle();
+            return;
+        }
+
+        let key = self.settings.translate(key);
+
+        if key == Key::F3 {
+            self.debug_overlay = !self.debug_overlay;
+            return;
+        }
+
+        if key == Key::F2 {
+            self.collision_overlay = !self.collision_overlay;
+            return;
+        }
+
+        if key == Key::F4 {
+            self.inspecting = !self.inspecting;
+            self.inspected = None;
+            return;
+        }
+        if self.inspecting && self.inspector_key(key) {
+            return;
+        }
+
+        let time_scale = match key {
+            Key::F9 => Some(TIME_SCALES[0]),
+            Key::F10 => Some(TIME_SCALES[1]),
+            Key::F11 => Some(TIME_SCALES[2]),
+            _ => None,
+        };
+        if let Some(time_scale) = time_scale {
+            self.time_scale = time_scale;
+            return;
+        }
+
+        let effect = match key {
+            Key::F5 => Some(PostEffect::Bloom),
+            Key::F6 => Some(PostEffect::Aberration),
+            Key::F7 => Some(PostEffect::Vignette),
+            Key::F8 => Some(PostEffect::Crt),
+            _ => None,
+        };
+        if let (Some(effect), Some(gfx)) = (effect, &mut self.gfx) {
+            gfx.renderer.toggle_effect(effect);
+            return;
+        }
+
         // match key {
         //     Key::G => {
         //         let view = self.current_view;
@@ -233,9 +1343,205 @@ impl<'a> Game<'a> {
         //     _ => (),
         // }
 
+        match &mut self.replay {
+            Some(Replay::Recording(r)) => r.record(Event::Key(key)),
+            // live input would throw the run off
+            Some(Replay::Playback(_)) => return,
+            None => (),
+        }
+
         let _ = self.keystroke_tx.send(key);
     }
 
+    fn type_char(&mut self, c: char) {
+        self.console.type_char(c);
+    }
+
+    /// carries out a console command, returns what the console says back
+    fn run_command(&mut self, command: Command) -> String {
+        let snake = self.man.find_by_tag(archetype::snake::TAG);
+        match command {
+            Command::Spawn(what, n) => {
+                let mut rng = thread_rng();
+                for _ in 0..n {
+                    let pos = self.room.random_position();
+                    match what {
+                        Spawnable::Enemy => archetype::enemy::unshield_enemy(&mut self.man, pos),
+                        Spawnable::ShieldedEnemy => archetype::enemy::shield_enemy(&mut self.man, pos),
+                        Spawnable::Pickup => {
+                            let kind = archetype::pickup::Pickup::roll(&mut rng);
+                            archetype::pickup::new(&mut self.man, kind, pos)
+                        }
+                    };
+                }
+                format!("spawned {n} {what:?}")
+            }
+            Command::Give(what, n) => {
+                let Some(mut view) = snake.and_then(|s| self.man.view(s)) else {
+                    return "there's no snake".to_string();
+                };
+                match what {
+                    Gift::Score => view.with_mut_property(archetype::snake::SCORE, |s| *s += n),
+                    Gift::Length => view.with_mut::<component::BodyLength, _>(|l| {
+                        *l = l.saturating_add(n.max(0) as BodyLength)
+                    }),
+                }
+                format!("gave {n} {what:?}")
+            }
+            Command::Goto(n) => {
+                self.room_ctr = n;
+                if !self.cleared {
+                    self.force_clear = true;
+                }
+                format!("the next room is room {n}")
+            }
+            Command::SetPalette(name) if palette::by_name(&name).is_none() => format!("no palette called {name}"),
+            Command::SetPalette(name) => {
+                let reply = format!("palette set to {name}");
+                self.settings.palette = name;
+                if let State::Settings(screen) = &mut self.state {
+                    screen.menu.set_label(SettingsItem::Palette, SettingsItem::Palette.label(&self.settings));
+                }
+                reply
+            }
+            Command::Clear if self.cleared => "the room's already cleared".to_string(),
+            Command::Clear => {
+                self.force_clear = true;
+                "room cleared".to_string()
+            }
+            Command::Help => Command::HELP.to_string(),
+        }
+    }
+
+    /// alt-tabbing out pauses the run and quiets everything down
+    /// keys that piled up before are thrown out when coming back
+    fn focus(&mut self, focused: bool) {
+        if !focused && matches!(self.state, State::Playing) {
+            self.paused = true;
+        }
+        self.sound.set_muted(self.settings.muted || !focused);
+        if focused {
+            self.man.flush_keys();
+        }
+    }
+
+    fn settings_key(&mut self, key: Key) {
+        let State::Settings(screen) = &mut self.state else {
+            return;
+        };
+
+        let item = screen.menu.selected();
+        let step = match key {
+            Key::A | Key::Left => -settings::VOLUME_STEP,
+            Key::D | Key::Right => settings::VOLUME_STEP,
+            _ => 0.0,
+        };
+        let picked = match key {
+            Key::Escape => Some(SettingsItem::Back),
+            _ if step != 0.0 => Some(item),
+            _ => screen.menu.key(key),
+        };
+        let Some(picked) = picked else {
+            return;
+        };
+
+        let settings = &mut self.settings;
+        let volume = |v: &mut f32| *v = (*v + step).clamp(0.0, 1.0);
+        match picked {
+            SettingsItem::MasterVolume => volume(&mut settings.master_volume),
+            SettingsItem::SfxVolume => volume(&mut settings.sfx_volume),
+            SettingsItem::AmbienceVolume => volume(&mut settings.ambience_volume),
+            SettingsItem::UiVolume => volume(&mut settings.ui_volume),
+            SettingsItem::MusicVolume => volume(&mut settings.music_volume),
+            SettingsItem::Mute => settings.muted = !settings.muted,
+            SettingsItem::Crt => settings.crt = !settings.crt,
+            SettingsItem::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingsItem::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            SettingsItem::SnakeSpeed => {
+                let speed = settings.snake_speed;
+                // enter goes up, wrapping back around to the slowest
+                settings.snake_speed = match step {
+                    _ if step < 0.0 => (speed - settings::SNAKE_SPEED_STEP).max(settings::MIN_SNAKE_SPEED),
+                    _ if step == 0.0 && speed >= 1.0 => settings::MIN_SNAKE_SPEED,
+                    _ => (speed + settings::SNAKE_SPEED_STEP).min(1.0),
+                };
+            }
+            SettingsItem::Markers => settings.markers = !settings.markers,
+            SettingsItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingsItem::Palette => {
+                let names = palette::names();
+                let at = names.iter().position(|n| *n == settings.palette).unwrap_or(0);
+                // enter goes forward too
+                let at = if step < 0.0 { at + names.len() - 1 } else { at + 1 };
+                settings.palette = names[at % names.len()].clone();
+            }
+            SettingsItem::Controls => {
+                self.state = State::Controls(ControlsScreen::new(settings));
+                return;
+            }
+            SettingsItem::Back => {
+                self.state = State::Title(TitleScreen::new(self.replay.is_none()));
+                return;
+            }
+        }
+
+        screen.menu.set_label(picked, picked.label(settings));
+        self.apply_settings();
+    }
+
+    fn controls_key(&mut self, key: Key) {
+        let State::Controls(screen) = &mut self.state else {
+            return;
+        };
+
+        if let Some(action) = screen.waiting.take() {
+            if key != Key::Escape && self.settings.bind(action, key) {
+                // a swap changes another action's label too
+                for action in Action::ALL {
+                    let item = ControlsItem::Bind(action);
+                    screen.menu.set_label(item, item.label(&self.settings));
+                }
+                if !self.settings.save() {
+                    log::warn!("couldn't save the key bindings");
+                }
+            } else {
+                let item = ControlsItem::Bind(action);
+                screen.menu.set_label(item, item.label(&self.settings));
+            }
+            return;
+        }
+
+        let picked = match key {
+            Key::Escape => Some(ControlsItem::Back),
+            _ => screen.menu.key(key),
+        };
+        match picked {
+            Some(item @ ControlsItem::Bind(action)) => {
+                screen.waiting = Some(action);
+                screen.menu.set_label(item, format!("{} ...", action.name()));
+            }
+            Some(ControlsItem::Back) => self.state = State::Settings(SettingsScreen::new(&self.settings)),
+            None => (),
+        }
+    }
+
+    /// puts changed settings into effect and saves them
+    fn apply_settings(&mut self) {
+        let settings = &self.settings;
+        self::set_volumes(&self.sound, settings);
+        self::set_motion(&mut self.camera, settings);
+        archetype::marker::show(settings.markers);
+        if let Some(gfx) = &mut self.gfx {
+            gfx.renderer.set_effect(PostEffect::Crt, settings.crt);
+            let mut crt = gfx.renderer.crt();
+            crt.flicker = if settings.reduced_motion { 0.0 } else { CrtSettings::default().flicker };
+            gfx.renderer.set_crt(crt);
+        }
+        if !settings.save() {
+            log::warn!("couldn't save the settings");
+        }
+    }
+
     fn mouse_move(&mut self, screen_x: f64, screen_y: f64) {
         // screen coords
         // normalized [0,1]
@@ -247,18 +1553,60 @@ impl<'a> Game<'a> {
         let ndc_y = -(2.0 * ny - 1.0);
 
         // world coords
-        let in_view = self.current_view.invert_screem();
+        let in_view = self.camera.steady_view().inverse().unwrap_or_default();
         // println!("view:\n{}", self.current_view);
         // println!("inverse:\n{}", in_view);
         // println!("unit?:\n{}", self.current_view * in_view);
         let Vec4 { x, y, .. } = in_view * Vec4::position(Vec3::new(ndc_x, ndc_y, 0.0));
 
         let pos = Vec2::new(x, y);
+        self.mouse = pos;
         // println!("mouse: {pos:?}");
+        match &mut self.replay {
+            Some(Replay::Recording(r)) => r.record(Event::Mouse(pos)),
+            Some(Replay::Playback(_)) => return,
+            None => (),
+        }
+
         let _ = self.mouse_tx.send(pos);
     }
 }
 
+fn set_volumes(sound: &SoundManager, settings: &Settings) {
+    sound.set_master_volume(settings.master_volume);
+    sound.set_muted(settings.muted);
+    for channel in Channel::ALL {
+        sound.set_volume(channel, settings.volume(channel));
+    }
+}
+
+// camera pans become straight cuts with reduced motion on
+fn pan_length(settings: &Settings) -> Duration {
+    if settings.reduced_motion {
+        Duration::ZERO
+    } else {
+        config::get().pan_length
+    }
+}
+
+/// fly in from far away once the crt warms up
+/// returns the view the camera ends up at
+fn fly_in(camera: &mut Camera, room: &world::Room, settings: &Settings) -> Mat4 {
+    let starting_view = room.view();
+    camera.cinematic(&[
+        (Mat4::scale(INTRO_ZOOM.into()) * starting_view, Duration::ZERO),
+        (starting_view, self::pan_length(settings)),
+    ]);
+    camera.set_mode(room.camera_mode(), room.position());
+    starting_view
+}
+
+fn set_motion(camera: &mut Camera, settings: &Settings) {
+    camera.set_shaking(settings.screen_shake && !settings.reduced_motion);
+    camera.set_still(settings.reduced_motion);
+    archetype::snake::set_speed(settings.snake_speed);
+}
+
 struct Window {
     width: f32,
     height: f32,
@@ -269,24 +1617,29 @@ struct Window {
 }
 
 impl Window {
-    fn new() -> Self {
-        let mut glfw = glfw::init(glfw::fail_on_errors).expect("Failed to init GLFW");
+    /// fullscreen is a borderless window as tall as the screen, so it stays square
+    fn new(fullscreen: bool) -> Result<Self> {
+        let mut glfw = glfw::init(|_, description| log::error!("glfw: {description}"))
+            .map_err(|e| Error::Gl(format!("couldn't start glfw: {e}")))?;
 
         // window hints
         glfw.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
         glfw.window_hint(WindowHint::ContextVersion(4, 5));
         glfw.window_hint(WindowHint::Samples(Some(16)));
+        glfw.window_hint(WindowHint::Decorated(!fullscreen));
 
-        let (screen_width, screen_height) = glfw.with_primary_monitor(|_, m| {
-            let m = m.expect("Can't get primary monitor");
-            let mode = m.get_video_mode().expect("Can't get video mode");
-            (mode.width as f32, mode.height as f32)
-        });
+        let (screen_width, screen_height) = glfw
+            .with_primary_monitor(|_, m| {
+                let mode = m?.get_video_mode()?;
+                Some((mode.width as f32, mode.height as f32))
+            })
+            .ok_or_else(|| Error::Gl("couldn't find a monitor to go on".to_string()))?;
 
         // aspect ratio 1:1
         let dim = screen_height.min(screen_width);
-        let width = SCALE_FACTOR * dim as f32;
-        let height = SCALE_FACTOR * dim as f32;
+        let scale = if fullscreen { 1.0 } else { SCALE_FACTOR };
+        let width = scale * dim as f32;
+        let height = scale * dim as f32;
 
         let (mut window, event_pump) = glfw
             .create_window(
@@ -295,13 +1648,17 @@ impl Window {
                 "snek?",
                 glfw::WindowMode::Windowed,
             )
-            .expect("Failed to create window");
+            .ok_or_else(|| Error::Gl("couldn't open a window, the game needs OpenGL 4.5".to_string()))?;
 
         // window setup
         window.set_resizable(false);
         window.set_key_polling(true);
+        window.set_char_polling(true);
         window.set_cursor_pos_polling(true);
-        let favicon = image::load_from_memory(resources::textures::ICON).unwrap();
+        window.set_mouse_button_polling(true);
+        window.set_focus_polling(true);
+        let favicon = image::load_from_memory(resources::textures::ICON)
+            .map_err(|e| Error::Texture(format!("icon: {e}")))?;
         window.set_icon(vec![favicon.into()]);
 
         // center the window
@@ -311,21 +1668,7 @@ impl Window {
 
         let draw_context = DrawContext::create(&mut window);
 
-        // set up opengl stuff here
-        // backface culling & apparently I can't specify vertices
-        gl::call!(FrontFace(CW));
-        gl::call!(Enable(CULL_FACE));
-        // enable depth buffer
-        gl::call!(Enable(DEPTH_TEST));
-        // enable blending
-        gl::call!(Enable(BLEND));
-        gl::call!(BlendFunc(SRC_ALPHA, ONE_MINUS_SRC_ALPHA));
-        // enable gamma correction
-        gl::call!(Enable(gl::raw::FRAMEBUFFER_SRGB));
-        // enable AA
-        gl::call!(Enable(MULTISAMPLE));
-
-        Self {
+        Ok(Self {
             width,
             height,
 
@@ -333,14 +1676,16 @@ impl Window {
             window,
             event_pump,
             draw_context,
-        }
+        })
     }
 
-    fn run(mut self) {
+    /// the run stops at the first gl error, there's no telling what's still on screen after one
+    fn run(mut self, replay: Option<Replay>, settings: Settings) -> Result<()> {
         self.window.show();
-        gl::call!(Clear(COLOR_BUFFER_BIT));
+        gl::FrameBuffer::clear();
         self.window.swap_buffers();
-        let mut game = Game::new(&self.draw_context, self.width, self.height);
+        let mut game = Game::new(Some(&self.draw_context), self.width, self.height, settings)?;
+        game.replay = replay;
 
         let mut last = Instant::now();
         while !self.window.should_close() {
@@ -353,6 +1698,11 @@ impl Window {
                     glfw::WindowEvent::Key(key, _, glfw::Action::Release, _) => {
                         game.key_press(key, false)
                     }
+                    glfw::WindowEvent::Char(c) => game.type_char(c),
+                    glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, glfw::Action::Press, _) => {
+                        game.click()
+                    }
+                    glfw::WindowEvent::Focus(focused) => game.focus(focused),
                     glfw::WindowEvent::CursorPos(x, y) => {
                         game.mouse_move(x, y);
                     }
@@ -367,11 +1717,67 @@ impl Window {
 
             game.draw();
             self.window.swap_buffers();
+            gl::take_error()?;
+            if game.quit {
+                self.window.set_should_close(true);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// runs the game without a window for `ticks` fixed steps, mashing random keys
+/// for smoke testing room generation and gameplay in CI
+/// the run ends early if the snake dies
+fn headless(ticks: usize) {
+    const DT: Duration = Duration::from_millis(16);
+    const KEYS: [Key; 5] = [Key::W, Key::A, Key::S, Key::D, Key::Space];
+
+    // the player's settings and bindings would throw off the key mashing
+    let mut game = Game::new(None, 1.0, 1.0, Settings::default())
+        .expect("headless runs don't set up anything that can fail");
+    let mut rng = thread_rng();
+    for _ in 0..ticks {
+        if rng.gen_ratio(1, 10) {
+            game.key_press(KEYS[rng.gen_range(0..KEYS.len())], true);
+        }
+        game.tick(DT);
+        if let State::Over(_) = game.state {
+            break;
         }
     }
 }
 
 fn main() {
-    let window = Window::new();
-    window.run()
+    logging::init();
+    config::init(Config::load(std::env::args()).expect("bad config"));
+    let mutators = mutator::from_args(std::env::args()).expect("unknown mutator");
+    let mut tunables = Tunables::with_mutators(mutators);
+    if let Some(rooms) = tunables::backtrack_from_args(std::env::args()) {
+        tunables.backtrack_rooms = rooms;
+    }
+    tunables::init(tunables);
+    if let Some(seed) = rng::from_args(std::env::args()) {
+        rng::seed(seed);
+    }
+
+    // `--headless [ticks]` runs without a window
+    let mut args = std::env::args().skip_while(|a| a != "--headless");
+    if args.next().is_some() {
+        let ticks = args.next().and_then(|t| t.parse().ok()).unwrap_or(HEADLESS_TICKS);
+        return headless(ticks);
+    }
+
+    // a replay plays out the recorded seed
+    let replay = Replay::from_args(std::env::args(), rng::current_seed()).expect("bad replay");
+    if let Some(Replay::Playback(playback)) = &replay {
+        rng::seed(playback.seed());
+    }
+
+    log::info!("seed {}", rng::current_seed());
+    let settings = Settings::load();
+    let run = Window::new(settings.fullscreen).and_then(|window| window.run(replay, settings));
+    if let Err(e) = run {
+        dialog::show_error(&e.to_string());
+    }
 }
@@ -1,6 +1,8 @@
 use core::fmt;
 use std::{
-    convert::identity, f32::consts::PI, ops::{Add, Index, IndexMut, Mul, Neg, Sub}
+    convert::identity,
+    ops::{Add, AddAssign, Div, Index, IndexMut, Mul, Neg, Sub, SubAssign},
+    time::Duration,
 };
 
 use crate::common::{as_bytes, Error, Result};
@@ -11,6 +13,9 @@ fn f32_eq_tolerance(lhs: f32, rhs: f32, tolerance: f32) -> bool {
 }
 
 pub const EPSILON: f32 = 0.01;
+// positions this close under a tile's edge still count as on the tile past it
+// so float error from adding up steps never lands something a tile short
+const TILE_SNAP: f32 = 0.001;
 
 pub fn f32_eq(lhs: f32, rhs: f32) -> bool {
     f32_eq_tolerance(lhs, rhs, EPSILON)
@@ -60,10 +65,6 @@ impl Vec2 {
         Self::new(n, n)
     }
 
-    pub fn eq(self, rhs: Self) -> bool {
-        f32_eq(self.x, rhs.x) && f32_eq(self.y, rhs.y)
-    }
-
     pub fn abs(self) -> Self {
         Self::new(self.x.abs(), self.y.abs())
     }
@@ -76,6 +77,34 @@ impl Vec2 {
         lhs.x * rhs.x + lhs.y * rhs.y
     }
 
+    /// z of the 3d cross product, positive when rhs is counterclockwise from lhs
+    pub fn cross(lhs: Self, rhs: Self) -> f32 {
+        lhs.x * rhs.y - lhs.y * rhs.x
+    }
+
+    pub fn distance(lhs: Self, rhs: Self) -> f32 {
+        (lhs - rhs).len()
+    }
+
+    pub fn min(self, rhs: Self) -> Self {
+        Self::new(self.x.min(rhs.x), self.y.min(rhs.y))
+    }
+
+    pub fn max(self, rhs: Self) -> Self {
+        Self::new(self.x.max(rhs.x), self.y.max(rhs.y))
+    }
+
+    /// each component on its own
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+
+    /// counterclockwise
+    pub fn rotate(self, angle: f32) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::new(c * self.x - s * self.y, s * self.x + c * self.y)
+    }
+
     pub fn len2(self) -> f32 {
         Self::dot(self, self)
     }
@@ -92,15 +121,15 @@ impl Vec2 {
         self
     }
 
+    /// counterclockwise from up
     pub fn angle(self) -> f32 {
-        let s = self.normalize();
-        f32::atan2(s.y, s.x) - 0.5 * PI
+        f32::atan2(Self::cross(Self::UP, self), Self::dot(Self::UP, self))
     }
 }
 
 impl PartialEq for Vec2 {
     fn eq(&self, other: &Self) -> bool {
-        Vec2::eq(*self, *other)
+        f32_eq(self.x, other.x) && f32_eq(self.y, other.y)
     }
 }
 
@@ -144,6 +173,44 @@ impl Mul for Vec2 {
     }
 }
 
+impl Mul<f32> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Div for Vec2 {
+    type Output = Self;
+
+    fn div(mut self, rhs: Self) -> Self::Output {
+        self.x /= rhs.x;
+        self.y /= rhs.y;
+        self
+    }
+}
+
+impl Div<f32> for Vec2 {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        (1.0 / rhs) * self
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
 impl Neg for Vec2 {
     type Output = Vec2;
 
@@ -207,10 +274,6 @@ impl Vec3 {
         Self::new(n, n, n)
     }
 
-    pub fn eq(self, rhs: Self) -> bool {
-        f32_eq(self.x, rhs.x) && f32_eq(self.y, rhs.y) && f32_eq(self.z, rhs.z)
-    }
-
     pub fn srgb_to_linear(self) -> Self {
         Self::new(
             srgb_to_linear(self.x),
@@ -219,8 +282,12 @@ impl Vec3 {
         )
     }
 
+    pub fn dot(lhs: Self, rhs: Self) -> f32 {
+        lhs.x * rhs.x + lhs.y * rhs.y + lhs.z * rhs.z
+    }
+
     pub fn len2(self) -> f32 {
-        self.x * self.x + self.y * self.y + self.z * self.z
+        Self::dot(self, self)
     }
 
     pub fn len(self) -> f32 {
@@ -233,6 +300,12 @@ impl Vec3 {
     }
 }
 
+impl PartialEq for Vec3 {
+    fn eq(&self, other: &Self) -> bool {
+        f32_eq(self.x, other.x) && f32_eq(self.y, other.y) && f32_eq(self.z, other.z)
+    }
+}
+
 impl From<(f32, f32, f32)> for Vec3 {
     fn from(value: (f32, f32, f32)) -> Self {
         Self::new(value.0, value.1, value.2)
@@ -301,6 +374,66 @@ impl Sub for Vec3 {
     }
 }
 
+impl Mul for Vec3 {
+    type Output = Self;
+
+    fn mul(mut self, rhs: Self) -> Self::Output {
+        self.x *= rhs.x;
+        self.y *= rhs.y;
+        self.z *= rhs.z;
+
+        self
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Div for Vec3 {
+    type Output = Self;
+
+    fn div(mut self, rhs: Self) -> Self::Output {
+        self.x /= rhs.x;
+        self.y /= rhs.y;
+        self.z /= rhs.z;
+
+        self
+    }
+}
+
+impl Div<f32> for Vec3 {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        (1.0 / rhs) * self
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        -1.0 * self
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Vec4 {
@@ -529,8 +662,9 @@ impl Mat4 {
         Self::scale((-1.0, 1.0).into())
     }
 
+    /// counterclockwise around z
     pub fn rotate(angle: f32) -> Self {
-        let mut ret = Self::zero();
+        let mut ret = Self::identity();
         let (s, c) = angle.sin_cos();
         ret[0][0] = c;
         ret[0][1] = s;
@@ -539,6 +673,11 @@ impl Mat4 {
         ret
     }
 
+    /// moves the origin to eye and turns +y towards target, like [`Vec2::angle`] measures from
+    pub fn look_at(eye: Vec2, target: Vec2) -> Self {
+        Self::translate((eye, 0.0).into()) * Self::rotate((target - eye).angle())
+    }
+
     // screen projection matrix with each tile's 0,0 being offset
     pub fn screen(position: Vec2, width: f32, height: f32) -> Self {
         let l = position.x - 0.5 * width;
@@ -562,18 +701,89 @@ impl Mat4 {
         ret
     }
 
-    /// Invert the matrix
-    /// assumes it's a screen matrix
-    pub fn invert_screem(self) -> Self {
-        let mut ret = Self::identity();
-        for e in 0..3 {
-            let s = 1.0 / self[e][e];
-            ret[e][e] *= s;
-            ret[3][e] -= s * self[3][e];
+    // row major, easier to eliminate on
+    fn rows(self) -> [[f32; 4]; 4] {
+        let mut ret = [[0.0; 4]; 4];
+        for (y, row) in ret.iter_mut().enumerate() {
+            for (x, e) in row.iter_mut().enumerate() {
+                *e = self[x][y];
+            }
         }
+        ret
+    }
 
+    fn from_rows(rows: [[f32; 4]; 4]) -> Self {
+        let mut ret = Self::zero();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &e) in row.iter().enumerate() {
+                ret[x][y] = e;
+            }
+        }
         ret
     }
+
+    // the row at or below the diagonal with the biggest entry in that column
+    fn pivot(rows: &[[f32; 4]; 4], column: usize) -> usize {
+        (column..4)
+            .max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))
+            .unwrap_or(column)
+    }
+
+    pub fn determinant(self) -> f32 {
+        let mut m = self.rows();
+        let mut det = 1.0;
+        for i in 0..4 {
+            let pivot = Self::pivot(&m, i);
+            if m[pivot][i] == 0.0 {
+                return 0.0;
+            }
+            if pivot != i {
+                m.swap(pivot, i);
+                det = -det;
+            }
+
+            det *= m[i][i];
+            let top = m[i];
+            for row in &mut m[i + 1..] {
+                let f = row[i] / top[i];
+                for (e, t) in row.iter_mut().zip(top).skip(i) {
+                    *e -= f * t;
+                }
+            }
+        }
+        det
+    }
+
+    /// none if the matrix squashes space flat
+    pub fn inverse(self) -> Option<Self> {
+        if self.determinant() == 0.0 {
+            return None;
+        }
+
+        // gauss-jordan, whatever turns self into the identity turns the identity into the inverse
+        let mut m = self.rows();
+        let mut inv = Self::identity().rows();
+        for i in 0..4 {
+            let pivot = Self::pivot(&m, i);
+            m.swap(pivot, i);
+            inv.swap(pivot, i);
+
+            let p = m[i][i];
+            for x in 0..4 {
+                m[i][x] /= p;
+                inv[i][x] /= p;
+            }
+            for y in (0..4).filter(|&y| y != i) {
+                let f = m[y][i];
+                for x in 0..4 {
+                    m[y][x] -= f * m[i][x];
+                    inv[y][x] -= f * inv[i][x];
+                }
+            }
+        }
+
+        Some(Self::from_rows(inv))
+    }
 }
 
 impl fmt::Display for Mat4 {
@@ -664,6 +874,103 @@ impl Add for Mat4 {
     }
 }
 
+/// a tile on the grid, for telling where things are without float tolerances
+/// the tile at (x, y) covers [x, x+1) by [y, y+1) in world space
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl TileCoord {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// the tile a world position falls on
+    pub fn of(pos: Vec2) -> Self {
+        let snap = |c: f32| (c + TILE_SNAP).floor() as i32;
+        Self::new(snap(pos.x), snap(pos.y))
+    }
+}
+
+impl Add for TileCoord {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for TileCoord {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// the tile's bottom left corner, where whatever's on it is positioned
+impl From<TileCoord> for Vec2 {
+    fn from(value: TileCoord) -> Self {
+        Self::new(value.x as f32, value.y as f32)
+    }
+}
+
+/// axis aligned rectangle
+#[derive(Debug, Clone, Copy)]
+pub struct Rect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Rect {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// the part of the world a screen matrix puts on screen
+    pub fn of_view(view: Mat4) -> Self {
+        // a view squashed flat doesn't show anything anyway
+        let inverse = view.inverse().unwrap_or_default();
+        Self::new(inverse * Vec2::diagonal(-1.0), inverse * Vec2::diagonal(1.0))
+    }
+
+    /// the area an instanced tile covers
+    /// rotated tiles get the rect around all of their corners
+    pub fn of_tile(transform: Mat4) -> Self {
+        let a = transform * Vec2::new(0.0, 0.0);
+        let b = transform * Vec2::new(1.0, 0.0);
+        let c = transform * Vec2::new(0.0, 1.0);
+        let d = transform * Vec2::new(1.0, 1.0);
+        Self {
+            min: a.min(b).min(c).min(d),
+            max: a.max(b).max(c).max(d),
+        }
+    }
+
+    pub fn grow(self, margin: f32) -> Self {
+        Self {
+            min: self.min - Vec2::diagonal(margin),
+            max: self.max + Vec2::diagonal(margin),
+        }
+    }
+
+    pub fn contains(self, p: Vec2) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    pub fn overlaps(self, other: Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+}
+
 pub fn lerp<T>(lhs: T, rhs: T, p: f32) -> <<f32 as Mul<T>>::Output as Add>::Output
 where
     f32: Mul<T>,
@@ -672,71 +979,218 @@ where
     (1.0 - p) * lhs + p * rhs
 }
 
+/// what a [`Tween`] can ease between
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, p: f32) -> Self;
+}
+
+impl<T: Copy + Add<Output = T>> Lerp for T
+where
+    f32: Mul<T, Output = T>,
+{
+    fn lerp(self, to: Self, p: f32) -> Self {
+        self::lerp(self, to, p)
+    }
+}
+
+/// a value easing from one end to the other over a set time
+/// ticked along by whoever owns it, so it runs on whatever clock they do
+#[derive(Debug, Clone, Copy)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    elapsed: Duration,
+    length: Duration,
+    easing: fn(f32) -> f32,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, length: Duration, easing: fn(f32) -> f32) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: Duration::ZERO,
+            length,
+            easing,
+        }
+    }
+
+    /// already at rest on the value
+    pub fn at(value: T) -> Self {
+        Self::new(value, value, Duration::ZERO, ease::linear)
+    }
+
+    pub fn tick(&mut self, dt: Duration) {
+        self.elapsed += dt;
+    }
+
+    /// in [0,1], before easing
+    pub fn progress(&self) -> f32 {
+        if self.length.is_zero() {
+            1.0
+        } else {
+            (self.elapsed.as_secs_f32() / self.length.as_secs_f32()).min(1.0)
+        }
+    }
+
+    pub fn value(&self) -> T {
+        if self.is_done() {
+            // land exactly on the end, whatever the easing does at 1
+            self.to
+        } else {
+            self.from.lerp(self.to, (self.easing)(self.progress()))
+        }
+    }
+
+    /// for ends that keep moving while it heads to them
+    pub fn retarget(&mut self, to: T) {
+        self.to = to;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.length
+    }
+}
+
 /// Make animations pleasant
 /// https://easings.net/#
 pub mod ease {
+    use std::f32::consts::PI;
+
     use super::Vec2;
 
-    /// cubic bezier defined by (0,0), p1, p2, (1,1)
+    /// cubic bezier defined by (0,0), p1, p2, (1,1), like css' cubic-bezier()
+    /// p1 and p2 need x in [0,1] for the curve to be a function of x
     pub struct _UnitBezier {
         p1: Vec2,
         p2: Vec2,
-        approximations: Vec<Vec2>,
     }
 
     impl _UnitBezier {
-        pub fn _new(p1x: f32, p1y: f32, p2x: f32, p2y: f32, num_approximations: usize) -> Self {
-            let p1 = Vec2::new(p1x, p1y);
-            let p2 = Vec2::new(p2x, p2y);
-
-            let step = 1.0 / num_approximations as f32;
-            let mut approximations = Vec::with_capacity(num_approximations);
-            for i in 0..num_approximations {
-                let t = step * i as f32;
-                let b = Self::_t(p1, p2, t);
-                approximations.push(b);
-            }
-
+        pub fn _new(p1x: f32, p1y: f32, p2x: f32, p2y: f32) -> Self {
             Self {
-                p1,
-                p2,
-                approximations,
+                p1: Vec2::new(p1x.clamp(0.0, 1.0), p1y),
+                p2: Vec2::new(p2x.clamp(0.0, 1.0), p2y),
             }
         }
 
-        /// Calculate B(t) = (x,y)
-        fn _t(p1: Vec2, p2: Vec2, t: f32) -> Vec2 {
-            let p3 = Vec2::diagonal(1.0);
-
-            (3.0 * t * t * t - 6.0 * t * t + 3.0 * t) * p1
-                + (-3.0 * t * t * t + 3.0 * t * t) * p2
-                + (t * t * t) * p3
-        }
-
-        /// Given a point B(t) = (x,y)
-        /// approximate the y value based on x
+        /// y where the curve passes through x, in [0,1]
         pub fn _apply(&self, x: f32) -> f32 {
-            let mut low = Vec2::default();
-            for v in &self.approximations {
-                if v.x < x {
-                    low = *v;
-                } else {
+            // how close solving for x gets before it stops
+            const PRECISION: f32 = 1e-5;
+            const ITERATIONS: usize = 32;
+
+            // B(t) = (x,y)
+            let at = |t: f32| {
+                let it = 1.0 - t;
+                (3.0 * it * it * t) * self.p1 + (3.0 * it * t * t) * self.p2 + Vec2::diagonal(t * t * t)
+            };
+
+            // x only ever grows along t, so bisecting for it always converges
+            let (mut low, mut high) = (0.0, 1.0);
+            let mut t = x.clamp(0.0, 1.0);
+            for _ in 0..ITERATIONS {
+                let at = at(t).x;
+                if (at - x).abs() < PRECISION {
                     break;
                 }
-            }
-
-            let mut high = Vec2::default();
-            for v in &self.approximations {
-                if v.x > x {
-                    high = *v;
+                if at < x {
+                    low = t;
                 } else {
-                    break;
+                    high = t;
                 }
+                t = 0.5 * (low + high);
             }
+            at(t).y
+        }
+    }
+
+    pub fn _in_sine(p: f32) -> f32 {
+        1.0 - (0.5 * PI * p).cos()
+    }
+
+    pub fn _out_sine(p: f32) -> f32 {
+        (0.5 * PI * p).sin()
+    }
+
+    pub fn in_out_sine(p: f32) -> f32 {
+        0.5 * (1.0 - (PI * p).cos())
+    }
+
+    pub fn _in_cubic(p: f32) -> f32 {
+        p * p * p
+    }
+
+    pub fn _out_cubic(p: f32) -> f32 {
+        1.0 - (1.0 - p).powi(3)
+    }
+
+    pub fn _in_out_cubic(p: f32) -> f32 {
+        if p < 0.5 {
+            4.0 * p * p * p
+        } else {
+            1.0 - 0.5 * (2.0 - 2.0 * p).powi(3)
+        }
+    }
+
+    // elastic ones overshoot and wobble around their ends
+    const fn _elastic_period(stretch: f32) -> f32 {
+        2.0 * PI / stretch
+    }
+
+    pub fn _in_elastic(p: f32) -> f32 {
+        match p {
+            _ if p <= 0.0 => 0.0,
+            _ if p >= 1.0 => 1.0,
+            _ => -(2.0f32.powf(10.0 * p - 10.0)) * ((10.0 * p - 10.75) * self::_elastic_period(3.0)).sin(),
+        }
+    }
+
+    pub fn _out_elastic(p: f32) -> f32 {
+        match p {
+            _ if p <= 0.0 => 0.0,
+            _ if p >= 1.0 => 1.0,
+            _ => 2.0f32.powf(-10.0 * p) * ((10.0 * p - 0.75) * self::_elastic_period(3.0)).sin() + 1.0,
+        }
+    }
+
+    pub fn _in_out_elastic(p: f32) -> f32 {
+        let wobble = ((20.0 * p - 11.125) * self::_elastic_period(4.5)).sin();
+        match p {
+            _ if p <= 0.0 => 0.0,
+            _ if p >= 1.0 => 1.0,
+            _ if p < 0.5 => -0.5 * 2.0f32.powf(20.0 * p - 10.0) * wobble,
+            _ => 0.5 * 2.0f32.powf(-20.0 * p + 10.0) * wobble + 1.0,
+        }
+    }
+
+    pub fn _out_bounce(p: f32) -> f32 {
+        const N: f32 = 7.5625;
+        const D: f32 = 2.75;
+
+        if p < 1.0 / D {
+            N * p * p
+        } else if p < 2.0 / D {
+            let p = p - 1.5 / D;
+            N * p * p + 0.75
+        } else if p < 2.5 / D {
+            let p = p - 2.25 / D;
+            N * p * p + 0.9375
+        } else {
+            let p = p - 2.625 / D;
+            N * p * p + 0.984375
+        }
+    }
+
+    pub fn _in_bounce(p: f32) -> f32 {
+        1.0 - self::_out_bounce(1.0 - p)
+    }
 
-            // normalized x
-            let n = (x - low.x) / (high.x - low.x);
-            super::lerp(low.y, high.y, n)
+    pub fn _in_out_bounce(p: f32) -> f32 {
+        if p < 0.5 {
+            0.5 * (1.0 - self::_out_bounce(1.0 - 2.0 * p))
+        } else {
+            0.5 * (1.0 + self::_out_bounce(2.0 * p - 1.0))
         }
     }
 
@@ -771,4 +1225,170 @@ pub mod ease {
         let ip = 1.0 - p;
         1.0 - ip * ip
     }
+
+    pub fn in_out_quad(p: f32) -> f32 {
+        if p < 0.5 {
+            2.0 * p * p
+        } else {
+            1.0 - 0.5 * (2.0 - 2.0 * p).powi(2)
+        }
+    }
+
+    pub fn linear(p: f32) -> f32 {
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use super::*;
+
+    fn mat_eq(lhs: Mat4, rhs: Mat4) -> bool {
+        (0..4).all(|x| (0..4).all(|y| f32_eq(lhs[x][y], rhs[x][y])))
+    }
+
+    #[test]
+    fn vec2_angle() {
+        assert!(f32_eq(Vec2::UP.angle(), 0.0));
+        assert!(f32_eq(Vec2::LEFT.angle(), FRAC_PI_2));
+        assert!(f32_eq(Vec2::RIGHT.angle(), -FRAC_PI_2));
+        assert!(f32_eq(Vec2::DOWN.angle().abs(), PI));
+        // length doesn't matter, only which way
+        assert!(f32_eq(Vec2::new(-3.0, 3.0).angle(), FRAC_PI_4));
+        // no direction at all, but it shouldn't come out as nan
+        assert_eq!(Vec2::default().angle(), 0.0);
+    }
+
+    #[test]
+    fn vec2_rotate() {
+        assert_eq!(Vec2::UP.rotate(FRAC_PI_2), Vec2::LEFT);
+        assert_eq!(Vec2::RIGHT.rotate(-FRAC_PI_2), Vec2::DOWN);
+        assert_eq!(Vec2::new(2.0, 1.0).rotate(PI), Vec2::new(-2.0, -1.0));
+
+        // rotating from up by the angle gets back to the same direction
+        let v = Vec2::new(1.0, -2.0);
+        assert_eq!(Vec2::UP.rotate(v.angle()), v.normalize());
+    }
+
+    #[test]
+    fn vec2_dot_cross() {
+        let a = Vec2::new(1.0, 2.0);
+        let b = Vec2::new(3.0, -4.0);
+        assert!(f32_eq(Vec2::dot(a, b), -5.0));
+        assert!(f32_eq(Vec2::cross(a, b), -10.0));
+        assert!(f32_eq(Vec2::cross(b, a), 10.0));
+        assert!(f32_eq(Vec2::dot(Vec2::UP, Vec2::RIGHT), 0.0));
+        assert!(f32_eq(Vec2::cross(Vec2::RIGHT, Vec2::UP), 1.0));
+        assert!(f32_eq(Vec2::cross(a, 2.0 * a), 0.0));
+    }
+
+    #[test]
+    fn vec2_clamp() {
+        let min = Vec2::new(-1.0, 0.0);
+        let max = Vec2::new(1.0, 5.0);
+        assert_eq!(Vec2::new(0.5, 2.0).clamp(min, max), Vec2::new(0.5, 2.0));
+        assert_eq!(Vec2::new(-3.0, 7.0).clamp(min, max), Vec2::new(-1.0, 5.0));
+        assert_eq!(Vec2::new(4.0, -2.0).clamp(min, max), Vec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn vec2_div_neg() {
+        assert_eq!(Vec2::new(6.0, -3.0) / Vec2::new(2.0, 3.0), Vec2::new(3.0, -1.0));
+        assert_eq!(Vec2::new(6.0, -3.0) / 3.0, Vec2::new(2.0, -1.0));
+        assert_eq!(-Vec2::new(1.0, -2.0), Vec2::new(-1.0, 2.0));
+        assert_eq!(-Vec2::UP, Vec2::DOWN);
+    }
+
+    #[test]
+    fn vec3_dot_len() {
+        let a = Vec3::new(1.0, 2.0, 2.0);
+        assert!(f32_eq(Vec3::dot(a, Vec3::new(2.0, 0.0, -1.0)), 0.0));
+        assert!(f32_eq(Vec3::dot(a, a), 9.0));
+        assert!(f32_eq(a.len(), 3.0));
+        assert_eq!(a.normalize(), Vec3::new(1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0));
+    }
+
+    #[test]
+    fn vec3_div_neg() {
+        assert_eq!(Vec3::new(6.0, -3.0, 1.0) / Vec3::new(2.0, 3.0, 4.0), Vec3::new(3.0, -1.0, 0.25));
+        assert_eq!(Vec3::new(6.0, -3.0, 1.5) / 3.0, Vec3::new(2.0, -1.0, 0.5));
+        assert_eq!(-Vec3::new(1.0, -2.0, 0.0), Vec3::new(-1.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn rotate_is_counterclockwise() {
+        let r = Mat4::rotate(FRAC_PI_2);
+        assert_eq!(r * Vec2::RIGHT, Vec2::UP);
+        assert_eq!(r * Vec2::UP, Vec2::LEFT);
+        assert_eq!(Mat4::rotate(-FRAC_PI_2) * Vec2::UP, Vec2::RIGHT);
+        assert!(mat_eq(r * r * r * r, Mat4::identity()));
+    }
+
+    #[test]
+    fn determinant() {
+        assert!(f32_eq(Mat4::identity().determinant(), 1.0));
+        assert!(f32_eq(Mat4::scale((2.0, 3.0).into()).determinant(), 6.0));
+        assert!(f32_eq(Mat4::translate((4.0, -5.0, 6.0).into()).determinant(), 1.0));
+        assert!(f32_eq(Mat4::rotate(1.0).determinant(), 1.0));
+        assert!(f32_eq(Mat4::flip_horizontal().determinant(), -1.0));
+
+        // needs a row swap to get a pivot, 2 * 4 * (0 * 0 - 3 * 1)
+        let m = Mat4::from_rows([
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 3.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 4.0],
+        ]);
+        assert!(f32_eq(m.determinant(), -24.0));
+    }
+
+    #[test]
+    fn inverse_round_trips() {
+        let m = Mat4::translate((3.0, -2.0, 0.5).into()) * Mat4::rotate(0.7) * Mat4::scale((2.0, 0.5).into());
+        let inv = m.inverse().expect("m isn't singular");
+        assert!(mat_eq(m * inv, Mat4::identity()));
+        assert!(mat_eq(inv * m, Mat4::identity()));
+
+        let p = Vec2::new(1.5, -4.0);
+        assert_eq!(inv * (m * p), p);
+    }
+
+    #[test]
+    fn inverse_of_singular() {
+        assert!(Mat4::zero().inverse().is_none());
+        assert!(Mat4::scale((1.0, 0.0).into()).inverse().is_none());
+
+        let m = Mat4::from_rows([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 6.0, 8.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert!(m.inverse().is_none());
+    }
+
+    #[test]
+    fn look_at() {
+        let eye = Vec2::new(2.0, 3.0);
+        // target's off to the left, a quarter turn counterclockwise from up
+        let m = Mat4::look_at(eye, Vec2::new(-2.0, 3.0));
+
+        let mut expected = Mat4::identity();
+        expected[0][0] = 0.0;
+        expected[0][1] = 1.0;
+        expected[1][0] = -1.0;
+        expected[1][1] = 0.0;
+        expected[3][0] = 2.0;
+        expected[3][1] = 3.0;
+        assert!(mat_eq(m, expected), "{m}");
+
+        assert_eq!(m * Vec2::new(0.0, 0.0), eye);
+        assert_eq!(m * Vec2::UP, Vec2::new(1.0, 3.0));
+
+        // straight up is no turn at all
+        let m = Mat4::look_at(eye, Vec2::new(2.0, 10.0));
+        assert!(mat_eq(m, Mat4::translate((eye, 0.0).into())));
+    }
 }
@@ -0,0 +1,59 @@
+use glfw::Key;
+
+use crate::{math::Vec2, palette::Palette, render::{hud::HudText, RenderManager}};
+
+// how much further down each item sits
+const ITEM_SPACING: f32 = 1.6;
+
+/// a list of items to pick from with the keyboard
+/// up/down or w/s to move, enter or space to pick
+pub struct Menu<T> {
+    items: Vec<(T, String)>,
+    selected: usize,
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub fn new(items: Vec<(T, String)>) -> Self {
+        debug_assert!(!items.is_empty());
+        Self { items, selected: 0 }
+    }
+
+    pub fn selected(&self) -> T {
+        self.items[self.selected].0
+    }
+
+    /// returns the item that got picked, if any
+    /// moving past either end wraps around
+    pub fn key(&mut self, key: Key) -> Option<T> {
+        let n = self.items.len();
+        match key {
+            Key::W | Key::Up => self.selected = (self.selected + n - 1) % n,
+            Key::S | Key::Down => self.selected = (self.selected + 1) % n,
+            Key::Enter | Key::Space => return Some(self.selected()),
+            _ => (),
+        }
+
+        None
+    }
+
+    /// for items that show a value, like a volume
+    pub fn set_label(&mut self, item: T, label: String) {
+        if let Some(i) = self.items.iter_mut().find(|(t, _)| *t == item) {
+            i.1 = label;
+        }
+    }
+
+    /// position is the top left of the first item in screen space
+    /// the selected item gets a marker and stands out
+    pub fn draw(&self, renderer: &mut RenderManager, position: Vec2, size: f32, palette: Palette) {
+        for (i, (_, label)) in self.items.iter().enumerate() {
+            let (text, col) = if i == self.selected {
+                (format!("> {label}"), palette.fruit)
+            } else {
+                (format!("  {label}"), palette.ui)
+            };
+            let position = position - Vec2::new(0.0, i as f32 * ITEM_SPACING * size);
+            renderer.push(HudText::new(&text, position, size, col));
+        }
+    }
+}
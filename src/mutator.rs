@@ -0,0 +1,112 @@
+use std::{fs, io::Write, path::PathBuf};
+
+use crate::common::{self, Error, Result};
+
+/// optional rule changes picked at the start of a run
+/// harder runs are worth more points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mutator {
+    DoubleEnemies,
+    NoSmoothing,
+    MirrorControls,
+    FragileBody,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 4] = [
+        Mutator::DoubleEnemies,
+        Mutator::NoSmoothing,
+        Mutator::MirrorControls,
+        Mutator::FragileBody,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DoubleEnemies => "double-enemies",
+            Self::NoSmoothing => "no-smoothing",
+            Self::MirrorControls => "mirror-controls",
+            Self::FragileBody => "fragile-body",
+        }
+    }
+
+    pub fn score_multiplier(self) -> f32 {
+        match self {
+            Self::DoubleEnemies => 1.5,
+            Self::NoSmoothing => 1.1,
+            Self::MirrorControls => 1.5,
+            Self::FragileBody => 1.25,
+        }
+    }
+}
+
+impl TryFrom<&str> for Mutator {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Mutator> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name() == value)
+            .ok_or(Error::InvalidMutator)
+    }
+}
+
+/// reads the mutators from the command line
+/// e.g. `snek --mutators double-enemies,mirror-controls`
+pub fn from_args(args: impl Iterator<Item = String>) -> Result<Vec<Mutator>> {
+    let mut args = args.skip_while(|a| a != "--mutators").skip(1);
+    let Some(list) = args.next() else {
+        return Ok(Vec::new());
+    };
+
+    let mut mutators = list
+        .split(',')
+        .map(Mutator::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    mutators.sort();
+    mutators.dedup();
+    Ok(mutators)
+}
+
+/// combos are stored sorted, one per line, e.g. `double-enemies+fragile-body`
+fn combo_name(mutators: &[Mutator]) -> String {
+    let names = mutators.iter().map(|m| m.name()).collect::<Vec<_>>();
+    names.join("+")
+}
+
+fn completed_path() -> Option<PathBuf> {
+    common::data_path("mutators.txt")
+}
+
+fn completed_combos() -> Vec<String> {
+    completed_path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .map(|s| s.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// remember that a run with the given mutators made it through
+/// returns true if the combo was completed for the first time
+pub fn mark_completed(mutators: &[Mutator]) -> bool {
+    if mutators.is_empty() {
+        return false;
+    }
+
+    let combo = self::combo_name(mutators);
+    if completed_combos().contains(&combo) {
+        return false;
+    }
+
+    let Some(path) = completed_path() else {
+        return false;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| writeln!(f, "{combo}"))
+        .is_ok()
+}
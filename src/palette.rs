@@ -1,13 +1,39 @@
-use crate::math::Vec3;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
 
-#[derive(Default, Clone, Copy)]
+use toml::Value;
+
+use crate::{
+    common::{Error, Result},
+    math::{self, ease, Lerp, Tween, Vec3},
+    resources,
+};
+
+#[derive(Debug, Default, Clone, Copy)]
 pub enum PaletteKey {
     #[default]
     None,
     Snake,
-    _Wall,
-    _Background,
-    _Fruit,
+    Wall,
+    Background,
+    Fruit,
+    Enemy,
+    White,
+    /// the snake's attacks
+    Projectile,
+    /// what sweeps around rooms on its own
+    Hazard,
+    Spikes,
+    Lava,
+    Belt,
+    Tar,
+    /// text and bars drawn over the run
+    Ui,
+    /// behind ui, where it needs something to stand out against
+    UiBackground,
 }
 
 #[derive(Clone, Copy)]
@@ -19,6 +45,15 @@ pub struct Palette {
     pub background: Vec3,
     pub fruit: Vec3,
     pub enemy: Vec3,
+    pub projectile: Vec3,
+    pub hazard: Vec3,
+    pub ui: Vec3,
+    pub ui_background: Vec3,
+    // terrain
+    pub spikes: Vec3,
+    pub lava: Vec3,
+    pub belt: Vec3,
+    pub tar: Vec3,
 }
 
 impl Palette {
@@ -26,9 +61,19 @@ impl Palette {
         match key {
             PaletteKey::None => Vec3::default(),
             PaletteKey::Snake => self.snake,
-            PaletteKey::_Wall => self.wall,
-            PaletteKey::_Background => self.background,
-            PaletteKey::_Fruit => self.fruit,
+            PaletteKey::Wall => self.wall,
+            PaletteKey::Background => self.background,
+            PaletteKey::Fruit => self.fruit,
+            PaletteKey::Enemy => self.enemy,
+            PaletteKey::White => self.white,
+            PaletteKey::Projectile => self.projectile,
+            PaletteKey::Hazard => self.hazard,
+            PaletteKey::Spikes => self.spikes,
+            PaletteKey::Lava => self.lava,
+            PaletteKey::Belt => self.belt,
+            PaletteKey::Tar => self.tar,
+            PaletteKey::Ui => self.ui,
+            PaletteKey::UiBackground => self.ui_background,
         }
     }
 
@@ -38,75 +83,223 @@ impl Palette {
             wall: self.wall.srgb_to_linear(),
             background: self.background.srgb_to_linear(),
             fruit: self.fruit.srgb_to_linear(),
+            projectile: self.projectile.srgb_to_linear(),
+            ui_background: self.ui_background.srgb_to_linear(),
+            spikes: self.spikes.srgb_to_linear(),
+            lava: self.lava.srgb_to_linear(),
+            belt: self.belt.srgb_to_linear(),
+            tar: self.tar.srgb_to_linear(),
 
             ..self
         }
     }
+
+    /// rotate the color channels of every color (rgb -> brg)
+    pub fn swap_channels(self) -> Self {
+        let swap = |c: Vec3| Vec3::new(c.z, c.x, c.y);
+        Self {
+            black: swap(self.black),
+            white: swap(self.white),
+            snake: swap(self.snake),
+            wall: swap(self.wall),
+            background: swap(self.background),
+            fruit: swap(self.fruit),
+            enemy: swap(self.enemy),
+            projectile: swap(self.projectile),
+            hazard: swap(self.hazard),
+            ui: swap(self.ui),
+            ui_background: swap(self.ui_background),
+            spikes: swap(self.spikes),
+            lava: swap(self.lava),
+            belt: swap(self.belt),
+            tar: swap(self.tar),
+        }
+    }
+}
+
+impl Lerp for Palette {
+    /// every color p of the way over to the other palette's
+    fn lerp(self, other: Self, p: f32) -> Self {
+        let mix = |a: Vec3, b: Vec3| math::lerp(a, b, p);
+        Self {
+            black: mix(self.black, other.black),
+            white: mix(self.white, other.white),
+            snake: mix(self.snake, other.snake),
+            wall: mix(self.wall, other.wall),
+            background: mix(self.background, other.background),
+            fruit: mix(self.fruit, other.fruit),
+            enemy: mix(self.enemy, other.enemy),
+            projectile: mix(self.projectile, other.projectile),
+            hazard: mix(self.hazard, other.hazard),
+            ui: mix(self.ui, other.ui),
+            ui_background: mix(self.ui_background, other.ui_background),
+            spikes: mix(self.spikes, other.spikes),
+            lava: mix(self.lava, other.lava),
+            belt: mix(self.belt, other.belt),
+            tar: mix(self.tar, other.tar),
+        }
+    }
+}
+
+/// what everything's drawn in until something else gets picked
+pub const DEFAULT: &str = "crt";
+
+// how long switching palettes takes
+const FADE: Duration = Duration::from_secs(1);
+
+// the colors every palette has to have, in the order they're parsed
+const COLORS: [&str; 15] = [
+    "black", "white", "snake", "wall", "background", "fruit", "enemy", "projectile", "hazard", "ui", "ui_background",
+    "spikes", "lava", "belt", "tar",
+];
+
+// "RRGGBB" or [r, g, b] in [0, 1]
+fn parse_color(value: &Value) -> Option<Vec3> {
+    match value {
+        Value::String(hex) if hex.len() == 6 => Vec3::hexcode(hex).ok(),
+        Value::Array(rgb) => {
+            let rgb = rgb.iter().map(|c| c.as_float().or_else(|| c.as_integer().map(|i| i as f64)));
+            match rgb.collect::<Option<Vec<_>>>()?.as_slice() {
+                &[r, g, b] => Some(Vec3::new(r as f32, g as f32, b as f32)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
 }
 
-pub fn _aperture() -> Palette {
-    let offwhite = Vec3::rgb(0xEA, 0xDF, 0xB4);
-    let light_blue = Vec3::rgb(0x9B, 0xB0, 0xC1);
-    let dark_blue = Vec3::rgb(0x51, 0x82, 0x9B);
-    let orange = Vec3::rgb(0xF6, 0x99, 0x5C);
-    Palette {
-        black: Vec3::rgb(1, 1, 1),
-        white: Vec3::new(1.0, 1.0, 1.0),
-        snake: offwhite,
-        wall: light_blue,
-        background: dark_blue,
-        fruit: orange,
-        enemy: Vec3::new(1.0, 0.0, 0.0),
+fn parse_palette(name: &str, table: &Value) -> Result<Palette> {
+    let mut colors = [Vec3::default(); COLORS.len()];
+    for (color, key) in colors.iter_mut().zip(COLORS) {
+        let value = table
+            .get(key)
+            .ok_or_else(|| Error::BadPalette(format!("{name} has no {key}")))?;
+        *color = self::parse_color(value)
+            .ok_or_else(|| Error::BadPalette(format!("{name}.{key} isn't a color")))?;
     }
-    .srgb_to_linear()
+
+    let [black, white, snake, wall, background, fruit, enemy, projectile, hazard, ui, ui_background, spikes, lava, belt, tar] =
+        colors;
+    let palette = Palette {
+        black,
+        white,
+        snake,
+        wall,
+        background,
+        fruit,
+        enemy,
+        projectile,
+        hazard,
+        ui,
+        ui_background,
+        spikes,
+        lava,
+        belt,
+        tar,
+    };
+    let srgb = table.get("srgb").and_then(Value::as_bool).unwrap_or(true);
+    Ok(if srgb { palette.srgb_to_linear() } else { palette })
 }
 
-pub fn _bright_pastel() -> Palette {
-    let green = Vec3::hexcode("5EFC8D").unwrap();
-    let lavender = Vec3::hexcode("FDECEF").unwrap();
-    let pink = Vec3::hexcode("EF476F").unwrap();
-    let indigo = Vec3::hexcode("8377D1").unwrap();
-    let sunglow = Vec3::hexcode("FFD166").unwrap();
-
-    Palette {
-        black: Vec3::rgb(1, 1, 1),
-        white: Vec3::new(1.0, 1.0, 1.0),
-        snake: green,
-        wall: indigo,
-        background: lavender,
-        fruit: sunglow,
-        enemy: pink,
+fn parse(contents: &[u8]) -> Result<HashMap<String, Palette>> {
+    let contents = std::str::from_utf8(contents).map_err(|_| Error::BadPalette("not utf-8".to_string()))?;
+    let table = contents
+        .parse::<Value>()
+        .map_err(|e| Error::BadPalette(e.to_string()))?;
+    let Value::Table(table) = table else {
+        return Err(Error::BadPalette("not a table".to_string()));
+    };
+
+    let palettes = table
+        .iter()
+        .map(|(name, palette)| Ok((name.clone(), self::parse_palette(name, palette)?)))
+        .collect::<Result<HashMap<_, _>>>()?;
+    if !palettes.contains_key(DEFAULT) {
+        return Err(Error::BadPalette(format!("there has to be a {DEFAULT} palette")));
     }
-    .srgb_to_linear()
+    Ok(palettes)
+}
+
+// with the generation they were read at
+static PALETTES: Mutex<Option<(usize, HashMap<String, Palette>)>> = Mutex::new(None);
+
+// with hot reloading on, which change to res/ the palettes are up to date with
+fn generation() -> usize {
+    #[cfg(feature = "hot-reload")]
+    return crate::hot_reload::generation();
+    #[cfg(not(feature = "hot-reload"))]
+    0
 }
 
-pub fn dark_pastel() -> Palette {
-    let dark_blue = Vec3::hexcode("102542").unwrap();
-    let light_blue = Vec3::hexcode("9DCDC0").unwrap();
-    let pink = Vec3::hexcode("EF476F").unwrap();
-    let indigo = Vec3::hexcode("8377D1").unwrap();
-    let sunglow = Vec3::hexcode("FFD166").unwrap();
-
-    Palette {
-        black: Vec3::rgb(1, 1, 1),
-        white: Vec3::new(1.0, 1.0, 1.0),
-        snake: light_blue,
-        wall: indigo,
-        background: dark_blue,
-        fruit: sunglow,
-        enemy: pink,
+/// for picking a palette by name, like from the console or the settings
+/// with hot reloading on, the palettes file gets read again whenever it changes
+pub fn by_name(name: &str) -> Option<Palette> {
+    let mut palettes = PALETTES.lock().unwrap_or_else(PoisonError::into_inner);
+    let generation = self::generation();
+    if palettes.as_ref().is_none_or(|&(seen, _)| seen != generation) {
+        // a bad edit keeps whatever was there before
+        let parsed = self::parse(&resources::PALETTES.source())
+            .or_else(|e| {
+                log::warn!("kept the palettes as they were: {e}");
+                match palettes.take() {
+                    Some((_, old)) => Ok(old),
+                    None => self::parse(resources::PALETTES.baked()),
+                }
+            })
+            .expect("built-in palettes should parse");
+        *palettes = Some((generation, parsed));
     }
-    .srgb_to_linear()
+
+    palettes.as_ref()?.1.get(name).copied()
+}
+
+/// every palette there is, in alphabetical order
+pub fn names() -> Vec<String> {
+    // loads them if nothing has yet
+    self::default();
+    let palettes = PALETTES.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut names: Vec<_> = palettes.iter().flat_map(|(_, p)| p.keys().cloned()).collect();
+    names.sort();
+    names
+}
+
+/// the palette everything's drawn in by default
+pub fn default() -> Palette {
+    self::by_name(DEFAULT).expect("the palettes always have a default")
+}
+
+/// eases into whichever palette is asked for instead of cutting straight to it
+pub struct Fade {
+    name: String,
+    tween: Tween<Palette>,
+    ticked: Instant,
 }
 
-pub fn crt() -> Palette {
-    Palette {
-        black: Vec3::rgb(1, 1, 1),
-        white: Vec3::new(1.0, 1.0, 1.0),
-        snake: Vec3::new(0.0, 1.0, 0.0),
-        wall: Vec3::new(1.0, 1.0, 1.0),
-        background: Vec3::rgb(2, 2, 2),
-        fruit: Vec3::new(1.0, 0.0, 0.0),
-        enemy: Vec3::new(1.0, 1.0, 0.1),
+impl Fade {
+    pub fn new(name: &str) -> Self {
+        let palette = self::by_name(name).unwrap_or_else(self::default);
+        Self {
+            name: name.to_string(),
+            tween: Tween::at(palette),
+            ticked: Instant::now(),
+        }
+    }
+
+    /// the palette to draw in this frame, on the way to the one called name
+    pub fn update(&mut self, name: &str) -> Palette {
+        let now = Instant::now();
+        self.tween.tick(now - self.ticked);
+        self.ticked = now;
+
+        // looked up every frame, so hot reloaded palettes show up right away
+        let to = self::by_name(name).unwrap_or_else(self::default);
+        if name != self.name {
+            // from whatever's on screen, even halfway through another switch
+            self.name = name.to_string();
+            self.tween = Tween::new(self.tween.value(), to, FADE, ease::in_out_sine);
+        } else {
+            self.tween.retarget(to);
+        }
+        self.tween.value()
     }
 }
@@ -1,12 +1,14 @@
 use std::mem::{offset_of, size_of};
 
 use crate::{
-    common::{as_bytes, AsBytes},
+    common::{as_bytes, AsBytes, Result},
     gl::{self, ArrayBuffer, DrawContext, Shader, Vao},
     math::{Vec2, Vec4},
     resources,
 };
 
+use super::Batch;
+
 pub struct Fireball {
     pub pos: Vec2,
     pub col: Vec4,
@@ -20,12 +22,11 @@ pub struct FireballManager<'a> {
     vbo: ArrayBuffer<'a>,
     shader: Shader<'a>,
 
-    num_fireballs: usize,
-    max_fireballs: usize,
+    fireballs: Batch<Fireball>,
 }
 
 impl<'a> FireballManager<'a> {
-    pub fn new(ctx: &'a DrawContext, max_fireballs: usize) -> Self {
+    pub fn new(ctx: &'a DrawContext, max_fireballs: usize) -> Result<Self> {
         let vbo = ArrayBuffer::new(ctx);
         vbo.reserve(
             max_fireballs * size_of::<Fireball>(),
@@ -67,37 +68,36 @@ impl<'a> FireballManager<'a> {
         ));
 
         // let shader = Shader::from_file(ctx, Path::new("res/shaders/fireball"))
-        let shader = Shader::from_resource(ctx, resources::shaders::FIREBALL)
-            .expect("Fireball shader error");
+        let shader = Shader::from_resource(ctx, resources::shaders::FIREBALL)?;
 
-        Self {
+        Ok(Self {
             vao,
             vbo,
             shader,
 
-            num_fireballs: 0,
-            max_fireballs,
-        }
+            fireballs: Batch::new(max_fireballs),
+        })
     }
 
     pub fn push(&mut self, fireball: Fireball) {
-        if self.num_fireballs == self.max_fireballs {
-            panic!("max fireballs")
-        }
-
-        self.vbo
-            .update(self.num_fireballs * size_of::<Fireball>(), unsafe {
-                fireball.as_bytes()
-            });
+        self.fireballs.push(fireball);
+    }
 
-        self.num_fireballs += 1;
+    pub fn dropped(&self) -> usize {
+        self.fireballs.dropped()
     }
 
     pub fn draw(&mut self) {
+        if self.fireballs.is_empty() {
+            return;
+        }
+
         self.vao.apply();
         self.shader.apply();
-        gl::call!(DrawArrays(POINTS, 0, self.num_fireballs as _));
+        self.vbo
+            .update(0, unsafe { self.fireballs.as_mut_slice().as_bytes() });
+        gl::draw!(DrawArrays(POINTS, 0, self.fireballs.len() as _));
 
-        self.num_fireballs = 0;
+        self.fireballs.clear();
     }
 }
@@ -0,0 +1,189 @@
+use std::mem::{offset_of, size_of};
+
+use crate::{
+    common::{as_bytes, AsBytes, Result},
+    gl::{self, ArrayBuffer, DrawContext, Shader, Texture2D, Vao},
+    math::{Vec2, Vec3},
+    resources,
+};
+
+use super::{
+    text::{Font, Glyph, LETTER_SIZE},
+    VaoHelper,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    pos: Vec2,
+    uv: Vec2,
+    col: Vec3,
+}
+
+as_bytes!(Vertex);
+
+const VERTICES_PER_GLYPH: usize = 6;
+
+/// text drawn in screen space, unaffected by the camera
+/// laid out once it reaches the manager, straight into its batch
+#[derive(Debug)]
+pub struct HudText {
+    text: String,
+    position: Vec2,
+    size: f32,
+    col: Vec3,
+}
+
+impl HudText {
+    /// position is the top left corner in screen space ([-1,1] on both axes)
+    /// size is the height of a glyph in screen space
+    pub fn new(text: &str, position: Vec2, size: f32, col: Vec3) -> Self {
+        Self {
+            text: text.to_string(),
+            position,
+            size,
+            col,
+        }
+    }
+
+    fn vertices<'g>(&self, glyphs: &'g [Glyph]) -> impl Iterator<Item = Vertex> + 'g {
+        let Self { position, col, .. } = *self;
+        let scale = self.size / LETTER_SIZE;
+        // font pixels go down, screen space goes up
+        let to_screen = move |p: Vec2| Vec2::new(position.x + scale * p.x, position.y - scale * p.y);
+
+        glyphs.iter().flat_map(move |g| {
+            let top_left = Vertex {
+                pos: to_screen(g.position),
+                uv: g.uv_min,
+                col,
+            };
+            let top_right = Vertex {
+                pos: to_screen(g.position + Vec2::new(g.size.x, 0.0)),
+                uv: Vec2::new(g.uv_max.x, g.uv_min.y),
+                col,
+            };
+            let bottom_left = Vertex {
+                pos: to_screen(g.position + Vec2::new(0.0, g.size.y)),
+                uv: Vec2::new(g.uv_min.x, g.uv_max.y),
+                col,
+            };
+            let bottom_right = Vertex {
+                pos: to_screen(g.position + g.size),
+                uv: g.uv_max,
+                col,
+            };
+
+            [
+                top_left,
+                top_right,
+                bottom_left,
+                top_right,
+                bottom_right,
+                bottom_left,
+            ]
+        })
+    }
+}
+
+/// a frame with more text than fits grows the buffer instead of losing any
+pub struct HudManager<'a> {
+    ctx: &'a DrawContext,
+    vao: Vao<'a>,
+    vbo: ArrayBuffer<'a>,
+    shader: Shader<'a>,
+    atlas: Texture2D<'a>,
+
+    vertices: Vec<Vertex>,
+    // how many vertices the buffer holds
+    capacity: usize,
+    // laid out into every frame, instead of a new one per text
+    glyphs: Vec<Glyph>,
+}
+
+impl<'a> HudManager<'a> {
+    /// max_glyphs is only where the buffer starts out
+    pub fn new(ctx: &'a DrawContext, max_glyphs: usize) -> Result<Self> {
+        let capacity = max_glyphs * VERTICES_PER_GLYPH;
+        let (vao, vbo) = Self::buffers(ctx, capacity);
+
+        Ok(Self {
+            ctx,
+            vao,
+            vbo,
+            shader: Shader::from_resource(ctx, resources::shaders::HUD)?,
+            atlas: Font::get().atlas(ctx),
+
+            vertices: Vec::with_capacity(capacity),
+            capacity,
+            glyphs: Vec::new(),
+        })
+    }
+
+    fn buffers(ctx: &'a DrawContext, capacity: usize) -> (Vao<'a>, ArrayBuffer<'a>) {
+        let vbo = ArrayBuffer::new(ctx);
+        vbo.reserve(
+            capacity * size_of::<Vertex>(),
+            gl::buffer_flags::DYNAMIC_STORAGE,
+        );
+
+        let vao = VaoHelper::new(ctx)
+            .bind_buffer(&vbo)
+            .push_attrib(
+                2,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Vertex>(),
+                offset_of!(Vertex, pos),
+            )
+            .push_attrib(
+                2,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Vertex>(),
+                offset_of!(Vertex, uv),
+            )
+            .push_attrib(
+                3,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Vertex>(),
+                offset_of!(Vertex, col),
+            )
+            .build();
+
+        (vao, vbo)
+    }
+
+    pub fn push(&mut self, text: HudText) {
+        self.glyphs.clear();
+        if let Err(e) = Font::get().layout_into(&text.text, &mut self.glyphs) {
+            log::warn!("can't draw {:?}: {e}", text.text);
+            return;
+        }
+        self.vertices.extend(text.vertices(&self.glyphs));
+    }
+
+    const BINDING_GLYPHS: usize = 0;
+
+    pub fn draw(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        if self.vertices.len() > self.capacity {
+            self.capacity = self.vertices.len().next_power_of_two();
+            (self.vao, self.vbo) = Self::buffers(self.ctx, self.capacity);
+            log::info!("hud buffer grew to {} vertices", self.capacity);
+        }
+
+        self.vao.apply();
+        self.shader.apply();
+        self.vbo
+            .update(0, unsafe { self.vertices.as_mut_slice().as_bytes() });
+        self.atlas.bind(Self::BINDING_GLYPHS);
+        gl::draw!(DrawArrays(TRIANGLES, 0, self.vertices.len() as _));
+
+        self.vertices.clear();
+    }
+}
@@ -1,15 +1,20 @@
 use std::{
-    mem::{size_of, size_of_val},
+    collections::HashMap,
+    mem::{self, size_of},
     ptr::null,
+    rc::Rc,
 };
 
 use crate::{
-    common::{as_bytes, AsBytes},
-    gl::{self, ArrayBuffer, DrawContext, IndexBuffer, Shader, Vao},
+    common::{as_bytes, AsBytes, Result},
+    entity::EntityId,
+    gl::{self, ArrayBuffer, DrawContext, IndexBuffer, Shader, Uniform, Vao},
     math::{Mat4, Vec3, Vec4},
     resources,
 };
 
+use super::Batch;
+
 // per vertex
 #[derive(Default)]
 pub struct Vertex {
@@ -27,16 +32,35 @@ pub struct Tile {
 as_bytes!(Vertex);
 as_bytes!(Tile);
 
+/// tiles that never move
+/// uploaded once, then redrawn for as long as they keep getting pushed
+pub struct Scenery {
+    /// identifies the batch between frames
+    pub id: EntityId,
+    pub tiles: Rc<Vec<Tile>>,
+    /// tints every tile in the batch
+    pub col: Vec3,
+}
+
+struct StaticBatch<'a> {
+    vao: Vao<'a>,
+    _instance_data: ArrayBuffer<'a>,
+    num_instances: usize,
+    col: Vec3,
+    drawn: bool,
+}
+
 pub struct InstancedShapeManager<'a> {
+    ctx: &'a DrawContext,
     vao: Vao<'a>,
     index_data: IndexBuffer<'a>,
-    _vertex_data: ArrayBuffer<'a>,
+    vertex_data: ArrayBuffer<'a>,
     instance_data: ArrayBuffer<'a>,
     shader: Shader<'a>,
+    statics: HashMap<EntityId, StaticBatch<'a>>,
 
     num_indices: usize,
-    num_instances: usize,
-    max_instances: usize,
+    instances: Batch<Tile>,
 }
 
 impl<'a> InstancedShapeManager<'a> {
@@ -46,7 +70,7 @@ impl<'a> InstancedShapeManager<'a> {
         index_data: IndexBuffer<'a>,
         max_instances: usize,
         num_indices: usize,
-    ) -> Self {
+    ) -> Result<Self> {
         let vao = Vao::new(ctx);
 
         let instance_data = ArrayBuffer::new(ctx);
@@ -57,21 +81,22 @@ impl<'a> InstancedShapeManager<'a> {
 
         // set up vertex_data + indices
         vao.bind_instance_attribs(&vertex_data, &instance_data);
-        Self {
+        Ok(Self {
+            ctx,
             vao,
             index_data,
-            _vertex_data: vertex_data,
+            vertex_data,
             instance_data,
             // shader: Shader::from_file(ctx, Path::new("res/shaders/instanced")).unwrap(),
-            shader: Shader::from_resource(ctx, resources::shaders::INSTANCED).unwrap(),
+            shader: Shader::from_resource(ctx, resources::shaders::INSTANCED)?,
+            statics: Default::default(),
 
             num_indices,
-            num_instances: 0,
-            max_instances,
-        }
+            instances: Batch::new(max_instances),
+        })
     }
 
-    pub fn quads(ctx: &'a DrawContext, max_instances: usize) -> Self {
+    pub fn quads(ctx: &'a DrawContext, max_instances: usize) -> Result<Self> {
         let vertex_data = ArrayBuffer::new(ctx);
         let index_data = IndexBuffer::new(ctx);
 
@@ -84,33 +109,81 @@ impl<'a> InstancedShapeManager<'a> {
         Self::new(ctx, vertex_data, index_data, max_instances, indices.len())
     }
 
-    /// Returns none if max instances reached
     pub fn push(&mut self, tile: Tile) {
-        if self.num_instances == self.max_instances {
-            panic!("Instance limit reached");
-        }
+        self.instances.push(tile);
+    }
 
-        let offset = size_of_val(&tile) * self.num_instances;
-        self.instance_data
-            .update(offset, unsafe { tile.as_bytes() });
+    pub fn dropped(&self) -> usize {
+        self.instances.dropped()
+    }
+
+    /// only the first push of a batch touches the gpu
+    pub fn push_scenery(&mut self, scenery: Scenery) {
+        if let Some(batch) = self.statics.get_mut(&scenery.id) {
+            batch.col = scenery.col;
+            batch.drawn = true;
+            return;
+        }
+        if scenery.tiles.is_empty() {
+            return;
+        }
 
-        self.num_instances += 1;
+        let instance_data = ArrayBuffer::new(self.ctx);
+        instance_data.set(
+            unsafe { scenery.tiles.as_slice().as_bytes() },
+            gl::buffer_flags::DEFAULT,
+        );
+        let vao = Vao::new(self.ctx);
+        vao.bind_instance_attribs(&self.vertex_data, &instance_data);
+
+        self.statics.insert(
+            scenery.id,
+            StaticBatch {
+                vao,
+                _instance_data: instance_data,
+                num_instances: scenery.tiles.len(),
+                col: scenery.col,
+                drawn: true,
+            },
+        );
     }
 
+    const UNIFORM_TINT: i32 = 0;
+
     pub fn draw(&mut self) {
-        self.vao.apply();
         self.shader.apply();
+
+        for batch in self.statics.values().filter(|b| b.drawn) {
+            batch.vao.apply();
+            self.index_data.apply();
+            batch.col.uniform(Self::UNIFORM_TINT);
+            self.draw_instances(batch.num_instances);
+        }
+        // batches that weren't pushed this frame belong to dead scenery
+        self.statics.retain(|_, b| mem::take(&mut b.drawn));
+
+        if self.instances.is_empty() {
+            return;
+        }
+
+        self.vao.apply();
         self.index_data.apply();
+        self.instance_data
+            .update(0, unsafe { self.instances.as_mut_slice().as_bytes() });
+        Vec3::diagonal(1.0).uniform(Self::UNIFORM_TINT);
+        self.draw_instances(self.instances.len());
 
-        gl::call!(DrawElementsInstanced(
+        self.instances.clear();
+    }
+
+    fn draw_instances(&self, num_instances: usize) {
+        gl::draw!(DrawElementsInstanced(
             TRIANGLES,
             self.num_indices as _,
             UNSIGNED_BYTE,
             null(),
-            self.num_instances as _,
+            num_instances as _,
         ));
-
-        self.num_instances = 0;
     }
 }
 
@@ -1,29 +1,40 @@
 use core::slice;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     mem::size_of_val,
     time::{Duration, Instant},
 };
 
 use crate::{
-    gl::{self, call, ArrayBuffer, DrawContext, FrameBuffer, Shader, Uniform, Vao},
-    math::{ease, Vec3},
-    resources,
+    common::Result,
+    gl::{self, ArrayBuffer, DrawContext, FrameBuffer, TimerQuery, Vao},
+    math::{ease, Mat4, Rect, Vec2, Vec3, Vec4},
 };
 
 use self::{
     fireball::{Fireball, FireballManager},
-    instanced::{InstancedShapeManager, Tile},
+    hud::{HudManager, HudText},
+    instanced::{InstancedShapeManager, Scenery, Tile},
+    particles::{Burst, ParticleManager},
+    post::{CrtSettings, Lighting, PointLight, PostChain, PostEffect, PostParams},
     shield::{Shield, ShieldManager},
     swoop::{Swoop, SwoopManager},
     text::{Text, TextManager},
+    trail::{Trail, TrailManager},
 };
 
 pub mod fireball;
+pub mod hud;
 pub mod instanced;
+pub mod particles;
+pub mod post;
 pub mod shield;
 pub mod swoop;
 pub mod text;
+pub mod trail;
+
+// how many frames the frame stats look back on
+const FRAME_HISTORY: usize = 48;
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 enum RenderType {
@@ -31,15 +42,50 @@ enum RenderType {
     Fireball,
     Shield,
     Swoop,
+    Trail,
+    Particles,
     Text,
+    Hud,
+}
+
+/// renderers are drawn layer by layer, in this order
+/// within a layer they're drawn in the order they were added
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    /// opaque tiles, drawn first so translucents can blend over them
+    Background,
+    World,
+    /// translucent, drawn back to front
+    Effects,
+    /// screen space, over everything
+    Ui,
 }
 
 pub enum Element {
     Tile(Tile),
+    Scenery(Scenery),
     Fireball(Fireball),
     Shield(Shield),
     Swoop(Swoop),
+    Trail(Trail),
+    Burst(Burst, Vec3),
     Text(Text),
+    Hud(HudText),
+}
+
+impl Element {
+    fn render_type(&self) -> RenderType {
+        match self {
+            Element::Tile(_) | Element::Scenery(_) => RenderType::Tile,
+            Element::Fireball(_) => RenderType::Fireball,
+            Element::Shield(_) => RenderType::Shield,
+            Element::Swoop(_) => RenderType::Swoop,
+            Element::Trail(_) => RenderType::Trail,
+            Element::Burst(..) => RenderType::Particles,
+            Element::Text(_) => RenderType::Text,
+            Element::Hud(_) => RenderType::Hud,
+        }
+    }
 }
 
 impl From<Tile> for Element {
@@ -48,6 +94,12 @@ impl From<Tile> for Element {
     }
 }
 
+impl From<Scenery> for Element {
+    fn from(value: Scenery) -> Self {
+        Self::Scenery(value)
+    }
+}
+
 impl From<Fireball> for Element {
     fn from(value: Fireball) -> Self {
         Self::Fireball(value)
@@ -66,18 +118,39 @@ impl From<Swoop> for Element {
     }
 }
 
+impl From<Trail> for Element {
+    fn from(value: Trail) -> Self {
+        Self::Trail(value)
+    }
+}
+
+impl From<(Burst, Vec3)> for Element {
+    fn from((burst, col): (Burst, Vec3)) -> Self {
+        Self::Burst(burst, col)
+    }
+}
+
 impl From<Text> for Element {
     fn from(value: Text) -> Self {
         Self::Text(value)
     }
 }
 
+impl From<HudText> for Element {
+    fn from(value: HudText) -> Self {
+        Self::Hud(value)
+    }
+}
+
 pub enum Renderer<'a> {
     Tile(InstancedShapeManager<'a>),
     Fireball(FireballManager<'a>),
     Shield(ShieldManager<'a>),
     Swoop(SwoopManager<'a>),
+    Trail(TrailManager<'a>),
+    Particles(ParticleManager<'a>),
     Text(TextManager<'a>),
+    Hud(HudManager<'a>),
 }
 
 impl<'a> From<InstancedShapeManager<'a>> for Renderer<'a> {
@@ -104,31 +177,66 @@ impl<'a> From<SwoopManager<'a>> for Renderer<'a> {
     }
 }
 
+impl<'a> From<ParticleManager<'a>> for Renderer<'a> {
+    fn from(value: ParticleManager<'a>) -> Self {
+        Self::Particles(value)
+    }
+}
+
+impl<'a> From<TrailManager<'a>> for Renderer<'a> {
+    fn from(value: TrailManager<'a>) -> Self {
+        Self::Trail(value)
+    }
+}
+
 impl<'a> From<TextManager<'a>> for Renderer<'a> {
     fn from(value: TextManager<'a>) -> Self {
         Self::Text(value)
     }
 }
 
+impl<'a> From<HudManager<'a>> for Renderer<'a> {
+    fn from(value: HudManager<'a>) -> Self {
+        Self::Hud(value)
+    }
+}
+
 impl<'a> Renderer<'a> {
+    /// where the renderer goes unless told otherwise
+    fn default_layer(&self) -> Layer {
+        match self {
+            Renderer::Tile(_) => Layer::Background,
+            Renderer::Text(_) => Layer::World,
+            Renderer::Swoop(_)
+            | Renderer::Trail(_)
+            | Renderer::Particles(_)
+            | Renderer::Fireball(_)
+            | Renderer::Shield(_) => Layer::Effects,
+            Renderer::Hud(_) => Layer::Ui,
+        }
+    }
+
     fn render_type(&self) -> RenderType {
         match self {
             Renderer::Tile(_) => RenderType::Tile,
             Renderer::Fireball(_) => RenderType::Fireball,
             Renderer::Shield(_) => RenderType::Shield,
             Renderer::Swoop(_) => RenderType::Swoop,
+            Renderer::Trail(_) => RenderType::Trail,
+            Renderer::Particles(_) => RenderType::Particles,
             Renderer::Text(_) => RenderType::Text,
+            Renderer::Hud(_) => RenderType::Hud,
         }
     }
 
     pub fn push(&mut self, element: impl Into<Element>) {
         let element = element.into();
         match self {
-            Renderer::Tile(tile) => {
-                if let Element::Tile(t) = element {
-                    tile.push(t)
-                }
-            }
+            Renderer::Tile(tile) => match element {
+                Element::Tile(t) => tile.push(t),
+                Element::Scenery(s) => tile.push_scenery(s),
+                _ => (),
+            },
             Renderer::Fireball(fire) => {
                 if let Element::Fireball(f) = element {
                     fire.push(f)
@@ -144,11 +252,41 @@ impl<'a> Renderer<'a> {
                     swoop.push(s)
                 }
             }
+            Renderer::Trail(trail) => {
+                if let Element::Trail(t) = element {
+                    trail.push(t)
+                }
+            }
+            Renderer::Particles(particles) => {
+                if let Element::Burst(b, col) = element {
+                    particles.push(b, col)
+                }
+            }
             Renderer::Text(text) => {
                 if let Element::Text(t) = element {
                     text.push(t)
                 }
             }
+            Renderer::Hud(hud) => {
+                if let Element::Hud(h) = element {
+                    hud.push(h)
+                }
+            }
+        }
+    }
+
+    /// elements dropped for lack of space, none if it never drops any
+    pub fn dropped(&self) -> Option<usize> {
+        match self {
+            Renderer::Tile(t) => Some(t.dropped()),
+            Renderer::Fireball(f) => Some(f.dropped()),
+            Renderer::Shield(s) => Some(s.dropped()),
+            Renderer::Swoop(s) => Some(s.dropped()),
+            Renderer::Trail(t) => Some(t.dropped()),
+            // the ring overwrites the oldest particles by design
+            Renderer::Particles(_) => None,
+            // text grows its buffer instead
+            Renderer::Text(_) | Renderer::Hud(_) => None,
         }
     }
 
@@ -158,23 +296,70 @@ impl<'a> Renderer<'a> {
             Renderer::Fireball(f) => f.draw(),
             Renderer::Shield(s) => s.draw(),
             Renderer::Swoop(s) => s.draw(),
+            Renderer::Trail(t) => t.draw(),
+            Renderer::Particles(p) => p.draw(),
             Renderer::Text(t) => t.draw(),
+            Renderer::Hud(h) => h.draw(),
         }
     }
 }
 
+/// how the last frames went, for the debug overlay
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    /// between the starts of consecutive frames, the oldest first
+    pub frame_times: VecDeque<Duration>,
+    /// none until the gpu gets back with a result
+    pub gpu_time: Option<Duration>,
+    pub draw_calls: usize,
+    /// elements that made it to each renderer last frame
+    pub pushed: Vec<(String, usize)>,
+}
+
+impl FrameStats {
+    pub fn average_frame_time(&self) -> Duration {
+        let frames = self.frame_times.len().max(1) as u32;
+        self.frame_times.iter().sum::<Duration>() / frames
+    }
+
+    pub fn fps(&self) -> f32 {
+        let average = self.average_frame_time().as_secs_f32();
+        if average > 0.0 { 1.0 / average } else { 0.0 }
+    }
+}
+
 pub struct RenderManager<'a> {
     framebuffer: FrameBuffer<'a>,
     vao: Vao<'a>,
     _vbo: ArrayBuffer<'a>,
-    shader: Shader<'a>,
+    post: PostChain<'a>,
+    lighting: Lighting<'a>,
     start_time: Instant,
+    glitch: f32,
+    danger: f32,
+    bloom: f32,
+    crt: CrtSettings,
+    view: Mat4,
+    visible: Option<Rect>,
+    // how dark it gets away from the lights, [0,1]
+    dark: f32,
+    // for this frame only
+    lights: Vec<PointLight>,
+
+    stats: FrameStats,
+    last_frame: Option<Instant>,
+    pushed: HashMap<RenderType, usize>,
+    // one times this frame while the other one's result comes in
+    timers: [TimerQuery<'a>; 2],
+    frames: usize,
 
     renderers: HashMap<RenderType, Renderer<'a>>,
+    // kept sorted by layer
+    draw_order: Vec<(Layer, RenderType)>,
 }
 
 impl<'a> RenderManager<'a> {
-    pub fn new(ctx: &'a DrawContext) -> Self {
+    pub fn new(ctx: &'a DrawContext) -> Result<Self> {
         let vao = Vao::new(ctx);
         let vbo = ArrayBuffer::new(ctx);
         let vertex_positions = [
@@ -196,28 +381,135 @@ impl<'a> RenderManager<'a> {
         gl::call!(EnableVertexAttribArray(1));
         gl::call!(VertexAttribPointer(1, 2, FLOAT, FALSE, 4 * 4, (4 * 2) as _));
 
-        Self {
+        Ok(Self {
             framebuffer: FrameBuffer::new_screen(ctx),
             vao,
             _vbo: vbo,
-            shader: Shader::from_resource(ctx, resources::shaders::CRT).expect("bad crt shader"),
+            post: PostChain::new(ctx)?,
+            lighting: Lighting::new(ctx)?,
             start_time: Instant::now(),
+            glitch: 0.0,
+            danger: 0.0,
+            bloom: post::BLOOM_INTENSITY,
+            crt: Default::default(),
+            view: Mat4::identity(),
+            visible: None,
+            dark: 0.0,
+            lights: Vec::new(),
+
+            stats: FrameStats::default(),
+            last_frame: None,
+            pushed: HashMap::new(),
+            timers: [TimerQuery::new(ctx), TimerQuery::new(ctx)],
+            frames: 0,
 
             renderers: Default::default(),
-        }
+            draw_order: Default::default(),
+        })
     }
 
     pub fn add_renderer(&mut self, renderer: impl Into<Renderer<'a>>) {
         let renderer = renderer.into();
-        self.renderers.insert(renderer.render_type(), renderer);
+        self.add_renderer_on(renderer.default_layer(), renderer);
+    }
+
+    /// replaces any renderer of the same kind
+    pub fn add_renderer_on(&mut self, layer: Layer, renderer: impl Into<Renderer<'a>>) {
+        let renderer = renderer.into();
+        let render_type = renderer.render_type();
+        self.renderers.insert(render_type, renderer);
+
+        self.draw_order.retain(|&(_, t)| t != render_type);
+        self.draw_order.push((layer, render_type));
+        // stable, so renderers keep the order they were added in within a layer
+        self.draw_order.sort_by_key(|&(l, _)| l);
+    }
+
+    /// strength of the full-screen glitch, [0,1]
+    pub fn set_glitch(&mut self, glitch: f32) {
+        self.glitch = glitch;
+    }
+
+    /// how close the snake is to dying, [0,1]
+    pub fn set_danger(&mut self, danger: f32) {
+        self.danger = danger;
+    }
+
+    /// how strongly bright things glow, see [`post::BLOOM_INTENSITY`]
+    pub fn set_bloom(&mut self, intensity: f32) {
+        self.bloom = intensity;
+    }
+
+    /// how dark everything outside of a light gets, [0,1]
+    pub fn set_darkness(&mut self, darkness: f32) {
+        self.dark = darkness;
+    }
+
+    /// only lasts for the frame it was pushed in
+    pub fn push_light(&mut self, light: PointLight) {
+        self.lights.push(light);
+    }
+
+    /// the crt warms up from here
+    pub fn power_on(&mut self) {
+        self.start_time = Instant::now();
+    }
+
+    pub fn crt(&self) -> CrtSettings {
+        self.crt
+    }
+
+    pub fn set_crt(&mut self, settings: CrtSettings) {
+        self.crt = settings;
+        self.post.set_enabled(PostEffect::Crt, settings.enabled);
+    }
+
+    pub fn set_effect(&mut self, effect: PostEffect, enabled: bool) {
+        self.post.set_enabled(effect, enabled);
+    }
+
+    /// returns whether the post-process effect is now enabled
+    pub fn toggle_effect(&mut self, effect: PostEffect) -> bool {
+        self.post.toggle(effect)
+    }
+
+    /// overflow counters for each renderer, for debugging
+    pub fn dropped(&self) -> Vec<(String, usize)> {
+        let mut dropped: Vec<_> = self
+            .renderers
+            .iter()
+            .filter_map(|(t, r)| Some((format!("{t:?}"), r.dropped()?)))
+            .collect();
+        dropped.sort();
+        dropped
+    }
+
+    /// as of the last frame drawn
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// the view used for culling
+    /// tiles outside of it are dropped before they reach the gpu
+    pub fn set_view(&mut self, view: Mat4) {
+        self.view = view;
+        // a little slack so nothing pops in at the edges
+        self.visible = Some(Rect::of_view(view).grow(1.0));
     }
 
     pub fn push(&mut self, element: impl Into<Element>) {
-        match element.into() {
+        let element = element.into();
+        let render_type = element.render_type();
+        let pushed = match element {
+            Element::Tile(tile) if !self.is_visible(Rect::of_tile(tile.transform)) => None,
             Element::Tile(tile) => self
                 .renderers
                 .get_mut(&RenderType::Tile)
                 .map(|r| r.push(tile)),
+            Element::Scenery(scenery) => self
+                .renderers
+                .get_mut(&RenderType::Tile)
+                .map(|r| r.push(scenery)),
             Element::Fireball(fire) => self
                 .renderers
                 .get_mut(&RenderType::Fireball)
@@ -230,52 +522,204 @@ impl<'a> RenderManager<'a> {
                 .renderers
                 .get_mut(&RenderType::Swoop)
                 .map(|r| r.push(swoop)),
+            Element::Trail(trail) => self
+                .renderers
+                .get_mut(&RenderType::Trail)
+                .map(|r| r.push(trail)),
+            Element::Burst(burst, col) => self
+                .renderers
+                .get_mut(&RenderType::Particles)
+                .map(|r| r.push((burst, col))),
             Element::Text(text) => self
                 .renderers
                 .get_mut(&RenderType::Text)
                 .map(|r| r.push(text)),
+            Element::Hud(hud) => self
+                .renderers
+                .get_mut(&RenderType::Hud)
+                .map(|r| r.push(hud)),
         };
+
+        if pushed.is_some() {
+            *self.pushed.entry(render_type).or_default() += 1;
+        }
+    }
+
+    /// whether any of the area is in view
+    /// everything is visible until a view is set
+    pub fn is_visible(&self, area: Rect) -> bool {
+        self.visible.is_none_or(|v| v.overlaps(area))
     }
 
     pub fn draw(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            self.stats.frame_times.push_back(now - last);
+            if self.stats.frame_times.len() > FRAME_HISTORY {
+                self.stats.frame_times.pop_front();
+            }
+        }
+        let timer = self.frames % 2;
+        self.timers[timer].begin();
+
         // render the scene first
         // for transparency to work properly
         // first render all opaque objects
         // then render translucents back to front
+        // the ui is left out, it goes on after the lighting
+        let is_ui = |&&(layer, _): &&(Layer, RenderType)| layer == Layer::Ui;
         self.framebuffer.with(|_| {
             FrameBuffer::clear();
 
-            self.renderers.get_mut(&RenderType::Tile).map(|r| r.draw());
+            for (_, render_type) in self.draw_order.iter().filter(|l| !is_ui(l)) {
+                if let Some(r) = self.renderers.get_mut(render_type) {
+                    r.draw();
+                }
+            }
+        });
 
-            self.renderers.get_mut(&RenderType::Text).map(|r| r.draw());
-            self.renderers.get_mut(&RenderType::Swoop).map(|r| r.draw());
-            self.renderers
-                .get_mut(&RenderType::Fireball)
-                .map(|r| r.draw());
-            self.renderers
-                .get_mut(&RenderType::Shield)
-                .map(|r| r.draw());
+        // lights glow, and in the dark only the area around them stays visible
+        let lights = self.project_lights();
+        let glows = lights.iter().any(|(_, color)| color.w > 0.0);
+        let scene = if self.dark > 0.0 || glows {
+            self.vao.apply();
+            self.lighting.draw(&self.framebuffer, self.dark, &lights);
+            &self.lighting.target
+        } else {
+            &self.framebuffer
+        };
+        scene.with(|_| {
+            for (_, render_type) in self.draw_order.iter().filter(is_ui) {
+                if let Some(r) = self.renderers.get_mut(render_type) {
+                    r.draw();
+                }
+            }
         });
 
-        // render the texture onto the monitor
-        FrameBuffer::clear();
-        self.vao.apply();
-        self.shader.apply();
-        self.start_time.elapsed().as_millis().uniform(0);
+        // run the scene through the post-process chain onto the monitor
         // set crt brightness
-        const CRT_LOADTIME: Duration = Duration::from_millis(1500);
-        let p = self.start_time.elapsed().as_secs_f32() / CRT_LOADTIME.as_secs_f32();
-        let brightness = ease::in_expo(p);
-        brightness.uniform(1);
+        // without the crt there's nothing to warm up
+        let warmup = self.crt.warmup;
+        let brightness = if self.post.is_enabled(PostEffect::Crt) && !warmup.is_zero() {
+            ease::in_expo(self.start_time.elapsed().as_secs_f32() / warmup.as_secs_f32())
+        } else {
+            1.0
+        };
+        let params = PostParams {
+            time: self.start_time.elapsed(),
+            brightness,
+            glitch: self.glitch,
+            danger: self.danger,
+            bloom: self.bloom,
+            crt: self.crt,
+        };
+
+        self.vao.apply();
+        self.post.draw(scene, &params);
+
+        self.timers[timer].end();
+        self.finish_stats();
 
         if brightness >= 1.0 {
             // set void color
             let clear_color = Vec3::rgb(7, 14, 54).srgb_to_linear();
             gl::call!(ClearColor(clear_color.x, clear_color.y, clear_color.z, 1.0));
         }
+    }
+
+    fn finish_stats(&mut self) {
+        // the other timer ran the frame before
+        if self.frames > 0 {
+            if let Some(gpu_time) = self.timers[(self.frames + 1) % 2].result() {
+                self.stats.gpu_time = Some(gpu_time);
+            }
+        }
+        self.frames += 1;
+
+        self.stats.draw_calls = gl::take_draw_calls();
+        let mut pushed: Vec<_> = self
+            .pushed
+            .drain()
+            .map(|(t, n)| (format!("{t:?}"), n))
+            .collect();
+        pushed.sort();
+        self.stats.pushed = pushed;
+    }
+
+    /// takes the frame's lights out, as uv centers and radii along with their glow
+    fn project_lights(&mut self) -> Vec<(Vec4, Vec4)> {
+        let view = self.view;
+        self.lights
+            .drain(..)
+            .map(|light| {
+                let c = view * light.center;
+                let r = (view * (light.center + Vec2::diagonal(light.radius)) - c).abs();
+                let uv = 0.5 * c + Vec2::diagonal(0.5);
+                let col = light.color;
+                (
+                    Vec4::new(uv.x, uv.y, 0.5 * r.x, 0.5 * r.y),
+                    Vec4::new(col.x, col.y, col.z, light.intensity),
+                )
+            })
+            .collect()
+    }
+}
+
+/// a frame's worth of elements waiting to be uploaded
+/// once full, the oldest ones get dropped (and counted) to make room
+/// so a chaotic room can't bring the game down
+/// vertices pushed in groups (like quads) stay whole
+/// as long as the capacity is a multiple of the group size
+struct Batch<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl<T> Batch<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+            self.dropped += 1;
+        }
+
+        self.items.push_back(item);
+    }
+
+    fn extend(&mut self, items: impl IntoIterator<Item = T>) {
+        for item in items {
+            self.push(item);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// ready to be uploaded in one go
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self.items.make_contiguous()
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+    }
 
-        self.framebuffer.bind_texture(0);
-        call!(DrawArrays(TRIANGLE_STRIP, 0, 4));
+    /// how many elements were dropped since the batch was made
+    fn dropped(&self) -> usize {
+        self.dropped
     }
 }
 
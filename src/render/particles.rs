@@ -0,0 +1,247 @@
+use std::{
+    f32::consts::PI,
+    mem::{offset_of, size_of},
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+use rand::{thread_rng, Rng};
+
+use crate::{
+    common::{as_bytes, AsBytes, Result},
+    gl::{self, ArrayBuffer, DrawContext, Shader, Uniform, Vao},
+    math::{Vec2, Vec3},
+    palette::PaletteKey,
+    resources,
+};
+
+use super::VaoHelper;
+
+// everything the gpu needs to animate a particle on its own
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    origin: Vec2,
+    velocity: Vec2,
+    col: Vec3,
+    size: f32,
+    // in seconds since the manager was created
+    born: f32,
+    lifetime: f32,
+}
+
+as_bytes!(Particle);
+
+/// a bunch of particles flying out of a single point
+#[derive(Debug, Clone, Copy)]
+pub struct Burst {
+    pub position: Vec2,
+    pub count: usize,
+    pub speed: f32,
+    pub lifetime: Duration,
+    pub size: f32,
+    pub col: PaletteKey,
+    /// (direction, half angle in radians)
+    /// none means all around
+    pub cone: Option<(Vec2, f32)>,
+}
+
+impl Burst {
+    /// fruit getting eaten
+    pub fn sparkle(position: Vec2) -> Self {
+        Self {
+            position,
+            count: 12,
+            speed: 3.0,
+            lifetime: Duration::from_millis(400),
+            size: 0.15,
+            col: PaletteKey::Fruit,
+            cone: None,
+        }
+    }
+
+    /// enemy getting killed
+    pub fn explosion(position: Vec2) -> Self {
+        Self {
+            position,
+            count: 32,
+            speed: 6.0,
+            lifetime: Duration::from_millis(600),
+            size: 0.25,
+            col: PaletteKey::Enemy,
+            cone: None,
+        }
+    }
+
+    /// something hitting a wall
+    /// normal points away from the wall
+    pub fn dust(position: Vec2, normal: Vec2) -> Self {
+        Self {
+            position,
+            count: 8,
+            speed: 2.0,
+            lifetime: Duration::from_millis(350),
+            size: 0.12,
+            col: PaletteKey::Wall,
+            cone: Some((normal, 0.4 * PI)),
+        }
+    }
+}
+
+/// lets entities emit bursts without access to the renderer
+#[derive(Clone)]
+pub struct Emitter {
+    tx: Sender<Burst>,
+}
+
+impl Emitter {
+    pub fn new(tx: Sender<Burst>) -> Self {
+        Self { tx }
+    }
+
+    pub fn emit(&self, burst: Burst) {
+        let _ = self.tx.send(burst);
+    }
+}
+
+/// particles are written once when their burst is pushed
+/// then animated entirely on the gpu until they fade out
+/// the buffer is a ring, new bursts overwrite the oldest particles
+pub struct ParticleManager<'a> {
+    vao: Vao<'a>,
+    vbo: ArrayBuffer<'a>,
+    shader: Shader<'a>,
+    start_time: Instant,
+
+    next: usize,
+    num_particles: usize,
+    max_particles: usize,
+}
+
+impl<'a> ParticleManager<'a> {
+    pub fn new(ctx: &'a DrawContext, max_particles: usize) -> Result<Self> {
+        let vbo = ArrayBuffer::new(ctx);
+        vbo.reserve(
+            max_particles * size_of::<Particle>(),
+            gl::buffer_flags::DYNAMIC_STORAGE,
+        );
+
+        let vao = VaoHelper::new(ctx)
+            .bind_buffer(&vbo)
+            .push_attrib(
+                2,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Particle>(),
+                offset_of!(Particle, origin),
+            )
+            .push_attrib(
+                2,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Particle>(),
+                offset_of!(Particle, velocity),
+            )
+            .push_attrib(
+                3,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Particle>(),
+                offset_of!(Particle, col),
+            )
+            .push_attrib(
+                1,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Particle>(),
+                offset_of!(Particle, size),
+            )
+            .push_attrib(
+                1,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Particle>(),
+                offset_of!(Particle, born),
+            )
+            .push_attrib(
+                1,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Particle>(),
+                offset_of!(Particle, lifetime),
+            )
+            .build();
+
+        Ok(Self {
+            vao,
+            vbo,
+            shader: Shader::from_resource(ctx, resources::shaders::PARTICLES)?,
+            start_time: Instant::now(),
+
+            next: 0,
+            num_particles: 0,
+            max_particles,
+        })
+    }
+
+    /// col is the burst's palette key resolved for this frame
+    pub fn push(&mut self, burst: Burst, col: Vec3) {
+        let mut rng = thread_rng();
+        let born = self.start_time.elapsed().as_secs_f32();
+
+        let mut particles = Vec::with_capacity(burst.count);
+        for _ in 0..burst.count {
+            let angle = match burst.cone {
+                Some((direction, half_angle)) => {
+                    let direction = direction.normalize();
+                    direction.y.atan2(direction.x) + rng.gen_range(-half_angle..=half_angle)
+                }
+                None => rng.gen_range(0.0..2.0 * PI),
+            };
+            let speed = burst.speed * rng.gen_range(0.5..=1.0);
+
+            let particle = Particle {
+                origin: burst.position,
+                velocity: speed * Vec2::RIGHT.rotate(angle),
+                col,
+                size: burst.size * rng.gen_range(0.75..=1.25),
+                born,
+                lifetime: burst.lifetime.as_secs_f32() * rng.gen_range(0.75..=1.0),
+            };
+
+            particles.push(particle);
+        }
+
+        // bursts bigger than the whole ring only keep their newest particles
+        let skip = particles.len().saturating_sub(self.max_particles);
+        let mut particles = &particles[skip..];
+        self.num_particles = (self.num_particles + particles.len()).min(self.max_particles);
+
+        // one upload, or two if the burst wraps around the end of the ring
+        while !particles.is_empty() {
+            let len = particles.len().min(self.max_particles - self.next);
+            let (head, tail) = particles.split_at(len);
+            self.vbo
+                .update(self.next * size_of::<Particle>(), unsafe { head.as_bytes() });
+            self.next = (self.next + len) % self.max_particles;
+            particles = tail;
+        }
+    }
+
+    const UNIFORM_TIME: i32 = 0;
+
+    pub fn draw(&mut self) {
+        if self.num_particles == 0 {
+            return;
+        }
+
+        self.vao.apply();
+        self.shader.apply();
+        self.start_time
+            .elapsed()
+            .as_secs_f32()
+            .uniform(Self::UNIFORM_TIME);
+        // dead particles are culled in the geometry shader
+        gl::draw!(DrawArrays(POINTS, 0, self.num_particles as _));
+    }
+}
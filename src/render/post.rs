@@ -0,0 +1,305 @@
+use std::time::Duration;
+
+use crate::{
+    common::Result,
+    gl::{self, DrawContext, FrameBuffer, Shader, Uniform},
+    math::{Vec2, Vec3, Vec4},
+    resources,
+};
+
+/// how strongly bright things glow when nothing's going on
+pub const BLOOM_INTENSITY: f32 = 0.8;
+/// the most lights shown at once, the rest stay dark
+pub const MAX_LIGHTS: usize = 16;
+
+/// full-screen passes run over the scene, in this order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostEffect {
+    /// bright things (like fireballs) glow
+    Bloom,
+    /// color channels split apart while the world glitches
+    Aberration,
+    /// the edges of the screen bleed red when the snake is in danger
+    Vignette,
+    /// monitor warp and scanlines
+    Crt,
+}
+
+impl PostEffect {
+    const CHAIN: [Self; 4] = [Self::Bloom, Self::Aberration, Self::Vignette, Self::Crt];
+
+    fn shader(self) -> resources::Shader {
+        match self {
+            Self::Bloom => resources::shaders::BLOOM,
+            Self::Aberration => resources::shaders::ABERRATION,
+            Self::Vignette => resources::shaders::VIGNETTE,
+            Self::Crt => resources::shaders::CRT,
+        }
+    }
+}
+
+/// knobs for the crt pass
+/// everything scales the stock look, so 0 turns that part off
+#[derive(Debug, Clone, Copy)]
+pub struct CrtSettings {
+    /// for players who find the crt hard to read
+    pub enabled: bool,
+    pub curvature: f32,
+    pub scanlines: f32,
+    pub flicker: f32,
+    /// how long the screen takes to fade in on boot
+    pub warmup: Duration,
+}
+
+impl Default for CrtSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            curvature: 1.0,
+            scanlines: 1.0,
+            flicker: 1.0,
+            warmup: Duration::from_millis(1500),
+        }
+    }
+}
+
+impl CrtSettings {
+    /// `--no-crt` turns the effect off
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        Self {
+            enabled: !args.any(|a| a == "--no-crt"),
+            ..Default::default()
+        }
+    }
+}
+
+/// everything the passes read, refreshed every frame
+#[derive(Debug, Clone, Copy)]
+pub struct PostParams {
+    pub time: Duration,
+    /// fades the screen in while the crt warms up, [0,1]
+    pub brightness: f32,
+    pub glitch: f32,
+    pub danger: f32,
+    pub bloom: f32,
+    pub crt: CrtSettings,
+}
+
+fn draw_quad() {
+    gl::draw!(DrawArrays(TRIANGLE_STRIP, 0, 4));
+}
+
+/// the glow half of the bloom: bright-pass then a separable gaussian blur
+/// the blur bounces between two framebuffers, one axis at a time
+struct Glow<'a> {
+    bright: Shader<'a>,
+    blur: Shader<'a>,
+    ping: FrameBuffer<'a>,
+    pong: FrameBuffer<'a>,
+}
+
+impl<'a> Glow<'a> {
+    fn new(ctx: &'a DrawContext) -> Result<Self> {
+        Ok(Self {
+            bright: Shader::from_resource(ctx, resources::shaders::BLOOM_BRIGHT)?,
+            blur: Shader::from_resource(ctx, resources::shaders::BLUR)?,
+            ping: FrameBuffer::new_screen(ctx),
+            pong: FrameBuffer::new_screen(ctx),
+        })
+    }
+
+    /// leaves the blurred bright parts of input in ping
+    fn render(&self, input: &FrameBuffer) {
+        self.ping.with(|_| {
+            FrameBuffer::clear();
+            self.bright.apply();
+            input.bind_texture(0);
+            draw_quad();
+        });
+
+        self.blur.apply();
+        self.pong.with(|_| {
+            FrameBuffer::clear();
+            Vec2::new(1.0, 0.0).uniform(0);
+            self.ping.bind_texture(0);
+            draw_quad();
+        });
+        self.ping.with(|_| {
+            FrameBuffer::clear();
+            Vec2::new(0.0, 1.0).uniform(0);
+            self.pong.bind_texture(0);
+            draw_quad();
+        });
+    }
+}
+
+struct Pass<'a> {
+    effect: PostEffect,
+    shader: Shader<'a>,
+    // where the pass draws to when it isn't the last one
+    target: FrameBuffer<'a>,
+    // only for bloom
+    glow: Option<Glow<'a>>,
+    enabled: bool,
+}
+
+impl<'a> Pass<'a> {
+    fn new(ctx: &'a DrawContext, effect: PostEffect) -> Result<Self> {
+        Ok(Self {
+            effect,
+            shader: Shader::from_resource(ctx, effect.shader())?,
+            target: FrameBuffer::new_screen(ctx),
+            glow: (effect == PostEffect::Bloom).then(|| Glow::new(ctx)).transpose()?,
+            enabled: true,
+        })
+    }
+
+    /// intermediate steps, run before the pass' own target gets bound
+    fn prepare(&self, input: &FrameBuffer) {
+        if let Some(glow) = &self.glow {
+            glow.render(input);
+        }
+    }
+
+    fn draw(&self, input: &FrameBuffer, params: &PostParams) {
+        self.shader.apply();
+        // only set the uniforms the shader actually has
+        match self.effect {
+            PostEffect::Bloom => params.bloom.uniform(0),
+            PostEffect::Aberration => params.glitch.uniform(0),
+            PostEffect::Vignette => {
+                params.time.as_millis().uniform(0);
+                params.danger.uniform(1);
+            }
+            PostEffect::Crt => {
+                params.time.as_millis().uniform(0);
+                params.brightness.uniform(1);
+                params.glitch.uniform(2);
+                params.crt.curvature.uniform(3);
+                params.crt.scanlines.uniform(4);
+                params.crt.flicker.uniform(5);
+            }
+        }
+
+        input.bind_texture(0);
+        if let Some(glow) = &self.glow {
+            glow.ping.bind_texture(1);
+        }
+        draw_quad();
+    }
+}
+
+/// a light in world space, for a single frame
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    pub center: Vec2,
+    // in tiles
+    pub radius: f32,
+    pub color: Vec3,
+    // how strongly it glows, zero only lights up the dark
+    pub intensity: f32,
+}
+
+/// lights glow over the scene, and in dark rooms everything away from them goes dark
+/// runs on the scene before the ui goes on top, so the hud stays readable
+pub struct Lighting<'a> {
+    shader: Shader<'a>,
+    pub target: FrameBuffer<'a>,
+}
+
+impl<'a> Lighting<'a> {
+    pub fn new(ctx: &'a DrawContext) -> Result<Self> {
+        Ok(Self {
+            shader: Shader::from_resource(ctx, resources::shaders::LIGHTING)?,
+            target: FrameBuffer::new_screen(ctx),
+        })
+    }
+
+    /// darkness is how dark it gets away from the lights, [0,1]
+    /// each light is its center and then its radius along either axis in uv,
+    /// along with its color and how strongly it glows
+    /// expects the full-screen quad to be bound
+    pub fn draw(&self, scene: &FrameBuffer, darkness: f32, lights: &[(Vec4, Vec4)]) {
+        let lights = &lights[..lights.len().min(MAX_LIGHTS)];
+        self.target.with(|_| {
+            FrameBuffer::clear();
+            self.shader.apply();
+            darkness.uniform(0);
+            (lights.len() as u128).uniform(1);
+            for (i, (light, color)) in lights.iter().enumerate() {
+                light.uniform(2 + i as i32);
+                color.uniform((2 + MAX_LIGHTS + i) as i32);
+            }
+            scene.bind_texture(0);
+            draw_quad();
+        });
+    }
+}
+
+/// chain of full-screen passes between the scene and the screen
+/// each pass renders into its own framebuffer for the next one to read,
+/// except the last enabled one, which draws straight to the screen
+pub struct PostChain<'a> {
+    passes: Vec<Pass<'a>>,
+    // copies the scene to the screen when every pass is off
+    present: Shader<'a>,
+}
+
+impl<'a> PostChain<'a> {
+    pub fn new(ctx: &'a DrawContext) -> Result<Self> {
+        Ok(Self {
+            passes: PostEffect::CHAIN
+                .into_iter()
+                .map(|effect| Pass::new(ctx, effect))
+                .collect::<Result<_>>()?,
+            present: Shader::from_resource(ctx, resources::shaders::PRESENT)?,
+        })
+    }
+
+    fn pass_mut(&mut self, effect: PostEffect) -> &mut Pass<'a> {
+        // every effect gets a pass on creation
+        self.passes.iter_mut().find(|p| p.effect == effect).unwrap()
+    }
+
+    pub fn set_enabled(&mut self, effect: PostEffect, enabled: bool) {
+        self.pass_mut(effect).enabled = enabled;
+    }
+
+    pub fn is_enabled(&self, effect: PostEffect) -> bool {
+        self.passes.iter().any(|p| p.effect == effect && p.enabled)
+    }
+
+    /// returns whether the effect is now enabled
+    pub fn toggle(&mut self, effect: PostEffect) -> bool {
+        let pass = self.pass_mut(effect);
+        pass.enabled = !pass.enabled;
+        pass.enabled
+    }
+
+    /// expects the full-screen quad to be bound
+    pub fn draw(&self, scene: &FrameBuffer, params: &PostParams) {
+        let enabled: Vec<_> = self.passes.iter().filter(|p| p.enabled).collect();
+        let Some((last, rest)) = enabled.split_last() else {
+            FrameBuffer::clear();
+            self.present.apply();
+            params.brightness.uniform(0);
+            scene.bind_texture(0);
+            draw_quad();
+            return;
+        };
+
+        let mut input = scene;
+        for pass in rest {
+            pass.prepare(input);
+            pass.target.with(|_| {
+                FrameBuffer::clear();
+                pass.draw(input, params);
+            });
+            input = &pass.target;
+        }
+
+        last.prepare(input);
+        FrameBuffer::clear();
+        last.draw(input, params);
+    }
+}
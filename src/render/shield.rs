@@ -1,13 +1,13 @@
 use std::mem::{offset_of, size_of};
 
 use crate::{
-    common::{as_bytes, AsBytes},
+    common::{as_bytes, AsBytes, Result},
     gl::{self, ArrayBuffer, DrawContext, Shader, Vao},
     math::{Vec2, Vec4},
     resources,
 };
 
-use super::VaoHelper;
+use super::{Batch, VaoHelper};
 
 #[repr(C)]
 pub struct Shield {
@@ -66,13 +66,11 @@ pub struct ShieldManager<'a> {
     vbo: ArrayBuffer<'a>,
     shader: Shader<'a>,
 
-    max_shields: usize,
-    shields: Vec<Shield>,
-    fixes: Vec<Shield>,
+    shields: Batch<Shield>,
 }
 
 impl<'a> ShieldManager<'a> {
-    pub fn new(ctx: &'a DrawContext, max_shields: usize) -> Self {
+    pub fn new(ctx: &'a DrawContext, max_shields: usize) -> Result<Self> {
         let vbo = ArrayBuffer::new(ctx);
         vbo.reserve(
             max_shields * size_of::<Shield>(),
@@ -145,57 +143,46 @@ impl<'a> ShieldManager<'a> {
             );
 
         // let shader = Shader::from_file(ctx, Path::new("res/shaders/shield")).unwrap();
-        let shader = Shader::from_resource(ctx, resources::shaders::SHIELD)
-            .expect("shield shader should compile properly");
-        Self {
+        let shader = Shader::from_resource(ctx, resources::shaders::SHIELD)?;
+        Ok(Self {
             vao: vao.build(),
             vbo,
             shader,
 
-            max_shields,
-
-            shields: Vec::new(),
-            fixes: Vec::new(),
-        }
+            shields: Batch::new(max_shields),
+        })
     }
 
-    fn update_buffer(&mut self, is_fixes: bool) -> gl::raw::GLint {
-        let buf = if is_fixes {
-            &mut self.fixes
-        } else {
-            &mut self.shields
-        };
-        for (idx, shield) in buf.iter_mut().enumerate() {
-            self.vbo
-                .update(idx * size_of::<Shield>(), unsafe { shield.as_bytes() });
-        }
-
-        let len = buf.len() as _;
-        buf.clear();
-
-        len
+    pub fn push(&mut self, shield: Shield) {
+        self.shields.push(shield);
     }
 
-    pub fn push(&mut self, shield: Shield) {
-        if shield.is_fix == 1 {
-            self.fixes.push(shield);
-        } else {
-            self.shields.push(shield);
-        }
+    pub fn dropped(&self) -> usize {
+        self.shields.dropped()
     }
 
     pub fn draw(&mut self) {
         self.vao.apply();
         self.shader.apply();
 
-        let fixes = self.update_buffer(true);
+        if self.shields.is_empty() {
+            return;
+        }
+
+        // fixes get drawn first, so put them at the front of the buffer
+        let shields = self.shields.as_mut_slice();
+        shields.sort_by_key(|s| s.is_fix == 0);
+        let fixes = shields.iter().take_while(|s| s.is_fix == 1).count();
+        self.vbo.update(0, unsafe { shields.as_bytes() });
+
         if fixes > 0 {
-            gl::call!(DrawArrays(POINTS, 0, fixes));
+            gl::draw!(DrawArrays(POINTS, 0, fixes as _));
         }
 
-        let shields = self.update_buffer(false);
-        if shields > 0 {
-            gl::call!(DrawArrays(POINTS, 0, shields));
+        if fixes < shields.len() {
+            gl::draw!(DrawArrays(POINTS, fixes as _, (shields.len() - fixes) as _));
         }
+
+        self.shields.clear();
     }
 }
@@ -1,7 +1,7 @@
 use std:: mem::{offset_of, size_of};
 
 use crate::{
-    common::{as_bytes, AsBytes},
+    common::{as_bytes, AsBytes, Result},
     gl::{
         self,
         raw::{FALSE, FLOAT},
@@ -11,7 +11,7 @@ use crate::{
     resources,
 };
 
-use super::VaoHelper;
+use super::{Batch, VaoHelper};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -62,9 +62,8 @@ impl Swoop {
 
     pub fn new<D: Into<Vec2>>(pos: Vec2, scale: f32, direction: D, alpha: f32) -> Self {
         Self::base(alpha)
-            .transform(Mat4::rotate(Into::<Vec2>::into(direction).angle()))
             .transform(Mat4::scale(scale.into()))
-            .transform(Mat4::translate((pos, 0.0).into()))
+            .transform(Mat4::look_at(pos, pos + direction.into()))
     }
 
     fn transform(mut self, t: Mat4) -> Self {
@@ -81,15 +80,17 @@ pub struct SwoopManager<'a> {
     vbo: ArrayBuffer<'a>,
     shader: Shader<'a>,
 
-    num_swoops: usize,
-    max_swoops: usize,
+    vertices: Batch<Vertex>,
 }
 
 impl<'a> SwoopManager<'a> {
-    pub fn new(ctx: &'a DrawContext, max_swoops: usize) -> Self {
-        let max_swoops = max_swoops * size_of::<Swoop>();
+    pub fn new(ctx: &'a DrawContext, max_swoops: usize) -> Result<Self> {
+        let max_vertices = max_swoops * size_of::<Swoop>() / size_of::<Vertex>();
         let vbo = ArrayBuffer::new(ctx);
-        vbo.reserve(max_swoops, gl::buffer_flags::DYNAMIC_STORAGE);
+        vbo.reserve(
+            max_vertices * size_of::<Vertex>(),
+            gl::buffer_flags::DYNAMIC_STORAGE,
+        );
 
         let vao = VaoHelper::new(ctx)
             .bind_buffer(&vbo)
@@ -116,37 +117,35 @@ impl<'a> SwoopManager<'a> {
             )
             .build();
 
-        Self {
+        Ok(Self {
             vao,
             vbo,
-            shader: Shader::from_resource(ctx, resources::shaders::SWOOP)
-                .expect("bad swoop shader"),
+            shader: Shader::from_resource(ctx, resources::shaders::SWOOP)?,
 
-            num_swoops: 0,
-            max_swoops,
-        }
+            vertices: Batch::new(max_vertices),
+        })
     }
 
     pub fn push(&mut self, swoop: Swoop) {
-        for v in swoop.vertices {
-            if self.num_swoops == self.max_swoops {
-                panic!("max swoops")
-            }
-
-            self.vbo
-                .update(self.num_swoops * size_of::<Vertex>(), unsafe {
-                    v.as_bytes()
-                });
+        self.vertices.extend(swoop.vertices);
+    }
 
-            self.num_swoops += 1;
-        }
+    /// in vertices
+    pub fn dropped(&self) -> usize {
+        self.vertices.dropped()
     }
 
     pub fn draw(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
         self.vao.apply();
         self.shader.apply();
-        gl::call!(DrawArrays(TRIANGLES, 0, self.num_swoops as _));
+        self.vbo
+            .update(0, unsafe { self.vertices.as_mut_slice().as_bytes() });
+        gl::draw!(DrawArrays(TRIANGLES, 0, self.vertices.len() as _));
 
-        self.num_swoops = 0;
+        self.vertices.clear();
     }
 }
@@ -1,13 +1,17 @@
 use std::{
     collections::HashMap,
     mem::{offset_of, size_of},
+    str::FromStr,
+    sync::OnceLock,
 };
 
+use image::RgbaImage;
+
 use crate::{
     common::{as_bytes, AsBytes, Error, Result},
-    gl::{self, ArrayBuffer, DrawContext, Shader, Texture2D, Uniform, Vao},
-    math::{Mat4, Vec2, Vec3},
-    resources::{self, Texture},
+    gl::{self, ArrayBuffer, DrawContext, Shader, Texture2D, Vao},
+    math::{Mat4, Vec2, Vec3, Vec4},
+    resources::{self, Asset, Texture},
 };
 
 use super::VaoHelper;
@@ -17,6 +21,11 @@ use super::VaoHelper;
 struct Vertex {
     pos: Vec2,
     uv: Vec2,
+    // (current frame, total frames)
+    frame: Vec2,
+    // where the texture is in the atlas, filled in by the manager
+    // in the format (min u, min v, max u, max v)
+    rect: Vec4,
 }
 
 as_bytes!(Vertex);
@@ -78,7 +87,7 @@ impl TryFrom<u8> for TextNames {
 }
 
 impl TextNames {
-    fn resource(self) -> Texture {
+    fn resource(self) -> Asset {
         use crate::resources::textures::text::*;
         match self {
             Self::Snek => SNEK,
@@ -155,38 +164,257 @@ pub const LETTER_SIZE: f32 = 14.0;
 pub const LETTER_GAP_WIDTH: f32 = 2.0;
 pub const LINE_SEPARATOR_HEIGHT: f32 = 10.0;
 
+pub const LINE_HEIGHT: f32 = LETTER_SIZE + LINE_SEPARATOR_HEIGHT;
+// each glyph in the atlas takes up a letter plus the gap after it
+const GLYPH_CELL_WIDTH: f32 = LETTER_SIZE + LETTER_GAP_WIDTH;
+const SPACE_WIDTH: f32 = 6.0;
+
+/// glyphs in the font atlas, left to right
+const FONT_GLYPHS: &str = " !'+,-./0123456789:<>?ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// pairs of glyphs that look too far apart with regular spacing
+const KERNING: &[(char, char, f32)] = &[
+    ('A', 'T', -2.0),
+    ('A', 'V', -2.0),
+    ('A', 'Y', -2.0),
+    ('L', 'T', -2.0),
+    ('L', 'Y', -2.0),
+    ('T', 'A', -2.0),
+    ('V', 'A', -2.0),
+    ('Y', 'A', -2.0),
+    ('T', '.', -2.0),
+    ('T', ',', -2.0),
+];
+
+fn kerning(prev: char, next: char) -> f32 {
+    KERNING
+        .iter()
+        .find(|&&(a, b, _)| a == prev && b == next)
+        .map_or(0.0, |&(_, _, k)| k)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GlyphMetrics {
+    // in atlas pixels
+    left: f32,
+    width: f32,
+}
+
+/// a glyph laid out by the font
+/// everything is in font pixels, y goes down
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph {
+    /// top left corner
+    pub position: Vec2,
+    pub size: Vec2,
+    /// uv coords of the top left corner
+    pub uv_min: Vec2,
+    /// uv coords of the bottom right corner
+    pub uv_max: Vec2,
+}
+
+/// pixel font shared by all dynamic text
+/// glyph widths are measured from the atlas so narrow glyphs don't leave gaps
+pub struct Font {
+    image: RgbaImage,
+    glyphs: HashMap<char, GlyphMetrics>,
+}
+
+impl Font {
+    pub fn get() -> &'static Font {
+        static FONT: OnceLock<Font> = OnceLock::new();
+        FONT.get_or_init(|| Self::load(resources::textures::font::FONT))
+    }
+
+    fn load(resource: Texture) -> Self {
+        let image = image::load_from_memory(resource).unwrap().flipv().into_rgba8();
+
+        let mut glyphs = HashMap::new();
+        for (i, c) in FONT_GLYPHS.chars().enumerate() {
+            let cell = i as u32 * GLYPH_CELL_WIDTH as u32;
+            let inked = (cell..cell + LETTER_SIZE as u32)
+                .filter(|&x| (0..image.height()).any(|y| image.get_pixel(x, y)[3] > 0))
+                .collect::<Vec<_>>();
+
+            let metrics = match (inked.first(), inked.last()) {
+                (Some(&l), Some(&r)) => GlyphMetrics {
+                    left: l as f32,
+                    width: (r - l + 1) as f32,
+                },
+                _ => GlyphMetrics {
+                    left: cell as f32,
+                    width: 0.0,
+                },
+            };
+
+            glyphs.insert(c, metrics);
+        }
+
+        Self { image, glyphs }
+    }
+
+    /// the atlas image, already flipped for the gpu
+    fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// upload the atlas to the gpu
+    pub fn atlas<'a>(&self, ctx: &'a DrawContext) -> Texture2D<'a> {
+        let texture = Texture2D::new(ctx);
+        texture.apply();
+        // effectively clamp to a transparent background
+        gl::call!(TexParameteri(
+            texture.type_(),
+            TEXTURE_WRAP_S,
+            CLAMP_TO_BORDER as _
+        ));
+        gl::call!(TexParameteri(
+            texture.type_(),
+            TEXTURE_WRAP_T,
+            CLAMP_TO_BORDER as _
+        ));
+        // keep the pixel font crisp
+        gl::call!(TexParameteri(
+            texture.type_(),
+            TEXTURE_MIN_FILTER,
+            NEAREST as _
+        ));
+        gl::call!(TexParameteri(
+            texture.type_(),
+            TEXTURE_MAG_FILTER,
+            NEAREST as _
+        ));
+        gl::call!(TexImage2D(
+            texture.type_(),
+            0,
+            RGBA as _,
+            self.image.width() as _,
+            self.image.height() as _,
+            0,
+            RGBA,
+            UNSIGNED_BYTE,
+            self.image.as_raw().as_ptr().cast()
+        ));
+
+        texture
+    }
+
+    /// lowercase counts as uppercase, like when laying out
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c.to_ascii_uppercase())
+    }
+
+    /// leaves only what can be drawn, for text that could say anything
+    /// underscores become dashes so names stay readable
+    pub fn sanitize(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| if c == '_' { '-' } else { c })
+            .filter(|&c| c == '\n' || self.has_glyph(c))
+            .collect()
+    }
+
+    /// lay out text starting at the origin
+    /// lowercase is drawn as uppercase and '\n' starts a new line
+    pub fn layout(&self, text: &str) -> Result<Vec<Glyph>> {
+        let mut glyphs = Vec::with_capacity(text.len());
+        self.layout_into(text, &mut glyphs)?;
+        Ok(glyphs)
+    }
+
+    /// like [`Self::layout`], appending to a buffer that gets reused
+    pub fn layout_into(&self, text: &str, glyphs: &mut Vec<Glyph>) -> Result<()> {
+        let atlas_width = self.image.width() as f32;
+        let mut cursor = Vec2::default();
+        let mut prev = None;
+
+        for c in text.chars() {
+            if c == '\n' {
+                cursor = Vec2::new(0.0, cursor.y + LINE_HEIGHT);
+                prev = None;
+                continue;
+            }
+
+            let c = c.to_ascii_uppercase();
+            let metrics = *self.glyphs.get(&c).ok_or(Error::MissingGlyph(c))?;
+            if let Some(prev) = prev {
+                cursor.x += kerning(prev, c);
+            }
+            prev = Some(c);
+
+            if metrics.width == 0.0 {
+                cursor.x += SPACE_WIDTH;
+                continue;
+            }
+
+            glyphs.push(Glyph {
+                position: cursor,
+                size: Vec2::new(metrics.width, LETTER_SIZE),
+                uv_min: Vec2::new(metrics.left / atlas_width, 1.0),
+                uv_max: Vec2::new((metrics.left + metrics.width) / atlas_width, 0.0),
+            });
+
+            cursor.x += metrics.width + LETTER_GAP_WIDTH;
+        }
+
+        Ok(())
+    }
+
+    /// size of the laid out text in font pixels
+    pub fn measure(&self, glyphs: &[Glyph]) -> Vec2 {
+        glyphs.iter().fold(Vec2::default(), |size, g| {
+            Vec2::new(
+                size.x.max(g.position.x + g.size.x),
+                size.y.max(g.position.y + g.size.y),
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+enum Source {
+    Baked(TextNames),
+    Font,
+}
+
 #[derive(Debug)]
 pub struct Text {
-    name: TextNames,
-    frame: usize,
-    vertices: [Vertex; VERTICES_PER_SHAPE],
+    source: Source,
+    vertices: Vec<Vertex>,
 }
 
 impl Text {
     fn new(name: TextNames, frame: usize) -> Self {
+        let frame = Vec2::new(frame as f32, name.frames() as f32);
         let corners = [
             Vertex {
                 pos: Vec2::new(-0.5, 0.5),
                 uv: Vec2::new(0.0, 0.0),
+                frame,
+                rect: Vec4::default(),
             },
             Vertex {
                 pos: Vec2::new(-0.5, -0.5),
                 uv: Vec2::new(0.0, 1.0),
+                frame,
+                rect: Vec4::default(),
             },
             Vertex {
                 pos: Vec2::new(0.5, 0.5),
                 uv: Vec2::new(1.0, 0.0),
+                frame,
+                rect: Vec4::default(),
             },
             Vertex {
                 pos: Vec2::new(0.5, -0.5),
                 uv: Vec2::new(1.0, 1.0),
+                frame,
+                rect: Vec4::default(),
             },
         ];
 
         Self {
-            name,
-            frame,
-            vertices: [
+            source: Source::Baked(name),
+            vertices: vec![
                 corners[0], corners[1], corners[2], corners[3], corners[2], corners[1],
             ],
         }
@@ -211,13 +439,18 @@ impl Text {
             Mat4::default()
         };
 
-        let out = Self::new(name, frame)
+        Self::new(name, frame)
             .transform(Mat4::scale(dimensions))
             .transform(adjust)
             .transform(Mat4::scale(scale.into()))
-            .transform(Mat4::translate((position, 0.0).into()));
+            .transform(Mat4::translate((position, 0.0).into()))
+    }
 
-        out
+    /// for text made with from_str
+    /// scale converts from font pixels to world units
+    pub fn place(self, position: Vec2, scale: f32) -> Self {
+        self.transform(Mat4::scale(scale.into()))
+            .transform(Mat4::translate((position, 0.0).into()))
     }
 
     fn transform(mut self, t: Mat4) -> Self {
@@ -229,21 +462,114 @@ impl Text {
     }
 }
 
+impl FromStr for Text {
+    type Err = Error;
+
+    /// lays the string out with the font, centered on the origin
+    fn from_str(s: &str) -> Result<Self> {
+        let font = Font::get();
+        let glyphs = font.layout(s)?;
+        let center = 0.5 * font.measure(&glyphs);
+        // the font atlas is a single frame
+        let frame = Vec2::new(0.0, 1.0);
+
+        let mut vertices = Vec::with_capacity(VERTICES_PER_SHAPE * glyphs.len());
+        for g in glyphs {
+            let top_left = g.position - center;
+            let bottom_right = top_left + g.size;
+
+            // same winding as the baked quads
+            let corners = [
+                Vertex {
+                    pos: Vec2::new(top_left.x, bottom_right.y),
+                    uv: Vec2::new(g.uv_min.x, g.uv_max.y),
+                    frame,
+                    rect: Vec4::default(),
+                },
+                Vertex {
+                    pos: top_left,
+                    uv: g.uv_min,
+                    frame,
+                    rect: Vec4::default(),
+                },
+                Vertex {
+                    pos: bottom_right,
+                    uv: g.uv_max,
+                    frame,
+                    rect: Vec4::default(),
+                },
+                Vertex {
+                    pos: Vec2::new(bottom_right.x, top_left.y),
+                    uv: Vec2::new(g.uv_max.x, g.uv_min.y),
+                    frame,
+                    rect: Vec4::default(),
+                },
+            ];
+
+            vertices.extend([
+                corners[0], corners[1], corners[2], corners[3], corners[2], corners[1],
+            ]);
+        }
+
+        Ok(Self {
+            source: Source::Font,
+            vertices,
+        })
+    }
+}
+
+const ATLAS_WIDTH: u32 = 1024;
+// keeps linear filtering from bleeding into neighbouring textures
+const ATLAS_PADDING: u32 = 2;
+
+/// all texts live in a single atlas
+/// so they're uploaded in one go each frame and drawn in one call
+/// a frame with more text than fits grows the buffer instead of losing any
 pub struct TextManager<'a> {
+    ctx: &'a DrawContext,
     vao: Vao<'a>,
     vbo: ArrayBuffer<'a>,
     shader: Shader<'a>,
 
-    textures: HashMap<TextNames, Texture2D<'a>>,
+    atlas: Texture2D<'a>,
+    rects: HashMap<Source, Vec4>,
+    // which change to res/ the atlas is up to date with
+    #[cfg(feature = "hot-reload")]
+    reload: usize,
 
-    texts: Vec<Text>,
+    vertices: Vec<Vertex>,
+    // how many vertices the buffer holds
+    capacity: usize,
 }
 
 impl<'a> TextManager<'a> {
-    pub fn new(ctx: &'a DrawContext) -> Self {
+    /// a baked text takes up a single glyph
+    /// max_glyphs is only where the buffer starts out
+    pub fn new(ctx: &'a DrawContext, max_glyphs: usize) -> Result<Self> {
+        let capacity = max_glyphs * VERTICES_PER_SHAPE;
+        let (vao, vbo) = Self::buffers(ctx, capacity);
+        let (atlas, rects) = Self::load_textures(ctx)?;
+
+        Ok(Self {
+            ctx,
+            vao,
+            vbo,
+            shader: Shader::from_resource(ctx, resources::shaders::TEXT)?,
+
+            atlas,
+            rects,
+            #[cfg(feature = "hot-reload")]
+            reload: crate::hot_reload::generation(),
+
+            vertices: Vec::with_capacity(capacity),
+            capacity,
+        })
+    }
+
+    fn buffers(ctx: &'a DrawContext, capacity: usize) -> (Vao<'a>, ArrayBuffer<'a>) {
         let vbo = ArrayBuffer::new(ctx);
         vbo.reserve(
-            size_of::<Vertex>() * VERTICES_PER_SHAPE,
+            size_of::<Vertex>() * capacity,
             gl::buffer_flags::DYNAMIC_STORAGE,
         );
 
@@ -263,96 +589,145 @@ impl<'a> TextManager<'a> {
                 size_of::<Vertex>(),
                 offset_of!(Vertex, uv),
             )
+            .push_attrib(
+                2,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Vertex>(),
+                offset_of!(Vertex, frame),
+            )
+            .push_attrib(
+                4,
+                gl::raw::FLOAT,
+                gl::raw::FALSE,
+                size_of::<Vertex>(),
+                offset_of!(Vertex, rect),
+            )
             .build();
 
-        Self {
-            vao,
-            vbo,
-            shader: Shader::from_resource(ctx, resources::shaders::TEXT).expect("bad text shader"),
-
-            textures: Self::load_textures(ctx),
-
-            texts: Default::default(),
-        }
+        (vao, vbo)
     }
 
-    fn load_textures(ctx: &'a DrawContext) -> HashMap<TextNames, Texture2D<'a>> {
-        let mut ret = HashMap::new();
+    /// packs every text and the font into one atlas, row by row
+    fn load_textures(ctx: &'a DrawContext) -> Result<(Texture2D<'a>, HashMap<Source, Vec4>)> {
+        let mut images = Vec::new();
         for text_name_id in 0..(TextNames::_NumTexts as u8) {
             // don't forget to add new text names to the conversion table in try_from
-            let text_name = TextNames::try_from(text_name_id).unwrap();
-
-            let image = image::load_from_memory(text_name.resource()).unwrap();
-            let image = image.flipv();
-
-            let texture = Texture2D::new(ctx);
-            let width = text_name.dimensions().x as _;
-            let height = text_name.dimensions().y as _;
-            texture.apply();
-            // effectively clamp to a transparent background
-            gl::call!(TexParameteri(
-                texture.type_(),
-                TEXTURE_WRAP_S,
-                CLAMP_TO_BORDER as _
-            ));
-            gl::call!(TexParameteri(
-                texture.type_(),
-                TEXTURE_WRAP_T,
-                CLAMP_TO_BORDER as _
-            ));
-            gl::call!(TexParameteri(
-                texture.type_(),
-                TEXTURE_MIN_FILTER,
-                LINEAR as _
-            ));
-            gl::call!(TexParameteri(
-                texture.type_(),
-                TEXTURE_MAG_FILTER,
-                LINEAR as _
-            ));
-            gl::call!(TexImage2D(
-                texture.type_(),
-                0,
-                RGBA as _,
-                width,
-                height,
-                0,
-                RGBA,
-                UNSIGNED_BYTE,
-                image.as_bytes().as_ptr().cast()
-            ));
-
-            // push to hashmap
-            ret.insert(text_name, texture);
+            let text_name = TextNames::try_from(text_name_id)?;
+
+            let image = image::load_from_memory(&text_name.resource().source())
+                .map_err(|e| Error::Texture(format!("{text_name:?}: {e}")))?;
+            images.push((Source::Baked(text_name), image.flipv().into_rgba8()));
+        }
+        images.push((Source::Font, Font::get().image().clone()));
+
+        // tallest first keeps the rows tight
+        images.sort_by_key(|(_, image)| std::cmp::Reverse(image.height()));
+
+        let mut positions = Vec::with_capacity(images.len());
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for (_, image) in &images {
+            if x + image.width() > ATLAS_WIDTH {
+                x = 0;
+                y += row_height + ATLAS_PADDING;
+                row_height = 0;
+            }
+
+            positions.push((x, y));
+            x += image.width() + ATLAS_PADDING;
+            row_height = row_height.max(image.height());
+        }
+
+        let height = y + row_height;
+        let mut atlas = RgbaImage::new(ATLAS_WIDTH, height);
+        let mut rects = HashMap::new();
+        for ((source, image), (x, y)) in images.iter().zip(positions) {
+            image::imageops::replace(&mut atlas, image, x as _, y as _);
+
+            // the images are flipped already so v grows with y
+            let rect = Vec4::new(
+                x as f32 / ATLAS_WIDTH as f32,
+                y as f32 / height as f32,
+                (x + image.width()) as f32 / ATLAS_WIDTH as f32,
+                (y + image.height()) as f32 / height as f32,
+            );
+            rects.insert(*source, rect);
         }
 
-        ret
+        let texture = Texture2D::new(ctx);
+        texture.apply();
+        gl::call!(TexParameteri(
+            texture.type_(),
+            TEXTURE_MIN_FILTER,
+            LINEAR as _
+        ));
+        gl::call!(TexParameteri(
+            texture.type_(),
+            TEXTURE_MAG_FILTER,
+            LINEAR as _
+        ));
+        gl::call!(TexImage2D(
+            texture.type_(),
+            0,
+            RGBA as _,
+            atlas.width() as _,
+            atlas.height() as _,
+            0,
+            RGBA,
+            UNSIGNED_BYTE,
+            atlas.as_raw().as_ptr().cast()
+        ));
+
+        Ok((texture, rects))
     }
 
     pub fn push(&mut self, text: Text) {
-        self.texts.push(text);
+        let rect = self.rects[&text.source];
+        self.vertices
+            .extend(text.vertices.into_iter().map(|v| Vertex { rect, ..v }));
     }
 
     const BINDING_TEXT: usize = 0;
-    const UNIFORM_CURRENT_FRAME: i32 = 0;
-    const UNIFORM_TOTAL_FRAMES: i32 = 1;
 
-    pub fn draw(&mut self) {
-        self.vao.apply();
-        self.shader.apply();
+    /// everything gets repacked, since a text might have changed size
+    /// a text that doesn't load leaves the old atlas in place
+    #[cfg(feature = "hot-reload")]
+    fn reload(&mut self) {
+        let generation = crate::hot_reload::generation();
+        if std::mem::replace(&mut self.reload, generation) == generation {
+            return;
+        }
 
-        for text in &self.texts {
-            for (i, v) in text.vertices.iter().enumerate() {
-                let bytes = unsafe { v.as_bytes() };
-                self.vbo.update(i * size_of::<Vertex>(), bytes);
+        match Self::load_textures(self.ctx) {
+            Ok((atlas, rects)) => {
+                self.atlas = atlas;
+                self.rects = rects;
+                log::info!("reloaded the text textures");
             }
+            Err(e) => log::warn!("kept the text textures as they were: {e}"),
+        }
+    }
 
-            self.textures[&text.name].bind(Self::BINDING_TEXT);
-            (text.frame as f32).uniform(Self::UNIFORM_CURRENT_FRAME);
-            (text.name.frames() as f32).uniform(Self::UNIFORM_TOTAL_FRAMES);
-            gl::call!(DrawArrays(TRIANGLES, 0, VERTICES_PER_SHAPE as _));
+    pub fn draw(&mut self) {
+        #[cfg(feature = "hot-reload")]
+        self.reload();
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        if self.vertices.len() > self.capacity {
+            self.capacity = self.vertices.len().next_power_of_two();
+            (self.vao, self.vbo) = Self::buffers(self.ctx, self.capacity);
+            log::info!("text buffer grew to {} vertices", self.capacity);
         }
 
-        self.texts.clear();
+        self.vao.apply();
+        self.shader.apply();
+        self.vbo
+            .update(0, unsafe { self.vertices.as_mut_slice().as_bytes() });
+        self.atlas.bind(Self::BINDING_TEXT);
+        gl::draw!(DrawArrays(TRIANGLES, 0, self.vertices.len() as _));
+
+        self.vertices.clear();
     }
 }
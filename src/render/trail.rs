@@ -0,0 +1,178 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    mem::{offset_of, size_of},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    common::{as_bytes, AsBytes, Result},
+    entity::EntityId,
+    gl::{
+        self,
+        raw::{FALSE, FLOAT},
+        ArrayBuffer, DrawContext, Shader, Vao,
+    },
+    math::{Vec2, Vec4},
+    resources,
+};
+
+use super::{Batch, VaoHelper};
+
+// points closer than this to the last one don't make it into the trail
+const MIN_SPACING: f32 = 0.05;
+// anything moving further in a frame teleported, the ribbon breaks there
+const MAX_GAP: f32 = 2.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    pos: Vec2,
+    col: Vec4,
+}
+
+as_bytes!(Vertex);
+
+/// where a flagged entity is this frame, the manager remembers where it's been
+#[derive(Debug, Clone, Copy)]
+pub struct Trail {
+    // one trail per id
+    pub id: EntityId,
+    pub pos: Vec2,
+    // at the newest end, it tapers off from there
+    pub width: f32,
+    pub col: Vec4,
+    // how long a point stays in the trail
+    pub length: Duration,
+}
+
+struct History {
+    points: VecDeque<(Vec2, Instant)>,
+    // the latest look, so the trail can fade out once its entity is gone
+    width: f32,
+    col: Vec4,
+    length: Duration,
+}
+
+impl History {
+    fn record(&mut self, trail: Trail, now: Instant) {
+        self.width = trail.width;
+        self.col = trail.col;
+        self.length = trail.length;
+
+        let moved = self
+            .points
+            .back()
+            .is_none_or(|&(last, _)| Vec2::distance(trail.pos, last) > MIN_SPACING);
+        if moved {
+            self.points.push_back((trail.pos, now));
+        }
+    }
+
+    /// false once there's nothing left of it
+    fn expire(&mut self, now: Instant) -> bool {
+        while self.points.front().is_some_and(|&(_, at)| now - at > self.length) {
+            self.points.pop_front();
+        }
+        !self.points.is_empty()
+    }
+
+    // how far along its life a point recorded at this time is, newest being 0
+    fn age(&self, at: Instant, now: Instant) -> f32 {
+        ((now - at).as_secs_f32() / self.length.as_secs_f32()).min(1.0)
+    }
+
+    fn ribbon(&self, now: Instant, out: &mut Batch<Vertex>) {
+        for (&(from, from_at), &(to, to_at)) in self.points.iter().zip(self.points.iter().skip(1)) {
+            let along = to - from;
+            if along.len() > MAX_GAP {
+                continue;
+            }
+
+            let side = Vec2::new(-along.y, along.x).normalize();
+            let corner = |pos: Vec2, at: Instant| {
+                let fade = 1.0 - self.age(at, now);
+                let offset = 0.5 * self.width * fade * side;
+                let col = Vec4::new(self.col.x, self.col.y, self.col.z, self.col.w * fade);
+                (Vertex { pos: pos + offset, col }, Vertex { pos: pos - offset, col })
+            };
+            let (a, b) = corner(from, from_at);
+            let (c, d) = corner(to, to_at);
+            out.extend([a, b, c, d, c, b]);
+        }
+    }
+}
+
+/// fading ribbons behind whatever pushes its position every frame
+pub struct TrailManager<'a> {
+    vao: Vao<'a>,
+    vbo: ArrayBuffer<'a>,
+    shader: Shader<'a>,
+
+    trails: HashMap<EntityId, History>,
+    vertices: Batch<Vertex>,
+}
+
+impl<'a> TrailManager<'a> {
+    pub fn new(ctx: &'a DrawContext, max_segments: usize) -> Result<Self> {
+        // two triangles per segment
+        let max_vertices = 6 * max_segments;
+        let vbo = ArrayBuffer::new(ctx);
+        vbo.reserve(
+            max_vertices * size_of::<Vertex>(),
+            gl::buffer_flags::DYNAMIC_STORAGE,
+        );
+
+        let vao = VaoHelper::new(ctx)
+            .bind_buffer(&vbo)
+            .push_attrib(2, FLOAT, FALSE, size_of::<Vertex>(), offset_of!(Vertex, pos))
+            .push_attrib(4, FLOAT, FALSE, size_of::<Vertex>(), offset_of!(Vertex, col))
+            .build();
+
+        Ok(Self {
+            vao,
+            vbo,
+            shader: Shader::from_resource(ctx, resources::shaders::TRAIL)?,
+
+            trails: HashMap::new(),
+            vertices: Batch::new(max_vertices),
+        })
+    }
+
+    pub fn push(&mut self, trail: Trail) {
+        let now = Instant::now();
+        self.trails
+            .entry(trail.id)
+            .or_insert_with(|| History {
+                points: VecDeque::new(),
+                width: trail.width,
+                col: trail.col,
+                length: trail.length,
+            })
+            .record(trail, now);
+    }
+
+    /// in vertices
+    pub fn dropped(&self) -> usize {
+        self.vertices.dropped()
+    }
+
+    pub fn draw(&mut self) {
+        let now = Instant::now();
+        self.trails.retain(|_, history| history.expire(now));
+        for history in self.trails.values() {
+            history.ribbon(now, &mut self.vertices);
+        }
+
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        self.vao.apply();
+        self.shader.apply();
+        self.vbo
+            .update(0, unsafe { self.vertices.as_mut_slice().as_bytes() });
+        gl::draw!(DrawArrays(TRIANGLES, 0, self.vertices.len() as _));
+
+        self.vertices.clear();
+    }
+}
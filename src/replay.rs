@@ -0,0 +1,180 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use glfw::Key;
+
+use crate::{
+    common::{Error, Result},
+    math::Vec2,
+};
+
+// file layout, all little endian:
+// magic, seed: u64, then events until the end of the file
+// each event is a tag byte followed by its payload
+const MAGIC: &[u8; 8] = b"SNEKRPL1";
+const TAG_TICK: u8 = 0;
+const TAG_KEY: u8 = 1;
+const TAG_MOUSE: u8 = 2;
+
+// only keys the snake reacts to get recorded
+const KEYS: [Key; 10] = [
+    Key::W,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::Up,
+    Key::Left,
+    Key::Down,
+    Key::Right,
+    Key::Space,
+    // new keys go at the end so older replays still read
+    Key::E,
+];
+
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    /// a game tick, everything recorded before it happened during it
+    Tick(Duration),
+    Key(Key),
+    /// already in world coordinates
+    Mouse(Vec2),
+}
+
+/// writes a run's input as it happens
+/// flushed every tick, since the game quits the moment the snake dies
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, seed: u64) -> Result<Self> {
+        let file = File::create(path).map_err(|_| Error::FileNotFound)?;
+        let mut out = BufWriter::new(file);
+        out.write_all(MAGIC).map_err(|_| Error::FileNotFound)?;
+        out.write_all(&seed.to_le_bytes()).map_err(|_| Error::FileNotFound)?;
+        Ok(Self { out })
+    }
+
+    pub fn record(&mut self, event: Event) {
+        let mut buf = Vec::with_capacity(9);
+        match event {
+            Event::Tick(dt) => {
+                buf.push(TAG_TICK);
+                buf.extend((dt.as_micros() as u32).to_le_bytes());
+            }
+            Event::Key(key) => {
+                let Some(idx) = KEYS.iter().position(|&k| k == key) else {
+                    return;
+                };
+                buf.push(TAG_KEY);
+                buf.push(idx as u8);
+            }
+            Event::Mouse(pos) => {
+                buf.push(TAG_MOUSE);
+                buf.extend(pos.x.to_le_bytes());
+                buf.extend(pos.y.to_le_bytes());
+            }
+        }
+
+        let _ = self.out.write_all(&buf);
+        if let Event::Tick(_) = event {
+            let _ = self.out.flush();
+        }
+    }
+}
+
+/// a recorded run, fed back one tick at a time
+pub struct Playback {
+    seed: u64,
+    events: VecDeque<Event>,
+}
+
+impl Playback {
+    /// a cut off last event (from quitting mid-write) is dropped
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = fs::read(path).map_err(|_| Error::FileNotFound)?;
+        let (magic, data) = data.split_at_checked(MAGIC.len()).ok_or(Error::BadReplay)?;
+        if magic != MAGIC {
+            Err(Error::BadReplay)?;
+        }
+        let (seed, mut data) = data.split_first_chunk::<8>().ok_or(Error::BadReplay)?;
+
+        let mut events = VecDeque::new();
+        while let Some((&tag, rest)) = data.split_first() {
+            let (event, rest) = match tag {
+                TAG_TICK => {
+                    let Some((micros, rest)) = rest.split_first_chunk::<4>() else { break };
+                    let micros = u32::from_le_bytes(*micros);
+                    (Event::Tick(Duration::from_micros(micros as _)), rest)
+                }
+                TAG_KEY => {
+                    let Some((&idx, rest)) = rest.split_first() else { break };
+                    let key = *KEYS.get(idx as usize).ok_or(Error::BadReplay)?;
+                    (Event::Key(key), rest)
+                }
+                TAG_MOUSE => {
+                    let Some((x, rest)) = rest.split_first_chunk::<4>() else { break };
+                    let Some((y, rest)) = rest.split_first_chunk::<4>() else { break };
+                    let pos = Vec2::new(f32::from_le_bytes(*x), f32::from_le_bytes(*y));
+                    (Event::Mouse(pos), rest)
+                }
+                _ => Err(Error::BadReplay)?,
+            };
+            events.push_back(event);
+            data = rest;
+        }
+
+        Ok(Self {
+            seed: u64::from_le_bytes(*seed),
+            events,
+        })
+    }
+
+    /// the run has to be seeded with this before any room is made
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// the inputs of the next tick in order, then how long the tick was
+    /// None once the recording is over
+    pub fn next_tick(&mut self) -> Option<(Vec<Event>, Duration)> {
+        let mut inputs = Vec::new();
+        while let Some(event) = self.events.pop_front() {
+            match event {
+                Event::Tick(dt) => return Some((inputs, dt)),
+                input => inputs.push(input),
+            }
+        }
+        None
+    }
+}
+
+/// `--record <file>` or `--replay <file>`
+pub enum Replay {
+    Recording(Recorder),
+    Playback(Playback),
+}
+
+impl Replay {
+    /// recording needs the seed the run starts from
+    pub fn from_args(args: impl Iterator<Item = String>, seed: u64) -> Result<Option<Self>> {
+        let args: Vec<_> = args.collect();
+        let path_after = |flag: &str| {
+            let idx = args.iter().position(|a| a == flag)?;
+            args.get(idx + 1).map(Path::new)
+        };
+
+        if let Some(path) = path_after("--replay") {
+            Ok(Some(Self::Playback(Playback::open(path)?)))
+        } else if let Some(path) = path_after("--record") {
+            Ok(Some(Self::Recording(Recorder::create(path, seed)?)))
+        } else {
+            Ok(None)
+        }
+    }
+}
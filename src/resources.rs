@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 macro_rules! load {
     ($path:literal) => {
         include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/res/", $path))
@@ -13,7 +15,45 @@ macro_rules! load {
 pub type Resource = &'static [u8];
 pub type Texture = Resource;
 pub type Sound = Resource;
-pub type Shader = &'static [Resource];
+pub type Shader = &'static [Asset];
+
+/// baked in like any other resource, along with the file it came from
+/// so it can be reloaded from there
+pub struct Asset {
+    pub path: &'static str,
+    source: Resource,
+}
+
+impl Asset {
+    /// with hot reloading on, the file as it is on disk now, since that's what gets edited
+    pub fn source(&self) -> Cow<'static, [u8]> {
+        #[cfg(feature = "hot-reload")]
+        if let Some(source) = crate::hot_reload::read(self.path) {
+            return Cow::Owned(source);
+        }
+        Cow::Borrowed(self.source)
+    }
+
+    /// what got baked into the binary, whatever's on disk
+    pub fn baked(&self) -> Resource {
+        self.source
+    }
+}
+
+macro_rules! asset {
+    ($path:literal) => {
+        Asset {
+            path: $path,
+            source: load!($path),
+        }
+    };
+}
+
+/// the default balance, see [`crate::config::Config`]
+pub const CONFIG: Resource = load!("config.toml");
+
+/// every palette, see [`crate::palette`]
+pub const PALETTES: Asset = asset!("palettes.toml");
 
 // TEXTURES //
 pub mod textures {
@@ -21,28 +61,34 @@ pub mod textures {
 
     pub const ICON: Texture = load!("textures/favicon.ico");
 
-    pub mod text {
+    pub mod font {
         use super::Texture;
 
+        pub const FONT: Texture = load!("textures/font/font.png");
+    }
+
+    pub mod text {
+        use crate::resources::Asset;
+
         // tutorial room text
-        pub const SNEK: Texture = load!("textures/text/snek.png");
-        pub const SNEK_GLITCH: Texture = load!("textures/text/snek-glitch.png");
-        pub const CONTROLS: Texture = load!("textures/text/controls.png");
-        pub const FRUIT: Texture = load!("textures/text/fruit.png");
-        pub const FRUIT_GLITCH: Texture = load!("textures/text/fruit-glitch.png");
-        pub const FRUIT_GLITCH_VARIANT: Texture = load!("textures/text/fruit-glitch-variant.png");
-        pub const ATTACK: Texture = load!("textures/text/attack.png");
-        pub const ATTACK_GLITCH: Texture = load!("textures/text/attack-glitch.png");
-        pub const EMPOWER: Texture = load!("textures/text/empower.png");
-        pub const EMPOWER_GLITCH: Texture = load!("textures/text/empower-glitch.png");
-        pub const ENEMY: Texture = load!("textures/text/enemy.png");
-        pub const ENEMY_GLITCH: Texture = load!("textures/text/enemy-glitch.png");
-        pub const SHIELD: Texture = load!("textures/text/shield.png");
-        pub const SHIELD_GLITCH: Texture = load!("textures/text/shield-glitch.png");
-
-        pub const LUCKY_GLITCH: Texture = load!("textures/text/lucky-glitch.png");
-        pub const SWARM_GLITCH: Texture = load!("textures/text/swarm-glitch.png");
-        pub const BOSS_GLITCH: Texture = load!("textures/text/boss-glitch.png");
+        pub const SNEK: Asset = asset!("textures/text/snek.png");
+        pub const SNEK_GLITCH: Asset = asset!("textures/text/snek-glitch.png");
+        pub const CONTROLS: Asset = asset!("textures/text/controls.png");
+        pub const FRUIT: Asset = asset!("textures/text/fruit.png");
+        pub const FRUIT_GLITCH: Asset = asset!("textures/text/fruit-glitch.png");
+        pub const FRUIT_GLITCH_VARIANT: Asset = asset!("textures/text/fruit-glitch-variant.png");
+        pub const ATTACK: Asset = asset!("textures/text/attack.png");
+        pub const ATTACK_GLITCH: Asset = asset!("textures/text/attack-glitch.png");
+        pub const EMPOWER: Asset = asset!("textures/text/empower.png");
+        pub const EMPOWER_GLITCH: Asset = asset!("textures/text/empower-glitch.png");
+        pub const ENEMY: Asset = asset!("textures/text/enemy.png");
+        pub const ENEMY_GLITCH: Asset = asset!("textures/text/enemy-glitch.png");
+        pub const SHIELD: Asset = asset!("textures/text/shield.png");
+        pub const SHIELD_GLITCH: Asset = asset!("textures/text/shield-glitch.png");
+
+        pub const LUCKY_GLITCH: Asset = asset!("textures/text/lucky-glitch.png");
+        pub const SWARM_GLITCH: Asset = asset!("textures/text/swarm-glitch.png");
+        pub const BOSS_GLITCH: Asset = asset!("textures/text/boss-glitch.png");
     }
 }
 
@@ -67,32 +113,77 @@ pub mod sounds {
     pub const GLITCH_3: Sound = load!("sounds/glitch-3.wav");
     pub const GLITCH_4: Sound = load!("sounds/glitch-4.wav");
     pub const GLITCH_5: Sound = load!("sounds/glitch-5.wav");
+    pub const SHRINK: Sound = load!("sounds/shrink.wav");
+}
+
+// MUSIC //
+pub mod music {
+    use super::Sound;
+
+    pub const CALM: Sound = load!("music/calm.wav");
+    pub const TENSE: Sound = load!("music/tense.wav");
+}
+
+// ROOMS //
+pub mod rooms {
+    use super::Resource;
+
+    /// see [`crate::template::Template`] for the format
+    pub const ALL: &[Resource] = &[
+        load!("rooms/quarters.txt"),
+        load!("rooms/crypt.txt"),
+    ];
 }
 
 // SHADERS //
 pub mod shaders {
-    use super::Shader;
+    use super::{Shader, Asset};
 
     pub const INSTANCED: Shader = &[
-        load!("shaders/instanced.vert"),
-        load!("shaders/instanced.frag"),
+        asset!("shaders/instanced.vert"),
+        asset!("shaders/instanced.frag"),
     ];
 
     pub const FIREBALL: Shader = &[
-        load!("shaders/fireball.vert"),
-        load!("shaders/fireball.frag"),
-        load!("shaders/fireball.geom"),
+        asset!("shaders/fireball.vert"),
+        asset!("shaders/fireball.frag"),
+        asset!("shaders/fireball.geom"),
     ];
 
     pub const SHIELD: Shader = &[
-        load!("shaders/shield.vert"),
-        load!("shaders/shield.frag"),
-        load!("shaders/shield.geom"),
+        asset!("shaders/shield.vert"),
+        asset!("shaders/shield.frag"),
+        asset!("shaders/shield.geom"),
     ];
 
-    pub const CRT: Shader = &[load!("shaders/crt.vert"), load!("shaders/crt.frag")];
+    pub const CRT: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/crt.frag")];
+
+    // post-process passes all share the crt's full-screen quad
+    pub const BLOOM: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/bloom.frag")];
+
+    pub const BLOOM_BRIGHT: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/bloom_bright.frag")];
 
-    pub const SWOOP: Shader = &[load!("shaders/swoop.vert"), load!("shaders/swoop.frag")];
+    pub const BLUR: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/blur.frag")];
 
-    pub const TEXT: Shader = &[load!("shaders/text.vert"), load!("shaders/text.frag")];
+    pub const ABERRATION: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/aberration.frag")];
+
+    pub const VIGNETTE: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/vignette.frag")];
+
+    pub const PRESENT: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/present.frag")];
+
+    pub const LIGHTING: Shader = &[asset!("shaders/crt.vert"), asset!("shaders/lighting.frag")];
+
+    pub const SWOOP: Shader = &[asset!("shaders/swoop.vert"), asset!("shaders/swoop.frag")];
+
+    pub const TRAIL: Shader = &[asset!("shaders/trail.vert"), asset!("shaders/trail.frag")];
+
+    pub const TEXT: Shader = &[asset!("shaders/text.vert"), asset!("shaders/text.frag")];
+
+    pub const HUD: Shader = &[asset!("shaders/hud.vert"), asset!("shaders/hud.frag")];
+
+    pub const PARTICLES: Shader = &[
+        asset!("shaders/particles.vert"),
+        asset!("shaders/particles.frag"),
+        asset!("shaders/particles.geom"),
+    ];
 }
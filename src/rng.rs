@@ -0,0 +1,89 @@
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, thread_rng, Rng, RngCore, SeedableRng};
+
+/// independent streams of numbers, so glitch rolls (which depend on timing)
+/// can't throw off room generation
+/// purely cosmetic randomness (particles, shakes, sound variants) stays on thread_rng
+#[derive(Debug, Clone, Copy)]
+pub enum Stream {
+    /// rooms, fruit placement, enemies
+    World,
+    Corruption,
+}
+
+impl Stream {
+    const ALL: [Self; 2] = [Self::World, Self::Corruption];
+}
+
+struct State {
+    seed: u64,
+    streams: Vec<StdRng>,
+}
+
+impl State {
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: Stream::ALL
+                .into_iter()
+                .map(|s| StdRng::seed_from_u64(seed.wrapping_add(s as u64)))
+                .collect(),
+        }
+    }
+}
+
+thread_local! {
+    static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+fn with<T>(f: impl FnOnce(&mut State) -> T) -> T {
+    STATE.with(|s| {
+        // unseeded runs pick a seed at random
+        let mut s = s.borrow_mut();
+        f(s.get_or_insert_with(|| State::new(thread_rng().gen())))
+    })
+}
+
+/// restart every stream from the seed
+/// the same seed and inputs play out the same run
+pub fn seed(seed: u64) {
+    STATE.with(|s| *s.borrow_mut() = Some(State::new(seed)));
+}
+
+/// the seed the current run started from
+pub fn current_seed() -> u64 {
+    with(|s| s.seed)
+}
+
+/// `--seed <n>`
+pub fn from_args(args: impl Iterator<Item = String>) -> Option<u64> {
+    let mut args = args.skip_while(|a| a != "--seed").skip(1);
+    args.next()?.parse().ok()
+}
+
+/// handle to one of the seeded streams, use it like thread_rng
+#[derive(Debug, Clone, Copy)]
+pub struct GameRng(Stream);
+
+pub fn get(stream: Stream) -> GameRng {
+    GameRng(stream)
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        with(|s| s.streams[self.0 as usize].next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        with(|s| s.streams[self.0 as usize].next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        with(|s| s.streams[self.0 as usize].fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        with(|s| s.streams[self.0 as usize].try_fill_bytes(dest))
+    }
+}
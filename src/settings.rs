@@ -0,0 +1,249 @@
+use std::fs;
+
+use glfw::Key;
+
+use crate::{common, palette, sound::Channel};
+
+const FILE: &str = "settings.txt";
+// how much a volume goes up or down per key press
+pub const VOLUME_STEP: f32 = 0.1;
+// the snake can be slowed down to this fraction of its usual speed, a step at a time
+pub const MIN_SNAKE_SPEED: f32 = 0.5;
+pub const SNAKE_SPEED_STEP: f32 = 0.1;
+
+/// what a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Left,
+    Down,
+    Right,
+    Attack,
+    Ice,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Up,
+        Action::Left,
+        Action::Down,
+        Action::Right,
+        Action::Attack,
+        Action::Ice,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Left => "left",
+            Self::Down => "down",
+            Self::Right => "right",
+            Self::Attack => "attack",
+            Self::Ice => "ice",
+        }
+    }
+
+    /// the key the snake listens for, bindings get translated to it
+    fn default_key(self) -> Key {
+        match self {
+            Self::Up => Key::W,
+            Self::Left => Key::A,
+            Self::Down => Key::S,
+            Self::Right => Key::D,
+            Self::Attack => Key::Space,
+            Self::Ice => Key::E,
+        }
+    }
+}
+
+// keys that can be bound, by the name they're saved and shown as
+// enter and escape are left out so menus always work, and m so muting does
+const KEY_NAMES: [(Key, &str); 35] = [
+    (Key::A, "A"), (Key::B, "B"), (Key::C, "C"), (Key::D, "D"), (Key::E, "E"), (Key::F, "F"),
+    (Key::G, "G"), (Key::H, "H"), (Key::I, "I"), (Key::J, "J"), (Key::K, "K"), (Key::L, "L"),
+    (Key::N, "N"), (Key::O, "O"), (Key::P, "P"), (Key::Q, "Q"), (Key::R, "R"),
+    (Key::S, "S"), (Key::T, "T"), (Key::U, "U"), (Key::V, "V"), (Key::W, "W"), (Key::X, "X"),
+    (Key::Y, "Y"), (Key::Z, "Z"),
+    (Key::Up, "UP"), (Key::Left, "LEFT"), (Key::Down, "DOWN"), (Key::Right, "RIGHT"),
+    (Key::Space, "SPACE"), (Key::Tab, "TAB"), (Key::LeftShift, "SHIFT"), (Key::LeftControl, "CTRL"),
+    (Key::LeftAlt, "ALT"), (Key::Backspace, "BACKSPACE"),
+];
+
+pub fn key_name(key: Key) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(k, _)| *k == key).map(|(_, n)| *n)
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    KEY_NAMES.iter().find(|(_, n)| *n == name).map(|(k, _)| *k)
+}
+
+/// the player's preferences, kept between runs
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// volumes are in [0,1]
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub ambience_volume: f32,
+    pub ui_volume: f32,
+    pub music_volume: f32,
+    pub muted: bool,
+    pub crt: bool,
+    pub screen_shake: bool,
+    /// shapes on fruits and enemies, for telling them apart by more than color
+    pub markers: bool,
+    /// cuts instead of pans, and no shakes, flicker or glitches
+    pub reduced_motion: bool,
+    /// in [MIN_SNAKE_SPEED, 1]
+    pub snake_speed: f32,
+    /// only takes effect the next time the game starts
+    pub fullscreen: bool,
+    /// by name, rooms can draw in another one for a while
+    pub palette: String,
+    bindings: Vec<(Action, Key)>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            ambience_volume: 1.0,
+            ui_volume: 1.0,
+            music_volume: 1.0,
+            muted: false,
+            crt: true,
+            screen_shake: true,
+            markers: false,
+            reduced_motion: false,
+            snake_speed: 1.0,
+            fullscreen: false,
+            palette: palette::DEFAULT.to_string(),
+            bindings: Action::ALL.into_iter().map(|a| (a, a.default_key())).collect(),
+        }
+    }
+}
+
+impl Settings {
+    /// the defaults fill in anything missing or unreadable
+    pub fn load() -> Self {
+        let mut ret = Self::default();
+        let contents = common::data_path(FILE)
+            .and_then(|p| fs::read_to_string(p).ok())
+            .unwrap_or_default();
+        for line in contents.lines() {
+            let Some((name, value)) = line.split_once(' ') else {
+                continue;
+            };
+            ret.set(name, value.trim());
+        }
+        ret
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        let volume = || value.parse::<f32>().ok().map(|v| v.clamp(0.0, 1.0));
+        let flag = || value.parse::<bool>().ok();
+        match name {
+            "master_volume" => self.master_volume = volume().unwrap_or(self.master_volume),
+            "sfx_volume" => self.sfx_volume = volume().unwrap_or(self.sfx_volume),
+            "ambience_volume" => self.ambience_volume = volume().unwrap_or(self.ambience_volume),
+            "ui_volume" => self.ui_volume = volume().unwrap_or(self.ui_volume),
+            "music_volume" => self.music_volume = volume().unwrap_or(self.music_volume),
+            "muted" => self.muted = flag().unwrap_or(self.muted),
+            "crt" => self.crt = flag().unwrap_or(self.crt),
+            "screen_shake" => self.screen_shake = flag().unwrap_or(self.screen_shake),
+            "markers" => self.markers = flag().unwrap_or(self.markers),
+            "reduced_motion" => self.reduced_motion = flag().unwrap_or(self.reduced_motion),
+            "snake_speed" => {
+                let speed = value.parse::<f32>().ok().map(|s| s.clamp(MIN_SNAKE_SPEED, 1.0));
+                self.snake_speed = speed.unwrap_or(self.snake_speed);
+            }
+            "fullscreen" => self.fullscreen = flag().unwrap_or(self.fullscreen),
+            "palette" if palette::by_name(value).is_some() => self.palette = value.to_string(),
+            _ => {
+                let action = name
+                    .strip_prefix("bind_")
+                    .and_then(|n| Action::ALL.into_iter().find(|a| a.name() == n));
+                if let (Some(action), Some(key)) = (action, key_from_name(value)) {
+                    self.bind(action, key);
+                }
+            }
+        }
+    }
+
+    /// one `name value` pair per line
+    /// returns false if the file couldn't be written
+    pub fn save(&self) -> bool {
+        let Some(path) = common::data_path(FILE) else {
+            return false;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+
+        let values = [
+            ("master_volume", self.master_volume.to_string()),
+            ("sfx_volume", self.sfx_volume.to_string()),
+            ("ambience_volume", self.ambience_volume.to_string()),
+            ("ui_volume", self.ui_volume.to_string()),
+            ("music_volume", self.music_volume.to_string()),
+            ("muted", self.muted.to_string()),
+            ("crt", self.crt.to_string()),
+            ("screen_shake", self.screen_shake.to_string()),
+            ("markers", self.markers.to_string()),
+            ("reduced_motion", self.reduced_motion.to_string()),
+            ("snake_speed", self.snake_speed.to_string()),
+            ("fullscreen", self.fullscreen.to_string()),
+            ("palette", self.palette.clone()),
+        ];
+        let mut contents = values.map(|(name, value)| format!("{name} {value}\n")).concat();
+        for &(action, key) in &self.bindings {
+            if let Some(key) = key_name(key) {
+                contents += &format!("bind_{} {key}\n", action.name());
+            }
+        }
+        fs::write(path, contents).is_ok()
+    }
+
+    pub fn volume(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Sfx => self.sfx_volume,
+            Channel::Ambience => self.ambience_volume,
+            Channel::Ui => self.ui_volume,
+            Channel::Music => self.music_volume,
+        }
+    }
+
+    pub fn binding(&self, action: Action) -> Key {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(action.default_key(), |(_, k)| *k)
+    }
+
+    /// a key can only do one thing, if another action had it the two swap keys
+    /// returns false if the key can't be bound
+    pub fn bind(&mut self, action: Action, key: Key) -> bool {
+        if key_name(key).is_none() {
+            return false;
+        }
+
+        let old = self.binding(action);
+        for (a, k) in &mut self.bindings {
+            if *a == action {
+                *k = key;
+            } else if *k == key {
+                *k = old;
+            }
+        }
+        true
+    }
+
+    /// the key the snake should see for a key that was pressed
+    /// unbound keys go through as they are, so the defaults and arrows keep working
+    pub fn translate(&self, key: Key) -> Key {
+        self.bindings
+            .iter()
+            .find(|(_, k)| *k == key)
+            .map_or(key, |(a, _)| a.default_key())
+    }
+}
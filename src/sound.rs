@@ -1,18 +1,24 @@
 use std::{
-    sync::mpsc::{self, Receiver, Sender},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
 use rand::{thread_rng, Rng};
-use soloud::{AudioExt, LoadExt, Soloud, Wav};
+use soloud::{AudioExt, FilterExt, Handle, LoadExt, LofiFilter, LofiFilterAttr, Soloud, Wav, WavStream};
 
 use crate::{
     common::{Error, Result},
+    math::Vec2,
     resources::Resource,
 };
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sounds {
     Die,
     Eat,
@@ -31,6 +37,7 @@ pub enum Sounds {
     Glitch3,
     Glitch4,
     Glitch5,
+    Shrink,
 
     _NumSounds,
 }
@@ -56,11 +63,56 @@ impl Sounds {
             Self::Glitch3 => GLITCH_3,
             Self::Glitch4 => GLITCH_4,
             Self::Glitch5 => GLITCH_5,
+            Self::Shrink => SHRINK,
 
             Self::_NumSounds => panic!(),
         }
     }
 
+    // how far the pitch and volume of each play can stray, so repeats don't sound identical
+    // pitch goes either way, volume only goes down
+    fn variation(self) -> (f32, f32) {
+        match self {
+            Self::Eat | Self::Swoop => (0.08, 0.1),
+            Self::Move => (0.05, 0.1),
+            Self::Fireball | Self::CrtClick => (0.1, 0.2),
+            Self::Glitch0 | Self::Glitch1 | Self::Glitch2 | Self::Glitch3 | Self::Glitch4 | Self::Glitch5 => {
+                (0.15, 0.2)
+            }
+            _ => (0.0, 0.0),
+        }
+    }
+
+    // how many of it can play at once, the oldest one gets cut off to make room
+    fn max_voices(self) -> usize {
+        match self {
+            Self::Die | Self::CrtOn | Self::CrtBuzz => 1,
+            Self::Move | Self::CameraPan | Self::RoomUnlocked => 2,
+            Self::Glitch0 | Self::Glitch1 | Self::Glitch2 | Self::Glitch3 | Self::Glitch4 | Self::Glitch5 => 2,
+            Self::Eat | Self::Swoop | Self::CrtClick | Self::ShieldUp | Self::Shrink => 3,
+            Self::Fireball => 4,
+            Self::_NumSounds => 0,
+        }
+    }
+
+    /// a random play speed and volume within the sound's variation
+    fn vary(self) -> (f32, f32) {
+        let (pitch, volume) = self.variation();
+        let mut rng = thread_rng();
+        (1.0 + rng.gen_range(-pitch..=pitch), 1.0 - rng.gen_range(0.0..=volume))
+    }
+
+    pub fn channel(self) -> Channel {
+        match self {
+            Self::CrtOn | Self::CrtClick | Self::CrtBuzz => Channel::Ambience,
+            Self::Glitch0 | Self::Glitch1 | Self::Glitch2 | Self::Glitch3 | Self::Glitch4 | Self::Glitch5 => {
+                Channel::Ambience
+            }
+            Self::CameraPan | Self::RoomUnlocked => Channel::Ui,
+            _ => Channel::Sfx,
+        }
+    }
+
     pub fn glitch() -> Self {
         let mut rng = thread_rng();
         let first_glitch = Self::Glitch0;
@@ -93,52 +145,353 @@ impl TryFrom<u8> for Sounds {
             14 => S::Glitch3,
             15 => S::Glitch4,
             16 => S::Glitch5,
+            17 => S::Shrink,
 
             _ => Err(Error::InvalidSoundId)?,
         })
     }
 }
 
+/// tracks stream in and loop until another one takes over
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Music {
+    Calm,
+    Tense,
+}
+
+impl Music {
+    const ALL: [Music; 2] = [Music::Calm, Music::Tense];
+
+    fn resource(self) -> Resource {
+        use crate::resources::music::*;
+        match self {
+            Self::Calm => CALM,
+            Self::Tense => TENSE,
+        }
+    }
+}
+
+/// sounds are mixed in groups, each with its own volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// gameplay, the snake and whatever it runs into
+    Sfx,
+    /// the crt humming along and glitching out
+    Ambience,
+    /// cues about the game itself, like rooms opening up
+    Ui,
+    Music,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 4] = [Channel::Sfx, Channel::Ambience, Channel::Ui, Channel::Music];
+}
+
+enum Command {
+    /// with a position it's panned and quieter the further it is from the listener
+    Play(Sounds, Option<Vec2>),
+    /// where the sounds are heard from, in world coordinates
+    Listener(Vec2),
+    Bitcrush(f32),
+    /// volumes are in [0,1]
+    Volume(Channel, f32),
+    Master(f32),
+    Mute(bool),
+    /// with the fade in length, fading out whatever was playing before
+    PlayMusic(Music, Duration),
+    StopMusic(Duration),
+    /// keeps playing the sound at a fraction of its channel's volume
+    Loop(Sounds, f32),
+    StopLoop(Sounds, Duration),
+}
+
+// lofi filter slot on every sound
+const BITCRUSH_FILTER: u32 = 0;
+const BITCRUSH_SAMPLERATE: f32 = 6000.0;
+const BITCRUSH_DEPTH: f32 = 4.0;
+// sounds this many tiles off to either side play out of just that speaker
+const PAN_WIDTH: f32 = 16.0;
+// sounds this many tiles away play at half volume
+const FALLOFF: f32 = 12.0;
+
+/// how far to either side and how loud a sound is
+/// from wherever the listener is
+fn spatialize(offset: Vec2) -> (f32, f32) {
+    let pan = (offset.x / PAN_WIDTH).clamp(-1.0, 1.0);
+    let gain = 1.0 / (1.0 + offset.len() / FALLOFF);
+    (pan, gain)
+}
+
+fn start(sl: &mut Soloud, wav: &Wav, bitcrush_wet: f32, volume: f32) -> Handle {
+    let handle = sl.play(wav);
+    sl.set_filter_param(handle, BITCRUSH_FILTER, LofiFilterAttr::Wet, bitcrush_wet);
+    sl.set_volume(handle, volume);
+    handle
+}
+
+/// stops the voice once it's faded out
+fn fade_out(sl: &mut Soloud, handle: Handle, fade: Duration) {
+    sl.fade_volume(handle, 0.0, fade.as_secs_f64());
+    sl.schedule_stop(handle, fade.as_secs_f64());
+}
+
+// how often to look for an audio device while there isn't one
+const DEVICE_RETRY: Duration = Duration::from_secs(2);
+
+/// holds on to whatever comes in until the next retry
+/// one-shots get dropped, they'd be out of place by the time there's a device
+/// returns false once nobody's sending anymore
+fn wait_for_device(sound_queue: &Receiver<Command>, pending: &mut Vec<Command>) -> bool {
+    let deadline = Instant::now() + DEVICE_RETRY;
+    loop {
+        match sound_queue.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Command::Play(..)) => (),
+            Ok(command) => pending.push(command),
+            Err(RecvTimeoutError::Timeout) => return true,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        }
+    }
+}
+
 pub struct SoundManager {
-    tx: Sender<Sounds>,
+    tx: Sender<Command>,
+    available: Arc<AtomicBool>,
+}
+
+impl Default for SoundManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SoundManager {
+    /// starts out silent if there's no audio device, until one gets plugged in
     pub fn new() -> Self {
         let (tx, rx) = mpsc::channel();
+        let available = Arc::new(AtomicBool::new(false));
+
+        Self::start_engine(rx, available.clone());
+        Self { tx, available }
+    }
+
+    /// drops every sound, for running without an audio device
+    pub fn silent() -> Self {
+        let (tx, _) = mpsc::channel();
+        Self {
+            tx,
+            available: Arc::new(AtomicBool::new(false)),
+        }
+    }
 
-        let _handle = Self::start_engine(rx);
-        Self { tx }
+    /// whether there's a device to play sounds on
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Relaxed)
     }
 
-    fn start_engine(sound_queue: Receiver<Sounds>) {
+    /// every sound, filtered through the bitcrusher, and every music track
+    fn load_sounds(bitcrush: &mut LofiFilter) -> Result<(Vec<Wav>, Vec<WavStream>)> {
+        let audio = |what: &str, e: soloud::SoloudError| Error::Audio(format!("{what}: {e}"));
+        bitcrush
+            .set_params(BITCRUSH_SAMPLERATE, BITCRUSH_DEPTH)
+            .map_err(|e| audio("bitcrush", e))?;
+
+        let mut sounds = Vec::with_capacity(Sounds::_NumSounds as _);
+        for sound_id in 0..(Sounds::_NumSounds as u8) {
+            // don't forget to add new sounds to the conversion table in try_from
+            let sound = Sounds::try_from(sound_id)?;
+            let mut wav = Wav::default();
+            wav.load_mem(sound.resource())
+                .map_err(|e| audio(&format!("{sound:?}"), e))?;
+            wav.set_filter(BITCRUSH_FILTER, Some(&*bitcrush));
+            sounds.push(wav);
+        }
+
+        let mut tracks = Vec::with_capacity(Music::ALL.len());
+        for track in Music::ALL {
+            let mut stream = WavStream::default();
+            stream.load_mem(track.resource())
+                .map_err(|e| audio(&format!("{track:?}"), e))?;
+            stream.set_looping(true);
+            tracks.push(stream);
+        }
+
+        Ok((sounds, tracks))
+    }
+
+    fn start_engine(sound_queue: Receiver<Command>, available: Arc<AtomicBool>) {
+        // the global volume doubles as the mute switch
+        let global = |master: f32, muted: bool| if muted { 0.0 } else { master };
+
         // run the engine
         thread::spawn(move || {
-            let sl = Soloud::default().unwrap();
-            // load sounds
-            let mut sounds = Vec::with_capacity(Sounds::_NumSounds as _);
-            for sound_id in 0..(Sounds::_NumSounds as u8) {
-                // don't forget to add new sounds to the conversion table in try_from
-                let sound = Sounds::try_from(sound_id).unwrap();
-                let mut wav = Wav::default();
-                wav.load_mem(sound.resource())
-                    .expect("can't find sound file");
-                sounds.push(wav);
-            }
+            // volumes, music and loops set while there's no device apply once there is one
+            let mut pending = Vec::new();
+            let mut sl = loop {
+                if let Ok(sl) = Soloud::default() {
+                    break sl;
+                }
+                if !self::wait_for_device(&sound_queue, &mut pending) {
+                    return;
+                }
+            };
 
-            loop {
-                if let Ok(sound) = sound_queue.recv() {
-                    sl.play(&sounds[sound as usize]);
-                    sl.voice_count();
-                } else {
+            // declared before the sounds so it outlives them
+            let mut bitcrush = LofiFilter::default();
+            let (sounds, tracks) = match Self::load_sounds(&mut bitcrush) {
+                Ok(loaded) => loaded,
+                // the game goes on without sound
+                Err(e) => {
+                    log::error!("{e}");
                     return;
                 }
+            };
+            available.store(true, Ordering::Relaxed);
+            let mut bitcrush_wet = 0.0;
+            let mut volumes = [1.0; Channel::ALL.len()];
+            let (mut master, mut muted) = (1.0, false);
+            // whatever might still be playing, with how loud it is on its channel
+            // so volume changes reach it
+            let mut playing: Vec<(Channel, Handle, f32)> = Vec::new();
+            let mut loops: Vec<(Sounds, Handle)> = Vec::new();
+            let mut music: Option<(Music, Handle)> = None;
+            let mut listener = Vec2::default();
+            // every sound's voices that might still be playing, oldest first
+            let mut voices = vec![Vec::new(); Sounds::_NumSounds as usize];
+
+            let mut pending = pending.into_iter();
+            loop {
+                let command = pending.next().map_or_else(|| sound_queue.recv(), Ok);
+                match command {
+                    Ok(Command::Play(sound, position)) => {
+                        let voices: &mut Vec<Handle> = &mut voices[sound as usize];
+                        voices.retain(|&h| sl.is_valid_voice_handle(h));
+                        if voices.len() >= sound.max_voices() {
+                            sl.stop(voices.remove(0));
+                        }
+
+                        let channel = sound.channel();
+                        let (pan, gain) = position.map_or((0.0, 1.0), |p| self::spatialize(p - listener));
+                        let (speed, loudness) = sound.vary();
+                        let gain = gain * loudness;
+                        let wav = &sounds[sound as usize];
+                        let handle = self::start(&mut sl, wav, bitcrush_wet, gain * volumes[channel as usize]);
+                        sl.set_pan(handle, pan);
+                        // only fails on speeds too close to zero
+                        let _ = sl.set_relative_play_speed(handle, speed);
+                        voices.push(handle);
+                        playing.retain(|&(_, h, _)| sl.is_valid_voice_handle(h));
+                        playing.push((channel, handle, gain));
+                    }
+                    Ok(Command::Listener(position)) => listener = position,
+                    Ok(Command::Bitcrush(wet)) => bitcrush_wet = wet,
+                    Ok(Command::Volume(channel, volume)) => {
+                        volumes[channel as usize] = volume;
+                        playing.retain(|&(_, h, _)| sl.is_valid_voice_handle(h));
+                        for &(c, h, gain) in &playing {
+                            if c == channel {
+                                sl.set_volume(h, gain * volume);
+                            }
+                        }
+                    }
+                    // already playing
+                    Ok(Command::PlayMusic(track, _)) if music.is_some_and(|(m, _)| m == track) => (),
+                    Ok(Command::PlayMusic(track, fade)) => {
+                        if let Some((_, old)) = music.take() {
+                            playing.retain(|&(_, h, _)| h != old);
+                            self::fade_out(&mut sl, old, fade);
+                        }
+                        let handle = sl.play(&tracks[track as usize]);
+                        sl.set_volume(handle, 0.0);
+                        sl.fade_volume(handle, volumes[Channel::Music as usize], fade.as_secs_f64());
+                        playing.push((Channel::Music, handle, 1.0));
+                        music = Some((track, handle));
+                    }
+                    Ok(Command::StopMusic(fade)) => {
+                        if let Some((_, old)) = music.take() {
+                            playing.retain(|&(_, h, _)| h != old);
+                            self::fade_out(&mut sl, old, fade);
+                        }
+                    }
+                    Ok(Command::Loop(sound, gain)) => {
+                        let channel = sound.channel();
+                        let wav = &sounds[sound as usize];
+                        let handle = self::start(&mut sl, wav, bitcrush_wet, gain * volumes[channel as usize]);
+                        sl.set_looping(handle, true);
+                        playing.push((channel, handle, gain));
+                        loops.push((sound, handle));
+                    }
+                    Ok(Command::StopLoop(sound, fade)) => {
+                        for &(s, handle) in &loops {
+                            if s == sound {
+                                playing.retain(|&(_, h, _)| h != handle);
+                                self::fade_out(&mut sl, handle, fade);
+                            }
+                        }
+                        loops.retain(|&(s, _)| s != sound);
+                    }
+                    Ok(Command::Master(volume)) => {
+                        master = volume;
+                        sl.set_global_volume(global(master, muted));
+                    }
+                    Ok(Command::Mute(mute)) => {
+                        muted = mute;
+                        sl.set_global_volume(global(master, muted));
+                    }
+                    Err(_) => return,
+                }
             }
         });
     }
 
     pub fn play(&self, sound: Sounds) {
-        let _ = self.tx.send(sound);
+        let _ = self.tx.send(Command::Play(sound, None));
+    }
+
+    /// bitcrush all sounds played from now on
+    /// wet is in the range [0,1] where 0 is clean
+    pub fn set_bitcrush(&self, wet: f32) {
+        let _ = self.tx.send(Command::Bitcrush(wet.clamp(0.0, 1.0)));
+    }
+
+    /// volume is in [0,1], sounds already playing on the channel change too
+    pub fn set_volume(&self, channel: Channel, volume: f32) {
+        let _ = self.tx.send(Command::Volume(channel, volume.clamp(0.0, 1.0)));
+    }
+
+    /// scales every channel, volume is in [0,1]
+    pub fn set_master_volume(&self, volume: f32) {
+        let _ = self.tx.send(Command::Master(volume.clamp(0.0, 1.0)));
+    }
+
+    /// silences everything without touching any volume
+    pub fn set_muted(&self, muted: bool) {
+        let _ = self.tx.send(Command::Mute(muted));
+    }
+
+    /// crossfades from whatever track was playing, if it isn't this one already
+    pub fn play_music(&self, track: Music, fade: Duration) {
+        let _ = self.tx.send(Command::PlayMusic(track, fade));
+    }
+
+    pub fn stop_music(&self, fade: Duration) {
+        let _ = self.tx.send(Command::StopMusic(fade));
+    }
+
+    /// plays the sound over and over until it's stopped
+    /// gain is how loud it is compared to the rest of its channel
+    pub fn play_loop(&self, sound: Sounds, gain: f32) {
+        let _ = self.tx.send(Command::Loop(sound, gain));
+    }
+
+    /// stops every loop of the sound
+    pub fn stop_loop(&self, sound: Sounds, fade: Duration) {
+        let _ = self.tx.send(Command::StopLoop(sound, fade));
+    }
+
+    /// sounds played at a position are heard from here
+    pub fn set_listener(&self, position: Vec2) {
+        let _ = self.tx.send(Command::Listener(position));
     }
 
     pub fn player(&self) -> Player {
@@ -150,11 +503,16 @@ impl SoundManager {
 
 #[derive(Clone)]
 pub struct Player {
-    tx: Sender<Sounds>,
+    tx: Sender<Command>,
 }
 
 impl Player {
     pub fn play(&self, sound: Sounds) {
-        let _ = self.tx.send(sound);
+        let _ = self.tx.send(Command::Play(sound, None));
+    }
+
+    /// position is in world coordinates, see [`SoundManager::set_listener`]
+    pub fn play_at(&self, sound: Sounds, position: Vec2) {
+        let _ = self.tx.send(Command::Play(sound, Some(position)));
     }
 }
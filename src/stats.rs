@@ -0,0 +1,160 @@
+use std::{fs, sync::mpsc::Receiver, time::Duration};
+
+use crate::{
+    archetype::snake,
+    common,
+    entity::EntityManager,
+    event::EventKind,
+};
+
+/// how far a run got
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunStats {
+    pub rooms: u32,
+    pub fruits: u32,
+    pub kills: u32,
+    pub attacks: u32,
+    pub time: Duration,
+}
+
+impl RunStats {
+    // same order as they're shown and saved in
+    fn fields(&self) -> [(&'static str, u64); 5] {
+        [
+            ("rooms", self.rooms as u64),
+            ("fruits", self.fruits as u64),
+            ("kills", self.kills as u64),
+            ("attacks", self.attacks as u64),
+            ("time", self.time.as_secs()),
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: u64) {
+        let value32 = value.try_into().unwrap_or(u32::MAX);
+        match name {
+            "rooms" => self.rooms = value32,
+            "fruits" => self.fruits = value32,
+            "kills" => self.kills = value32,
+            "attacks" => self.attacks = value32,
+            "time" => self.time = Duration::from_secs(value),
+            _ => (),
+        }
+    }
+}
+
+/// keeps count of what the snake gets up to over a run
+pub struct Tracker {
+    run: RunStats,
+    fruits: Receiver<()>,
+    kills: Receiver<()>,
+    attacks: Receiver<()>,
+}
+
+impl Tracker {
+    pub fn new(man: &mut EntityManager) -> Self {
+        let snake = man.find_by_tag(snake::TAG);
+        Self {
+            run: RunStats::default(),
+            fruits: man.events().subscribe(EventKind::FruitEaten, None),
+            kills: man.events().subscribe(EventKind::EnemyDied, None),
+            attacks: man.events().subscribe(EventKind::SnakeAttacked, snake),
+        }
+    }
+
+    pub fn tick(&mut self, dt: Duration) {
+        self.run.fruits += self.fruits.try_iter().count() as u32;
+        self.run.kills += self.kills.try_iter().count() as u32;
+        self.run.attacks += self.attacks.try_iter().count() as u32;
+        self.run.time += dt;
+    }
+
+    /// rooms don't have an entity to publish from, so whoever's running them reports it
+    pub fn clear_room(&mut self) {
+        self.run.rooms += 1;
+    }
+
+    pub fn run(&self) -> RunStats {
+        self.run
+    }
+}
+
+/// a finished run next to the best one so far
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub run: RunStats,
+    /// already counts the run
+    pub best: RunStats,
+    // the stats the run set a new best in
+    records: Vec<&'static str>,
+}
+
+impl Summary {
+    pub fn new(run: RunStats) -> Self {
+        let mut best = self::load_best();
+        let mut records = Vec::new();
+        for ((name, value), (_, old)) in run.fields().into_iter().zip(best.fields()) {
+            if value > old {
+                best.set(name, value);
+                records.push(name);
+            }
+        }
+
+        Self { run, best, records }
+    }
+
+    /// what the end screen shows, a line per stat
+    pub fn lines(&self) -> Vec<String> {
+        let best = self.best.fields();
+        self.run
+            .fields()
+            .into_iter()
+            .zip(best)
+            .map(|((name, value), (_, best))| {
+                let unit = if name == "time" { "S" } else { "" };
+                let record = if self.records.contains(&name) { " NEW BEST!" } else { "" };
+                format!("{name} {value}{unit} - best {best}{unit}{record}")
+            })
+            .collect()
+    }
+
+    /// best values are kept as `name value` lines
+    /// returns false if they couldn't be written
+    pub fn save(&self) -> bool {
+        if self.records.is_empty() {
+            return true;
+        }
+
+        let Some(path) = common::data_path(BEST_FILE) else {
+            return false;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+
+        let contents = self
+            .best
+            .fields()
+            .map(|(name, value)| format!("{name} {value}\n"))
+            .concat();
+        fs::write(path, contents).is_ok()
+    }
+}
+
+const BEST_FILE: &str = "best.txt";
+
+/// missing or unreadable values count as zero
+fn load_best() -> RunStats {
+    let mut best = RunStats::default();
+    let contents = common::data_path(BEST_FILE)
+        .and_then(|p| fs::read_to_string(p).ok())
+        .unwrap_or_default();
+    for line in contents.lines() {
+        let Some((name, value)) = line.split_once(' ') else {
+            continue;
+        };
+        if let Ok(value) = value.trim().parse() {
+            best.set(name, value);
+        }
+    }
+    best
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use crate::math::{lerp, Vec3};
+
+// each stack of haste speeds the pace up by this much
+const HASTE_PER_STACK: f32 = 0.5;
+// each stack of slow multiplies the pace by this much
+const SLOW_PER_STACK: f32 = 0.5;
+// how far an active status pulls the color towards its own
+const TINT: f32 = 0.45;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// moves faster, stacks
+    Haste,
+    /// can't get hurt
+    Shield,
+    /// everything runs slower, stacks
+    Slow,
+    /// just took a hit, can't take another one yet
+    Recovering,
+}
+
+impl Status {
+    pub const ALL: [Status; 4] = [Status::Haste, Status::Shield, Status::Slow, Status::Recovering];
+
+    pub fn color(self) -> Vec3 {
+        match self {
+            Self::Haste => Vec3::new(1.0, 0.85, 0.3),
+            Self::Shield => Vec3::new(0.9, 0.95, 1.0),
+            Self::Slow => Vec3::new(0.45, 0.75, 1.0),
+            Self::Recovering => Vec3::new(1.0, 0.5, 0.5),
+        }
+    }
+
+    /// what the hud shows while the status is active
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Haste => ">>",
+            Self::Shield => "+",
+            Self::Slow => "<<",
+            Self::Recovering => "!",
+        }
+    }
+
+    pub fn tint(self, col: Vec3) -> Vec3 {
+        lerp(col, self.color(), TINT)
+    }
+}
+
+/// timed statuses an entity is under
+/// every application is its own stack and wears off on its own
+#[derive(Debug, Clone, Default)]
+pub struct StatusEffects {
+    stacks: Vec<(Status, Duration)>,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, status: Status, length: Duration) {
+        self.stacks.push((status, length));
+    }
+
+    pub fn tick(&mut self, dt: Duration) {
+        for (_, left) in &mut self.stacks {
+            *left = left.saturating_sub(dt);
+        }
+        self.stacks.retain(|(_, left)| !left.is_zero());
+    }
+
+    pub fn stacks(&self, status: Status) -> usize {
+        self.stacks.iter().filter(|&&(s, _)| s == status).count()
+    }
+
+    pub fn has(&self, status: Status) -> bool {
+        self.stacks(status) > 0
+    }
+
+    /// until the last stack of status wears off
+    pub fn remaining(&self, status: Status) -> Duration {
+        self.stacks
+            .iter()
+            .filter(|&&(s, _)| s == status)
+            .map(|&(_, left)| left)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// how fast the entity's clock runs compared to everyone else's
+    pub fn pace(&self) -> f32 {
+        let haste = self.stacks(Status::Haste) as f32;
+        let slow = self.stacks(Status::Slow) as i32;
+        (1.0 + HASTE_PER_STACK * haste) * SLOW_PER_STACK.powi(slow)
+    }
+
+    /// col, tinted by every active status
+    pub fn tint(&self, col: Vec3) -> Vec3 {
+        Status::ALL
+            .into_iter()
+            .filter(|&s| self.has(s))
+            .fold(col, |col, s| s.tint(col))
+    }
+}
@@ -0,0 +1,181 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use crate::{
+    common::{Error, Result},
+    math::Vec2,
+    palette, resources,
+};
+
+/// what a character in a template's grid stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    /// `#`
+    Wall,
+    /// `%`, breaks after a few strong fireballs
+    Cracked,
+    /// `f`, where fruits can show up
+    Fruit,
+    /// `1` to `9`, an enemy with that much hp
+    Enemy(i32),
+    /// `p`, a random pickup
+    Pickup,
+    /// `t`, where the next label goes
+    Label,
+    /// `n`, where the next note goes, for the debug tools only
+    Note,
+    /// `s`, where the next named spawn point goes, for the debug tools only
+    Spawn,
+}
+
+impl Marker {
+    fn from_char(c: char) -> Result<Option<Self>> {
+        Ok(Some(match c {
+            '.' | ' ' => return Ok(None),
+            '#' => Self::Wall,
+            '%' => Self::Cracked,
+            'f' => Self::Fruit,
+            'p' => Self::Pickup,
+            't' => Self::Label,
+            'n' => Self::Note,
+            's' => Self::Spawn,
+            '1'..='9' => Self::Enemy(c as i32 - '0' as i32),
+            c => return Err(Error::BadTemplate(format!("unknown marker {c:?}"))),
+        }))
+    }
+}
+
+/// a handmade room, described in plain text
+///
+/// a header of `key value` lines, then `---`, then the room's insides as a grid
+/// the outer walls aren't part of the grid, the room gets them like any other
+/// lines starting with `;` are comments
+///
+/// `fruits N` is how many fruits have to be eaten, by default one per fruit marker
+/// `label TEXT` is the text for the next label marker, in reading order
+/// `note TEXT` and `spawn NAME` go with the note and spawn markers the same way
+/// `palette NAME` draws the room in that palette instead of the player's
+///
+/// the room is cleared once its fruits are eaten
+/// rooms without fruits are cleared once their enemies are dead
+#[derive(Debug, Clone)]
+pub struct Template {
+    /// of the grid, not counting the outer walls
+    pub dimensions: Vec2,
+    /// in room-space coordinates
+    pub markers: Vec<(Vec2, Marker)>,
+    pub labels: Vec<String>,
+    pub notes: Vec<String>,
+    pub spawns: Vec<String>,
+    pub fruits: i32,
+    pub palette: Option<String>,
+}
+
+impl Template {
+    /// every marker of one kind
+    pub fn find(&self, marker: Marker) -> impl Iterator<Item = Vec2> + '_ {
+        self.markers
+            .iter()
+            .filter(move |&&(_, m)| m == marker)
+            .map(|&(pos, _)| pos)
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s.lines().filter(|l| !l.starts_with(';'));
+
+        let mut fruits = None;
+        let mut labels = Vec::new();
+        let mut notes = Vec::new();
+        let mut spawns = Vec::new();
+        let mut palette = None;
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "---" {
+                break;
+            }
+
+            match line.split_once(' ') {
+                Some(("fruits", n)) => {
+                    let n = n.trim().parse().map_err(|_| Error::BadTemplate(format!("bad fruit count {n:?}")))?;
+                    fruits = Some(n);
+                }
+                Some(("label", text)) => labels.push(text.trim().to_string()),
+                Some(("note", text)) => notes.push(text.trim().to_string()),
+                Some(("spawn", name)) => spawns.push(name.trim().to_string()),
+                Some(("palette", name)) => {
+                    let name = name.trim();
+                    if palette::by_name(name).is_none() {
+                        return Err(Error::BadTemplate(format!("no palette called {name}")));
+                    }
+                    palette = Some(name.to_string());
+                }
+                _ if line.is_empty() => (),
+                _ => return Err(Error::BadTemplate(format!("unknown header {line:?}"))),
+            }
+        }
+
+        let rows: Vec<&str> = lines.map(str::trim_end).collect();
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        let height = rows.len();
+        // rooms are centered on a tile corner
+        if width == 0 || !width.is_multiple_of(2) || !height.is_multiple_of(2) {
+            return Err(Error::BadTemplate(format!("grid is {width}x{height}, has to be even")));
+        }
+
+        let mut markers = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if let Some(marker) = Marker::from_char(c)? {
+                    let pos = Vec2::new(x as f32 - (width / 2) as f32, y as f32 - (height / 2) as f32);
+                    markers.push((pos, marker));
+                }
+            }
+        }
+
+        let mut ret = Self {
+            dimensions: Vec2::new(width as f32, height as f32),
+            markers,
+            labels,
+            notes,
+            spawns,
+            fruits: 0,
+            palette,
+        };
+
+        let spots = ret.find(Marker::Fruit).count() as i32;
+        let has_enemies = ret.markers.iter().any(|&(_, m)| matches!(m, Marker::Enemy(_)));
+        if spots == 0 && !has_enemies {
+            return Err(Error::BadTemplate("nothing to clear the room with".to_string()));
+        }
+        if ret.find(Marker::Label).count() > ret.labels.len() {
+            return Err(Error::BadTemplate("more label markers than labels".to_string()));
+        }
+        if ret.find(Marker::Note).count() > ret.notes.len() {
+            return Err(Error::BadTemplate("more note markers than notes".to_string()));
+        }
+        if ret.find(Marker::Spawn).count() > ret.spawns.len() {
+            return Err(Error::BadTemplate("more spawn markers than spawns".to_string()));
+        }
+
+        ret.fruits = fruits.unwrap_or(spots);
+        Ok(ret)
+    }
+}
+
+/// every template the game ships with
+/// they're embedded, so a bad one is a bug
+pub fn all() -> &'static [Template] {
+    static TEMPLATES: OnceLock<Vec<Template>> = OnceLock::new();
+    TEMPLATES.get_or_init(|| {
+        resources::rooms::ALL
+            .iter()
+            .map(|&r| {
+                let src = std::str::from_utf8(r).expect("room template isn't utf-8");
+                Template::from_str(src).expect("bad room template")
+            })
+            .collect()
+    })
+}
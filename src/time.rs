@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct Threshold {
     acc: Duration,
     threshold: Duration,
@@ -18,6 +18,10 @@ impl Threshold {
         self.threshold = threshold;
     }
 
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
     pub fn tick(&mut self, dt: Duration) -> bool {
         self.acc += dt;
         if self.acc > self.threshold {
@@ -37,7 +41,7 @@ impl Threshold {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct Cooldown {
     acc: Duration,
     cooldown: Duration,
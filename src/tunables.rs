@@ -0,0 +1,70 @@
+use std::sync::OnceLock;
+
+use crate::mutator::Mutator;
+
+/// gameplay knobs for the current run
+/// set once at startup, read by archetypes and room generators
+#[derive(Debug, Clone)]
+pub struct Tunables {
+    pub mutators: Vec<Mutator>,
+    pub enemy_multiplier: usize,
+    pub smoothing: bool,
+    pub mirror_controls: bool,
+    pub fragile_body: bool,
+    pub score_multiplier: f32,
+    // power levels the stronger attacks unlock at, in order
+    pub power_tiers: [i32; 4],
+    // how many rooms are kept around behind the snake to come back to
+    pub backtrack_rooms: usize,
+}
+
+impl Default for Tunables {
+    fn default() -> Self {
+        Self {
+            mutators: Vec::new(),
+            enemy_multiplier: 1,
+            smoothing: true,
+            mirror_controls: false,
+            fragile_body: false,
+            score_multiplier: 1.0,
+            power_tiers: [2, 3, 4, 6],
+            backtrack_rooms: 0,
+        }
+    }
+}
+
+impl Tunables {
+    pub fn with_mutators(mutators: Vec<Mutator>) -> Self {
+        let mut ret = Self::default();
+        for &m in &mutators {
+            match m {
+                Mutator::DoubleEnemies => ret.enemy_multiplier *= 2,
+                Mutator::NoSmoothing => ret.smoothing = false,
+                Mutator::MirrorControls => ret.mirror_controls = true,
+                Mutator::FragileBody => ret.fragile_body = true,
+            }
+            ret.score_multiplier *= m.score_multiplier();
+        }
+
+        ret.mutators = mutators;
+        ret
+    }
+}
+
+/// reads how many rooms to keep from the command line
+/// e.g. `snek --backtrack 2`
+pub fn backtrack_from_args(args: impl Iterator<Item = String>) -> Option<usize> {
+    let mut args = args.skip_while(|a| a != "--backtrack").skip(1);
+    args.next()?.parse().ok()
+}
+
+static TUNABLES: OnceLock<Tunables> = OnceLock::new();
+
+/// only the first call has any effect
+pub fn init(tunables: Tunables) {
+    let _ = TUNABLES.set(tunables);
+}
+
+pub fn get() -> &'static Tunables {
+    TUNABLES.get_or_init(Tunables::default)
+}
@@ -1,23 +1,104 @@
 use core::{arch, panic};
 use std::{
     mem::swap,
+    ops::RangeInclusive,
     sync::mpsc::{self, Receiver, Sender},
     time::Duration,
 };
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use crate::{
-    archetype::{self, enemy, fruit, logic, snake, text},
-    entity::{Direction, Entities, EntityId, EntityManager, Position, Scale},
-    math::{Mat4, Vec2, Vec3, Vec4},
+    archetype::{
+        self,
+        annotation::{self, Annotation},
+        enemy, fruit, hazard, logic, obstacle, portal,
+        pickup::{self, Pickup},
+        snake,
+        terrain::{self, Kind},
+        text,
+        wave_spawner::{self, Wave},
+    },
+    camera::{self, Mode},
+    config,
+    difficulty::Difficulty,
+    endless::{self, Modifier},
+    entity::{component, Budget, Components, Direction, Entities, EntityId, EntityManager, Position, Scale},
+    event::EventKind,
+    math::{Mat4, Rect, TileCoord, Vec2, Vec3, Vec4},
+    palette::PaletteKey,
     render::text::TextNames,
-    sound::Sounds,
+    rng::{self, Stream},
+    sound::{Music, Sounds},
+    template::{self, Marker, Template},
     time::Threshold,
+    tunables,
 };
 
 const BACKGROUND_DEPTH: f32 = 0.9;
 const WALL_DEPTH: f32 = 0.8;
+const TERRAIN_DEPTH: f32 = 0.85;
+// the camera closes in a little on halls
+const HALL_ZOOM: f32 = 1.10;
+
+// the oldest entities get evicted once a room goes over
+const DEFAULT_BUDGETS: [(Budget, usize); 1] = [(Budget::Projectile, 16)];
+
+const SWARM_WAVES: usize = 3;
+// big enough that the camera has to follow the snake around
+const SPRAWL_SIZE: f32 = 60.0;
+// one in this many procedural rooms has a pickup lying around
+const PICKUP_ODDS: u32 = 2;
+// one in this many has a pickup walled in
+const VAULT_ODDS: u32 = 3;
+const VAULT_WALL_HP: i32 = 3;
+const VAULT_MARGIN: f32 = 5.0;
+// maze cells are this many tiles across, counting one wall
+const MAZE_CELL: isize = 3;
+const MAZE_CELLS: isize = 5;
+const PILLAR_SPACING: usize = 4;
+const DONUT_HOLE: isize = 4;
+// gauntlets are long and narrow, with hazards sweeping across
+const GAUNTLET_SIZE: (f32, f32) = (14.0, 40.0);
+const GAUNTLET_LANES: isize = 6;
+// foundries have a lava pool this many tiles out from the middle, with belts running past it
+const FOUNDRY_SIZE: f32 = 24.0;
+const FOUNDRY_POOL: isize = 2;
+const FOUNDRY_BELT_GAP: isize = 2;
+const FOUNDRY_BELT_REACH: isize = 8;
+const FOUNDRY_TAR: usize = 6;
+// presses are split down the middle by a wall with a sliding door,
+// with crushers sweeping the lanes above and below it
+const PRESS_SIZE: f32 = 24.0;
+const PRESS_LANES: [isize; 2] = [-7, 5];
+const PRESS_REACH: isize = 9;
+const PRESS_WALL: isize = 5;
+const PRESS_STEP: Duration = Duration::from_millis(200);
+const PRESS_WAIT: usize = 6;
+const DOOR_STEP: Duration = Duration::from_millis(300);
+const DOOR_WAIT: usize = 10;
+// wrap rooms are lined with portals to the opposite side,
+// except this far either way of the middle, where the halls come in
+const WRAP_SIZE: f32 = 20.0;
+const WRAP_GAP: isize = 4;
+// one in this many procedural rooms is dark, save for around the snake and its fireballs
+const DARK_ODDS: u32 = 5;
+// one in this many procedural rooms offers a risky way out besides the usual one
+const BRANCH_ODDS: u32 = 3;
+// a risky room is as hard as one this much deeper, but always has a pickup
+const RISK_DEPTH: usize = 4;
+// procedural rooms past this many get the tense music, like risky ones always do
+const TENSE_DEPTH: usize = 5;
+// procedural rooms past this many are drawn in a gloomier palette, unless they pick their own
+const DEEP_DEPTH: usize = 12;
+const DEEP_PALETTE: &str = "dark-pastel";
+// endless mode's hazards sweep this many times as fast, and waves and obstacles come on as much quicker
+const FAST_ENEMIES: f32 = 2.0;
+// the rewards for clearing a room show up this far to either side of its center
+const REWARD_SPREAD: f32 = 4.0;
+const REWARD_LABEL_OFFSET: Vec3 = Vec3 { x: 0.5, y: -0.6, z: 0.0 };
+const CARD_SCALE: f32 = 1.0 / 28.0;
+const REWARD_LABEL_SCALE: f32 = 1.0 / 40.0;
 
 pub enum _RoomType {
     Spawn,
@@ -28,36 +109,112 @@ pub enum _RoomType {
 
 type Hall = Box<Room>;
 
+/// what lies down a hall
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Path {
+    #[default]
+    Safe,
+    Risky,
+}
+
+/// how the snake did in a room, shown once it's cleared
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stats {
+    /// spent inside of the room
+    pub time: Duration,
+    pub fruits: u32,
+    /// taken while shielded, any other hit would've been the end
+    pub hits: u32,
+}
+
+// what a room listens to while it's going on
+struct Watch {
+    fruits: Receiver<()>,
+    hits: Receiver<()>,
+}
+
+/// what the collision overlay outlines a rect as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outline {
+    /// the outer edge of a room's or a hall's walls
+    Bounds,
+    /// where a wall gets broken through, or was
+    Opening,
+}
+
+/// a way out besides the room's main hall
+/// only one exit gets taken, the rest are walled back up
+struct Branch {
+    hall: Hall,
+    direction: Direction,
+    width: f32,
+    path: Path,
+}
+
 pub struct Room {
-    snake_id: EntityId,
+    // how many rooms came before this one
+    depth: usize,
+    budgets: Vec<(Budget, usize)>,
 
     position: Vec2,
     dimensions: Scale,
     parts: Vec<EntityId>,
+    // where nothing random gets placed, in world space
+    keep_out: Vec<Rect>,
 
     last_hall: Option<Hall>,
     hall: Option<Hall>,
     hall_open: bool,
     hall_direction: Direction,
     hall_width: f32,
+    // where the main hall leads
+    path: Path,
+    branches: Vec<Branch>,
+    // exits that were walled back up once another one got taken
+    skipped: Vec<Direction>,
+    // the room came down a risky path, so it's tuned as if it was deeper
+    risk: usize,
+    // only what's near a light can be seen
+    dark: bool,
+    // drawn in this instead of the player's palette
+    palette: Option<&'static str>,
+    // the side the snake came in through, and how wide the hole is
+    entrance: Option<(Direction, f32)>,
+    stats: Stats,
+    // until the room is cleared
+    watch: Option<Watch>,
+    // up for grabs once the room is cleared, taking one takes the rest away
+    rewards: Vec<EntityId>,
 }
 
 impl Room {
-    fn new(man: &mut EntityManager, position: Vec2, dimensions: Scale, snake_id: EntityId) -> Self {
+    fn new(man: &mut EntityManager, position: Vec2, dimensions: Scale) -> Self {
         let dimensions = dimensions + Vec2::diagonal(2.0);
 
         let mut this = Self {
-            snake_id,
+            depth: 0,
+            budgets: DEFAULT_BUDGETS.to_vec(),
 
             position,
             dimensions,
             parts: Vec::new(),
+            keep_out: Vec::new(),
 
             last_hall: None,
             hall: None,
             hall_open: false,
             hall_direction: Direction::default(),
             hall_width: 0.0,
+            path: Path::default(),
+            branches: Vec::new(),
+            skipped: Vec::new(),
+            risk: 0,
+            dark: false,
+            palette: None,
+            entrance: None,
+            stats: Stats::default(),
+            watch: None,
+            rewards: Vec::new(),
         };
 
         // wall it off
@@ -81,7 +238,7 @@ impl Room {
         };
 
         let d = Vec2::from(next_room_at);
-        (pos + 0.5 * d * (dim + next_room_dim)).floor()
+        TileCoord::of(pos + 0.5 * d * (dim + next_room_dim)).into()
     }
 
     /// give hallway and snake into self
@@ -97,6 +254,20 @@ impl Room {
         width: usize,
         length: usize,
     ) {
+        let (hall, width) = self.build_hall(man, direction, width, length);
+        self.hall = Some(hall);
+        self.hall_direction = direction;
+        self.hall_width = width;
+    }
+
+    /// returns the hall along with its width, walls included
+    fn build_hall(
+        &self,
+        man: &mut EntityManager,
+        direction: Direction,
+        width: usize,
+        length: usize,
+    ) -> (Hall, f32) {
         // add walls
         let width = (width + 2) as f32;
         let length = length as f32;
@@ -125,21 +296,49 @@ impl Room {
             _ => panic!(),
         };
 
-        let hall = Self::new(man, pos, dim, self.snake_id);
-        self.hall = Some(Box::new(hall));
-        self.hall_direction = direction;
-        self.hall_width = width;
+        let mut hall = Self::new(man, pos, dim);
+        hall.annotate(man, Vec2::default(), Annotation::CameraHint { zoom: HALL_ZOOM });
+        (Box::new(hall), width)
     }
 
-    /// breaks wall, optionally putting triggers in its place
-    fn break_wall(
-        &mut self,
-        man: &mut EntityManager,
-        side: Direction,
-        hole_size: f32,
-        tx: Option<Sender<()>>,
-    ) {
-        let (xs, xe, ys, ye) = match side {
+    /// a risky way out, on a side that doesn't have one yet
+    /// marked with glitching text so the player knows what they're getting into
+    fn add_branch(&mut self, man: &mut EntityManager, entrance: Direction) {
+        let taken: Vec<_> = std::iter::once(entrance)
+            .chain(std::iter::once(self.hall_direction))
+            .chain(self.branches.iter().map(|b| b.direction))
+            .collect();
+        let free: Vec<_> = [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+            .into_iter()
+            .filter(|d| !taken.contains(d))
+            .collect();
+        if free.is_empty() {
+            return;
+        }
+
+        let mut rng = rng::get(Stream::World);
+        let direction = free[rng.gen_range(0..free.len())];
+        let width = self::roll_even(&mut rng, &config::get().hall_width);
+        let length = self::roll_even(&mut rng, &config::get().hall_length);
+        let (hall, width) = self.build_hall(man, direction, width, length);
+        self.branches.push(Branch {
+            hall,
+            direction,
+            width,
+            path: Path::Risky,
+        });
+
+        // just inside of where the hole will be
+        let inside = 0.5 * self.dimensions - Vec2::diagonal(4.0);
+        let pos = Vec2::from(direction) * inside;
+        let txt = self.text_at(man, TextNames::BossGlitch, pos, 1.0 / 28.0);
+        text::enable_glitching(man, txt);
+    }
+
+    /// the tiles a hole of the given size takes up in a wall
+    /// as (x start, x end, y start, y end), inclusive
+    fn hole(&self, side: Direction, hole_size: f32) -> (f32, f32, f32, f32) {
+        match side {
             Direction::Up => {
                 let y = self.position.y - 0.5 * self.dimensions.y;
                 let xs = self.position.x - 0.5 * hole_size;
@@ -165,9 +364,21 @@ impl Room {
                 (x, x, ys, ye)
             }
             _ => panic!(),
-        };
+        }
+    }
+
+    /// breaks wall, optionally putting triggers in its place
+    fn break_wall(
+        &mut self,
+        man: &mut EntityManager,
+        side: Direction,
+        hole_size: f32,
+        tx: Option<Sender<()>>,
+    ) {
+        let (xs, xe, ys, ye) = self.hole(side, hole_size);
 
         let mut triggers = Vec::new();
+        let mut broken = Vec::new();
         for &id in &self.parts {
             if let Some(mut wall) = man.view(id) {
                 if wall.which() != Entities::Wall {
@@ -177,6 +388,7 @@ impl Room {
 
                 if xs <= pos.x && pos.x <= xe && ys <= pos.y && pos.y <= ye {
                     wall.kill();
+                    broken.push(id);
 
                     if let Some(tx) = tx.clone() {
                         let t = archetype::trigger::new(
@@ -191,39 +403,90 @@ impl Room {
             }
         }
 
+        // the kills only go through next tick, so forget the walls now
+        self.parts.retain(|id| !broken.contains(id));
         for t in triggers {
             self.parts.push(t);
         }
+        self.bake_scenery(man);
+    }
+
+    /// puts the wall back up where a hole was broken through it
+    fn seal(&mut self, man: &mut EntityManager, side: Direction, hole_size: f32) {
+        let (xs, xe, ys, ye) = self.hole(side, hole_size);
+        let in_hole = |p: Vec3| xs <= p.x && p.x <= xe && ys <= p.y && p.y <= ye;
+
+        let mut triggers = Vec::new();
+        for &id in &self.parts {
+            if let Some(trigger) = man.view(id) {
+                if trigger.which() == Entities::Trigger && in_hole(trigger.get_position()) {
+                    triggers.push(id);
+                }
+            }
+        }
+        self.parts.retain(|id| !triggers.contains(id));
+        for id in triggers {
+            man.kill(id);
+        }
+
+        let walls = (ys as isize..=ye as isize)
+            .flat_map(|y| (xs as isize..=xe as isize).map(move |x| Vec3::new(x as f32, y as f32, WALL_DEPTH)));
+        self.parts.extend(archetype::wall::batch(man, walls));
+        self.bake_scenery(man);
     }
 
-    /// returns two trigger listeners
+    /// opens a hall all the way through
+    /// returns the triggers for entering it and leaving it
+    fn open_exit(&mut self, man: &mut EntityManager, hall: &mut Room, direction: Direction, width: f32) -> (Receiver<()>, Receiver<()>) {
+        let (tx_near, rx_near) = mpsc::channel();
+        let (tx_far, rx_far) = mpsc::channel();
+
+        hall.break_wall(man, direction, width, Some(tx_far));
+        hall.break_wall(man, direction.reverse(), width, None);
+        self.break_wall(man, direction, width, Some(tx_near));
+
+        (rx_near, rx_far)
+    }
+
+    /// returns two trigger listeners for every exit, the main hall's first
     /// the first triggers when the player leaves the room and enters the hallway
     /// the second triggers when the player is about to leave the hallway and enter the next room
-    pub fn open_hallway(&mut self, man: &mut EntityManager) -> Option<(Receiver<()>, Receiver<()>)> {
+    /// once a hallway is entered, see [`Self::take_exit`]
+    pub fn open_hallway(&mut self, man: &mut EntityManager) -> Option<Vec<(Receiver<()>, Receiver<()>)>> {
         if self.hall_open { return None; }
         self.hall_open = true;
-        let hall = self.hall.as_mut().expect("should have hallway");
 
         archetype::oneshot::play_sound(man, Sounds::RoomUnlocked);
 
-        let (tx_near, rx_near) = mpsc::channel();
-        let (tx_far, rx_far) = mpsc::channel();
+        let mut hall = self.hall.take().expect("should have hallway");
+        let mut exits = vec![self.open_exit(man, &mut hall, self.hall_direction, self.hall_width)];
+        self.hall = Some(hall);
 
-        hall.break_wall(
-            man,
-            self.hall_direction,
-            self.hall_width,
-            Some(tx_far.clone()),
-        );
-        hall.break_wall(man, self.hall_direction.reverse(), self.hall_width, None);
-        self.break_wall(
-            man,
-            self.hall_direction,
-            self.hall_width,
-            Some(tx_near.clone()),
-        );
+        let mut branches = std::mem::take(&mut self.branches);
+        for branch in &mut branches {
+            exits.push(self.open_exit(man, &mut branch.hall, branch.direction, branch.width));
+        }
+        self.branches = branches;
 
-        Some((rx_near, rx_far))
+        Some(exits)
+    }
+
+    /// commits to one of the exits, in the order [`Self::open_hallway`] returned them
+    /// the others get walled back up
+    pub fn take_exit(&mut self, man: &mut EntityManager, exit: usize) {
+        if exit > 0 {
+            let branch = &mut self.branches[exit - 1];
+            swap(self.hall.as_mut().expect("should have hallway"), &mut branch.hall);
+            swap(&mut self.hall_direction, &mut branch.direction);
+            swap(&mut self.hall_width, &mut branch.width);
+            swap(&mut self.path, &mut branch.path);
+        }
+
+        for mut branch in std::mem::take(&mut self.branches) {
+            self.seal(man, branch.direction, branch.width);
+            branch.hall.destroy(man);
+            self.skipped.push(branch.direction);
+        }
     }
 
     pub fn redraw_walls_and_bg(&mut self, man: &mut EntityManager) {
@@ -248,6 +511,7 @@ impl Room {
 
         let width = self.dimensions.x as usize;
         let height = self.dimensions.y as usize;
+        let mut walls = Vec::new();
         for y in 0..height {
             for x in 0..width {
                 if !(y == 0 || y == height - 1 || x == 0 || x == width - 1) {
@@ -256,59 +520,62 @@ impl Room {
 
                 let room_pos = Vec4::new(x as f32, y as f32, 0.0, 1.0);
                 let world_pos4 = room_to_world * room_pos;
-                let p = Position::new(world_pos4.x, world_pos4.y, WALL_DEPTH);
-
-                let wall = archetype::wall::new(man, p);
-                new_parts.push(wall);
+                walls.push(Position::new(world_pos4.x, world_pos4.y, WALL_DEPTH));
             }
         }
+        new_parts.extend(archetype::wall::batch(man, walls));
 
         std::mem::swap(&mut new_parts, &mut self.parts);
         for part in new_parts {
             man.kill(part);
         }
+        self.bake_scenery(man);
     }
 
-    pub fn close_hall_entrance(&mut self, man: &mut EntityManager) {
-        let side = self.hall_direction;
-        if let Some(hall) = &mut self.hall {
-            let mut xs = f32::MAX;
-            let mut xe = f32::MIN;
-            let mut ys = f32::MAX;
-            let mut ye = f32::MIN;
-
-            let mut triggers = Vec::new();
-            for (idx, &ent) in hall.parts.iter().enumerate() {
-                if let Some(trigger) = man.view(ent) {
-                    if trigger.which() != Entities::Trigger {
-                        continue;
-                    }
+    /// walls and background never move, so they're drawn from static batches
+    /// has to be redone whenever a wall comes or goes
+    fn bake_scenery(&mut self, man: &mut EntityManager) {
+        let mut walls = Vec::new();
+        let mut background = Vec::new();
+        let mut stale = Vec::new();
+        for &id in &self.parts {
+            let Some(part) = man.view(id) else {
+                continue;
+            };
+            match part.which() {
+                Entities::Wall if !archetype::wall::is_cracked(&part) => walls.push(archetype::wall::tile(&part)),
+                Entities::Background => background.push(archetype::background::tile(&part)),
+                Entities::Scenery => stale.push(id),
+                _ => (),
+            }
+        }
 
-                    triggers.push(idx);
-                    let pos = trigger.get_position();
-                    xs = xs.min(pos.x);
-                    xe = xe.max(pos.x);
+        self.parts.retain(|id| !stale.contains(id));
+        for id in stale {
+            man.kill(id);
+        }
 
-                    ys = ys.min(pos.y);
-                    ye = ye.max(pos.y);
-                }
-            }
+        let walls = archetype::scenery::new(man, walls, PaletteKey::Wall);
+        let background = archetype::scenery::new(man, background, PaletteKey::Background);
+        self.parts.push(walls);
+        self.parts.push(background);
+    }
 
-            // purge triggers
-            for idx in triggers.into_iter().rev() {
-                let id = hall.parts.swap_remove(idx);
-                man.kill(id);
-            }
+    /// walls the way in back up
+    /// returns false if it's already been walled up, or there never was one
+    pub fn close_hall_entrance(&mut self, man: &mut EntityManager) -> bool {
+        let Some((side, width)) = self.entrance.take() else {
+            return false;
+        };
+        self.seal(man, side, width);
+        true
+    }
 
-            // make walls in their place
-            for y in ys as isize..=ye as isize {
-                let y = y as f32;
-                for x in xs as isize..=xs as isize {
-                    let x = x as f32;
-                    archetype::wall::new(man, Vec3::new(x, y, WALL_DEPTH));
-                }
-            }
-        }
+    /// whether the whole snake made it past the room's walls
+    pub fn holds_snake(&self, man: &EntityManager) -> bool {
+        man.query(&[Components::Position])
+            .filter(|e| matches!(e.which(), Entities::SnakeHead | Entities::SnakeBody))
+            .all(|e| self.contains(e.get_position().into()))
     }
 
     pub fn destroy(&mut self, man: &mut EntityManager) {
@@ -320,6 +587,10 @@ impl Room {
             hall.destroy(man);
         }
 
+        for branch in &mut self.branches {
+            branch.hall.destroy(man);
+        }
+
         if let Some(hall) = &mut self.last_hall {
             hall.destroy(man);
         }
@@ -337,7 +608,7 @@ impl Room {
             // let dim = hall.dimensions;
             // let dim = dim.x.max(dim.y);
             // Mat4::screen(hall.position, dim, dim)
-            Mat4::scale((1.10).into()) * hall.view()
+            Mat4::scale(HALL_ZOOM.into()) * hall.view()
         } else {
             panic!()
         }
@@ -405,34 +676,67 @@ impl Room {
         ))
     }
 
+    fn label_at(&mut self, man: &mut EntityManager, text: &str, position: Vec2, scale: f32) -> EntityId {
+        let label = archetype::label::new(man, text, self.position + position, scale).expect("bad label");
+        self.parts.push(label);
+        label
+    }
+
+    /// caps on spammable entities while the room is active
+    pub fn budgets(&self) -> &[(Budget, usize)] {
+        &self.budgets
+    }
+
+    fn set_budget(&mut self, budget: Budget, limit: usize) {
+        if let Some(b) = self.budgets.iter_mut().find(|(b, _)| *b == budget) {
+            b.1 = limit;
+        }
+    }
+
     /// generate a random position in the room (in world space coordinates)
     pub fn random_position(&self) -> Vec2 {
-        Self::make_random_gen(&self)(Vec2::diagonal(0.5))
+        Self::make_random_gen(self)(Vec2::diagonal(0.5))
     }
 
     fn make_random_gen(&self) -> impl Fn(Vec2) -> Vec2 {
         let dimensions = self.dimensions;
         let position = self.position;
+        let keep_out = self.keep_out.clone();
         move |v| {
-            let mut rng = thread_rng();
+            let mut rng = rng::get(Stream::World);
             loop {
                 // let x = (0.5 * rng.gen_range(1.0..dimensions.x - 1.0)).floor();
                 // let y = (0.5 * rng.gen_range(1.0..dimensions.y - 1.0)).floor();
                 // let next = position - Vec2::new(x, y);
                 let dx = (dimensions.x - 4.0) * 0.5;
                 let dy = (dimensions.y - 4.0) * 0.5;
-                let x = rng.gen_range(-dx..dx).floor();
-                let y = rng.gen_range(-dy..dy).floor();
-                let next = position - Vec2::new(x,y);
+                let offset = TileCoord::of(Vec2::new(rng.gen_range(-dx..dx), rng.gen_range(-dy..dy)));
+                let next = Vec2::from(TileCoord::of(position) - offset);
 
-
-                if !v.eq(next) {
+                if TileCoord::of(v) != TileCoord::of(next) && !keep_out.iter().any(|r| r.contains(next)) {
                     break next;
                 }
             }
         }
     }
 
+    /// places an annotation in room-space coordinates
+    pub fn annotate(&mut self, man: &mut EntityManager, position: Vec2, annotation: Annotation) -> EntityId {
+        let id = annotation::new(man, self.position + position, annotation);
+        self.parts.push(id);
+        id
+    }
+
+    /// all of the room's annotations along with their world space positions, for the collision overlay
+    pub fn annotations(&self, man: &EntityManager) -> Vec<(Vec2, Annotation)> {
+        self.parts
+            .iter()
+            .filter_map(|&id| man.view(id))
+            .filter(|e| e.which() == Entities::Annotation)
+            .map(|e| (e.get_position().into(), annotation::get(&e)))
+            .collect()
+    }
+
     pub fn add_logic(&mut self, man: &mut EntityManager, on_tick: impl FnMut(Duration) + 'static) {
         let logic = logic::new(man, Box::new(on_tick));
         self.parts.push(logic);
@@ -441,12 +745,33 @@ impl Room {
         self.position
     }
 
+    pub fn is_dark(&self) -> bool {
+        self.dark
+    }
+
+    /// by name, if the room wants a palette of its own
+    pub fn palette(&self) -> Option<&'static str> {
+        self.palette
+    }
+
+    /// rooms that don't fit on screen follow the snake around
+    pub fn camera_mode(&self) -> Mode {
+        if self.dimensions.x.max(self.dimensions.y) > camera::MAX_FIXED_SIZE {
+            Mode::Follow {
+                window: camera::FOLLOW_WINDOW,
+                deadzone: camera::FOLLOW_DEADZONE,
+            }
+        } else {
+            Mode::Fixed
+        }
+    }
+
     // Room types
-    fn empty(man: &mut EntityManager, position: Vec2, side: Direction, dimensions: Scale, snake_id: EntityId) -> Self {
-        let mut ret = Self::new(man, position, dimensions, snake_id);
-        let mut rng = thread_rng();
-        let width = rng.gen_range(1..4) * 2;
-        let length = rng.gen_range(5..=10) * 2;
+    fn empty(man: &mut EntityManager, position: Vec2, side: Direction, dimensions: Scale) -> Self {
+        let mut ret = Self::new(man, position, dimensions);
+        let mut rng = rng::get(Stream::World);
+        let width = self::roll_even(&mut rng, &config::get().hall_width);
+        let length = self::roll_even(&mut rng, &config::get().hall_length);
         ret.make_hall(man, side, width, length);
         ret
     }
@@ -459,8 +784,18 @@ impl Room {
                 break rand;
             }
         };
-        let mut ret = Self::empty(man, next_pos, rand_side, dimensions, last.snake_id);
+        let mut ret = Self::empty(man, next_pos, rand_side, dimensions);
+        ret.depth = last.depth + 1;
+        if last.path == Path::Risky {
+            ret.risk = RISK_DEPTH;
+        }
         ret.break_wall(man, last.hall_direction.reverse(), last.hall_width, None);
+        ret.entrance = Some((last.hall_direction.reverse(), last.hall_width));
+        let snek = man.find_by_tag(snake::TAG);
+        ret.watch = Some(Watch {
+            fruits: man.events().subscribe(EventKind::FruitEaten, None),
+            hits: man.events().subscribe(EventKind::SnakeHurt, snek),
+        });
         ret
     }
 
@@ -470,14 +805,19 @@ impl Room {
             Vec2::new(0.0, 0.0),
             Direction::random(),
             Vec2::diagonal(20.0),
-            0,
         );
 
-        let snake_position =ret.random_position();
-        let snek = snake::new(man, snake_position);
-        ret.snake_id = snek;
+        let snake_position = ret.random_position() - ret.position;
+        ret.annotate(man, snake_position, Annotation::SpawnPoint("snake"));
+        ret.annotate(
+            man,
+            Vec2::default(),
+            Annotation::Note("the hall opens 3s after the snake's first move".into()),
+        );
+
+        let snek = snake::new(man, ret.position + snake_position);
 
-        let snek_move_rx = snake::make_move_trigger(man, snek);
+        let snek_move_rx = man.events().subscribe(EventKind::SnakeMoved, Some(snek));
 
         ret.text_at(
             man,
@@ -518,6 +858,30 @@ impl Room {
         (ret, rx_hall)
     }
 
+    /// a bare room standing in for the first few rooms of the tutorial, with its hall open from the start
+    /// the snake's caught up on whatever it would've picked up in the rooms that got skipped
+    pub fn shortcut(man: &mut EntityManager, skipped: usize) -> (Self, Receiver<()>) {
+        let mut ret = Self::empty(
+            man,
+            Vec2::new(0.0, 0.0),
+            Direction::random(),
+            Vec2::diagonal(20.0),
+        );
+        ret.depth = skipped;
+
+        let snake_position = ret.random_position();
+        ret.annotate(man, snake_position - ret.position, Annotation::SpawnPoint("snake"));
+        let snek = snake::new(man, snake_position);
+
+        // the fruit room comes first, then the attack room
+        let fruits = if skipped > 0 { TUT_FRUITS } else { 0 };
+        snake::head_start(man, snek, fruits, skipped > 1);
+
+        let (tx_hall, rx_hall) = mpsc::channel();
+        let _ = tx_hall.send(());
+        (ret, rx_hall)
+    }
+
     pub fn tut_fruit(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
         let mut ret = Self::next(man, last, Vec2::new(20.0, 20.0));
         let fruit_txt = ret.text_at(
@@ -530,9 +894,9 @@ impl Room {
             .text_after(man, fruit_txt, TextNames::FruitGlitch)
             .unwrap();
 
-        let fruit_id = fruit::bounded(man, ret.make_random_gen(), 2);
-        let on_eat = fruit::make_eaten_trigger(man, fruit_id);
-        let on_kill = fruit::make_kill_trigger(man, fruit_id);
+        let fruit_id = fruit::bounded(man, ret.make_random_gen(), TUT_FRUITS - 1);
+        let on_eat = man.events().subscribe(EventKind::FruitEaten, Some(fruit_id));
+        let on_kill = man.events().subscribe(EventKind::FruitDepleted, Some(fruit_id));
         text::add_glitch_trigger(man, fruit_glitch_txt, on_eat);
 
         (ret, on_kill)
@@ -572,8 +936,11 @@ impl Room {
         
         let (enable_attack, ea_rx) = mpsc::channel();
         let _ = enable_attack.send(());
-        snake::add_attack_enable_trigger(man, ret.snake_id, ea_rx);
-        let rx = snake::make_attack_trigger(man, ret.snake_id);
+        let snek = man.find_by_tag(snake::TAG);
+        if let Some(snek) = snek {
+            snake::add_attack_enable_trigger(man, snek, ea_rx);
+        }
+        let rx = man.events().subscribe(EventKind::SnakeAttacked, snek);
 
         (ret, rx)
     }
@@ -582,12 +949,13 @@ impl Room {
         let mut ret = Self::next(man, last, Vec2::new(20.0, 20.0));
         let enemy_pos = ret.random_position();
         let enemy = enemy::unshield_enemy(man, enemy_pos);
-        let rx = enemy::make_kill_trigger(man, enemy);
+        let rx = man.events().subscribe(EventKind::EnemyDied, Some(enemy));
 
         let enemy_txt = ret.text_at(man, TextNames::Enemy, Vec2::new(-ret.dimensions.x / 10.0, ret.dimensions.y / 4.0), 1.0 / 28.0);
         let enemy_glitch_txt = ret.text_after(man, enemy_txt, TextNames::EnemyGlitch).unwrap();
         
-        let glitch_trigger = snake::make_attack_trigger(man, ret.snake_id);
+        let snek = man.find_by_tag(snake::TAG);
+        let glitch_trigger = man.events().subscribe(EventKind::SnakeAttacked, snek);
         text::add_glitch_trigger(man, enemy_glitch_txt, glitch_trigger);
 
         (ret, rx)
@@ -597,147 +965,719 @@ impl Room {
         let mut ret = Self::next(man, last, Vec2::new(20.0, 20.0));
         let enemy_pos = ret.random_position();
         let enemy = enemy::new(man, enemy_pos, 3);
-        let rx = enemy::make_kill_trigger(man, enemy);
+        let rx = man.events().subscribe(EventKind::EnemyDied, Some(enemy));
 
         let shield_glitch_txt = ret.text_at(man, TextNames::ShieldGlitch, Vec2::new(-ret.dimensions.x / 2.85, ret.dimensions.y / 4.0), 1.0 / 28.0);
         ret.text_after(man, shield_glitch_txt, TextNames::Shield).unwrap();
         
-        let glitch_trigger = snake::make_attack_trigger(man, ret.snake_id);
+        let snek = man.find_by_tag(snake::TAG);
+        let glitch_trigger = man.events().subscribe(EventKind::SnakeAttacked, snek);
         text::add_glitch_trigger(man, shield_glitch_txt, glitch_trigger);
 
         (ret, rx)
     }
 
     fn proc_next(man: &mut EntityManager, last: &Room) -> Self {
-        // let mut rng = thread_rng();
-        // let width = rng.gen_range(8..=40) as f32;
-        // let height = rng.gen_range(8..=40) as f32;
-        // let dimensions = Vec2::new(width, height);
-        Self::next(man, last, Vec2::diagonal(20.0))
+        let mut rng = rng::get(Stream::World);
+        let sizes = &config::get().room_size;
+        let width = self::roll_even(&mut rng, sizes) as f32;
+        let height = self::roll_even(&mut rng, sizes) as f32;
+        let mut ret = Self::next(man, last, Vec2::new(width, height));
+        if rng.gen_ratio(1, VAULT_ODDS) {
+            ret.add_vault(man);
+        }
+        ret
+    }
+
+    /// a pickup walled in by cracked walls, for strong fireballs to break into
+    /// added before anything else so fruits and enemies stay out of it
+    fn add_vault(&mut self, man: &mut EntityManager) {
+        let mut rng = rng::get(Stream::World);
+        // far enough from the edges that the snake doesn't walk in on it
+        let dx = 0.5 * self.dimensions.x - VAULT_MARGIN;
+        let dy = 0.5 * self.dimensions.y - VAULT_MARGIN;
+        let center = TileCoord::of(self.position + Vec2::new(rng.gen_range(-dx..dx), rng.gen_range(-dy..dy)));
+
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let pos = Vec2::from(center + TileCoord::new(x, y));
+                let part = if x == 0 && y == 0 {
+                    pickup::new(man, Pickup::roll(&mut rng), pos)
+                } else {
+                    archetype::wall::cracked(man, Position::new(pos.x, pos.y, WALL_DEPTH), VAULT_WALL_HP)
+                };
+                self.parts.push(part);
+            }
+        }
+
+        let center = Vec2::from(center);
+        let keep_out = Rect::new(center - Vec2::diagonal(1.0), center + Vec2::diagonal(2.0));
+        self.keep_out.push(keep_out);
+    }
+
+    /// scales procedural rooms with the number of them that came before
+    pub fn music(&self) -> Music {
+        if self.risk > 0 || self.dark || self.depth > TUTORIAL_ROOMS + TENSE_DEPTH {
+            Music::Tense
+        } else {
+            Music::Calm
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::at(self.depth.saturating_sub(TUTORIAL_ROOMS) + self.risk)
+    }
+
+    /// puts interior walls down at every tile, in room-space coordinates
+    /// nothing random gets placed on them afterwards
+    fn stamp(&mut self, man: &mut EntityManager, tiles: impl IntoIterator<Item = Vec2>) {
+        let mut walls = Vec::new();
+        for tile in tiles {
+            let pos = self.position + tile;
+            walls.push(Position::new(pos.x, pos.y, WALL_DEPTH));
+            self.keep_out.push(Rect::new(pos, pos));
+        }
+        self.parts.extend(archetype::wall::batch(man, walls));
+        self.bake_scenery(man);
+    }
+
+    /// floor tiles in room space coordinates, skipping any that are already taken
+    /// nothing else spawns on them afterwards
+    fn lay(&mut self, man: &mut EntityManager, kind: Kind, tiles: impl IntoIterator<Item = Vec2>) {
+        for tile in tiles {
+            let pos = self.position + tile;
+            if self.keep_out.iter().any(|r| r.contains(pos)) {
+                continue;
+            }
+
+            let id = terrain::new(man, kind, Position::new(pos.x, pos.y, TERRAIN_DEPTH));
+            self.parts.push(id);
+            self.keep_out.push(Rect::new(pos, pos));
+        }
+    }
+
+    /// a moving wall, with its path in room space coordinates
+    /// nothing else spawns anywhere along it
+    fn add_obstacle(&mut self, man: &mut EntityManager, path: Vec<Vec2>, step: Duration) {
+        let path: Vec<_> = path.into_iter().map(|p| self.position + p).collect();
+        self.keep_out.extend(path.iter().map(|&p| Rect::new(p, p)));
+        let id = obstacle::new(man, path, WALL_DEPTH, step);
+        self.parts.push(id);
+    }
+
+    /// two portals into each other, in room space coordinates
+    /// facing is the way into each one
+    fn add_portals(&mut self, man: &mut EntityManager, (a, a_facing): (Vec2, Direction), (b, b_facing): (Vec2, Direction)) {
+        let a = self.position + a;
+        let b = self.position + b;
+        let pa = portal::new(man, Position::new(a.x, a.y, TERRAIN_DEPTH), Some(a_facing));
+        let pb = portal::new(man, Position::new(b.x, b.y, TERRAIN_DEPTH), Some(b_facing));
+        portal::link(man, pa, pb);
+
+        self.parts.extend([pa, pb]);
+        self.keep_out.extend([Rect::new(a, a), Rect::new(b, b)]);
+    }
+
+    /// whether p is inside of the room's walls, in world space
+    pub fn contains(&self, p: Vec2) -> bool {
+        let half = 0.5 * self.dimensions;
+        Rect::new(self.position - half + Vec2::diagonal(1.0), self.position + half - Vec2::diagonal(2.0)).contains(p)
+    }
+
+    /// the room's and its halls' walls, and every hole [`Self::break_wall`] makes in them
+    /// in tile space, for seeing where the wall math actually lands
+    pub fn outlines(&self) -> Vec<(Outline, Rect)> {
+        let hole = |room: &Room, side: Direction, width: f32| {
+            let (xs, xe, ys, ye) = room.hole(side, width);
+            // the hole's range is inclusive
+            (Outline::Opening, Rect::new(Vec2::new(xs, ys), Vec2::new(xe + 1.0, ye + 1.0)))
+        };
+        let bounds = |room: &Room| {
+            let half = 0.5 * room.dimensions;
+            (Outline::Bounds, Rect::new(room.position - half, room.position + half))
+        };
+
+        let mut ret = vec![bounds(self)];
+        if let Some((side, width)) = self.entrance {
+            ret.push(hole(self, side, width));
+        }
+
+        let exits = self
+            .hall
+            .iter()
+            .map(|hall| (hall, self.hall_direction, self.hall_width))
+            .chain(self.branches.iter().map(|b| (&b.hall, b.direction, b.width)));
+        for (hall, side, width) in exits {
+            ret.extend([
+                hole(self, side, width),
+                bounds(hall),
+                hole(hall, side, width),
+                hole(hall, side.reverse(), width),
+            ]);
+        }
+
+        if let Some(hall) = &self.last_hall {
+            ret.push(bounds(hall));
+            if let Some((side, width)) = self.entrance {
+                ret.extend([hole(hall, side, width), hole(hall, side.reverse(), width)]);
+            }
+        }
+
+        ret
+    }
+
+    /// stops or restarts everything in the room, including the hall that leads to it
+    pub fn freeze(&self, man: &mut EntityManager, frozen: bool) {
+        for &id in &self.parts {
+            man.freeze(id, frozen);
+        }
+        if let Some(hall) = &self.last_hall {
+            hall.freeze(man, frozen);
+        }
+    }
+
+    /// forgets parts that died since the last update, like broken walls
+    pub fn update(&mut self, man: &EntityManager) {
+        self.parts.retain(|&id| man.view(id).is_some());
+        if let Some(hall) = &mut self.hall {
+            hall.update(man);
+        }
+        for branch in &mut self.branches {
+            branch.hall.update(man);
+        }
+    }
+
+    /// counts towards the room's stats until it's cleared
+    pub fn track(&mut self, man: &EntityManager, dt: Duration) {
+        let Some(watch) = &self.watch else {
+            return;
+        };
+
+        self.stats.fruits += watch.fruits.try_iter().count() as u32;
+        self.stats.hits += watch.hits.try_iter().count() as u32;
+        let inside = man
+            .find_by_tag(snake::TAG)
+            .and_then(|s| man.view(s))
+            .is_some_and(|s| self.contains(s.get_position().into()));
+        if inside {
+            self.stats.time += dt;
+        }
+    }
+
+    /// shows how the room went and puts a choice of rewards down
+    /// returns the trigger for when one gets taken
+    /// none for the tutorial, which doesn't hand out rewards
+    pub fn finish(&mut self, man: &mut EntityManager) -> Option<Receiver<()>> {
+        self.watch = None;
+        if self.depth <= TUTORIAL_ROOMS {
+            return None;
+        }
+
+        let Stats { time, fruits, hits } = self.stats;
+        let card = format!("CLEARED IN {}S\nFRUITS {fruits}\nHITS {hits}\nPICK ONE", time.as_secs());
+        self.label_at(man, &card, Vec2::new(0.0, -REWARD_SPREAD), CARD_SCALE);
+
+        let choices = [(Pickup::Growth, "LONGER", -1.0), (Pickup::Focus, "FASTER", 1.0)];
+        let mut taken = Vec::new();
+        for (kind, name, side) in choices {
+            let pos = self.position + Vec2::new(side * REWARD_SPREAD, 0.0);
+            let reward = pickup::new(man, kind, pos);
+            let label = archetype::label::new(man, name, pos, REWARD_LABEL_SCALE).expect("bad label");
+            man.attach(label, reward, REWARD_LABEL_OFFSET);
+            taken.push(man.events().subscribe(EventKind::PickupCollected, Some(reward)));
+            self.rewards.push(reward);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        self.add_logic(man, move |_| {
+            if taken.iter().any(|t| t.try_recv().is_ok()) {
+                let _ = tx.send(());
+            }
+        });
+        Some(rx)
+    }
+
+    /// clears away whatever rewards weren't taken
+    pub fn claim_reward(&mut self, man: &mut EntityManager) {
+        for reward in self.rewards.drain(..) {
+            man.kill(reward);
+        }
     }
 
     pub fn procedural(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
-        const ROOMS: [FnRoomGen; 5] = [
+        const ROOMS: [FnRoomGen; 14] = [
             Room::lucky,
             Room::lucky,
             Room::easy_swarm,
             Room::easy_swarm,
             Room::hard_swarm,
+            Room::sprawl,
+            Room::maze,
+            Room::pillars,
+            Room::donut,
+            Room::gauntlet,
+            Room::foundry,
+            Room::press,
+            Room::wrap,
+            Room::templated,
         ];
 
-        let mut rng = thread_rng();
+        let mut rng = rng::get(Stream::World);
         let i = rng.gen_range(0..ROOMS.len());
-        ROOMS[i](man, last)
+        let (mut ret, rx) = ROOMS[i](man, last);
+
+        let depth = ret.depth.saturating_sub(TUTORIAL_ROOMS);
+        let label_pos = Vec2::new(0.0, 0.5 * ret.dimensions.y - 2.0);
+        ret.label_at(man, &format!("DEPTH {depth}"), label_pos, 1.0 / 28.0);
+
+        if ret.risk > 0 || rng.gen_ratio(1, PICKUP_ODDS) {
+            let kind = Pickup::roll(&mut rng);
+            let pickup = pickup::new(man, kind, ret.random_position());
+            ret.parts.push(pickup);
+        }
+
+        if rng.gen_ratio(1, BRANCH_ODDS) {
+            ret.add_branch(man, last.hall_direction.reverse());
+        }
+
+        ret.dark = rng.gen_ratio(1, DARK_ODDS);
+        if depth > DEEP_DEPTH {
+            ret.palette = ret.palette.or(Some(DEEP_PALETTE));
+        }
+
+        if let Some(modifier) = endless::descend(depth) {
+            // glitching text over what just kicked in, under the depth
+            let name_pos = label_pos - Vec2::new(0.0, 2.0);
+            ret.label_at(man, modifier.name(), name_pos, 1.0 / 28.0);
+            let txt = ret.text_at(man, TextNames::BossGlitch, name_pos - Vec2::new(0.0, 3.0), 1.0 / 28.0);
+            text::enable_glitching(man, txt);
+        }
+        for modifier in endless::active() {
+            ret.modify(man, modifier);
+        }
+
+        (ret, rx)
     }
 
-    fn lucky(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
-        let mut rng = thread_rng();
-        let num_fruits = rng.gen_range(4..=7);
+    /// endless mode's side of a modifier, on a freshly made room
+    /// the rest of them change the snake instead, see [`endless::snake_tick`]
+    fn modify(&mut self, man: &mut EntityManager, modifier: Modifier) {
+        match modifier {
+            Modifier::Darkness => self.dark = true,
+            Modifier::FastEnemies => {
+                for &id in &self.parts {
+                    let Some(mut part) = man.view(id) else {
+                        continue;
+                    };
+                    match part.which() {
+                        Entities::Hazard => part.with_mut::<component::Kinematics, _>(|k| k.velocity = FAST_ENEMIES * k.velocity),
+                        Entities::Obstacle | Entities::WaveSpawner => {
+                            part.access_timer(|t| t.set_threshold(t.threshold().div_f32(FAST_ENEMIES)))
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            Modifier::NoSmoothing | Modifier::InvertedControls => (),
+        }
+    }
 
+    fn lucky(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
         let mut ret = Self::proc_next(man, last);
+        let mut rng = rng::get(Stream::World);
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(4..=7));
+
         let txt = ret.text_at(man, TextNames::LuckyGlitch, Vec2::new(-0.5, 0.0), 1.0 / 14.0);
         
         let fruit_id = fruit::bounded(man, ret.make_random_gen(), num_fruits);
-        let rx = fruit::make_kill_trigger(man, fruit_id);
-        let glitch_trigger = fruit::make_eaten_trigger(man, fruit_id);
+        let rx = man.events().subscribe(EventKind::FruitDepleted, Some(fruit_id));
+        let glitch_trigger = man.events().subscribe(EventKind::FruitEaten, Some(fruit_id));
         text::add_glitch_trigger(man, txt, glitch_trigger);
 
         (ret, rx)
     }
 
-    fn easy_swarm(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
-        let mut rng = thread_rng();
-        let num_enemies = rng.gen_range(10..16);
+    /// a lucky room too big for the screen
+    /// fruits are spread all over so the snake has to roam
+    fn sprawl(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, Vec2::diagonal(SPRAWL_SIZE));
+        let mut rng = rng::get(Stream::World);
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(8..=12));
 
-        let mut ret = Self::proc_next(man, last);
-        let txt = ret.text_at(man, TextNames::SwarmGlitch, Vec2::new(-0.5, 0.0), 1.0 / 20.0);
-        
-        let glitch_trigger = snake::make_attack_trigger(man, ret.snake_id);
+        let txt = ret.text_at(man, TextNames::LuckyGlitch, Vec2::new(-0.5, 0.0), 1.0 / 14.0);
+
+        let fruit_id = fruit::bounded(man, ret.make_random_gen(), num_fruits);
+        let rx = man.events().subscribe(EventKind::FruitDepleted, Some(fruit_id));
+        let glitch_trigger = man.events().subscribe(EventKind::FruitEaten, Some(fruit_id));
         text::add_glitch_trigger(man, txt, glitch_trigger);
 
-        let mut enemy_positions = Vec::new();
-        for _ in 0..num_enemies {
-            loop {
-                let next = ret.random_position();
-                if !enemy_positions.contains(&next) {
-                    enemy_positions.push(next);
-                    break;
+        (ret, rx)
+    }
+
+    /// fruits to collect once the interior is in place
+    fn add_fruits(&mut self, man: &mut EntityManager, num_fruits: i32) -> Receiver<()> {
+        let fruit_id = fruit::bounded(man, self.make_random_gen(), num_fruits);
+        man.events().subscribe(EventKind::FruitDepleted, Some(fruit_id))
+    }
+
+    /// a lucky room split up by a maze, carved out by a recursive backtracker
+    /// the maze has no outer wall, so every corridor opens onto the room's edges
+    fn maze(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, Vec2::diagonal(20.0));
+        let mut rng = rng::get(Stream::World);
+
+        let n = MAZE_CELLS;
+        let mut visited = vec![false; (n * n) as usize];
+        // cells are joined through the walls on their right and bottom
+        let mut right = vec![false; (n * n) as usize];
+        let mut down = vec![false; (n * n) as usize];
+        let at = |x: isize, y: isize| (y * n + x) as usize;
+
+        let start = (rng.gen_range(0..n), rng.gen_range(0..n));
+        visited[at(start.0, start.1)] = true;
+        let mut stack = vec![start];
+        while let Some(&(x, y)) = stack.last() {
+            let unvisited: Vec<_> = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .into_iter()
+                .map(|(dx, dy)| (x + dx, y + dy))
+                .filter(|&(nx, ny)| 0 <= nx && nx < n && 0 <= ny && ny < n && !visited[at(nx, ny)])
+                .collect();
+            if unvisited.is_empty() {
+                stack.pop();
+                continue;
+            }
+
+            let (nx, ny) = unvisited[rng.gen_range(0..unvisited.len())];
+            match (nx - x, ny - y) {
+                (1, _) => right[at(x, y)] = true,
+                (-1, _) => right[at(nx, ny)] = true,
+                (_, 1) => down[at(x, y)] = true,
+                _ => down[at(nx, ny)] = true,
+            }
+            visited[at(nx, ny)] = true;
+            stack.push((nx, ny));
+        }
+
+        let origin = -MAZE_CELL * n / 2;
+        let mut tiles = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let (left, top) = (origin + MAZE_CELL * x, origin + MAZE_CELL * y);
+                // the corner post, for every corner inside the maze
+                if x > 0 && y > 0 {
+                    tiles.push((left, top));
+                }
+                for i in 1..MAZE_CELL {
+                    if x < n - 1 && !right[at(x, y)] {
+                        tiles.push((left + MAZE_CELL, top + i));
+                    }
+                    if y < n - 1 && !down[at(x, y)] {
+                        tiles.push((left + i, top + MAZE_CELL));
+                    }
                 }
             }
         }
+        ret.stamp(man, tiles.into_iter().map(|(x, y)| Vec2::new(x as f32, y as f32)));
+
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(4..=7));
+        let rx = ret.add_fruits(man, num_fruits);
+        (ret, rx)
+    }
+
+    /// a swarm fought around a grid of pillars
+    fn pillars(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, Vec2::diagonal(20.0));
+
+        // a few tiles clear of the walls, so the snake has room to turn coming in
+        let offsets = || (-6..=6).step_by(PILLAR_SPACING).map(|o| o as f32);
+        let tiles: Vec<_> = offsets()
+            .flat_map(|y| offsets().map(move |x| Vec2::new(x, y)))
+            .collect();
+        ret.stamp(man, tiles);
+
+        let waves = ret.easy_waves();
+        let rx = ret.add_wave_spawner(man, waves);
+        (ret, rx)
+    }
+
+    /// a lucky room around a solid block
+    fn donut(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, Vec2::diagonal(20.0));
+
+        let tiles: Vec<_> = (-DONUT_HOLE..DONUT_HOLE)
+            .flat_map(|y| (-DONUT_HOLE..DONUT_HOLE).map(move |x| Vec2::new(x as f32, y as f32)))
+            .collect();
+        ret.stamp(man, tiles);
 
-        // let mut rng = thread_rng();
-        let mut enemy_die_triggers = Vec::new();
-        for p in enemy_positions {
-            // let hp = rng.gen_range(1..=3);
-            let e = enemy::new(man, p, 1);
-            let trigger = enemy::make_kill_trigger(man, e);
-            enemy_die_triggers.push(trigger);
+        let mut rng = rng::get(Stream::World);
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(5..=8));
+        let rx = ret.add_fruits(man, num_fruits);
+        (ret, rx)
+    }
+
+    /// a long corridor with hazards sweeping across it
+    /// it runs the same way the snake comes in
+    fn gauntlet(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let (short, long) = GAUNTLET_SIZE;
+        let along = Vec2::from(last.hall_direction).abs();
+        let across = Vec2::new(along.y, along.x);
+        let dimensions = short * across + long * along;
+        let mut ret = Self::next(man, last, dimensions);
+
+        // from one wall to the other, inside of the room
+        let reach = 0.5 * short - 1.0;
+        let speed = ret.difficulty().hazard_speed;
+        let lanes = (long as isize / 2 - GAUNTLET_LANES) / GAUNTLET_LANES;
+        for (i, lane) in (-lanes..=lanes).enumerate() {
+            let offset = (lane * GAUNTLET_LANES) as f32 * along;
+            let (from, to) = (offset - reach * across, offset + (reach - 1.0) * across);
+            // every other hazard starts on the opposite wall
+            let (from, to) = if i % 2 == 0 { (from, to) } else { (to, from) };
+            // and every third one cuts through the snake's body
+            let hazard = if i % 3 == 1 {
+                hazard::trimmer(man, ret.position + from, ret.position + to, speed)
+            } else {
+                hazard::new(man, ret.position + from, ret.position + to, speed)
+            };
+            ret.parts.push(hazard);
         }
 
-        let mut ctr = 0;
-        let total = enemy_die_triggers.len();
-        let (tx, rx) = mpsc::channel();
-        ret.add_logic(man, move |_| {
-            for t in &enemy_die_triggers {
-                if t.try_recv().is_ok() {
-                    ctr += 1;
-                }
+        let mut rng = rng::get(Stream::World);
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(3..=5));
+        let rx = ret.add_fruits(man, num_fruits);
+        (ret, rx)
+    }
+
+    /// a lucky room around a lava pool, with belts running past it either way
+    /// and spikes and tar scattered about
+    fn foundry(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, Vec2::diagonal(FOUNDRY_SIZE));
+
+        let square = |r: isize| (-r..r).flat_map(move |y| (-r..r).map(move |x| Vec2::new(x as f32, y as f32)));
+        ret.lay(man, Kind::Lava, square(FOUNDRY_POOL));
+
+        let belt = |y: isize| (-FOUNDRY_BELT_REACH..FOUNDRY_BELT_REACH).map(move |x| Vec2::new(x as f32, y as f32));
+        let gap = FOUNDRY_POOL + FOUNDRY_BELT_GAP;
+        ret.lay(man, Kind::Belt(Direction::Right), belt(gap));
+        ret.lay(man, Kind::Belt(Direction::Left), belt(-gap - 1));
+
+        let spikes = ret.difficulty().spikes;
+        for (kind, n) in [(Kind::Spikes, spikes), (Kind::Tar, FOUNDRY_TAR)] {
+            for _ in 0..n {
+                let tile = ret.random_position() - ret.position;
+                ret.lay(man, kind, [tile]);
             }
+        }
 
-            if ctr == total {
-                let _ = tx.send(());
+        let mut rng = rng::get(Stream::World);
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(4..=6));
+        let rx = ret.add_fruits(man, num_fruits);
+        (ret, rx)
+    }
+
+    /// a lucky room split by a wall with a door that keeps opening and closing
+    /// crushers sweep the lanes around either end of the wall
+    fn press(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, Vec2::diagonal(PRESS_SIZE));
+
+        // the door is the two tiles in the middle
+        let wall: Vec<_> = (-PRESS_WALL..PRESS_WALL)
+            .filter(|y| !(-1..=0).contains(y))
+            .map(|y| Vec2::new(0.0, y as f32))
+            .collect();
+        ret.stamp(man, wall);
+        // each half slides into the wall on its side
+        ret.add_obstacle(man, obstacle::shuttle(Vec2::new(0.0, 0.0), Vec2::new(0.0, 2.0), DOOR_WAIT), DOOR_STEP);
+        ret.add_obstacle(man, obstacle::shuttle(Vec2::new(0.0, -1.0), Vec2::new(0.0, -3.0), DOOR_WAIT), DOOR_STEP);
+
+        // two tiles tall, every other one starting on the opposite side
+        for (i, lane) in PRESS_LANES.into_iter().enumerate() {
+            let (mut from, mut to) = (-PRESS_REACH as f32, (PRESS_REACH - 1) as f32);
+            if i % 2 == 1 {
+                swap(&mut from, &mut to);
             }
-        });
+            for y in [lane, lane + 1] {
+                let y = y as f32;
+                let path = obstacle::shuttle(Vec2::new(from, y), Vec2::new(to, y), PRESS_WAIT);
+                ret.add_obstacle(man, path, PRESS_STEP);
+            }
+        }
 
+        let mut rng = rng::get(Stream::World);
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(4..=6));
+        let rx = ret.add_fruits(man, num_fruits);
         (ret, rx)
     }
 
-    fn hard_swarm(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
-        let mut rng = thread_rng();
-        let num_enemies = rng.gen_range(6..=12);
+    /// a lucky room that wraps around: going off one side comes back in on the other
+    fn wrap(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, Vec2::diagonal(WRAP_SIZE));
+
+        // the first and last tiles inside the walls
+        let near = 1.0 - 0.5 * WRAP_SIZE;
+        let far = 0.5 * WRAP_SIZE - 2.0;
+        let edge = (near as isize + 1..far as isize).filter(|i| !(-WRAP_GAP..WRAP_GAP).contains(i));
+        for i in edge {
+            let i = i as f32;
+            ret.add_portals(man, (Vec2::new(near, i), Direction::Left), (Vec2::new(far, i), Direction::Right));
+            ret.add_portals(man, (Vec2::new(i, near), Direction::Up), (Vec2::new(i, far), Direction::Down));
+        }
 
-        let mut ret = Self::proc_next(man, last);
-        let txt = ret.text_at(man, TextNames::SwarmGlitch, Vec2::new(-0.5, 0.0), 1.0 / 20.0);
-        
-        let glitch_trigger = snake::make_attack_trigger(man, ret.snake_id);
-        text::add_glitch_trigger(man, txt, glitch_trigger);
+        let mut rng = rng::get(Stream::World);
+        let num_fruits = ret.difficulty().fruits(rng.gen_range(5..=8));
+        let rx = ret.add_fruits(man, num_fruits);
+        (ret, rx)
+    }
 
-        let mut enemy_positions = Vec::new();
-        for _ in 0..num_enemies {
-            loop {
-                let next = ret.random_position();
-                if !enemy_positions.contains(&next) {
-                    enemy_positions.push(next);
-                    break;
+    fn templated(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let templates = template::all();
+        let i = rng::get(Stream::World).gen_range(0..templates.len());
+        Self::from_template(man, last, &templates[i])
+    }
+
+    /// builds the next room from a handmade layout
+    pub fn from_template(man: &mut EntityManager, last: &Room, template: &'static Template) -> (Self, Receiver<()>) {
+        let mut ret = Self::next(man, last, template.dimensions);
+        ret.stamp(man, template.find(Marker::Wall));
+        ret.palette = template.palette.as_deref();
+
+        let mut rng = rng::get(Stream::World);
+        let mut labels = template.labels.iter();
+        let mut notes = template.notes.iter();
+        let mut spawns = template.spawns.iter();
+        let mut enemies = Vec::new();
+        for &(pos, marker) in &template.markers {
+            let world = ret.position + pos;
+            let part = match marker {
+                Marker::Cracked => archetype::wall::cracked(man, Position::new(world.x, world.y, WALL_DEPTH), VAULT_WALL_HP),
+                Marker::Pickup => pickup::new(man, Pickup::roll(&mut rng), world),
+                Marker::Enemy(hp) => {
+                    let enemy = enemy::new(man, world, hp);
+                    enemies.push(man.events().subscribe(EventKind::EnemyDied, Some(enemy)));
+                    enemy
                 }
-            }
+                Marker::Label => {
+                    let text = labels.next().expect("checked when parsing");
+                    // labels are centered, markers are tile corners
+                    ret.label_at(man, text, pos + Vec2::diagonal(0.5), 1.0 / 28.0);
+                    continue;
+                }
+                Marker::Note => {
+                    let note = notes.next().expect("checked when parsing");
+                    ret.annotate(man, pos, Annotation::Note(note.clone()));
+                    continue;
+                }
+                Marker::Spawn => {
+                    let name = spawns.next().expect("checked when parsing");
+                    ret.annotate(man, pos, Annotation::SpawnPoint(name));
+                    continue;
+                }
+                Marker::Wall | Marker::Fruit => continue,
+            };
+            ret.parts.push(part);
         }
 
-        let mut rng = thread_rng();
-        let mut enemy_die_triggers = Vec::new();
-        for p in enemy_positions {
-            let hp = rng.gen_range(2..=6);
-            let e = enemy::new(man, p, hp);
-            let trigger = enemy::make_kill_trigger(man, e);
-            enemy_die_triggers.push(trigger);
+        let spots: Vec<_> = template.find(Marker::Fruit).map(|p| ret.position + p).collect();
+        if !spots.is_empty() {
+            // the next fruit shows up on any other spot
+            let rand_gen = move |last: Vec2| loop {
+                let next = spots[rng::get(Stream::World).gen_range(0..spots.len())];
+                if spots.len() == 1 || next != last {
+                    break next;
+                }
+            };
+            let fruit_id = fruit::bounded(man, rand_gen, template.fruits);
+            let rx = man.events().subscribe(EventKind::FruitDepleted, Some(fruit_id));
+            return (ret, rx);
         }
 
-        let mut ctr = 0;
-        let total = enemy_die_triggers.len();
         let (tx, rx) = mpsc::channel();
+        let mut alive = enemies.len();
         ret.add_logic(man, move |_| {
-            for t in &enemy_die_triggers {
-                if t.try_recv().is_ok() {
-                    ctr += 1;
+            let died = enemies.iter().filter(|e| e.try_recv().is_ok()).count();
+            if died > 0 {
+                alive -= died;
+                if alive == 0 {
+                    let _ = tx.send(());
                 }
             }
-
-            if ctr == total {
-                let _ = tx.send(());
-            }
         });
+        (ret, rx)
+    }
+
+    /// places the wave spawner's counter at the top of the room
+    /// returns the trigger for when the final wave is cleared
+    fn add_wave_spawner(&mut self, man: &mut EntityManager, waves: Vec<Wave>) -> Receiver<()> {
+        let multiplier = tunables::get().enemy_multiplier;
+        let waves = waves
+            .into_iter()
+            .map(|w| Wave {
+                enemies: w.enemies * multiplier,
+                ..w
+            })
+            .collect();
 
+        let (tx, rx) = mpsc::channel();
+        let counter_pos = self.position - Vec2::new(0.0, 0.5 * self.dimensions.y - 2.0);
+        let spawner = wave_spawner::new(
+            man,
+            counter_pos,
+            waves,
+            self.difficulty().wave_interval,
+            self.make_random_gen(),
+            tx,
+        );
+        self.parts.push(spawner);
+        rx
+    }
+
+    fn easy_swarm(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::proc_next(man, last);
+        let txt = ret.text_at(man, TextNames::SwarmGlitch, Vec2::new(-0.5, 0.0), 1.0 / 20.0);
+        
+        let snek = man.find_by_tag(snake::TAG);
+        let glitch_trigger = man.events().subscribe(EventKind::SnakeAttacked, snek);
+        text::add_glitch_trigger(man, txt, glitch_trigger);
+
+        let waves = ret.easy_waves();
+        let rx = ret.add_wave_spawner(man, waves);
+        (ret, rx)
+    }
+
+    // every wave (and every room deeper) brings more enemies
+    fn easy_waves(&self) -> Vec<Wave> {
+        let mut rng = rng::get(Stream::World);
+        let difficulty = self.difficulty();
+        (0..SWARM_WAVES)
+            .map(|wave| Wave {
+                enemies: rng.gen_range(3..6) + wave + difficulty.extra_enemies,
+                hp: 1,
+            })
+            .collect()
+    }
+
+    fn hard_swarm(man: &mut EntityManager, last: &Room) -> (Self, Receiver<()>) {
+        let mut ret = Self::proc_next(man, last);
+        let txt = ret.text_at(man, TextNames::SwarmGlitch, Vec2::new(-0.5, 0.0), 1.0 / 20.0);
+        
+        let snek = man.find_by_tag(snake::TAG);
+        let glitch_trigger = man.events().subscribe(EventKind::SnakeAttacked, snek);
+        text::add_glitch_trigger(man, txt, glitch_trigger);
+
+        // tougher enemies take more hits to bring down
+        ret.set_budget(Budget::Projectile, 24);
+
+        // every wave (and every room deeper) brings tougher enemies
+        let mut rng = rng::get(Stream::World);
+        let difficulty = ret.difficulty();
+        let waves = (0..SWARM_WAVES)
+            .map(|wave| Wave {
+                enemies: rng.gen_range(2..=4) + wave + difficulty.extra_enemies / 2,
+                hp: difficulty.hp(rng.gen_range(2..=4) + wave as i32),
+            })
+            .collect();
+
+        let rx = ret.add_wave_spawner(man, waves);
         (ret, rx)
     }
 
@@ -746,18 +1686,105 @@ impl Room {
     // }
 }
 
+/// the rooms a run went through, and the branches it passed up on the way
+#[derive(Default)]
+pub struct Graph {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    // in world space, walls included
+    bounds: Rect,
+    path: Path,
+    skipped: Vec<Direction>,
+}
+
+impl Graph {
+    /// call as the snake leaves the room, once its exit is taken
+    pub fn visit(&mut self, room: &Room) {
+        let half = 0.5 * room.dimensions;
+        self.nodes.push(Node {
+            bounds: Rect::new(room.position - half, room.position + half),
+            path: room.path,
+            skipped: room.skipped.clone(),
+        });
+    }
+
+    pub fn rooms(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// how many times the risky branch got taken
+    pub fn risks(&self) -> usize {
+        self.nodes.iter().filter(|n| n.path == Path::Risky).count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.nodes.iter().map(|n| n.skipped.len()).sum()
+    }
+
+    /// where every room was, in the order they were visited
+    pub fn layout(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.nodes.iter().map(|n| n.bounds)
+    }
+
+    /// the area all of the rooms visited so far take up
+    pub fn extent(&self) -> Option<Rect> {
+        self.layout().reduce(|a, b| Rect::new(a.min.min(b.min), a.max.max(b.max)))
+    }
+}
+
 pub type FnRoomGen = fn(&mut EntityManager, &Room) -> (Room, Receiver<()>);
-const ROOM_ORDER: [FnRoomGen; 5] = [
-    Room::tut_fruit,
-    Room::tut_attack,
-    Room::tut_enemy,
-    Room::tut_shield,
-    Room::procedural,
+/// number of handcrafted rooms before procedural generation kicks in
+// rooms and halls have to be even-sized to line up with each other
+// a range with nothing to pick from doesn't touch the rng, so seeds keep playing out the same
+fn roll_even(rng: &mut impl Rng, range: &RangeInclusive<usize>) -> usize {
+    let (min, max) = (range.start().div_ceil(2), range.end() / 2);
+    if min >= max {
+        return 2 * min;
+    }
+    2 * rng.gen_range(min..=max)
+}
+
+pub const TUTORIAL_ROOMS: usize = ROOM_ORDER.len() - 1;
+// how many fruits the fruit room has the snake eat
+const TUT_FRUITS: i32 = 3;
+// named for the chapter select, the controls room comes before all of them
+const ROOM_ORDER: [(&str, FnRoomGen); 5] = [
+    ("FRUIT", Room::tut_fruit),
+    ("ATTACK", Room::tut_attack),
+    ("ENEMIES", Room::tut_enemy),
+    ("SHIELD", Room::tut_shield),
+    ("DEPTHS", Room::procedural),
 ];
+pub const CHAPTERS: usize = ROOM_ORDER.len() + 1;
 
 pub fn next_room(current_room: &mut usize) -> FnRoomGen {
     let i = (ROOM_ORDER.len() - 1).min(*current_room);
-    let ret = self::ROOM_ORDER[i];
+    let (_, ret) = self::ROOM_ORDER[i];
     *current_room += 1;
     ret
 }
+
+pub fn chapter_name(chapter: usize) -> &'static str {
+    match chapter.min(ROOM_ORDER.len()) {
+        0 => "CONTROLS",
+        i => ROOM_ORDER[i - 1].0,
+    }
+}
+
+/// the room a run starts in at the given chapter, the trigger for opening its hall,
+/// and the room counter to carry on from
+/// anything past the first chapter starts in a shortcut, see [`Room::shortcut`]
+pub fn first_room(man: &mut EntityManager, chapter: usize) -> (Room, Receiver<()>, usize) {
+    match chapter.min(ROOM_ORDER.len()) {
+        0 => {
+            let (room, rx) = Room::tut_controls(man);
+            (room, rx, 0)
+        }
+        i => {
+            let (room, rx) = Room::shortcut(man, i - 1);
+            (room, rx, i - 1)
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! walks seeded chains of rooms the way a run does, headless,
+//! checking that the wall math in `break_wall` and `offset_from` lines everything up
+
+use std::{collections::HashSet, time::Duration};
+
+use snek::{
+    entity::{Components, Entities, EntityId, EntityManager},
+    math::{Rect, TileCoord, Vec2},
+    rng,
+    world::{self, Outline, Room},
+};
+
+const SEEDS: u64 = 10;
+// long enough to get well past the tutorial, through every kind of procedural room
+const ROOMS: usize = 30;
+// a tick goes by so kills go through, short enough that nothing gets anywhere
+const SETTLE: Duration = Duration::from_millis(1);
+
+fn settle(man: &mut EntityManager, room: &mut Room) {
+    man.tick(SETTLE);
+    room.update(man);
+}
+
+fn tiles_of(man: &EntityManager, which: Entities) -> Vec<(EntityId, TileCoord)> {
+    man.query(&[Components::Position])
+        .filter(|e| e.which() == which)
+        .map(|e| (e.id(), TileCoord::of(e.get_position().into())))
+        .collect()
+}
+
+fn walls(man: &EntityManager) -> HashSet<TileCoord> {
+    tiles_of(man, Entities::Wall).into_iter().map(|(_, t)| t).collect()
+}
+
+fn rects(outlines: &[(Outline, Rect)], kind: Outline) -> Vec<Rect> {
+    outlines.iter().filter(|(o, _)| *o == kind).map(|&(_, r)| r).collect()
+}
+
+/// the tile is wholly inside of the rect
+fn covers(rect: Rect, t: TileCoord) -> bool {
+    let corner = Vec2::from(t);
+    rect.contains(corner) && rect.contains(corner + Vec2::diagonal(1.0))
+}
+
+/// every tile of the rect's outermost ring
+fn ring(rect: Rect) -> impl Iterator<Item = TileCoord> {
+    let min = TileCoord::of(rect.min);
+    let max = TileCoord::of(rect.max);
+    (min.y..max.y)
+        .flat_map(move |y| (min.x..max.x).map(move |x| TileCoord::new(x, y)))
+        .filter(move |t| t.x == min.x || t.x == max.x - 1 || t.y == min.y || t.y == max.y - 1)
+}
+
+/// everywhere the snake can get to from the given tiles without going through a wall
+/// none if it gets outside of every bound, through a gap that shouldn't be there
+fn flood(walls: &HashSet<TileCoord>, bounds: &[Rect], from: Vec<TileCoord>) -> Option<HashSet<TileCoord>> {
+    let mut reached: HashSet<_> = from.iter().copied().collect();
+    let mut next = from;
+    while let Some(t) = next.pop() {
+        if !bounds.iter().any(|&b| covers(b, t)) {
+            return None;
+        }
+        for step in [TileCoord::new(1, 0), TileCoord::new(-1, 0), TileCoord::new(0, 1), TileCoord::new(0, -1)] {
+            let n = t + step;
+            if !walls.contains(&n) && reached.insert(n) {
+                next.push(n);
+            }
+        }
+    }
+    Some(reached)
+}
+
+fn walk(seed: u64) {
+    rng::seed(seed);
+    let (mut man, _) = EntityManager::headless();
+    let (mut room, _) = Room::tut_controls(&mut man);
+    let mut ctr = 0;
+
+    for n in 0..ROOMS {
+        settle(&mut man, &mut room);
+        let entered = room.outlines();
+
+        // the snake made it in, now it's walled in until the room's cleared
+        room.close_hall_entrance(&mut man);
+        settle(&mut man, &mut room);
+        let walls = self::walls(&man);
+        let own = rects(&room.outlines(), Outline::Bounds)[0];
+        for t in ring(own) {
+            assert!(walls.contains(&t), "seed {seed} room {n}: gap in the walls at {t:?}");
+        }
+
+        let exits = room.open_hallway(&mut man).expect("hall opened twice");
+        room.take_exit(&mut man, n % exits.len());
+        settle(&mut man, &mut room);
+
+        let exited = room.outlines();
+        let openings: Vec<_> = rects(&entered, Outline::Opening)
+            .into_iter()
+            .chain(rects(&exited, Outline::Opening))
+            .collect();
+        for (_, t) in tiles_of(&man, Entities::Trigger) {
+            assert!(
+                openings.iter().any(|&o| covers(o, t)),
+                "seed {seed} room {n}: trigger at {t:?} outside of every hole",
+            );
+        }
+
+        let fruits: HashSet<_> = tiles_of(&man, Entities::Fruit).into_iter().map(|(id, _)| id).collect();
+        let (mut next, _) = world::next_room(&mut ctr)(&mut man, &room);
+        settle(&mut man, &mut next);
+        for (id, t) in tiles_of(&man, Entities::Fruit) {
+            if !fruits.contains(&id) {
+                assert!(next.contains(t.into()), "seed {seed} room {n}: fruit at {t:?} outside of the next room");
+            }
+        }
+
+        // from the hall the snake can get into both rooms, and nowhere else
+        let bounds: Vec<_> = rects(&exited, Outline::Bounds)
+            .into_iter()
+            .chain(rects(&next.outlines(), Outline::Bounds))
+            .collect();
+        let hall: Vec<_> = rects(&exited, Outline::Opening)
+            .into_iter()
+            .flat_map(|o| ring(o).collect::<Vec<_>>())
+            .collect();
+        let reached = flood(&self::walls(&man), &bounds, hall)
+            .unwrap_or_else(|| panic!("seed {seed} room {n}: the way out leaks"));
+        assert!(
+            reached.iter().any(|&t| room.contains(t.into())),
+            "seed {seed} room {n}: the hall doesn't line up with the room",
+        );
+        assert!(
+            reached.iter().any(|&t| next.contains(t.into())),
+            "seed {seed} room {n}: the hall doesn't line up with the next room",
+        );
+
+        room.swap(&mut next);
+        next.destroy(&mut man);
+    }
+}
+
+#[test]
+fn seeded_rooms_line_up() {
+    for seed in 0..SEEDS {
+        self::walk(seed);
+    }
+}